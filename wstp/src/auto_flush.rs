@@ -0,0 +1,91 @@
+//! Automatic flushing of links used for request/response protocols.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(feature = "expr")]
+use wolfram_expr::Expr;
+
+use crate::{Error, Link};
+
+/// Whether [`Link::set_auto_flush()`] has ever enabled auto-flush on a link.
+///
+/// This lets puts skip looking up the settings of a link when no link uses
+/// auto-flush.
+static ANY_AUTO_FLUSH: AtomicBool = AtomicBool::new(false);
+
+/// # Flushing
+impl Link {
+    /// Enable or disable auto-flush mode on this link.
+    ///
+    /// WSTP buffers the data put on a link until the buffer is full or
+    /// [`Link::flush()`] is called. In a request/response protocol, forgetting to flush
+    /// after putting a request leaves the peer waiting forever for data that is
+    /// sitting in the buffer.
+    ///
+    /// When auto-flush is enabled, the link is flushed after each complete expression
+    /// put using [`Link::put_expr()`], and after each call to [`Link::end_packet()`].
+    /// Expressions put one token at a time, using methods like
+    /// [`Link::put_function()`], are not flushed until the expression is completed
+    /// using [`Link::end_packet()`], or [`Link::flush()`] is called explicitly.
+    ///
+    /// Auto-flush is disabled by default, which is usually the right choice for bulk
+    /// producers that put many expressions and can batch sends explicitly.
+    ///
+    /// The setting applies to all [`Link`] values referring to the same underlying
+    /// [`WSLINK`][crate::sys::WSLINK].
+    pub fn set_auto_flush(&mut self, auto_flush: bool) {
+        if auto_flush {
+            ANY_AUTO_FLUSH.store(true, Ordering::Relaxed);
+        }
+
+        self.update_settings(|settings| settings.auto_flush = auto_flush)
+    }
+
+    /// Returns `true` if auto-flush mode is enabled on this link.
+    ///
+    /// See [`Link::set_auto_flush()`].
+    pub fn auto_flush(&self) -> bool {
+        if !ANY_AUTO_FLUSH.load(Ordering::Relaxed) {
+            return false;
+        }
+
+        self.with_settings(|settings| settings.auto_flush)
+    }
+
+    /// Put `expr`, and then flush this link.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wolfram_expr::Expr;
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_expr_and_flush(&Expr::from(5)).unwrap();
+    ///
+    /// assert_eq!(link.get_i64().unwrap(), 5);
+    /// ```
+    #[cfg(feature = "expr")]
+    pub fn put_expr_and_flush(&mut self, expr: &Expr) -> Result<(), Error> {
+        self.put_expr(expr)?;
+        self.flush()
+    }
+
+    /// Mark the end of the current packet, and then flush this link.
+    ///
+    /// See [`Link::end_packet()`].
+    pub fn end_packet_and_flush(&mut self) -> Result<(), Error> {
+        self.end_packet()?;
+        self.flush()
+    }
+
+    /// Flush this link if auto-flush is enabled.
+    pub(crate) fn flush_if_auto(&mut self) -> Result<(), Error> {
+        if self.auto_flush() {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+}
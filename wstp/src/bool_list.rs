@@ -0,0 +1,141 @@
+//! Transfer of boolean vectors.
+
+use crate::{Error, Link, TokenType};
+
+/// Encoding used when putting a boolean vector onto a [`Link`].
+///
+/// [`Link::get_bool_list()`] accepts either encoding.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum BoolEncoding {
+    /// Put the vector as a `List` of the symbols `True` and `False`.
+    ///
+    /// For example, `[true, false]` is put as `{True, False}`.
+    List,
+    /// Put the vector as a packed `"UnsignedInteger8"` [`NumericArray`][NumericArray]
+    /// of `1`'s and `0`'s.
+    ///
+    /// For example, `[true, false]` is put as `NumericArray[{1, 0}, "UnsignedInteger8"]`.
+    ///
+    /// The Wolfram Language has no bit-packed `NumericArray` element type, so each
+    /// element occupies one byte. This is still significantly more compact than the
+    /// [`BoolEncoding::List`] encoding, and is the form typically expected for mask
+    /// arrays by image processing and machine learning functions.
+    ///
+    /// [NumericArray]: https://reference.wolfram.com/language/ref/NumericArray.html
+    NumericArray,
+}
+
+impl Link {
+    /// Put a vector of booleans, encoded according to `encoding`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{BoolEncoding, Link};
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// // Put NumericArray[{1, 0, 1}, "UnsignedInteger8"]
+    /// link.put_bool_list(&[true, false, true], BoolEncoding::NumericArray)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(link.get_bool_list().unwrap(), vec![true, false, true]);
+    /// ```
+    pub fn put_bool_list(
+        &mut self,
+        values: &[bool],
+        encoding: BoolEncoding,
+    ) -> Result<(), Error> {
+        match encoding {
+            BoolEncoding::List => self.put_guarded(|link| {
+                link.put_function("System`List", values.len())?;
+
+                for &value in values {
+                    link.put_symbol(match value {
+                        true => "System`True",
+                        false => "System`False",
+                    })?;
+                }
+
+                Ok(())
+            }),
+            BoolEncoding::NumericArray => {
+                let bytes: Vec<u8> =
+                    values.iter().map(|&value| u8::from(value)).collect();
+
+                self.put_numeric_array::<u8>(&bytes, &[bytes.len()])
+            },
+        }
+    }
+
+    /// Get a vector of booleans, in either of the encodings described by
+    /// [`BoolEncoding`].
+    pub fn get_bool_list(&mut self) -> Result<Vec<bool>, Error> {
+        if self.get_type()? != TokenType::Function {
+            return Err(Error::custom(format!(
+                "expected List or NumericArray of booleans, got {:?}",
+                self.get_type()?
+            )));
+        }
+
+        let argc = self.get_arg_count()?;
+
+        let is_list = {
+            let head = self.get_symbol_ref()?;
+
+            match head.as_str() {
+                "System`List" => true,
+                "System`NumericArray" => false,
+                other => {
+                    return Err(Error::custom(format!(
+                        "expected List or NumericArray of booleans, got {}[...]",
+                        other
+                    )))
+                },
+            }
+        };
+
+        if is_list {
+            let mut values = Vec::with_capacity(argc);
+
+            for _ in 0..argc {
+                let symbol = self.get_symbol_ref()?;
+
+                values.push(match symbol.as_str() {
+                    "System`True" => true,
+                    "System`False" => false,
+                    other => {
+                        return Err(Error::custom(format!(
+                            "expected True or False in boolean List, got {}",
+                            other
+                        )))
+                    },
+                });
+            }
+
+            return Ok(values);
+        }
+
+        let array = self.get_numeric_array_arguments::<u8>(argc)?;
+
+        if array.dimensions.len() != 1 {
+            return Err(Error::custom(format!(
+                "expected boolean NumericArray to have rank 1, got rank {}",
+                array.dimensions.len()
+            )));
+        }
+
+        array
+            .data
+            .iter()
+            .map(|&byte| match byte {
+                0 => Ok(false),
+                1 => Ok(true),
+                _ => Err(Error::custom(format!(
+                    "expected 0 or 1 in boolean NumericArray, got {}",
+                    byte
+                ))),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,214 @@
+//! Transfer of [`ByteArray`][ByteArray] expressions.
+//!
+//! A `ByteArray` is written to a link as the expression `ByteArray[{b1, b2, ...}]`, where
+//! the list of bytes is transferred as a single packed `Integer8` array.
+//!
+//! The Wolfram Language kernel instead writes a `ByteArray` as `ByteArray["<base64>"]`,
+//! where the bytes are encoded as a [base64](https://en.wikipedia.org/wiki/Base64)
+//! string. Both forms are accepted when a `ByteArray` is read.
+//!
+//! [ByteArray]: https://reference.wolfram.com/language/ref/ByteArray.html
+
+use crate::{Array, Error, Link, TokenType};
+
+impl Link {
+    /// Put a [`ByteArray`][ByteArray] expression containing `data`.
+    ///
+    /// The bytes are passed directly to
+    /// [`WSPutInteger8Array()`](https://reference.wolfram.com/language/ref/c/WSPutInteger8Array.html),
+    /// without any intermediate copy.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_byte_array(&[1, 2, 3]).unwrap();
+    ///
+    /// assert_eq!(link.get_byte_array().unwrap().data(), &[1, 2, 3]);
+    /// ```
+    ///
+    /// [ByteArray]: https://reference.wolfram.com/language/ref/ByteArray.html
+    pub fn put_byte_array(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.put_guarded(|link| {
+            link.put_function("System`ByteArray", 1)?;
+            link.put_u8_array(data, &[data.len()])
+        })
+    }
+
+    /// Get a [`ByteArray`][ByteArray] expression.
+    ///
+    /// The byte data may be given either as a packed array, `ByteArray[{b1, b2, ...}]`,
+    /// in which case the returned [`Array`] borrows the byte data from the link and no
+    /// copy of the data is made, or as the base64 string written by the Wolfram
+    /// Language kernel, `ByteArray["<base64>"]`, in which case the returned [`Array`]
+    /// owns the decoded bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// // Put ByteArray["AQID"], as written by the kernel for ByteArray[{1, 2, 3}]
+    /// link.put_function("System`ByteArray", 1).unwrap();
+    /// link.put_str("AQID").unwrap();
+    ///
+    /// assert_eq!(link.get_byte_array().unwrap().data(), &[1, 2, 3]);
+    /// ```
+    ///
+    /// [ByteArray]: https://reference.wolfram.com/language/ref/ByteArray.html
+    pub fn get_byte_array(&mut self) -> Result<Array<'_, u8>, Error> {
+        let argc = self.test_head("System`ByteArray")?;
+
+        if argc != 1 {
+            return Err(Error::custom(format!(
+                "expected ByteArray expression to have 1 argument, got {}",
+                argc
+            )));
+        }
+
+        if self.get_type()? == TokenType::String {
+            let data = decode_base64(self.get_string_ref()?.as_str())?;
+
+            return Ok(Array::from_vec(self, data));
+        }
+
+        let array = self.get_u8_array()?;
+
+        if array.rank() != 1 {
+            return Err(Error::custom(format!(
+                "expected ByteArray data to have rank 1, got rank {}",
+                array.rank()
+            )));
+        }
+
+        Ok(array)
+    }
+}
+
+/// Decode the standard base64 encoding of a `ByteArray`, with optional padding.
+///
+/// Only the canonical encoding of the data is accepted: if padding is present it must
+/// complete the last group, and the unused low bits of the last digit must be zero.
+fn decode_base64(encoded: &str) -> Result<Vec<u8>, Error> {
+    let invalid =
+        || Error::custom("ByteArray data is not a valid base64 string".to_owned());
+
+    let digits = encoded.trim_end_matches('=').as_bytes();
+    let padding = encoded.len() - digits.len();
+
+    // A single digit after the last complete group encodes fewer than 8 bits.
+    if digits.len() % 4 == 1 {
+        return Err(invalid());
+    }
+
+    if padding != 0 && (digits.len() % 4 == 0 || encoded.len() % 4 != 0) {
+        return Err(invalid());
+    }
+
+    let mut data = Vec::with_capacity(digits.len() * 3 / 4);
+    let mut bits: u32 = 0;
+    let mut bit_count = 0;
+
+    for &digit in digits {
+        let value = match digit {
+            b'A'..=b'Z' => digit - b'A',
+            b'a'..=b'z' => digit - b'a' + 26,
+            b'0'..=b'9' => digit - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return Err(invalid()),
+        };
+
+        bits = (bits << 6) | u32::from(value);
+        bit_count += 6;
+
+        if bit_count >= 8 {
+            bit_count -= 8;
+            data.push((bits >> bit_count) as u8);
+        }
+    }
+
+    if bits & ((1 << bit_count) - 1) != 0 {
+        return Err(invalid());
+    }
+
+    Ok(data)
+}
+
+//======================================
+// `bytes` crate integration
+//======================================
+
+#[cfg(feature = "bytes")]
+impl Link {
+    /// Put a [`ByteArray`][ByteArray] expression containing the contents of `buf`.
+    ///
+    /// This is equivalent to [`Link::put_byte_array()`]; the contents of `buf` are not
+    /// copied.
+    ///
+    /// [ByteArray]: https://reference.wolfram.com/language/ref/ByteArray.html
+    pub fn put_bytes(&mut self, buf: &bytes::Bytes) -> Result<(), Error> {
+        self.put_byte_array(buf.as_ref())
+    }
+
+    /// Get a [`ByteArray`][ByteArray] expression as a [`bytes::Bytes`].
+    ///
+    /// The data is copied exactly once, directly out of the buffer owned by the link.
+    /// This copy can't be avoided: the buffer is allocated by WSTP, and must be
+    /// released using the link it was read from, so it can't be handed to a
+    /// [`bytes::Bytes`] that may outlive the borrow of the link.
+    ///
+    /// [ByteArray]: https://reference.wolfram.com/language/ref/ByteArray.html
+    pub fn get_bytes(&mut self) -> Result<bytes::Bytes, Error> {
+        Ok(self.get_bytes_mut()?.freeze())
+    }
+
+    /// Get a [`ByteArray`][ByteArray] expression as a [`bytes::BytesMut`].
+    ///
+    /// Like [`Link::get_bytes()`], the data is copied exactly once out of the buffer
+    /// owned by the link.
+    ///
+    /// [ByteArray]: https://reference.wolfram.com/language/ref/ByteArray.html
+    pub fn get_bytes_mut(&mut self) -> Result<bytes::BytesMut, Error> {
+        let mut buf = bytes::BytesMut::new();
+        let () = self.get_bytes_into(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Get a [`ByteArray`][ByteArray] expression, appending its contents to `buf`.
+    ///
+    /// Re-using the same `buf` across calls avoids allocating a new buffer for each
+    /// payload read from the link.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bytes::BytesMut;
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_byte_array(b"abc").unwrap();
+    /// link.put_byte_array(b"def").unwrap();
+    ///
+    /// let mut buf = BytesMut::new();
+    /// link.get_bytes_into(&mut buf).unwrap();
+    /// link.get_bytes_into(&mut buf).unwrap();
+    ///
+    /// assert_eq!(&buf[..], b"abcdef");
+    /// ```
+    ///
+    /// [ByteArray]: https://reference.wolfram.com/language/ref/ByteArray.html
+    pub fn get_bytes_into(&mut self, buf: &mut bytes::BytesMut) -> Result<(), Error> {
+        let array = self.get_byte_array()?;
+
+        buf.extend_from_slice(array.data());
+
+        Ok(())
+    }
+}
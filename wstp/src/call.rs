@@ -0,0 +1,68 @@
+//! Synchronous request/response calls over a link.
+
+use std::time::{Duration, Instant};
+
+use wolfram_expr::Expr;
+
+use crate::{sys, Error, Link};
+
+/// # Calls
+impl Link {
+    /// Put `expr` on this link, flush it, and wait up to `timeout` for one expression
+    /// to be sent in response.
+    ///
+    /// This is the simplest form of remote procedure call over a link, for peers that
+    /// reply to each expression they receive with exactly one expression. No packet
+    /// conventions are assumed: `expr` is sent as-is, and the next expression received
+    /// is returned as the response. To send an expression to a Wolfram Language kernel
+    /// and get the result of evaluating it, use [`Link::evaluate()`] instead.
+    ///
+    /// An error is returned if the link has been closed, if writing `expr` or reading
+    /// the response fails, or if no response is received within `timeout`. If a call
+    /// times out, the response may still arrive later and be read by the next call,
+    /// so the link should usually be discarded.
+    ///
+    /// On a loopback link the response is `expr` itself, because loopback links have
+    /// no peer.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use wolfram_expr::{Expr, Symbol};
+    /// use wstp::Protocol;
+    ///
+    /// let (mut client, mut server) = wstp::channel(Protocol::IntraProcess).unwrap();
+    ///
+    /// // Respond to each number with its square.
+    /// let server = std::thread::spawn(move || {
+    ///     let n = server.get_i64().unwrap();
+    ///     server.put_i64(n * n).unwrap();
+    ///     server.flush().unwrap();
+    /// });
+    ///
+    /// let response = client.call(&Expr::from(7), Duration::from_secs(5)).unwrap();
+    ///
+    /// assert_eq!(response, Expr::from(49));
+    /// # server.join().unwrap();
+    /// ```
+    pub fn call(&mut self, expr: &Expr, timeout: Duration) -> Result<Expr, Error> {
+        let deadline = Instant::now() + timeout;
+
+        if !self.is_open() {
+            return Err(Error::from_code(sys::WSECLOSED));
+        }
+
+        self.put_expr(expr)?;
+        self.flush()?;
+
+        if !self.wait_until(deadline)? {
+            return Err(Error::custom(format!(
+                "call: no response received within {:?}",
+                timeout
+            )));
+        }
+
+        self.get_expr()
+    }
+}
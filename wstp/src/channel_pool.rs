@@ -0,0 +1,195 @@
+//! Pool of pre-activated [`IntraProcess`][crate::Protocol::IntraProcess] channels.
+
+use std::{
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    thread::{self, JoinHandle},
+};
+
+use crate::{Error, Link, Protocol};
+
+/// Pool of pre-created, pre-activated [`IntraProcess`][Protocol::IntraProcess] link
+/// pairs, for use as scratch channels.
+///
+/// Creating and activating a link pair with [`channel()`][crate::channel] involves
+/// several round trips between the two endpoints, which can dominate the cost of
+/// fine-grained workloads that use short-lived channels for staging, relaying, or
+/// copying expressions. A [`ChannelPool`] keeps up to `capacity` activated pairs ready,
+/// so that [`ChannelPool::take()`] usually returns immediately.
+///
+/// A background thread replaces each pair taken from the pool. If the pool is empty,
+/// [`ChannelPool::take()`] creates a new pair itself instead of waiting for the
+/// background thread. Pairs are not returned to the pool after use, so each pair
+/// returned by [`ChannelPool::take()`] is fresh, with no leftover data.
+///
+/// [`ChannelPool`] is [`Sync`], so it can be shared between threads, e.g. using an
+/// [`Arc`].
+///
+/// # Example
+///
+/// ```
+/// use wstp::ChannelPool;
+///
+/// let pool = ChannelPool::new(4);
+///
+/// // Optionally, wait for the pool to be filled before it is first used.
+/// pool.fill().unwrap();
+///
+/// let (mut a, mut b) = pool.take().unwrap();
+///
+/// a.put_i64(5).unwrap();
+/// a.flush().unwrap();
+///
+/// assert_eq!(b.get_i64().unwrap(), 5);
+/// ```
+#[derive(Debug)]
+pub struct ChannelPool {
+    shared: Arc<Shared>,
+    refiller: Option<JoinHandle<()>>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<State>,
+    /// Notified when a pair is taken from the pool, or the pool is dropped.
+    not_full: Condvar,
+    capacity: usize,
+}
+
+#[derive(Debug)]
+struct State {
+    pairs: Vec<(Link, Link)>,
+    closed: bool,
+}
+
+impl ChannelPool {
+    /// Create a pool that keeps up to `capacity` activated link pairs ready.
+    ///
+    /// The pool is filled by a background thread. Use [`ChannelPool::fill()`] to wait
+    /// for the pool to be filled.
+    pub fn new(capacity: usize) -> Self {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                pairs: Vec::with_capacity(capacity),
+                closed: false,
+            }),
+            not_full: Condvar::new(),
+            capacity,
+        });
+
+        let refiller = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || refill(&shared))
+        };
+
+        ChannelPool {
+            shared,
+            refiller: Some(refiller),
+        }
+    }
+
+    /// Take an activated link pair from the pool.
+    ///
+    /// If the pool is empty, a new pair is created and activated, as if by
+    /// [`channel(Protocol::IntraProcess)`][crate::channel].
+    pub fn take(&self) -> Result<(Link, Link), Error> {
+        let pair = self.shared.lock().pairs.pop();
+
+        self.shared.not_full.notify_one();
+
+        match pair {
+            Some(pair) => Ok(pair),
+            None => crate::channel(Protocol::IntraProcess),
+        }
+    }
+
+    /// Fill the pool to its capacity on the calling thread, returning once every
+    /// pair in the pool has been activated.
+    ///
+    /// This can be used to avoid paying the cost of creating link pairs when the pool
+    /// is first used.
+    pub fn fill(&self) -> Result<(), Error> {
+        while self.len() < self.shared.capacity {
+            let pair = crate::channel(Protocol::IntraProcess)?;
+
+            let mut state = self.shared.lock();
+
+            if state.pairs.len() < self.shared.capacity {
+                state.pairs.push(pair);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the number of activated link pairs currently in the pool.
+    pub fn len(&self) -> usize {
+        self.shared.lock().pairs.len()
+    }
+
+    /// Returns `true` if there are no activated link pairs currently in the pool.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the maximum number of link pairs kept in the pool.
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+}
+
+impl Drop for ChannelPool {
+    fn drop(&mut self) {
+        self.shared.lock().closed = true;
+        self.shared.not_full.notify_all();
+
+        if let Some(refiller) = self.refiller.take() {
+            let _ = refiller.join();
+        }
+    }
+}
+
+const POISONED: &str = "ChannelPool state lock was poisoned";
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect(POISONED)
+    }
+}
+
+/// Body of the background thread that keeps the pool filled.
+fn refill(shared: &Shared) {
+    loop {
+        {
+            let mut state = shared.lock();
+
+            while !state.closed && state.pairs.len() >= shared.capacity {
+                state = shared.not_full.wait(state).expect(POISONED);
+            }
+
+            if state.closed {
+                return;
+            }
+        }
+
+        match crate::channel(Protocol::IntraProcess) {
+            Ok(pair) => {
+                let mut state = shared.lock();
+
+                if !state.closed && state.pairs.len() < shared.capacity {
+                    state.pairs.push(pair);
+                }
+            },
+            Err(_) => {
+                // Don't retry until another pair is taken from the pool, and
+                // `take()` surfaces the error by creating a pair itself.
+                let state = shared.lock();
+
+                if state.closed {
+                    return;
+                }
+
+                drop(shared.not_full.wait(state).expect(POISONED));
+            },
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! Structural comparison of the expressions available on two links.
+
+use std::fmt::{self, Display};
+
+use crate::{Error, ExprPath, Link, TokenType};
+
+/// The first difference found between two expressions by [`compare_exprs()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExprDifference {
+    /// Path to the differing sub-expression.
+    ///
+    /// The path is empty if the expressions differ at the top level.
+    pub path: ExprPath,
+    /// Description of the token read from the first link at `path`.
+    pub left: String,
+    /// Description of the token read from the second link at `path`.
+    pub right: String,
+}
+
+impl Display for ExprDifference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ExprDifference { path, left, right } = self;
+
+        if path.steps().is_empty() {
+            write!(f, "expressions differ: {} != {}", left, right)
+        } else {
+            write!(f, "expressions differ at {}: {} != {}", path, left, right)
+        }
+    }
+}
+
+/// Compare the next expression on `a` with the next expression on `b`.
+///
+/// Returns `true` if the expressions are structurally equal. See [`compare_exprs()`].
+pub fn exprs_equal(a: &mut Link, b: &mut Link) -> Result<bool, Error> {
+    Ok(compare_exprs(a, b)?.is_none())
+}
+
+/// Compare the next expression on `a` with the next expression on `b`, returning the
+/// first difference between them.
+///
+/// The expressions are compared token by token, without constructing either
+/// expression in memory. Comparison stops at the first difference, after which the
+/// remainder of both expressions is skipped, so that the full expression is consumed
+/// from each link.
+///
+/// Returns `None` if the expressions are structurally equal.
+///
+/// # Example
+///
+/// ```
+/// use wstp::{compare_exprs, ExprPath, Link};
+///
+/// let mut a = Link::new_loopback().unwrap();
+/// let mut b = Link::new_loopback().unwrap();
+///
+/// // Put f[1, {2, 3}] and f[1, {2, 4}]
+/// for (link, last) in [(&mut a, 3), (&mut b, 4)] {
+///     link.put_function("Global`f", 2).unwrap();
+///     link.put_i64(1).unwrap();
+///     link.put_function("System`List", 2).unwrap();
+///     link.put_i64(2).unwrap();
+///     link.put_i64(last).unwrap();
+/// }
+///
+/// let difference = compare_exprs(&mut a, &mut b).unwrap().unwrap();
+///
+/// assert_eq!(difference.path, ExprPath::new().arg(2).arg(2));
+/// assert_eq!(
+///     difference.to_string(),
+///     "expressions differ at args[2]/args[2]: Integer 3 != Integer 4"
+/// );
+/// ```
+pub fn compare_exprs(
+    a: &mut Link,
+    b: &mut Link,
+) -> Result<Option<ExprDifference>, Error> {
+    // For each function expression being compared, the index of the part currently
+    // being read (where 0 is the head), and the total number of parts.
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+
+    loop {
+        let left = CompareToken::read(a)?;
+        let right = CompareToken::read(b)?;
+
+        if left != right {
+            let pending: usize =
+                stack.iter().map(|(index, total)| total - index - 1).sum();
+
+            for _ in 0..(left.part_count() + pending) {
+                a.skip_expr()?;
+            }
+            for _ in 0..(right.part_count() + pending) {
+                b.skip_expr()?;
+            }
+
+            let path = stack.iter().fold(ExprPath::new(), |path, (index, _)| {
+                if *index == 0 {
+                    path.head()
+                } else {
+                    path.arg(*index)
+                }
+            });
+
+            return Ok(Some(ExprDifference {
+                path,
+                left: left.to_string(),
+                right: right.to_string(),
+            }));
+        }
+
+        if let CompareToken::Function(argc) = left {
+            stack.push((0, argc + 1));
+            continue;
+        }
+
+        // Advance to the next part, finishing any function expressions whose parts
+        // have all been compared.
+        loop {
+            match stack.last_mut() {
+                None => return Ok(None),
+                Some((index, total)) => {
+                    *index += 1;
+
+                    if *index < *total {
+                        break;
+                    }
+
+                    stack.pop();
+                },
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum CompareToken {
+    Integer(String),
+    Real(String),
+    String(String),
+    Symbol(String),
+    Function(usize),
+}
+
+impl CompareToken {
+    fn read(link: &mut Link) -> Result<Self, Error> {
+        let token = match link.get_type()? {
+            TokenType::Integer => CompareToken::Integer(link.get_number_string()?),
+            TokenType::Real => CompareToken::Real(link.get_number_string()?),
+            TokenType::String => {
+                CompareToken::String(link.get_string_ref()?.as_str().to_owned())
+            },
+            TokenType::Symbol => {
+                CompareToken::Symbol(link.get_symbol_ref()?.as_str().to_owned())
+            },
+            TokenType::Function => CompareToken::Function(link.get_arg_count()?),
+        };
+
+        Ok(token)
+    }
+
+    /// The number of parts of the expression that follow this token.
+    fn part_count(&self) -> usize {
+        match *self {
+            CompareToken::Function(argc) => argc + 1,
+            _ => 0,
+        }
+    }
+}
+
+impl Display for CompareToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompareToken::Integer(digits) => write!(f, "Integer {}", digits),
+            CompareToken::Real(digits) => write!(f, "Real {}", digits),
+            CompareToken::String(string) => write!(f, "String {:?}", string),
+            CompareToken::Symbol(symbol) => write!(f, "Symbol {}", symbol),
+            CompareToken::Function(argc) => {
+                write!(f, "expression with {} arguments", argc)
+            },
+        }
+    }
+}
@@ -0,0 +1,136 @@
+//! Transfer of expressions as compressed WXF, for links with limited bandwidth.
+
+use std::io::Write;
+
+use flate2::{write::ZlibEncoder, Compression};
+use wolfram_expr::Expr;
+
+use crate::{wxf, Error, Link};
+
+/// Wrapper around a [`Link`] that transfers each expression as compressed
+/// [WXF][WXF].
+///
+/// [`CompressedLink::put_expr()`] puts each expression as
+/// `BinaryDeserialize[ByteArray[...]]`, where the bytes are the zlib-compressed WXF
+/// encoding of the expression. A Wolfram Language peer recovers the original
+/// expression by evaluating it. [`CompressedLink::get_expr()`] reverses this, and also
+/// accepts a bare `ByteArray` produced by
+/// [`BinarySerialize[expr, PerformanceGoal -> "Size"]`][BinarySerialize].
+///
+/// This trades CPU time for bandwidth, which is worthwhile on slow network
+/// connections to a kernel, but usually not for local links.
+///
+/// # Example
+///
+/// ```
+/// use wolfram_expr::Expr;
+/// use wstp::{CompressedLink, Link};
+///
+/// let mut link = CompressedLink::new(Link::new_loopback().unwrap());
+///
+/// let expr = Expr::list(vec![Expr::string("a"); 1000]);
+///
+/// link.put_expr(&expr).unwrap();
+///
+/// assert_eq!(link.get_expr().unwrap(), expr);
+/// ```
+///
+/// [WXF]: https://reference.wolfram.com/language/tutorial/WXFFormatDescription.html
+/// [BinarySerialize]: https://reference.wolfram.com/language/ref/BinarySerialize.html
+#[derive(Debug)]
+pub struct CompressedLink {
+    link: Link,
+    level: u32,
+}
+
+impl CompressedLink {
+    /// Wrap `link`, using the default compression level.
+    pub fn new(link: Link) -> Self {
+        CompressedLink::with_level(link, Compression::default().level())
+    }
+
+    /// Wrap `link`, using compression `level`, from `0` (no compression) to `9` (best
+    /// compression).
+    pub fn with_level(link: Link, level: u32) -> Self {
+        CompressedLink {
+            link,
+            level: level.min(9),
+        }
+    }
+
+    /// Get the compression level used by [`CompressedLink::put_expr()`].
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    /// Get a reference to the wrapped link.
+    pub fn link(&self) -> &Link {
+        &self.link
+    }
+
+    /// Get a mutable reference to the wrapped link.
+    ///
+    /// Expressions read or written directly using the wrapped link are not compressed.
+    pub fn link_mut(&mut self) -> &mut Link {
+        &mut self.link
+    }
+
+    /// Unwrap the link.
+    pub fn into_inner(self) -> Link {
+        self.link
+    }
+
+    /// Put `expr` as `BinaryDeserialize[ByteArray[...]]`, containing the compressed WXF
+    /// encoding of `expr`.
+    pub fn put_expr(&mut self, expr: &Expr) -> Result<(), Error> {
+        let wxf = compress(expr, Compression::new(self.level))?;
+
+        self.link.put_guarded(|link| {
+            link.put_function("System`BinaryDeserialize", 1)?;
+            link.put_byte_array(&wxf)
+        })
+    }
+
+    /// Get an expression put by [`CompressedLink::put_expr()`].
+    ///
+    /// The incoming expression must be `BinaryDeserialize[ByteArray[...]]` or
+    /// `ByteArray[...]`, where the bytes are a WXF encoding, compressed or not. Any
+    /// other expression is read as-is, so that a peer can send small expressions
+    /// uncompressed.
+    pub fn get_expr(&mut self) -> Result<Expr, Error> {
+        let wrapped = self.link.try_get(|link| {
+            match link.test_head("System`BinaryDeserialize")? {
+                1 => Ok(()),
+                argc => Err(Error::custom(format!(
+                    "expected BinaryDeserialize expression to have 1 argument, got {}",
+                    argc
+                ))),
+            }
+        });
+
+        let bytes = match self
+            .link
+            .try_get(|link| link.get_byte_array().map(|array| array.data().to_vec()))
+        {
+            Ok(bytes) => bytes,
+            Err(err) if wrapped.is_ok() => return Err(err),
+            Err(_) => return self.link.get_expr(),
+        };
+
+        let mut loopback = Link::new_loopback()?;
+        loopback.put_wxf(&bytes)?;
+        loopback.get_expr()
+    }
+}
+
+fn compress(expr: &Expr, compression: Compression) -> Result<Vec<u8>, Error> {
+    let mut body = Vec::new();
+    wxf::write_expr(&mut body, expr);
+
+    let mut encoder = ZlibEncoder::new(wxf::COMPRESSED_HEADER.to_vec(), compression);
+
+    encoder
+        .write_all(&body)
+        .and_then(|()| encoder.finish())
+        .map_err(|err| Error::custom(format!("error compressing WXF: {}", err)))
+}
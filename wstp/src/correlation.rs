@@ -0,0 +1,387 @@
+//! Correlation of concurrent requests and responses sent over one link.
+//!
+//! Requests and responses are sent using the expressions:
+//!
+//! ```text
+//! Global`request[id, payload]
+//! Global`response[id, payload]
+//! ```
+//!
+//! where `id` is an integer chosen by the client, and copied by the server into the
+//! response to the request. Responses can be sent in any order.
+//!
+//! Clients send requests using a [`RequestClient`]. Servers read requests using
+//! [`Link::get_request()`], and send responses using [`Link::put_response()`].
+
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    ops::ControlFlow,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex, MutexGuard,
+    },
+    task::{Context, Poll, Waker},
+    thread::{self, JoinHandle},
+};
+
+use wolfram_expr::Expr;
+
+use crate::{Error, Link};
+
+const REQUEST_HEAD: &str = "Global`request";
+const RESPONSE_HEAD: &str = "Global`response";
+
+const POISONED: &str = "RequestClient state lock was poisoned";
+
+/// Client that multiplexes concurrent requests over one link, and matches each
+/// response to the request it answers.
+///
+/// [`RequestClient::request()`] sends a `request[id, payload]` expression, and returns
+/// a [`PendingResponse`] that completes when the `response[id, payload]` expression
+/// with the same `id` is received. Any number of requests can be in progress at once,
+/// and the server can respond to them in any order.
+///
+/// The link is owned by an I/O thread, which puts the requests on the link and reads
+/// the responses from it. [`RequestClient`] is [`Sync`], so it can be shared between
+/// threads issuing requests, e.g. using an [`Arc`].
+///
+/// See the [module documentation][self] for the conventions used.
+///
+/// # Example
+///
+/// ```
+/// use wolfram_expr::Expr;
+/// use wstp::{Protocol, RequestClient};
+///
+/// let (client, mut server) = wstp::channel(Protocol::IntraProcess).unwrap();
+///
+/// // Serve two requests, responding to them in reverse order.
+/// let server = std::thread::spawn(move || {
+///     let (id1, payload1) = server.get_request().unwrap();
+///     let (id2, payload2) = server.get_request().unwrap();
+///
+///     server.put_response(id2, &payload2).unwrap();
+///     server.put_response(id1, &payload1).unwrap();
+///     server.flush().unwrap();
+///
+///     server
+/// });
+///
+/// let client = RequestClient::new(client);
+///
+/// let first = client.request(Expr::from(1)).unwrap();
+/// let second = client.request(Expr::from(2)).unwrap();
+///
+/// assert_eq!(first.wait().unwrap(), Expr::from(1));
+/// assert_eq!(second.wait().unwrap(), Expr::from(2));
+/// # let _server = server.join().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct RequestClient {
+    shared: Arc<Shared>,
+    io: Option<JoinHandle<Link>>,
+}
+
+/// Response to a request sent using [`RequestClient::request()`].
+///
+/// Use [`PendingResponse::wait()`] to block until the response is received, or
+/// `.await` it from asynchronous code.
+#[derive(Debug)]
+pub struct PendingResponse {
+    id: i64,
+    slot: Arc<Slot>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<State>,
+    /// Notified when a request is queued, or the client is closed.
+    wake: Condvar,
+    /// Set when the I/O thread should stop waiting for incoming responses.
+    interrupt: AtomicBool,
+}
+
+#[derive(Debug)]
+struct State {
+    next_id: i64,
+    outgoing: VecDeque<(i64, Expr)>,
+    pending: HashMap<i64, Arc<Slot>>,
+    closed: bool,
+    /// Error that stopped the I/O thread.
+    error: Option<Error>,
+}
+
+#[derive(Debug, Default)]
+struct Slot {
+    state: Mutex<SlotState>,
+    ready: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct SlotState {
+    result: Option<Result<Expr, Error>>,
+    waker: Option<Waker>,
+}
+
+impl RequestClient {
+    /// Start an I/O thread that sends requests and receives responses using `link`.
+    pub fn new(link: Link) -> Self {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                next_id: 1,
+                outgoing: VecDeque::new(),
+                pending: HashMap::new(),
+                closed: false,
+                error: None,
+            }),
+            wake: Condvar::new(),
+            interrupt: AtomicBool::new(false),
+        });
+
+        let io = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || run_io(link, &shared))
+        };
+
+        RequestClient {
+            shared,
+            io: Some(io),
+        }
+    }
+
+    /// Send a `request[id, payload]` expression, returning the response to it.
+    ///
+    /// An error is returned if the I/O thread has stopped because of a link error.
+    pub fn request(&self, payload: Expr) -> Result<PendingResponse, Error> {
+        let mut state = self.shared.lock();
+
+        if let Some(ref err) = state.error {
+            return Err(Error::custom(format!("RequestClient link failed: {}", err)));
+        }
+
+        let id = state.next_id;
+        state.next_id += 1;
+
+        let slot = Arc::new(Slot::default());
+
+        state.pending.insert(id, Arc::clone(&slot));
+        state.outgoing.push_back((id, payload));
+
+        self.shared.notify();
+
+        Ok(PendingResponse { id, slot })
+    }
+
+    /// Get the number of requests that have not yet received a response.
+    pub fn pending(&self) -> usize {
+        self.shared.lock().pending.len()
+    }
+
+    /// Stop the I/O thread, and return the link.
+    ///
+    /// Requests that have not yet received a response complete with an error. If the
+    /// I/O thread stopped because of a link error, that error is returned instead.
+    pub fn close(mut self) -> Result<Link, Error> {
+        let link = self.stop_io();
+
+        match self.shared.lock().error.take() {
+            Some(err) => Err(err),
+            None => Ok(link),
+        }
+    }
+
+    fn stop_io(&mut self) -> Link {
+        {
+            let mut state = self.shared.lock();
+            state.closed = true;
+            self.shared.notify();
+        }
+
+        let io = self
+            .io
+            .take()
+            .expect("RequestClient I/O thread was already stopped");
+
+        match io.join() {
+            Ok(link) => link,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+}
+
+impl Drop for RequestClient {
+    fn drop(&mut self) {
+        if self.io.is_some() {
+            let _: Link = self.stop_io();
+        }
+    }
+}
+
+impl PendingResponse {
+    /// Get the id of the request this is the response to.
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    /// Block until the response is received, and return its payload.
+    pub fn wait(self) -> Result<Expr, Error> {
+        let mut state = self.slot.lock();
+
+        loop {
+            if let Some(result) = state.result.take() {
+                return result;
+            }
+
+            state = self.slot.ready.wait(state).expect(POISONED);
+        }
+    }
+}
+
+impl Future for PendingResponse {
+    type Output = Result<Expr, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.slot.lock();
+
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            },
+        }
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect(POISONED)
+    }
+
+    /// Wake the I/O thread. Must be called while holding the state lock.
+    fn notify(&self) {
+        self.interrupt.store(true, Ordering::SeqCst);
+        self.wake.notify_one();
+    }
+}
+
+impl Slot {
+    fn lock(&self) -> MutexGuard<'_, SlotState> {
+        self.state.lock().expect(POISONED)
+    }
+
+    fn complete(&self, result: Result<Expr, Error>) {
+        let mut state = self.lock();
+
+        state.result = Some(result);
+
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+
+        self.ready.notify_all();
+    }
+}
+
+/// Body of the I/O thread of a [`RequestClient`].
+fn run_io(mut link: Link, shared: &Shared) -> Link {
+    let result = (|| -> Result<(), Error> {
+        loop {
+            let outgoing = {
+                let mut state = shared.lock();
+
+                while state.outgoing.is_empty()
+                    && state.pending.is_empty()
+                    && !state.closed
+                {
+                    state = shared.wake.wait(state).expect(POISONED);
+                }
+
+                if state.closed {
+                    return Ok(());
+                }
+
+                shared.interrupt.store(false, Ordering::SeqCst);
+
+                std::mem::take(&mut state.outgoing)
+            };
+
+            if !outgoing.is_empty() {
+                for (id, payload) in &outgoing {
+                    link.put_guarded(|link| {
+                        link.put_function(REQUEST_HEAD, 2)?;
+                        link.put_i64(*id)?;
+                        link.put_expr(payload)
+                    })?;
+                }
+
+                link.flush()?;
+            }
+
+            // Wait for a response, or until there are new requests to send.
+            let is_ready = link.wait_with_callback(|_: &mut Link| {
+                if shared.interrupt.load(Ordering::SeqCst) {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            })?;
+
+            if !is_ready {
+                continue;
+            }
+
+            let () = link.expect_head_arity(RESPONSE_HEAD, 2)?;
+            let id = link.get_i64()?;
+            let payload = link.get_expr()?;
+
+            // Ignore responses to unknown requests.
+            let slot = shared.lock().pending.remove(&id);
+
+            if let Some(slot) = slot {
+                slot.complete(Ok(payload));
+            }
+        }
+    })();
+
+    let mut state = shared.lock();
+
+    let err = match result {
+        Ok(()) => Error::custom("RequestClient was closed".to_owned()),
+        Err(err) => {
+            state.error = Some(err.clone());
+            err
+        },
+    };
+
+    for (_, slot) in state.pending.drain() {
+        slot.complete(Err(err.clone()));
+    }
+
+    link
+}
+
+/// # Request correlation
+impl Link {
+    /// Get a `request[id, payload]` expression sent by a [`RequestClient`].
+    pub fn get_request(&mut self) -> Result<(i64, Expr), Error> {
+        let () = self.expect_head_arity(REQUEST_HEAD, 2)?;
+
+        let id = self.get_i64()?;
+        let payload = self.get_expr()?;
+
+        Ok((id, payload))
+    }
+
+    /// Put a `response[id, payload]` expression, the response to the request with the
+    /// same `id`.
+    pub fn put_response(&mut self, id: i64, payload: &Expr) -> Result<(), Error> {
+        self.put_guarded(|link| {
+            link.put_function(RESPONSE_HEAD, 2)?;
+            link.put_i64(id)?;
+            link.put_expr(payload)
+        })
+    }
+}
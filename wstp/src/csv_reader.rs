@@ -0,0 +1,176 @@
+//! Streaming of CSV data onto a link.
+
+use std::io::Read;
+
+use crate::{DatasetWriter, Error, Link, ListWriter};
+
+/// Options controlling how [`Link::put_csv_reader()`] converts CSV data into an
+/// expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvOptions {
+    /// Field delimiter. Defaults to `b','`.
+    pub delimiter: u8,
+    /// Whether the first record contains column names. Defaults to `true`.
+    ///
+    /// When `false`, every record is treated as data, and
+    /// [`CsvOutput::Dataset`] uses the column names `"1"`, `"2"`, etc.
+    pub has_headers: bool,
+    /// Whether to convert fields that parse as integers or reals into `Integer`
+    /// and `Real` values. Defaults to `true`.
+    ///
+    /// When `false`, every field is put as a `String`.
+    pub infer_types: bool,
+    /// The expression form of the CSV data. Defaults to [`CsvOutput::Lists`].
+    pub output: CsvOutput,
+}
+
+/// Expression form produced by [`Link::put_csv_reader()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CsvOutput {
+    /// A `List` of records, each of which is a `List` of fields.
+    ///
+    /// If [`CsvOptions::has_headers`] is `true`, the header record is included as the
+    /// first element.
+    Lists,
+    /// A [`Dataset`][Dataset] of `Association` rows, keyed by the column names.
+    ///
+    /// [Dataset]: https://reference.wolfram.com/language/ref/Dataset.html
+    Dataset,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: b',',
+            has_headers: true,
+            infer_types: true,
+            output: CsvOutput::Lists,
+        }
+    }
+}
+
+impl Link {
+    /// Put the CSV data read from `reader` as an expression.
+    ///
+    /// Records are read and converted one at a time, so the CSV data is never parsed
+    /// into an in-memory table. Converted records are staged using [`ListWriter`] until
+    /// the total number of records is known.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{CsvOptions, Link};
+    ///
+    /// let csv = "name,score\nAlice,9.5\nBob,7\n";
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// // Put {{"name", "score"}, {"Alice", 9.5}, {"Bob", 7}}
+    /// link.put_csv_reader(csv.as_bytes(), &CsvOptions::default())
+    ///     .unwrap();
+    /// ```
+    pub fn put_csv_reader<R: Read>(
+        &mut self,
+        reader: R,
+        options: &CsvOptions,
+    ) -> Result<(), Error> {
+        let CsvOptions {
+            delimiter,
+            has_headers,
+            infer_types,
+            output,
+        } = *options;
+
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .has_headers(has_headers)
+            .from_reader(reader);
+
+        let mut record = csv::StringRecord::new();
+
+        match output {
+            CsvOutput::Lists => {
+                let mut records = ListWriter::new()?;
+
+                if has_headers {
+                    let headers = reader.headers().map_err(csv_error)?.clone();
+
+                    records.push(|link| put_csv_record(link, &headers, false))?;
+                }
+
+                while reader.read_record(&mut record).map_err(csv_error)? {
+                    records.push(|link| put_csv_record(link, &record, infer_types))?;
+                }
+
+                records.finish(self)
+            },
+            CsvOutput::Dataset => {
+                let columns: Vec<String> = if has_headers {
+                    let headers = reader.headers().map_err(csv_error)?;
+
+                    headers.iter().map(ToOwned::to_owned).collect()
+                } else {
+                    // Read the first record to determine the number of columns.
+                    let has_first = reader.read_record(&mut record).map_err(csv_error)?;
+
+                    let count = if has_first { record.len() } else { 0 };
+
+                    (1..=count).map(|index| index.to_string()).collect()
+                };
+
+                let mut dataset = DatasetWriter::new(&columns)?;
+
+                let mut pending = !has_headers && !columns.is_empty();
+
+                while pending || reader.read_record(&mut record).map_err(csv_error)? {
+                    pending = false;
+
+                    dataset.write_row(|row| {
+                        for field in record.iter() {
+                            put_csv_field(row, field, infer_types)?;
+                        }
+
+                        Ok(())
+                    })?;
+                }
+
+                dataset.finish(self)
+            },
+        }
+    }
+}
+
+fn put_csv_record(
+    link: &mut Link,
+    record: &csv::StringRecord,
+    infer_types: bool,
+) -> Result<(), Error> {
+    link.put_function("System`List", record.len())?;
+
+    for field in record.iter() {
+        put_csv_field(link, field, infer_types)?;
+    }
+
+    Ok(())
+}
+
+fn put_csv_field(link: &mut Link, field: &str, infer_types: bool) -> Result<(), Error> {
+    if infer_types {
+        if let Ok(integer) = field.parse::<i64>() {
+            return link.put_i64(integer);
+        }
+
+        // Note: Don't interpret strings like "inf" or "NaN" as reals.
+        if field.bytes().any(|byte| byte.is_ascii_digit()) {
+            if let Ok(real) = field.parse::<f64>() {
+                return link.put_f64(real);
+            }
+        }
+    }
+
+    link.put_str(field)
+}
+
+fn csv_error(err: csv::Error) -> Error {
+    Error::custom(format!("error reading CSV data: {}", err))
+}
@@ -0,0 +1,129 @@
+//! Incremental writing of [`Dataset`][Dataset] expressions.
+//!
+//! [Dataset]: https://reference.wolfram.com/language/ref/Dataset.html
+
+use crate::{Error, Link, ListWriter};
+
+/// Writer for a [`Dataset`][Dataset] expression, which is built up one row at a time.
+///
+/// The dataset is written as `Dataset[{<|col1 -> v11, ...|>, <|col1 -> v21, ...|>, ...}]`,
+/// where each row is an `Association` with the same keys. Rows are staged in their
+/// serialized WSTP form using [`ListWriter`], so large tables can be exported without
+/// constructing the full expression tree in memory.
+///
+/// # Example
+///
+/// ```
+/// use wstp::{DatasetWriter, Link};
+///
+/// let mut dataset = DatasetWriter::new(&["name", "age"]).unwrap();
+///
+/// dataset
+///     .write_row(|row| {
+///         row.put_str("Alice")?;
+///         row.put_i64(31)
+///     })
+///     .unwrap();
+///
+/// dataset
+///     .write_row(|row| {
+///         row.put_str("Bob")?;
+///         row.put_i64(27)
+///     })
+///     .unwrap();
+///
+/// let mut link = Link::new_loopback().unwrap();
+///
+/// // Put Dataset[{<|"name" -> "Alice", "age" -> 31|>, <|"name" -> "Bob", "age" -> 27|>}]
+/// dataset.finish(&mut link).unwrap();
+/// ```
+///
+/// [Dataset]: https://reference.wolfram.com/language/ref/Dataset.html
+#[derive(Debug)]
+pub struct DatasetWriter {
+    columns: Vec<String>,
+    rows: ListWriter,
+    /// Staging link for the column values of the row currently being written.
+    row: Link,
+}
+
+impl DatasetWriter {
+    /// Construct a new `DatasetWriter` whose rows have the specified column names.
+    pub fn new<S: AsRef<str>>(columns: &[S]) -> Result<Self, Error> {
+        Ok(DatasetWriter {
+            columns: columns.iter().map(|col| col.as_ref().to_owned()).collect(),
+            rows: ListWriter::new()?,
+            row: Link::new_loopback()?,
+        })
+    }
+
+    /// Get the column names of this dataset.
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// Get the number of rows written so far.
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Write a row to the dataset.
+    ///
+    /// `put_values` must put exactly one expression for each column, in the order the
+    /// columns were specified in [`DatasetWriter::new()`].
+    ///
+    /// If `put_values` returns an error, or an error is returned because it put the
+    /// wrong number of values, the contents of this `DatasetWriter` are unspecified,
+    /// and it should be discarded.
+    pub fn write_row<F>(&mut self, put_values: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut Link) -> Result<(), Error>,
+    {
+        let DatasetWriter { columns, rows, row } = self;
+
+        let row_index = rows.len();
+
+        let () = put_values(row)?;
+
+        rows.push(|link| {
+            link.put_function("System`Association", columns.len())?;
+
+            for (index, column) in columns.iter().enumerate() {
+                if !row.is_ready() {
+                    return Err(Error::custom(format!(
+                        "dataset row {} is missing a value for column {:?} (expected {} \
+                         values, got {})",
+                        row_index,
+                        column,
+                        columns.len(),
+                        index
+                    )));
+                }
+
+                link.put_function("System`Rule", 2)?;
+                link.put_str(column)?;
+                row.transfer_expr_to(link)?;
+            }
+
+            Ok(())
+        })?;
+
+        if row.is_ready() {
+            return Err(Error::custom(format!(
+                "dataset row {} has more values than the {} columns",
+                row_index,
+                columns.len()
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Put the completed `Dataset` expression onto `dest`.
+    pub fn finish(self, dest: &mut Link) -> Result<(), Error> {
+        dest.put_guarded(|dest| {
+            dest.put_function("System`Dataset", 1)?;
+            self.rows.finish(dest)
+        })
+    }
+}
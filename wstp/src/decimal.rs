@@ -0,0 +1,355 @@
+//! Exact transfer of decimal numbers.
+//!
+//! Decimal values are written as either exact rationals or arbitrary-precision reals,
+//! depending on the [`DecimalPolicy`] chosen by the caller. Unlike [`f64`], both
+//! encodings preserve every digit of the decimal value.
+
+use crate::{Error, Link, TokenType};
+
+/// Encoding used when putting a decimal value onto a [`Link`].
+///
+/// Decimal getters (e.g. [`Link::get_decimal()`]) accept either encoding.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DecimalPolicy {
+    /// Put the value as an exact [`Integer`][Integer] or [`Rational`][Rational].
+    ///
+    /// For example, `12.50` is put as `Rational[25, 2]`.
+    ///
+    /// [Integer]: https://reference.wolfram.com/language/ref/Integer.html
+    /// [Rational]: https://reference.wolfram.com/language/ref/Rational.html
+    ExactRational,
+    /// Put the value as an arbitrary-precision [`Real`][Real], with a precision equal to
+    /// the number of digits in the decimal value.
+    ///
+    /// For example, `12.50` is put as ``12.50`4``.
+    ///
+    /// [Real]: https://reference.wolfram.com/language/ref/Real.html
+    PrecisionReal,
+}
+
+/// Number read from a link in one of the forms produced by [`DecimalPolicy`].
+enum ExactNumber {
+    /// Integer digits.
+    Integer(String),
+    /// Real number, as decimal mantissa digits and a base 10 exponent.
+    Real { mantissa: String, exponent: i64 },
+    /// Numerator and denominator integer digits.
+    Rational(String, String),
+}
+
+impl Link {
+    /// Put a real number given as a sign, integer digits string, and base 10 exponent.
+    fn put_precision_real(
+        &mut self,
+        negative: bool,
+        digits: &str,
+        exponent: i64,
+    ) -> Result<(), Error> {
+        let sign = if negative { "-" } else { "" };
+
+        // E.g. 12.50 is written as "1250`4.*^-2".
+        let string = format!("{sign}{digits}`{}.*^{exponent}", digits.len());
+
+        self.put_real_number_str(&string)
+    }
+
+    fn get_exact_number(&mut self) -> Result<ExactNumber, Error> {
+        let number = match self.get_type()? {
+            TokenType::Integer => ExactNumber::Integer(self.get_number_string()?),
+            TokenType::Real => {
+                let string = self.get_number_string()?;
+                let (mantissa, exponent) = split_real_number_string(&string)?;
+
+                ExactNumber::Real {
+                    mantissa: mantissa.to_owned(),
+                    exponent,
+                }
+            },
+            TokenType::Function => {
+                let argc = self.test_head("System`Rational")?;
+
+                if argc != 2 {
+                    return Err(Error::custom(format!(
+                        "expected Rational expression to have 2 arguments, got {}",
+                        argc
+                    )));
+                }
+
+                let numerator = self.get_integer_digits()?;
+                let denominator = self.get_integer_digits()?;
+
+                ExactNumber::Rational(numerator, denominator)
+            },
+            other => {
+                return Err(Error::custom(format!(
+                    "expected Integer, Real, or Rational decimal value, got {:?}",
+                    other
+                )))
+            },
+        };
+
+        Ok(number)
+    }
+
+    fn get_integer_digits(&mut self) -> Result<String, Error> {
+        match self.get_type()? {
+            TokenType::Integer => self.get_number_string(),
+            other => Err(Error::custom(format!(
+                "expected Integer in Rational expression, got {:?}",
+                other
+            ))),
+        }
+    }
+}
+
+/// Split the WSTP textual form of a real number (e.g. ``"1.25`20.*^-3"``) into its
+/// mantissa digits and exponent, discarding any precision or accuracy mark.
+fn split_real_number_string(string: &str) -> Result<(&str, i64), Error> {
+    let (number, exponent) = match string.split_once("*^") {
+        Some((number, exponent)) => {
+            let exponent: i64 = exponent.parse().map_err(|err| {
+                Error::custom(format!(
+                    "invalid exponent in real number '{string}': {err}"
+                ))
+            })?;
+            (number, exponent)
+        },
+        None => (string, 0),
+    };
+
+    let mantissa = match number.split_once('`') {
+        Some((mantissa, _precision)) => mantissa,
+        None => number,
+    };
+
+    let mantissa = mantissa.strip_suffix('.').unwrap_or(mantissa);
+
+    if mantissa.is_empty() || mantissa.contains("^^") {
+        return Err(Error::custom(format!(
+            "unsupported real number format: '{string}'"
+        )));
+    }
+
+    Ok((mantissa, exponent))
+}
+
+/// Remove the factors of 2 and 5 shared by `numerator` and `10^scale`.
+///
+/// Returns the reduced numerator and the remaining powers of 2 and 5 in the denominator.
+macro_rules! reduce_decimal_fraction {
+    ($numerator:expr, $scale:expr, $zero:expr, $two:expr, $five:expr) => {{
+        let mut numerator = $numerator;
+        let mut twos = $scale;
+        let mut fives = $scale;
+
+        while twos > 0 && &numerator % &$two == $zero {
+            numerator = &numerator / &$two;
+            twos -= 1;
+        }
+
+        while fives > 0 && &numerator % &$five == $zero {
+            numerator = &numerator / &$five;
+            fives -= 1;
+        }
+
+        (numerator, twos, fives)
+    }};
+}
+
+//======================================
+// `rust_decimal` crate integration
+//======================================
+
+#[cfg(feature = "rust_decimal")]
+impl Link {
+    /// Put a [`rust_decimal::Decimal`] value, encoded according to `policy`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    /// use wstp::{DecimalPolicy, Link};
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// let price = Decimal::new(1250, 2);
+    ///
+    /// // Put 12.50 as Rational[25, 2]
+    /// link.put_decimal(&price, DecimalPolicy::ExactRational).unwrap();
+    ///
+    /// assert_eq!(link.get_decimal().unwrap(), price);
+    /// ```
+    pub fn put_decimal(
+        &mut self,
+        value: &rust_decimal::Decimal,
+        policy: DecimalPolicy,
+    ) -> Result<(), Error> {
+        let mantissa: i128 = value.mantissa();
+        let scale: u32 = value.scale();
+
+        match policy {
+            DecimalPolicy::ExactRational => {
+                let (numerator, twos, fives) =
+                    reduce_decimal_fraction!(mantissa, scale, 0, 2, 5);
+
+                if twos == 0 && fives == 0 {
+                    return self.put_i128(numerator);
+                }
+
+                // Note: `scale` is at most 28, so the denominator can't overflow.
+                let denominator: i128 = 2i128.pow(twos) * 5i128.pow(fives);
+
+                self.put_guarded(|link| {
+                    link.put_function("System`Rational", 2)?;
+                    link.put_i128(numerator)?;
+                    link.put_i128(denominator)
+                })
+            },
+            DecimalPolicy::PrecisionReal => self.put_precision_real(
+                mantissa < 0,
+                &mantissa.unsigned_abs().to_string(),
+                -i64::from(scale),
+            ),
+        }
+    }
+
+    /// Get a [`rust_decimal::Decimal`] value.
+    ///
+    /// The incoming expression may be an `Integer`, a `Real`, or a `Rational` whose
+    /// value has an exact decimal representation.
+    pub fn get_decimal(&mut self) -> Result<rust_decimal::Decimal, Error> {
+        use rust_decimal::Decimal;
+        use std::str::FromStr;
+
+        let parse = |digits: &str| {
+            Decimal::from_str(digits).map_err(|err| {
+                Error::custom(format!("unable to convert '{digits}' to Decimal: {err}"))
+            })
+        };
+
+        match self.get_exact_number()? {
+            ExactNumber::Integer(digits) => parse(&digits),
+            ExactNumber::Real { mantissa, exponent } => {
+                let string = format!("{mantissa}e{exponent}");
+
+                Decimal::from_scientific(&string).map_err(|err| {
+                    Error::custom(format!(
+                        "unable to convert '{string}' to Decimal: {err}"
+                    ))
+                })
+            },
+            ExactNumber::Rational(numerator, denominator) => {
+                let numerator = parse(&numerator)?;
+                let denominator = parse(&denominator)?;
+
+                match numerator.checked_div(denominator) {
+                    Some(quotient) if quotient * denominator == numerator => Ok(quotient),
+                    _ => Err(Error::custom(format!(
+                        "Rational[{numerator}, {denominator}] has no exact Decimal \
+                         representation"
+                    ))),
+                }
+            },
+        }
+    }
+
+    fn put_i128(&mut self, value: i128) -> Result<(), Error> {
+        match i64::try_from(value) {
+            Ok(value) => self.put_i64(value),
+            Err(_) => self.put_integer_digits(&value.to_string()),
+        }
+    }
+}
+
+//======================================
+// `bigdecimal` crate integration
+//======================================
+
+#[cfg(feature = "bigdecimal")]
+impl Link {
+    /// Put a [`bigdecimal::BigDecimal`] value, encoded according to `policy`.
+    pub fn put_big_decimal(
+        &mut self,
+        value: &bigdecimal::BigDecimal,
+        policy: DecimalPolicy,
+    ) -> Result<(), Error> {
+        use bigdecimal::num_bigint::{BigInt, Sign};
+
+        let (mantissa, scale): (BigInt, i64) = value.as_bigint_and_exponent();
+
+        match policy {
+            DecimalPolicy::ExactRational => {
+                if scale <= 0 {
+                    let value =
+                        mantissa * BigInt::from(10).pow(scale.unsigned_abs() as u32);
+                    return self.put_integer_digits(&value.to_string());
+                }
+
+                let (numerator, twos, fives) = reduce_decimal_fraction!(
+                    mantissa,
+                    scale,
+                    BigInt::from(0),
+                    BigInt::from(2),
+                    BigInt::from(5)
+                );
+
+                if twos == 0 && fives == 0 {
+                    return self.put_integer_digits(&numerator.to_string());
+                }
+
+                let denominator =
+                    BigInt::from(2).pow(twos as u32) * BigInt::from(5).pow(fives as u32);
+
+                self.put_guarded(|link| {
+                    link.put_function("System`Rational", 2)?;
+                    link.put_integer_digits(&numerator.to_string())?;
+                    link.put_integer_digits(&denominator.to_string())
+                })
+            },
+            DecimalPolicy::PrecisionReal => self.put_precision_real(
+                mantissa.sign() == Sign::Minus,
+                &mantissa.magnitude().to_string(),
+                -scale,
+            ),
+        }
+    }
+
+    /// Get a [`bigdecimal::BigDecimal`] value.
+    ///
+    /// The incoming expression may be an `Integer`, a `Real`, or a `Rational` whose
+    /// value has an exact decimal representation.
+    pub fn get_big_decimal(&mut self) -> Result<bigdecimal::BigDecimal, Error> {
+        use bigdecimal::BigDecimal;
+        use std::str::FromStr;
+
+        let parse = |digits: &str| {
+            BigDecimal::from_str(digits).map_err(|err| {
+                Error::custom(format!(
+                    "unable to convert '{digits}' to BigDecimal: {err}"
+                ))
+            })
+        };
+
+        match self.get_exact_number()? {
+            ExactNumber::Integer(digits) => parse(&digits),
+            ExactNumber::Real { mantissa, exponent } => {
+                parse(&format!("{mantissa}e{exponent}"))
+            },
+            ExactNumber::Rational(numerator, denominator) => {
+                let numerator = parse(&numerator)?;
+                let denominator = parse(&denominator)?;
+
+                let quotient = &numerator / &denominator;
+
+                if &quotient * &denominator != numerator {
+                    return Err(Error::custom(format!(
+                        "Rational[{numerator}, {denominator}] has no exact BigDecimal \
+                         representation"
+                    )));
+                }
+
+                Ok(quotient)
+            },
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! Routing of incoming expressions to handlers based on their head.
+
+use std::fmt::{self, Debug};
+
+use crate::{sys, Error, Link, TokenType};
+
+type Handler<'h> = Box<dyn FnMut(&mut Link) -> Result<(), Error> + 'h>;
+
+/// Routes incoming expressions to handlers registered for their head symbol.
+///
+/// Handlers are registered using [`Dispatcher::on()`], [`Dispatcher::on_arity()`],
+/// and [`Dispatcher::on_args()`]. When an expression is dispatched, the handlers are
+/// tried in the order they were registered, and the first handler whose pattern
+/// matches the expression is called. Expressions that don't match any handler are
+/// passed to the [default handler][Dispatcher::on_default], if one is registered.
+///
+/// The incoming expression is matched by [peeking][Link::peek] at it, so handlers
+/// are called with the link positioned at the start of the full expression, and must
+/// read all of it from the link.
+///
+/// # Example
+///
+/// ```
+/// use wstp::{Dispatcher, Link, TokenType};
+///
+/// let mut link = Link::new_loopback().unwrap();
+///
+/// // Put Add[2, 3], then Echo["hello"]
+/// link.put_function("Global`Add", 2).unwrap();
+/// link.put_i64(2).unwrap();
+/// link.put_i64(3).unwrap();
+/// link.put_function("Global`Echo", 1).unwrap();
+/// link.put_str("hello").unwrap();
+///
+/// let mut sums = Vec::new();
+/// let mut echoes = Vec::new();
+///
+/// let mut dispatcher = Dispatcher::new();
+///
+/// dispatcher
+///     .on_args("Global`Add", &[TokenType::Integer, TokenType::Integer], |link| {
+///         let _ = link.test_head("Global`Add")?;
+///         sums.push(link.get_i64()? + link.get_i64()?);
+///         Ok(())
+///     })
+///     .on_arity("Global`Echo", 1, |link| {
+///         let _ = link.test_head("Global`Echo")?;
+///         echoes.push(link.get_string()?);
+///         Ok(())
+///     });
+///
+/// dispatcher.run(&mut link).unwrap();
+///
+/// drop(dispatcher);
+///
+/// assert_eq!(sums, vec![5]);
+/// assert_eq!(echoes, vec!["hello"]);
+/// ```
+pub struct Dispatcher<'h> {
+    routes: Vec<Route<'h>>,
+    default: Option<Handler<'h>>,
+}
+
+struct Route<'h> {
+    head: String,
+    pattern: ArgPattern,
+    handler: Handler<'h>,
+}
+
+#[derive(Debug)]
+enum ArgPattern {
+    Any,
+    Arity(usize),
+    Types(Vec<TokenType>),
+}
+
+impl<'h> Dispatcher<'h> {
+    /// Construct a new `Dispatcher` with no registered handlers.
+    pub fn new() -> Self {
+        Dispatcher {
+            routes: Vec::new(),
+            default: None,
+        }
+    }
+
+    /// Register a handler for expressions with head `head`, with any number of
+    /// arguments.
+    ///
+    /// `head` must be a fully qualified symbol name, e.g. ``"Global`foo"``.
+    pub fn on<F>(&mut self, head: &str, handler: F) -> &mut Self
+    where
+        F: FnMut(&mut Link) -> Result<(), Error> + 'h,
+    {
+        self.add_route(head, ArgPattern::Any, Box::new(handler))
+    }
+
+    /// Register a handler for expressions with head `head` and exactly `arity`
+    /// arguments.
+    pub fn on_arity<F>(&mut self, head: &str, arity: usize, handler: F) -> &mut Self
+    where
+        F: FnMut(&mut Link) -> Result<(), Error> + 'h,
+    {
+        self.add_route(head, ArgPattern::Arity(arity), Box::new(handler))
+    }
+
+    /// Register a handler for expressions with head `head` whose arguments have the
+    /// token types listed in `arg_types`.
+    ///
+    /// An argument matches [`TokenType::Function`] if it is any non-atomic
+    /// expression.
+    pub fn on_args<F>(
+        &mut self,
+        head: &str,
+        arg_types: &[TokenType],
+        handler: F,
+    ) -> &mut Self
+    where
+        F: FnMut(&mut Link) -> Result<(), Error> + 'h,
+    {
+        self.add_route(
+            head,
+            ArgPattern::Types(arg_types.to_vec()),
+            Box::new(handler),
+        )
+    }
+
+    /// Register a handler for expressions that don't match any other handler.
+    ///
+    /// If a default handler was already registered, it is replaced.
+    pub fn on_default<F>(&mut self, handler: F) -> &mut Self
+    where
+        F: FnMut(&mut Link) -> Result<(), Error> + 'h,
+    {
+        self.default = Some(Box::new(handler));
+        self
+    }
+
+    fn add_route(
+        &mut self,
+        head: &str,
+        pattern: ArgPattern,
+        handler: Handler<'h>,
+    ) -> &mut Self {
+        self.routes.push(Route {
+            head: head.to_owned(),
+            pattern,
+            handler,
+        });
+        self
+    }
+
+    /// Read the next expression from `link` and pass it to the matching handler.
+    ///
+    /// If no handler matches the expression and no default handler is registered, the
+    /// expression is discarded and an error is returned.
+    ///
+    /// The result of the handler is returned.
+    pub fn dispatch(&mut self, link: &mut Link) -> Result<(), Error> {
+        let head: Option<(String, usize)> = link.peek(peek_head)?;
+
+        let mut matched = None;
+
+        if let Some((ref head, argc)) = head {
+            for (index, route) in self.routes.iter().enumerate() {
+                if route.head != *head {
+                    continue;
+                }
+
+                let is_match = match route.pattern {
+                    ArgPattern::Any => true,
+                    ArgPattern::Arity(arity) => arity == argc,
+                    ArgPattern::Types(ref types) => {
+                        types.len() == argc
+                            && link.peek(|link| args_match(link, types))?
+                    },
+                };
+
+                if is_match {
+                    matched = Some(index);
+                    break;
+                }
+            }
+        }
+
+        if let Some(index) = matched {
+            return (self.routes[index].handler)(link);
+        }
+
+        if let Some(ref mut default) = self.default {
+            return default(link);
+        }
+
+        let description = match head {
+            Some((head, argc)) => format!("{}[...] with {} arguments", head, argc),
+            None => format!("{:?} expression", link.get_type()?),
+        };
+
+        link.skip_expr()?;
+
+        Err(Error::custom(format!(
+            "no handler registered for incoming {}",
+            description
+        )))
+    }
+
+    /// Dispatch incoming expressions from `link` until the link is closed.
+    ///
+    /// If `link` is a [loopback link][Link::new_loopback], expressions are dispatched
+    /// until it is empty.
+    ///
+    /// If [`Dispatcher::dispatch()`] returns an error for an expression, processing
+    /// stops, and that error is returned.
+    pub fn run(&mut self, link: &mut Link) -> Result<(), Error> {
+        loop {
+            if link.is_loopback() && !link.is_ready() {
+                return Ok(());
+            }
+
+            match self.dispatch(link) {
+                Ok(()) => (),
+                Err(err) if err.code() == Some(sys::WSECLOSED) => return Ok(()),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+impl<'h> Default for Dispatcher<'h> {
+    fn default() -> Self {
+        Dispatcher::new()
+    }
+}
+
+impl<'h> Debug for Dispatcher<'h> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Dispatcher { routes, default } = self;
+
+        let routes: Vec<(&str, &ArgPattern)> = routes
+            .iter()
+            .map(|route| (route.head.as_str(), &route.pattern))
+            .collect();
+
+        f.debug_struct("Dispatcher")
+            .field("routes", &routes)
+            .field("has_default", &default.is_some())
+            .finish()
+    }
+}
+
+/// Read the head symbol and argument count of the next expression, if it is a
+/// function with a symbol head.
+pub(crate) fn peek_head(link: &mut Link) -> Result<Option<(String, usize)>, Error> {
+    if link.get_type()? != TokenType::Function {
+        return Ok(None);
+    }
+
+    let argc = link.get_arg_count()?;
+
+    if link.get_type()? != TokenType::Symbol {
+        return Ok(None);
+    }
+
+    let head = link.get_symbol_ref()?.as_str().to_owned();
+
+    Ok(Some((head, argc)))
+}
+
+fn args_match(link: &mut Link, types: &[TokenType]) -> Result<bool, Error> {
+    let _ = link.get_arg_count()?;
+
+    // Skip the head.
+    link.skip_expr()?;
+
+    for type_ in types {
+        if link.get_type()? != *type_ {
+            return Ok(false);
+        }
+
+        link.skip_expr()?;
+    }
+
+    Ok(true)
+}
@@ -26,7 +26,10 @@
 
 use std::{
     ops::Deref,
-    sync::{Mutex, MutexGuard},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, MutexGuard,
+    },
 };
 
 use once_cell::sync::Lazy;
@@ -39,6 +42,22 @@ use crate::{sys, Error};
 static STDENV: Lazy<Mutex<Option<WstpEnv>>> =
     Lazy::new(|| Mutex::new(Some(initialize().unwrap())));
 
+/// Whether [`initialize()`] has been called.
+static INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+/// Whether [`disable_signal_handlers()`] has been called.
+static DISABLE_SIGNAL_HANDLERS: Mutex<bool> = Mutex::new(false);
+
+/// Encoding set using [`set_text_encoding()`].
+static TEXT_ENCODING: Mutex<Option<TextEncoding>> = Mutex::new(None);
+
+/// Signals that WSTP installs handlers for by default, and which
+/// [`disable_signal_handlers()`] prevents WSTP from handling.
+///
+/// These signal numbers are the same on every platform supported by WSTP.
+const HANDLED_SIGNALS: [(i32, &str); 3] =
+    [(2, "SIGINT"), (11, "SIGSEGV"), (15, "SIGTERM")];
+
 /// Private. A WSTP library environment.
 ///
 /// NOTE: This function should remain private. See note on [`crate::env`].
@@ -76,10 +95,67 @@ impl Deref for StdEnv {
 fn initialize() -> Result<WstpEnv, Error> {
     let raw_env: sys::WSENV;
 
+    let (disable_signal_handlers, text_encoding) = {
+        let disable = DISABLE_SIGNAL_HANDLERS.lock().map_err(|err| {
+            Error::custom(format!(
+                "Unable to acquire lock on DISABLE_SIGNAL_HANDLERS: {}",
+                err
+            ))
+        })?;
+        let encoding = TEXT_ENCODING.lock().map_err(|err| {
+            Error::custom(format!("Unable to acquire lock on TEXT_ENCODING: {}", err))
+        })?;
+
+        INITIALIZED.store(true, Ordering::SeqCst);
+
+        (*disable, *encoding)
+    };
+
     // TODO: Is this thread-safe?
     //       Is it safe to call WSInitialize() multiple times in the same process?
     unsafe {
-        raw_env = sys::WSInitialize(std::ptr::null_mut());
+        if disable_signal_handlers || text_encoding.is_some() {
+            let params = sys::WSNewParameters(
+                sys::WSREVISION as std::os::raw::c_ulong,
+                sys::WSAPIREVISION as std::os::raw::c_ulong,
+            );
+
+            if params.is_null() {
+                return Err(Error::custom("WSNewParameters() failed".to_owned()));
+            }
+
+            if disable_signal_handlers {
+                for (signum, name) in HANDLED_SIGNALS {
+                    if sys::WSDoNotHandleSignalParameter(params, signum) != 0 {
+                        sys::WSReleaseParameters(params);
+
+                        return Err(Error::custom(format!(
+                            "WSDoNotHandleSignalParameter() failed for {}",
+                            name
+                        )));
+                    }
+                }
+            }
+
+            if let Some(encoding) = text_encoding {
+                let code = encoding.code() as std::os::raw::c_uint;
+
+                if sys::WSSetEncodingParameter(params, code) != 0 {
+                    sys::WSReleaseParameters(params);
+
+                    return Err(Error::custom(format!(
+                        "WSSetEncodingParameter() failed for {:?}",
+                        encoding
+                    )));
+                }
+            }
+
+            raw_env = sys::WSInitialize(params);
+
+            sys::WSReleaseParameters(params);
+        } else {
+            raw_env = sys::WSInitialize(std::ptr::null_mut());
+        }
     }
 
     if raw_env.is_null() {
@@ -93,6 +169,135 @@ fn initialize() -> Result<WstpEnv, Error> {
 }
 
 
+/// Prevent WSTP from installing handlers for the `SIGINT`, `SIGSEGV`, and `SIGTERM`
+/// signals.
+///
+/// By default, the WSTP library installs its own handlers for these signals when it
+/// is initialized. This can interfere with Rust's handling of stack overflows, crates
+/// that handle Ctrl-C, and debuggers.
+///
+/// The WSTP library is initialized by this crate the first time a link is created.
+/// This function must be called before that, or an error is returned.
+///
+/// # Example
+///
+/// ```
+/// // Call this at the start of main(), before any links are created.
+/// wstp::disable_signal_handlers().unwrap();
+///
+/// let link = wstp::Link::new_loopback().unwrap();
+///
+/// // Once the WSTP library has been initialized, it's too late to change how
+/// // signals are handled.
+/// assert!(wstp::disable_signal_handlers().is_err());
+/// ```
+///
+/// *WSTP C API Documentation:* [`WSDoNotHandleSignalParameter()`](https://reference.wolfram.com/language/ref/c/WSDoNotHandleSignalParameter.html)
+pub fn disable_signal_handlers() -> Result<(), Error> {
+    let mut disable = DISABLE_SIGNAL_HANDLERS.lock().map_err(|err| {
+        Error::custom(format!(
+            "Unable to acquire lock on DISABLE_SIGNAL_HANDLERS: {}",
+            err
+        ))
+    })?;
+
+    if INITIALIZED.load(Ordering::SeqCst) {
+        return Err(Error::custom(
+            "signal handlers cannot be disabled after the WSTP library has been \
+             initialized"
+                .to_owned(),
+        ));
+    }
+
+    *disable = true;
+
+    Ok(())
+}
+
+/// Character encoding used by the WSTP library for text sent over links.
+///
+/// See [`set_text_encoding()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TextEncoding {
+    /// 7-bit ASCII.
+    Ascii,
+    /// Raw bytes, without any interpretation.
+    Bytes,
+    /// UCS-2, the encoding used internally by older versions of WSTP.
+    Ucs2,
+    /// UTF-8.
+    Utf8,
+    /// UTF-16.
+    Utf16,
+    /// UTF-32.
+    Utf32,
+}
+
+impl TextEncoding {
+    /// Get the WSTP encoding code of this encoding.
+    fn code(self) -> i32 {
+        match self {
+            TextEncoding::Ascii => sys::MLASCII_ENC,
+            TextEncoding::Bytes => sys::MLBYTES_ENC,
+            TextEncoding::Ucs2 => sys::MLUCS2_ENC,
+            TextEncoding::Utf8 => sys::MLUTF8_ENC,
+            TextEncoding::Utf16 => sys::MLUTF16_ENC,
+            TextEncoding::Utf32 => sys::MLUTF32_ENC,
+        }
+    }
+}
+
+/// Set the character encoding used by the WSTP library for text sent over links.
+///
+/// Setting [`TextEncoding::Utf8`] avoids converting strings to and from UCS-2 inside
+/// the WSTP library, which preserves code points outside of the Basic Multilingual
+/// Plane, and improves the throughput of string-heavy traffic. The string methods of
+/// [`Link`][crate::Link], like [`Link::put_str()`][crate::Link::put_str] and
+/// [`Link::get_string()`][crate::Link::get_string], already transfer UTF-8 data.
+///
+/// The WSTP library is initialized by this crate the first time a link is created.
+/// This function must be called before that, or an error is returned. The encoding
+/// applies to every link created afterwards. WSTP does not provide a way to query the
+/// encoding negotiated with the peer of an individual link.
+///
+/// # Example
+///
+/// ```
+/// use wstp::TextEncoding;
+///
+/// // Call this at the start of main(), before any links are created.
+/// wstp::set_text_encoding(TextEncoding::Utf8).unwrap();
+///
+/// assert_eq!(wstp::text_encoding(), Some(TextEncoding::Utf8));
+/// ```
+///
+/// *WSTP C API Documentation:* [`WSSetEncodingParameter()`](https://reference.wolfram.com/language/ref/c/WSSetEncodingParameter.html)
+pub fn set_text_encoding(encoding: TextEncoding) -> Result<(), Error> {
+    let mut text_encoding = TEXT_ENCODING.lock().map_err(|err| {
+        Error::custom(format!("Unable to acquire lock on TEXT_ENCODING: {}", err))
+    })?;
+
+    if INITIALIZED.load(Ordering::SeqCst) {
+        return Err(Error::custom(
+            "text encoding cannot be set after the WSTP library has been initialized"
+                .to_owned(),
+        ));
+    }
+
+    *text_encoding = Some(encoding);
+
+    Ok(())
+}
+
+/// Get the character encoding set using [`set_text_encoding()`].
+///
+/// Returns `None` if no encoding has been set, in which case the WSTP library uses
+/// its default encoding.
+pub fn text_encoding() -> Option<TextEncoding> {
+    TEXT_ENCODING.lock().map_or(None, |encoding| *encoding)
+}
+
 /// Deinitialize the [`WSENV`] static maintained by this library.
 ///
 /// Ideally, this function would not be necessary. However, the WSTP C library internally
@@ -4,6 +4,8 @@ use std::{
     os::raw::c_char,
 };
 
+use crate::InterfaceVersion;
+
 /// WSTP link error.
 ///
 /// Use [`Error::code()`] to retrieve the WSTP error code, if applicable.
@@ -11,6 +13,25 @@ use std::{
 pub struct Error {
     pub(crate) code: Option<i32>,
     pub(crate) message: String,
+    pub(crate) poisoned: bool,
+}
+
+/// How an operation that failed with an [`Error`] can be recovered from.
+///
+/// Returned by [`Error::recovery()`]. Retry loops and connection pools can use this to
+/// decide whether to retry an operation, replace the link, or give up.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorRecovery {
+    /// The link is still usable once the error is cleared using
+    /// [`Link::clear_error()`][crate::Link::clear_error].
+    ClearAndRetry,
+    /// The connection has failed, or could not be established. The link should be
+    /// closed, and a new connection made.
+    Reconnect,
+    /// The error is not expected to go away by retrying, e.g. because memory is
+    /// exhausted, or the link was created with invalid options.
+    Fatal,
 }
 
 impl Error {
@@ -22,10 +43,110 @@ impl Error {
         self.code
     }
 
+    /// Returns `true` if this error indicates that the peer of the link closed it, or
+    /// that the link has died.
+    ///
+    /// This is the case for the [`WSECLOSED`][crate::sys::WSECLOSED] and
+    /// [`WSEDEAD`][crate::sys::WSEDEAD] error codes. Other errors, like a protocol
+    /// error caused by reading the wrong type of data, leave the link usable.
+    ///
+    /// See also [`Link::is_open()`][crate::Link::is_open].
+    pub fn is_peer_closed(&self) -> bool {
+        matches!(self.code, Some(crate::sys::WSECLOSED | crate::sys::WSEDEAD))
+    }
+
+    /// Returns `true` if this error was returned because the link is
+    /// [poisoned][crate::Link::is_poisoned].
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// Classify how an operation that failed with this error can be recovered from.
+    ///
+    /// See [`ErrorRecovery`] for the possible classifications. Errors that don't have
+    /// a WSTP error code, like a value that can't be represented in the expected type,
+    /// don't affect the link, and are [`ErrorRecovery::ClearAndRetry`]. Errors caused by
+    /// a [poisoned][crate::Link::is_poisoned] link are [`ErrorRecovery::Reconnect`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{ErrorRecovery, Link};
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_str("not an integer").unwrap();
+    ///
+    /// let err = link.get_i64().unwrap_err();
+    ///
+    /// assert_eq!(err.recovery(), ErrorRecovery::ClearAndRetry);
+    /// ```
+    pub fn recovery(&self) -> ErrorRecovery {
+        use crate::sys::*;
+
+        if self.poisoned {
+            return ErrorRecovery::Reconnect;
+        }
+
+        let code = match self.code {
+            Some(code) => code,
+            None => return ErrorRecovery::ClearAndRetry,
+        };
+
+        match code {
+            // Errors caused by reading or writing the wrong kind of data, which leave
+            // the connection intact.
+            WSEGBAD | WSEGSEQ | WSEPBTK | WSEPSEQ | WSEPBIG | WSEOVFL | WSEDEPTH
+            | WSENODATA | WSENOMSG | WSENOTDELIVERED | WSEGETENDEXPR
+            | WSEPUTENDPACKET | WSENEXTPACKET | WSEUNKNOWNPACKET | WSEGETENDPACKET
+            | WSEABORT | WSEMORE | WSEBADPARAM | WSEPDATABAD | WSEPSCONVERT
+            | WSEGSCONVERT | WSENEEDBIGGERBUFFER | WSEBADNUMERICSID | WSEBADARGUMENT => {
+                ErrorRecovery::ClearAndRetry
+            },
+            // Errors caused by the connection failing or not being established, which
+            // may succeed with a new link.
+            WSEDEAD
+            | WSECLOSED
+            | WSEACCEPT
+            | WSECONNECT
+            | WSENOACK
+            | WSENOLISTEN
+            | WSENAMETAKEN
+            | WSELAUNCH
+            | WSELAUNCHAGAIN
+            | WSELAUNCHFAILED
+            | WSENOPARENT
+            | WSERESOURCE
+            | WSESERVICENOTAVAILABLE => ErrorRecovery::Reconnect,
+            // Out of memory, library version mismatches, invalid link options, and
+            // unknown errors.
+            _ => ErrorRecovery::Fatal,
+        }
+    }
+
+    /// Returns `true` if the link can be used again after this error is cleared using
+    /// [`Link::clear_error()`][crate::Link::clear_error].
+    ///
+    /// This is the case if [`Error::recovery()`] is [`ErrorRecovery::ClearAndRetry`].
+    pub fn is_recoverable(&self) -> bool {
+        self.recovery() == ErrorRecovery::ClearAndRetry
+    }
+
     pub(crate) fn custom(message: String) -> Self {
         Error {
             code: None,
             message,
+            poisoned: false,
+        }
+    }
+
+    pub(crate) fn poisoned() -> Self {
+        Error {
+            code: None,
+            message: "link is poisoned: an expression was abandoned partway through \
+                      being put on it"
+                .to_owned(),
+            poisoned: true,
         }
     }
 
@@ -54,13 +175,18 @@ impl Error {
         Error {
             code: Some(code),
             message,
+            poisoned: false,
         }
     }
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let Error { code, message } = self;
+        let Error {
+            code,
+            message,
+            poisoned: _,
+        } = self;
 
         if let Some(code) = code {
             write!(f, "WSTP error (code {}): {}", code, message)
@@ -78,3 +204,156 @@ impl Debug for Error {
 }
 
 impl std::error::Error for Error {}
+
+/// Error returned by [`Link::try_activate()`][crate::Link::try_activate].
+///
+/// In addition to the underlying [`Error`], this records the
+/// [stage of the handshake][HandshakeStage] between the link end points that failed,
+/// and the [`InterfaceVersion`] of the local WSTP library, to help diagnose
+/// connections between incompatible WSTP versions.
+///
+/// WSTP does not report the interface version of the peer, and the recorded
+/// [`local_version()`][ActivateError::local_version] is not a version negotiated with
+/// the peer. If the two libraries are
+/// incompatible, [`ActivateError::stage()`] is [`HandshakeStage::VersionCheck`], and the
+/// error code indicates whether the library used by the peer is newer
+/// ([`WSENEWLIB`][crate::sys::WSENEWLIB]) or older ([`WSEOLDLIB`][crate::sys::WSEOLDLIB]).
+///
+/// The handshake diagnostics are included in the [`Display`] output of an
+/// [`ActivateError`]. Use [`ActivateError::into_error()`] to get the underlying
+/// [`Error`].
+#[derive(Clone, PartialEq)]
+pub struct ActivateError {
+    error: Error,
+    stage: HandshakeStage,
+    local_version: Option<InterfaceVersion>,
+}
+
+/// Stage of the handshake performed by
+/// [`Link::try_activate()`][crate::Link::try_activate]
+/// that failed.
+///
+/// Returned by [`ActivateError::stage()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum HandshakeStage {
+    /// The connection to the peer could not be established, or was lost.
+    Connect,
+    /// The peer did not respond as expected by the link protocol or mode, e.g. because
+    /// both end points were created in the same mode.
+    ProtocolNegotiation,
+    /// The peer uses a version of the WSTP library that is incompatible with the local
+    /// library.
+    VersionCheck,
+    /// The stage could not be determined from the error.
+    Unknown,
+}
+
+impl ActivateError {
+    pub(crate) fn new(error: Error) -> Self {
+        ActivateError {
+            stage: HandshakeStage::from_error(&error),
+            local_version: InterfaceVersion::linked().ok(),
+            error,
+        }
+    }
+
+    /// Get the underlying link error.
+    pub fn error(&self) -> &Error {
+        &self.error
+    }
+
+    /// Get the underlying link error, discarding the handshake diagnostics.
+    pub fn into_error(self) -> Error {
+        self.error
+    }
+
+    /// Get the WSTP error code, if applicable.
+    ///
+    /// See [`Error::code()`].
+    pub fn code(&self) -> Option<i32> {
+        self.error.code()
+    }
+
+    /// Get the stage of the handshake that failed.
+    pub fn stage(&self) -> HandshakeStage {
+        self.stage
+    }
+
+    /// Get the interface version of the local WSTP library, if it could be
+    /// determined.
+    ///
+    /// This is the same as [`InterfaceVersion::linked()`]; it is *not* the version
+    /// used by the peer, which WSTP does not report.
+    pub fn local_version(&self) -> Option<InterfaceVersion> {
+        self.local_version
+    }
+
+    /// Describe the failed stage and the versions of the WSTP libraries involved.
+    fn diagnostics(&self) -> String {
+        let peer = match self.error.code {
+            Some(crate::sys::WSENEWLIB) => "peer WSTP library is newer",
+            Some(crate::sys::WSEOLDLIB) => "peer WSTP library is older",
+            _ => "peer WSTP version unknown",
+        };
+
+        let local = match self.local_version {
+            Some(version) => format!("local WSTP {}", version),
+            None => "local WSTP version unknown".to_owned(),
+        };
+
+        format!(
+            "link activation failed during {} ({}; {})",
+            self.stage, peer, local
+        )
+    }
+}
+
+impl HandshakeStage {
+    fn from_error(error: &Error) -> Self {
+        use crate::sys::*;
+
+        match error.code {
+            Some(
+                WSEDEAD | WSECLOSED | WSEACCEPT | WSECONNECT | WSENOLISTEN | WSEBADHOST
+                | WSEBADNAME | WSENOPARENT | WSERESOURCE,
+            ) => HandshakeStage::Connect,
+            Some(WSEPROTOCOL | WSEMODE | WSENOACK | WSEPDATABAD) => {
+                HandshakeStage::ProtocolNegotiation
+            },
+            Some(WSENEWLIB | WSEOLDLIB) => HandshakeStage::VersionCheck,
+            _ => HandshakeStage::Unknown,
+        }
+    }
+}
+
+impl Display for HandshakeStage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let stage = match self {
+            HandshakeStage::Connect => "connection",
+            HandshakeStage::ProtocolNegotiation => "protocol negotiation",
+            HandshakeStage::VersionCheck => "version check",
+            HandshakeStage::Unknown => "unknown stage",
+        };
+
+        f.write_str(stage)
+    }
+}
+
+impl Display for ActivateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.diagnostics(), self.error)
+    }
+}
+
+impl Debug for ActivateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl std::error::Error for ActivateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
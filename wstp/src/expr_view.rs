@@ -0,0 +1,385 @@
+//! Zero-copy views of the expressions available on a link.
+
+use std::mem::ManuallyDrop;
+
+use wolfram_expr::{symbol::SymbolRef, Expr};
+
+use crate::{sys, Array, Error, Link, LinkStr, TokenType};
+
+/// Expression read from a link whose atoms borrow the data owned by the link.
+///
+/// Returned by [`Link::get_expr_view()`].
+///
+/// Strings, symbols, and numeric arrays in an `ExprView` refer directly to the buffers
+/// allocated by WSTP, which are released when the view is dropped. No string or array
+/// data is copied until [`ExprView::to_owned()`] is called.
+#[derive(Debug)]
+pub enum ExprView<'link> {
+    /// Machine-sized integer.
+    Integer(i64),
+    /// Machine-sized real. This is never NaN.
+    Real(f64),
+    /// String, borrowed from the link.
+    String(LinkStr<'link>),
+    /// Symbol with a context, borrowed from the link.
+    Symbol(LinkStr<'link>),
+    /// Rectangular `List` array of integers, borrowed from the link.
+    Int64Array(Array<'link, i64>),
+    /// Rectangular `List` array of reals, borrowed from the link.
+    Real64Array(Array<'link, f64>),
+    /// Normal expression.
+    Normal {
+        /// Head of the expression.
+        head: Box<ExprView<'link>>,
+        /// Arguments of the expression.
+        args: Vec<ExprView<'link>>,
+    },
+}
+
+/// Expression type that can be put onto a link by reference, using
+/// [`Link::put_expr_ref()`].
+///
+/// This trait is implemented for [`Expr`] and [`ExprView`], and references to them.
+///
+/// This trait is sealed and cannot be implemented outside of `wstp`.
+pub trait AsExprView: private::Sealed {}
+
+/// # Zero-copy expressions
+impl Link {
+    /// Read the next expression on this link as an [`ExprView`], without copying its
+    /// string, symbol, or numeric array data.
+    ///
+    /// A `List` expression is read as an [`ExprView::Int64Array`] or
+    /// [`ExprView::Real64Array`] if it is a non-empty rectangular array whose
+    /// elements are all machine integers or all machine reals. Every other function
+    /// expression is read as an [`ExprView::Normal`].
+    ///
+    /// Like [`Link::get_expr()`], this returns an error if the expression contains a
+    /// NaN real value or a symbol without a context.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{ExprView, Link};
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// // Put Global`f["text", {1, 2, 3}]
+    /// link.put_function("Global`f", 2).unwrap();
+    /// link.put_str("text").unwrap();
+    /// link.put_i64_array(&[1, 2, 3], &[3]).unwrap();
+    ///
+    /// let view = link.get_expr_view().unwrap();
+    ///
+    /// match &view {
+    ///     ExprView::Normal { args, .. } => match &args[..] {
+    ///         [ExprView::String(string), ExprView::Int64Array(array)] => {
+    ///             assert_eq!(string.as_str(), "text");
+    ///             assert_eq!(array.data(), &[1, 2, 3]);
+    ///         },
+    ///         _ => panic!("unexpected arguments"),
+    ///     },
+    ///     _ => panic!("expected a normal expression"),
+    /// }
+    ///
+    /// assert_eq!(view.to_owned().to_string(), "Global`f[\"text\", {1, 2, 3}]");
+    /// ```
+    pub fn get_expr_view(&mut self) -> Result<ExprView<'_>, Error> {
+        let link: &Link = self;
+
+        // Reads that don't borrow link data are made through `reader`, which refers to
+        // the same WSLINK as `link`, but is never closed.
+        let mut reader = ManuallyDrop::new(unsafe { Link::unchecked_new(link.raw_link) });
+
+        read_view(link, &mut reader)
+    }
+
+    /// Put an expression, without copying or cloning any part of it.
+    ///
+    /// [`Expr`] values are put by walking the expression tree by reference, exactly like
+    /// [`Link::put_expr()`]. [`ExprView`] values are put directly from the buffers
+    /// they borrow from the link they were read from, so an expression can be forwarded
+    /// from one link to another without first converting it to an owned [`Expr`]
+    /// using [`ExprView::to_owned()`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut source = Link::new_loopback().unwrap();
+    /// let mut dest = Link::new_loopback().unwrap();
+    ///
+    /// // Put Global`f["text", {1, 2, 3}]
+    /// source.put_function("Global`f", 2).unwrap();
+    /// source.put_str("text").unwrap();
+    /// source.put_i64_array(&[1, 2, 3], &[3]).unwrap();
+    ///
+    /// let view = source.get_expr_view().unwrap();
+    ///
+    /// dest.put_expr_ref(&view).unwrap();
+    ///
+    /// assert_eq!(dest.get_expr().unwrap(), view.to_owned());
+    /// ```
+    pub fn put_expr_ref<E: AsExprView + ?Sized>(
+        &mut self,
+        expr: &E,
+    ) -> Result<(), Error> {
+        self.put_guarded(|link| expr.put(link))
+    }
+}
+
+impl<'link> ExprView<'link> {
+    /// Construct an owned [`Expr`] from this view, copying its data.
+    pub fn to_owned(&self) -> Expr {
+        match self {
+            ExprView::Integer(value) => Expr::from(*value),
+            ExprView::Real(value) => Expr::real(*value),
+            ExprView::String(string) => Expr::string(string.as_str()),
+            ExprView::Symbol(symbol) => {
+                // This cannot fail, because the symbol was checked by `read_view()`.
+                let symbol = SymbolRef::try_new(symbol.as_str())
+                    .expect("ExprView symbol has no context");
+
+                Expr::symbol(symbol.to_symbol())
+            },
+            ExprView::Int64Array(array) => {
+                array_to_expr(array.data(), array.dimensions(), &|value| {
+                    Expr::from(value)
+                })
+            },
+            ExprView::Real64Array(array) => {
+                array_to_expr(array.data(), array.dimensions(), &Expr::real)
+            },
+            ExprView::Normal { head, args } => Expr::normal(
+                ExprView::to_owned(head),
+                args.iter().map(ExprView::to_owned).collect(),
+            ),
+        }
+    }
+}
+
+//======================================
+// AsExprView impls
+//======================================
+
+mod private {
+    use crate::{Error, Link};
+
+    pub trait Sealed {
+        /// Put this expression onto `link`.
+        fn put(&self, link: &mut Link) -> Result<(), Error>;
+    }
+}
+
+impl AsExprView for Expr {}
+
+impl private::Sealed for Expr {
+    fn put(&self, link: &mut Link) -> Result<(), Error> {
+        link.put_expr(self)
+    }
+}
+
+impl<'link> AsExprView for ExprView<'link> {}
+
+impl<'link> private::Sealed for ExprView<'link> {
+    fn put(&self, link: &mut Link) -> Result<(), Error> {
+        match self {
+            ExprView::Integer(value) => link.put_i64(*value),
+            ExprView::Real(value) => link.put_f64(*value),
+            ExprView::String(string) => link.put_str(string.as_str()),
+            ExprView::Symbol(symbol) => link.put_symbol(symbol.as_str()),
+            ExprView::Int64Array(array) => {
+                link.put_i64_array(array.data(), array.dimensions())
+            },
+            ExprView::Real64Array(array) => {
+                link.put_f64_array(array.data(), array.dimensions())
+            },
+            ExprView::Normal { head, args } => {
+                link.put_raw_type(i32::from(sys::WSTKFUNC))?;
+                link.put_arg_count(args.len())?;
+
+                head.put(link)?;
+
+                for arg in args {
+                    arg.put(link)?;
+                }
+
+                Ok(())
+            },
+        }
+    }
+}
+
+impl<E: AsExprView + ?Sized> AsExprView for &E {}
+
+impl<E: AsExprView + ?Sized> private::Sealed for &E {
+    fn put(&self, link: &mut Link) -> Result<(), Error> {
+        E::put(self, link)
+    }
+}
+
+//======================================
+// Reading
+//======================================
+
+fn read_view<'link>(
+    link: &'link Link,
+    reader: &mut Link,
+) -> Result<ExprView<'link>, Error> {
+    let view = match reader.get_type()? {
+        TokenType::Integer => ExprView::Integer(reader.get_i64()?),
+        TokenType::Real => {
+            let value = reader.get_f64()?;
+
+            if value.is_nan() {
+                return Err(Error::custom(
+                    "NaN value passed on WSLINK cannot be used to construct an ExprView"
+                        .to_owned(),
+                ));
+            }
+
+            ExprView::Real(value)
+        },
+        TokenType::String => ExprView::String(link.get_string_ref_shared()?),
+        TokenType::Symbol => {
+            let symbol = link.get_symbol_ref_shared()?;
+
+            if SymbolRef::try_new(symbol.as_str()).is_none() {
+                return Err(Error::custom(format!(
+                    "symbol name '{}' has no context",
+                    symbol.as_str()
+                )));
+            }
+
+            ExprView::Symbol(symbol)
+        },
+        TokenType::Function => match reader.peek(array_leaf_type)? {
+            Some(TokenType::Integer) => ExprView::Int64Array(unsafe {
+                link.get_array(sys::WSGetInteger64Array, sys::WSReleaseInteger64Array)?
+            }),
+            Some(TokenType::Real) => ExprView::Real64Array(unsafe {
+                link.get_array(sys::WSGetReal64Array, sys::WSReleaseReal64Array)?
+            }),
+            _ => {
+                let argc = reader.get_arg_count()?;
+
+                let head = read_view(link, reader)?;
+
+                let mut args = Vec::with_capacity(argc);
+                for _ in 0..argc {
+                    args.push(read_view(link, reader)?);
+                }
+
+                ExprView::Normal {
+                    head: Box::new(head),
+                    args,
+                }
+            },
+        },
+    };
+
+    Ok(view)
+}
+
+/// Read the next expression, and return the type of its elements if it is a
+/// non-empty rectangular `List` array of machine integers or machine reals.
+fn array_leaf_type(link: &mut Link) -> Result<Option<TokenType>, Error> {
+    let mut dimensions = Vec::new();
+    let mut leaf_type = None;
+
+    if scan_array(link, 0, &mut dimensions, &mut leaf_type)? && !dimensions.is_empty() {
+        Ok(leaf_type)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Read the next part of an array at `depth`, returning `false` if it is not
+/// consistent with the `dimensions` and `leaf_type` of the parts read before it.
+///
+/// Reading stops at the first inconsistent part.
+fn scan_array(
+    link: &mut Link,
+    depth: usize,
+    dimensions: &mut Vec<usize>,
+    leaf_type: &mut Option<TokenType>,
+) -> Result<bool, Error> {
+    let type_ = link.get_type()?;
+
+    if type_ != TokenType::Function {
+        // Leaves are only allowed at the depth of the first leaf.
+        if depth == 0 || depth != dimensions.len() {
+            return Ok(false);
+        }
+
+        let is_valid = match type_ {
+            TokenType::Integer => {
+                let _: i64 = link.get_i64()?;
+                true
+            },
+            TokenType::Real => !link.get_f64()?.is_nan(),
+            _ => false,
+        };
+
+        if !is_valid || leaf_type.map_or(false, |leaf_type| leaf_type != type_) {
+            return Ok(false);
+        }
+
+        *leaf_type = Some(type_);
+
+        return Ok(true);
+    }
+
+    let length = link.get_arg_count()?;
+
+    if length == 0
+        || link.get_type()? != TokenType::Symbol
+        || link.get_symbol_ref()?.as_str() != "System`List"
+    {
+        return Ok(false);
+    }
+
+    match dimensions.get(depth) {
+        Some(&expected) if expected != length => return Ok(false),
+        Some(_) => (),
+        // The dimensions are determined by the path to the first leaf.
+        None if depth == dimensions.len() && leaf_type.is_none() => {
+            dimensions.push(length)
+        },
+        None => return Ok(false),
+    }
+
+    for _ in 0..length {
+        if !scan_array(link, depth + 1, dimensions, leaf_type)? {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+//======================================
+// Conversion
+//======================================
+
+fn array_to_expr<T: Copy>(
+    data: &[T],
+    dimensions: &[usize],
+    leaf: &dyn Fn(T) -> Expr,
+) -> Expr {
+    match dimensions.split_first() {
+        None => leaf(data[0]),
+        Some((&length, rest)) => {
+            let stride: usize = rest.iter().product();
+
+            let elements = data
+                .chunks(stride)
+                .take(length)
+                .map(|chunk| array_to_expr(chunk, rest, leaf))
+                .collect();
+
+            Expr::list(elements)
+        },
+    }
+}
@@ -0,0 +1,188 @@
+//! Transfer of fixed-size arrays, whose dimensions are part of their type.
+
+use crate::{get::SYSTEM_LIST, Error, Link};
+
+/// Element type of the fixed-size arrays transferred using
+/// [`Link::get_array_fixed()`], [`Link::get_matrix_fixed()`],
+/// [`Link::put_slice_as_vector()`], and [`Link::put_array_fixed()`].
+///
+/// This trait is sealed and cannot be implemented outside of `wstp`.
+pub trait FixedArrayType: Copy + Default + private::Sealed + 'static {}
+
+mod private {
+    use crate::{Error, Link};
+
+    pub trait Sealed: Sized {
+        fn get(link: &mut Link) -> Result<Self, Error>;
+
+        fn put_array(
+            link: &mut Link,
+            data: &[Self],
+            dimensions: &[usize],
+        ) -> Result<(), Error>;
+    }
+}
+
+macro_rules! fixed_array_type {
+    ($($type:ty: $get:ident, $put_array:ident;)*) => {
+        $(
+            impl FixedArrayType for $type {}
+
+            impl private::Sealed for $type {
+                fn get(link: &mut Link) -> Result<Self, Error> {
+                    link.$get()
+                }
+
+                fn put_array(
+                    link: &mut Link,
+                    data: &[Self],
+                    dimensions: &[usize],
+                ) -> Result<(), Error> {
+                    link.$put_array(data, dimensions)
+                }
+            }
+        )*
+    };
+}
+
+fixed_array_type! {
+    f64: get_f64, put_f64_array;
+    f32: get_f32, put_f32_array;
+    i64: get_i64, put_i64_array;
+    i32: get_i32, put_i32_array;
+    i16: get_i16, put_i16_array;
+    u8: get_u8, put_u8_array;
+}
+
+/// # Fixed-size arrays
+impl Link {
+    /// Get a `List` of exactly `N` elements.
+    ///
+    /// An error is returned if the incoming list does not have length `N`. No heap
+    /// memory is allocated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_f64_array(&[1.0, 2.0, 3.0], &[3]).unwrap();
+    ///
+    /// let position: [f64; 3] = link.get_array_fixed().unwrap();
+    ///
+    /// assert_eq!(position, [1.0, 2.0, 3.0]);
+    /// ```
+    pub fn get_array_fixed<T: FixedArrayType, const N: usize>(
+        &mut self,
+    ) -> Result<[T; N], Error> {
+        let len = self.test_head_cstr(SYSTEM_LIST)?;
+
+        if len != N {
+            return Err(Error::custom(format!(
+                "expected List of length {}, got length {}",
+                N, len
+            )));
+        }
+
+        let mut array = [T::default(); N];
+
+        for elem in &mut array {
+            *elem = T::get(self)?;
+        }
+
+        Ok(array)
+    }
+
+    /// Get a matrix with exactly `R` rows and `C` columns.
+    ///
+    /// An error is returned if the incoming expression is not a `List` of `R` lists of
+    /// length `C`. No heap memory is allocated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_f64_array(&[1.0, 0.0, 0.0, 1.0], &[2, 2]).unwrap();
+    ///
+    /// let identity: [[f64; 2]; 2] = link.get_matrix_fixed().unwrap();
+    ///
+    /// assert_eq!(identity, [[1.0, 0.0], [0.0, 1.0]]);
+    /// ```
+    pub fn get_matrix_fixed<T: FixedArrayType, const R: usize, const C: usize>(
+        &mut self,
+    ) -> Result<[[T; C]; R], Error> {
+        let rows = self.test_head_cstr(SYSTEM_LIST)?;
+
+        if rows != R {
+            return Err(Error::custom(format!(
+                "expected matrix with {} rows, got {} rows",
+                R, rows
+            )));
+        }
+
+        let mut matrix = [[T::default(); C]; R];
+
+        for row in &mut matrix {
+            *row = self.get_array_fixed()?;
+        }
+
+        Ok(matrix)
+    }
+
+    /// Put `vector` as a `List` of `N` elements.
+    ///
+    /// The dimensions of the array are derived from the type of `vector`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_slice_as_vector(&[1.0, 2.0, 3.0]).unwrap();
+    ///
+    /// assert_eq!(link.get_array_fixed::<f64, 3>().unwrap(), [1.0, 2.0, 3.0]);
+    /// ```
+    pub fn put_slice_as_vector<T: FixedArrayType, const N: usize>(
+        &mut self,
+        vector: &[T; N],
+    ) -> Result<(), Error> {
+        T::put_array(self, vector, &[N])
+    }
+
+    /// Put `matrix` as a `List` of `R` lists of `C` elements.
+    ///
+    /// The dimensions of the array are derived from the type of `matrix`, so they can't
+    /// be mismatched with the data.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_array_fixed(&[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]).unwrap();
+    ///
+    /// let array = link.get_f64_array().unwrap();
+    ///
+    /// assert_eq!(array.dimensions(), &[2, 3]);
+    /// assert_eq!(array.data(), &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    /// ```
+    pub fn put_array_fixed<T: FixedArrayType, const R: usize, const C: usize>(
+        &mut self,
+        matrix: &[[T; C]; R],
+    ) -> Result<(), Error> {
+        // SAFETY: `[[T; C]; R]` has the same layout as `[T; R * C]`.
+        let data: &[T] =
+            unsafe { std::slice::from_raw_parts(matrix.as_ptr() as *const T, R * C) };
+
+        T::put_array(self, data, &[R, C])
+    }
+}
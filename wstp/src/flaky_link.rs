@@ -0,0 +1,225 @@
+//! Fault injection for testing error handling and recovery logic.
+
+use std::time::Duration;
+
+#[cfg(feature = "expr")]
+use wolfram_expr::{Expr, ExprKind};
+
+use crate::{sys, Error, Link};
+
+/// Probabilities of the faults injected by a [`FlakyLink`].
+///
+/// Each probability is a number from `0.0` (never) to `1.0` (always), and is checked
+/// independently for every operation performed through the [`FlakyLink`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaultConfig {
+    /// Probability that an operation fails with an error, without touching the link.
+    pub error_probability: f64,
+    /// Probability that an operation is delayed by [`FaultConfig::delay`] before it is
+    /// performed.
+    pub delay_probability: f64,
+    /// Duration of the injected delays.
+    pub delay: Duration,
+    /// Probability that the link is disconnected in the middle of an operation.
+    ///
+    /// Once disconnected, every further operation on the [`FlakyLink`] fails with an
+    /// error for which [`Error::is_peer_closed()`] is `true`.
+    pub disconnect_probability: f64,
+    /// Seed of the random number generator used to decide which faults to inject.
+    ///
+    /// Two [`FlakyLink`]s with the same configuration inject the same faults when the
+    /// same sequence of operations is performed on them.
+    pub seed: u64,
+}
+
+impl Default for FaultConfig {
+    fn default() -> Self {
+        FaultConfig {
+            error_probability: 0.0,
+            delay_probability: 0.0,
+            delay: Duration::from_millis(10),
+            disconnect_probability: 0.0,
+            seed: 0,
+        }
+    }
+}
+
+/// Wrapper around a [`Link`] that injects errors, delays, and disconnects.
+///
+/// This is intended for use in tests, to exercise retry and recovery logic
+/// deterministically. Faults are injected into the operations performed through the
+/// [`FlakyLink`], including [`FlakyLink::with()`], which wraps arbitrary link
+/// operations.
+///
+/// # Example
+///
+/// ```
+/// use wolfram_expr::Expr;
+/// use wstp::{FaultConfig, FlakyLink, Link};
+///
+/// let mut link = FlakyLink::new(Link::new_loopback().unwrap(), FaultConfig {
+///     error_probability: 0.5,
+///     seed: 7,
+///     ..FaultConfig::default()
+/// });
+///
+/// // Retry until the put succeeds.
+/// while link.put_expr(&Expr::from(5)).is_err() {}
+///
+/// assert_eq!(link.link_mut().get_i64().unwrap(), 5);
+/// ```
+#[derive(Debug)]
+pub struct FlakyLink {
+    link: Link,
+    config: FaultConfig,
+    rng_state: u64,
+    disconnected: bool,
+}
+
+impl FlakyLink {
+    /// Wrap `link`, injecting faults as configured by `config`.
+    pub fn new(link: Link, config: FaultConfig) -> Self {
+        let rng_state = config.seed;
+
+        FlakyLink {
+            link,
+            config,
+            rng_state,
+            disconnected: false,
+        }
+    }
+
+    /// Get the fault configuration of this link.
+    pub fn config(&self) -> &FaultConfig {
+        &self.config
+    }
+
+    /// Get a reference to the wrapped link.
+    pub fn link(&self) -> &Link {
+        &self.link
+    }
+
+    /// Get a mutable reference to the wrapped link.
+    ///
+    /// No faults are injected into operations performed directly on the wrapped link.
+    pub fn link_mut(&mut self) -> &mut Link {
+        &mut self.link
+    }
+
+    /// Unwrap the link.
+    pub fn into_inner(self) -> Link {
+        self.link
+    }
+
+    /// Returns `true` if a disconnect has been injected.
+    pub fn is_disconnected(&self) -> bool {
+        self.disconnected
+    }
+
+    /// Perform `op` on the wrapped link, possibly injecting a fault.
+    ///
+    /// An injected disconnect happens before `op` is called.
+    pub fn with<T, F>(&mut self, op: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Link) -> Result<T, Error>,
+    {
+        self.inject()?;
+
+        if self.roll(self.config.disconnect_probability) {
+            return Err(self.disconnect());
+        }
+
+        op(&mut self.link)
+    }
+
+    /// Put `expr`, possibly injecting a fault.
+    ///
+    /// An injected disconnect happens after part of `expr` has been put.
+    #[cfg(feature = "expr")]
+    pub fn put_expr(&mut self, expr: &Expr) -> Result<(), Error> {
+        self.inject()?;
+
+        if !self.roll(self.config.disconnect_probability) {
+            return self.link.put_expr(expr);
+        }
+
+        if let ExprKind::Normal(normal) = expr.kind() {
+            let elements = normal.elements();
+
+            self.link.put_raw_type(i32::from(sys::WSTKFUNC))?;
+            self.link.put_arg_count(elements.len())?;
+            self.link.put_expr(normal.head())?;
+
+            for elem in &elements[..elements.len() / 2] {
+                self.link.put_expr(elem)?;
+            }
+        }
+
+        Err(self.disconnect())
+    }
+
+    /// Get an expression, possibly injecting a fault.
+    ///
+    /// An injected disconnect happens after the first token of the expression has been
+    /// read.
+    #[cfg(feature = "expr")]
+    pub fn get_expr(&mut self) -> Result<Expr, Error> {
+        self.inject()?;
+
+        if !self.roll(self.config.disconnect_probability) {
+            return self.link.get_expr();
+        }
+
+        let _ = self.link.get_token()?;
+
+        Err(self.disconnect())
+    }
+
+    /// Flush the wrapped link, possibly injecting a fault.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.with(|link| link.flush())
+    }
+
+    /// Inject an error or a delay.
+    fn inject(&mut self) -> Result<(), Error> {
+        if self.disconnected {
+            return Err(Error::from_code(sys::WSECLOSED));
+        }
+
+        if self.roll(self.config.delay_probability) {
+            std::thread::sleep(self.config.delay);
+        }
+
+        if self.roll(self.config.error_probability) {
+            return Err(Error::custom("FlakyLink: injected error".to_owned()));
+        }
+
+        Ok(())
+    }
+
+    fn disconnect(&mut self) -> Error {
+        self.disconnected = true;
+        self.link.notify_closed();
+
+        Error::from_code(sys::WSECLOSED)
+    }
+
+    /// Returns `true` with probability `probability`.
+    fn roll(&mut self, probability: f64) -> bool {
+        if probability <= 0.0 {
+            return false;
+        }
+
+        // SplitMix64
+        self.rng_state = self.rng_state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.rng_state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+
+        // Uniformly distributed value in [0, 1).
+        let sample = (z >> 11) as f64 / (1u64 << 53) as f64;
+
+        sample < probability
+    }
+}
@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::ffi::{CStr, CString};
 use std::iter::FromIterator;
 use std::{convert::TryFrom, fmt, os::raw::c_char};
@@ -13,6 +14,12 @@ use crate::{
     Error, Link, Utf16Str, Utf32Str, Utf8Str,
 };
 
+/// The ``System`List`` symbol, for use with [`Link::test_head_cstr()`] by getters that
+/// must not allocate.
+// SAFETY: The byte string is nul-terminated and contains no interior nul bytes.
+pub(crate) const SYSTEM_LIST: &CStr =
+    unsafe { CStr::from_bytes_with_nul_unchecked(b"System`List\0") };
+
 /// Basic unit of expression data read from a [`Link`].
 ///
 /// [`Link::get_token()`] is used to read the next available token from a [`Link`].
@@ -41,13 +48,13 @@ pub enum Token<'link> {
 /// documentation for a listing of WSTP token types.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum TokenType {
-    /// [`WSTKINT`][sys::WSTKINT]
+    /// [`WSTKINT`][sys::WSTKINT] or [`WSTKOLDINT`][sys::WSTKOLDINT]
     Integer,
-    /// [`WSTKREAL`][sys::WSTKREAL]
+    /// [`WSTKREAL`][sys::WSTKREAL] or [`WSTKOLDREAL`][sys::WSTKOLDREAL]
     Real,
-    /// [`WSTKSYM`][sys::WSTKSYM]
+    /// [`WSTKSYM`][sys::WSTKSYM] or [`WSTKOLDSYM`][sys::WSTKOLDSYM]
     Symbol,
-    /// [`WSTKSTR`][sys::WSTKSTR]
+    /// [`WSTKSTR`][sys::WSTKSTR] or [`WSTKOLDSTR`][sys::WSTKOLDSTR]
     String,
     /// [`WSTKFUNC`][sys::WSTKFUNC]
     Function,
@@ -77,6 +84,84 @@ pub enum TokenType {
 /// // Get a `&str` from the `LinkStr`
 /// assert_eq!(string.as_str(), "hello world");
 /// ```
+///
+/// # Lifetime
+///
+/// The string data is owned by the WSTP library, and is only valid until it is
+/// released when the `LinkStr` is dropped. The methods that return a `LinkStr` take
+/// `&mut self`, and the `'link` lifetime of the returned `LinkStr` keeps that mutable
+/// borrow of the [`Link`] alive, so the compiler rejects code that:
+///
+/// * performs another operation on the link while the `LinkStr` is alive,
+/// * uses a `&str` borrowed from the `LinkStr` after it has been dropped, or
+/// * keeps a `LinkStr` after the link it was read from has been dropped.
+///
+/// Copy the string, e.g. using `link_str.as_str().to_owned()` or
+/// [`Link::get_string()`], if it needs to be kept while the link is used.
+///
+// Note: These examples act as tests that the below code is not possible to write. Do
+//       not remove them without replacing them with other tests.
+/// ```compile_fail
+/// use wstp::Link;
+///
+/// let mut link = Link::new_loopback().unwrap();
+/// link.put_symbol("Global`x").unwrap();
+/// link.put_i64(5).unwrap();
+///
+/// let symbol = link.get_symbol_ref().unwrap();
+///
+/// // Error: cannot borrow `link` as mutable more than once at a time
+/// let value = link.get_i64().unwrap();
+///
+/// println!("{} = {}", symbol.as_str(), value);
+/// ```
+///
+/// ```compile_fail
+/// use wstp::Link;
+///
+/// let mut link = Link::new_loopback().unwrap();
+/// link.put_str("hello").unwrap();
+///
+/// let str: &str = {
+///     let string = link.get_string_ref().unwrap();
+///
+///     // Error: `string` does not live long enough
+///     string.as_str()
+/// };
+///
+/// println!("{}", str);
+/// ```
+///
+/// ```compile_fail
+/// use wstp::{Link, Token};
+///
+/// let mut link = Link::new_loopback().unwrap();
+/// link.put_str("first").unwrap();
+/// link.put_str("second").unwrap();
+///
+/// let first = link.get_token().unwrap();
+///
+/// // Error: cannot borrow `link` as mutable more than once at a time
+/// let second = link.get_token().unwrap();
+///
+/// if let (Token::String(first), Token::String(second)) = (first, second) {
+///     println!("{} {}", first.as_str(), second.as_str());
+/// }
+/// ```
+///
+/// ```compile_fail
+/// use wstp::Link;
+///
+/// let mut link = Link::new_loopback().unwrap();
+/// link.put_str("hello").unwrap();
+///
+/// let string = link.get_string_ref().unwrap();
+///
+/// // Error: cannot move out of `link` because it is borrowed
+/// drop(link);
+///
+/// println!("{}", string.as_str());
+/// ```
 pub struct LinkStr<'link, T: LinkStrType + ?Sized = str> {
     link: &'link Link,
 
@@ -110,17 +195,29 @@ impl Link {
     /// Get the type of the next token available to read on this link.
     ///
     /// See also [`Link::get_token()`].
+    ///
+    /// # Legacy token types
+    ///
+    /// Some older MathLink peers report atomic tokens using the legacy
+    /// [`WSTKOLDINT`][sys::WSTKOLDINT], [`WSTKOLDREAL`][sys::WSTKOLDREAL],
+    /// [`WSTKOLDSTR`][sys::WSTKOLDSTR], and [`WSTKOLDSYM`][sys::WSTKOLDSYM] type codes.
+    /// These are normalized to the equivalent [`TokenType`], so that they can be
+    /// read using the usual getters. Use [`Link::get_raw_type()`] to distinguish the
+    /// legacy type codes from the current ones.
     pub fn get_type(&self) -> Result<TokenType, Error> {
-        use wstp_sys::{WSTKFUNC, WSTKINT, WSTKREAL, WSTKSTR, WSTKSYM};
+        use wstp_sys::{
+            WSTKFUNC, WSTKINT, WSTKOLDINT, WSTKOLDREAL, WSTKOLDSTR, WSTKOLDSYM, WSTKREAL,
+            WSTKSTR, WSTKSYM,
+        };
 
         let type_: i32 = self.get_raw_type()?;
 
-        let token_type = match u8::try_from(type_).unwrap() {
-            WSTKINT => TokenType::Integer,
-            WSTKREAL => TokenType::Real,
-            WSTKSTR => TokenType::String,
-            WSTKSYM => TokenType::Symbol,
-            WSTKFUNC => TokenType::Function,
+        let token_type = match u8::try_from(type_) {
+            Ok(WSTKINT | WSTKOLDINT) => TokenType::Integer,
+            Ok(WSTKREAL | WSTKOLDREAL) => TokenType::Real,
+            Ok(WSTKSTR | WSTKOLDSTR) => TokenType::String,
+            Ok(WSTKSYM | WSTKOLDSYM) => TokenType::Symbol,
+            Ok(WSTKFUNC) => TokenType::Function,
             _ => return Err(Error::custom(format!("unknown WSLINK type: {}", type_))),
         };
 
@@ -198,8 +295,27 @@ impl Link {
     //         they will be deallocated when the mempool is freed (presumably during
     //         WSClose()?).
 
+    /// Get a string, borrowing the string data from the link.
+    ///
+    /// The link can't be used again until the returned [`LinkStr`] is dropped. See
+    /// [`LinkStr`#lifetime](LinkStr#lifetime).
+    ///
     /// *WSTP C API Documentation:* [`WSGetUTF8String()`](https://reference.wolfram.com/language/ref/c/WSGetUTF8String.html)
     pub fn get_string_ref<'link>(&'link mut self) -> Result<LinkStr<'link, str>, Error> {
+        self.get_string_ref_shared()
+    }
+
+    /// Variant of [`Link::get_string_ref()`] that borrows this link immutably.
+    ///
+    /// This allows several [`LinkStr`] values read from the same link to be alive at
+    /// once, as needed by [`Link::get_expr_view()`].
+    ///
+    /// The string data remains valid until it is released, independent of later reads,
+    /// but this must not be exposed publicly: a shared borrow would allow the link to
+    /// be used through another `&Link` while the [`LinkStr`] is alive.
+    pub(crate) fn get_string_ref_shared(&self) -> Result<LinkStr<'_, str>, Error> {
+        self.check_string_limit()?;
+
         let mut c_string: *const u8 = std::ptr::null();
         let mut num_bytes: i32 = 0;
         let mut num_chars = 0;
@@ -215,12 +331,15 @@ impl Link {
 
         let num_bytes = usize::try_from(num_bytes).unwrap();
 
-        Ok(LinkStr {
+        let string = LinkStr {
             link: self,
             ptr: c_string,
             length: num_bytes,
             is_symbol: false,
-        })
+        };
+
+
+        Ok(string)
     }
 
     /// Convenience wrapper around [`Link::get_string_ref()`].
@@ -228,8 +347,63 @@ impl Link {
         Ok(self.get_string_ref()?.get().to_owned())
     }
 
+    /// Get a string, and pass it to `read` as a [`Cow<str>`][Cow].
+    ///
+    /// If the string data returned by WSTP is valid UTF-8, `read` is passed a
+    /// [`Cow::Borrowed`] referring directly to the buffer owned by the link, which is
+    /// released after `read` returns. Otherwise, `read` is passed a [`Cow::Owned`]
+    /// copy of the data, in which any invalid sequences are replaced with
+    /// [`U+FFFD REPLACEMENT CHARACTER`][std::char::REPLACEMENT_CHARACTER].
+    ///
+    /// This lets callers avoid copying the string unless they need to keep it, by
+    /// calling [`Cow::into_owned()`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::borrow::Cow;
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_str("hello").unwrap();
+    ///
+    /// let is_borrowed = link
+    ///     .get_str_cow(|string| {
+    ///         assert_eq!(string, "hello");
+    ///         matches!(string, Cow::Borrowed(_))
+    ///     })
+    ///     .unwrap();
+    ///
+    /// assert!(is_borrowed);
+    /// ```
+    ///
+    /// *WSTP C API Documentation:* [`WSGetUTF8String()`](https://reference.wolfram.com/language/ref/c/WSGetUTF8String.html)
+    pub fn get_str_cow<R, F>(&mut self, read: F) -> Result<R, Error>
+    where
+        F: FnOnce(Cow<str>) -> R,
+    {
+        let string = self.get_utf8_str()?;
+
+        Ok(read(String::from_utf8_lossy(string.get().as_slice())))
+    }
+
+    /// Get a symbol, borrowing the symbol name from the link.
+    ///
+    /// The link can't be used again until the returned [`LinkStr`] is dropped. See
+    /// [`LinkStr`#lifetime](LinkStr#lifetime).
+    ///
     /// *WSTP C API Documentation:* [`WSGetUTF8Symbol()`](https://reference.wolfram.com/language/ref/c/WSGetUTF8Symbol.html)
     pub fn get_symbol_ref<'link>(&'link mut self) -> Result<LinkStr<'link, str>, Error> {
+        self.get_symbol_ref_shared()
+    }
+
+    /// Variant of [`Link::get_symbol_ref()`] that borrows this link immutably.
+    ///
+    /// See [`Link::get_string_ref_shared()`].
+    pub(crate) fn get_symbol_ref_shared(&self) -> Result<LinkStr<'_, str>, Error> {
+        self.check_string_limit()?;
+
         let mut c_string: *const u8 = std::ptr::null();
         let mut num_bytes: i32 = 0;
         let mut num_chars = 0;
@@ -250,12 +424,15 @@ impl Link {
 
         let num_bytes = usize::try_from(num_bytes).unwrap();
 
-        Ok(LinkStr {
+        let string = LinkStr {
             link: self,
             ptr: c_string,
             length: num_bytes,
             is_symbol: true,
-        })
+        };
+
+
+        Ok(string)
     }
 
     //==================================
@@ -266,6 +443,8 @@ impl Link {
     pub fn get_utf8_str<'link>(
         &'link mut self,
     ) -> Result<LinkStr<'link, Utf8Str>, Error> {
+        self.check_string_limit()?;
+
         let mut c_string: *const u8 = std::ptr::null();
         let mut num_bytes: i32 = 0;
         let mut num_chars = 0;
@@ -281,20 +460,25 @@ impl Link {
 
         let num_bytes = usize::try_from(num_bytes).unwrap();
 
-        Ok(LinkStr {
+        let string = LinkStr {
             link: self,
 
             ptr: c_string,
             length: num_bytes,
 
             is_symbol: false,
-        })
+        };
+
+
+        Ok(string)
     }
 
     /// *WSTP C API Documentation:* [`WSGetUTF16String()`](https://reference.wolfram.com/language/ref/c/WSGetUTF16String.html)
     pub fn get_utf16_str<'link>(
         &'link mut self,
     ) -> Result<LinkStr<'link, Utf16Str>, Error> {
+        self.check_string_limit()?;
+
         let mut c_string: *const u16 = std::ptr::null();
         let mut num_elems: i32 = 0;
         let mut num_chars = 0;
@@ -310,20 +494,25 @@ impl Link {
 
         let num_elems = usize::try_from(num_elems).unwrap();
 
-        Ok(LinkStr {
+        let string = LinkStr {
             link: self,
 
             ptr: c_string,
             length: num_elems,
 
             is_symbol: false,
-        })
+        };
+
+
+        Ok(string)
     }
 
     /// *WSTP C API Documentation:* [`WSGetUTF32String()`](https://reference.wolfram.com/language/ref/c/WSGetUTF32String.html)
     pub fn get_utf32_str<'link>(
         &'link mut self,
     ) -> Result<LinkStr<'link, Utf32Str>, Error> {
+        self.check_string_limit()?;
+
         let mut c_string: *const u32 = std::ptr::null();
         let mut num_elems: i32 = 0;
 
@@ -336,14 +525,17 @@ impl Link {
 
         let num_elems = usize::try_from(num_elems).unwrap();
 
-        Ok(LinkStr {
+        let string = LinkStr {
             link: self,
 
             ptr: c_string,
             length: num_elems,
 
             is_symbol: false,
-        })
+        };
+
+
+        Ok(string)
     }
 
     //==================================
@@ -407,9 +599,147 @@ impl Link {
 
         let len = usize::try_from(len).expect("c_int overflows usize");
 
+        self.check_arg_count_limit(len)?;
+
         Ok(len)
     }
 
+    //==================================
+    // Expectations
+    //==================================
+
+    /// Get a symbol, and check that it is the symbol `name`.
+    ///
+    /// If `name` has no context, it matches both `name` and ``System`name``, so that
+    /// symbols sent by the Wolfram Language kernel, which omits the ``System` ``
+    /// context, and symbols put with an explicit context, are both accepted.
+    ///
+    /// The error returned if the symbol does not match describes the expected and
+    /// actual tokens.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_symbol("System`Sin").unwrap();
+    /// link.put_symbol("System`Cos").unwrap();
+    ///
+    /// assert!(link.expect_symbol("Sin").is_ok());
+    /// assert_eq!(
+    ///     link.expect_symbol("Sin").unwrap_err().to_string(),
+    ///     "WSTP error: expected symbol Sin, got symbol System`Cos"
+    /// );
+    /// ```
+    pub fn expect_symbol(&mut self, name: &str) -> Result<(), Error> {
+        let type_ = self.get_type()?;
+
+        if type_ != TokenType::Symbol {
+            return Err(Error::custom(format!(
+                "expected symbol {}, got {:?} token",
+                name, type_
+            )));
+        }
+
+        let symbol = self.get_symbol_ref()?;
+
+        if !symbol_matches(symbol.as_str(), name) {
+            return Err(Error::custom(format!(
+                "expected symbol {}, got symbol {}",
+                name,
+                symbol.as_str()
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Get the header of a function with head symbol `head`, returning its number of
+    /// elements.
+    ///
+    /// See [`Link::expect_symbol()`] for how `head` is matched. Unlike
+    /// [`Link::test_head()`], the error returned if the function does not match
+    /// describes the expected and actual tokens.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_function("System`List", 2).unwrap();
+    /// link.put_i64(1).unwrap();
+    /// link.put_i64(2).unwrap();
+    ///
+    /// assert_eq!(link.expect_function("List").unwrap(), 2);
+    /// ```
+    pub fn expect_function(&mut self, head: &str) -> Result<usize, Error> {
+        let type_ = self.get_type()?;
+
+        if type_ != TokenType::Function {
+            return Err(Error::custom(format!(
+                "expected function with head {}, got {:?} token",
+                head, type_
+            )));
+        }
+
+        let length = self.get_arg_count()?;
+
+        let head_type = self.get_type()?;
+
+        if head_type != TokenType::Symbol {
+            return Err(Error::custom(format!(
+                "expected function with head {}, got function with {:?} head",
+                head, head_type
+            )));
+        }
+
+        let symbol = self.get_symbol_ref()?;
+
+        if !symbol_matches(symbol.as_str(), head) {
+            return Err(Error::custom(format!(
+                "expected function with head {}, got function with head {}",
+                head,
+                symbol.as_str()
+            )));
+        }
+
+        Ok(length)
+    }
+
+    /// Get the header of a function with head symbol `head` and `arity` elements.
+    ///
+    /// See [`Link::expect_function()`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_function("Global`Point", 2).unwrap();
+    /// link.put_f64(1.0).unwrap();
+    /// link.put_f64(2.0).unwrap();
+    ///
+    /// assert!(link.expect_head_arity("Global`Point", 3).is_err());
+    /// ```
+    pub fn expect_head_arity(&mut self, head: &str, arity: usize) -> Result<(), Error> {
+        let length = self.expect_function(head)?;
+
+        if length != arity {
+            return Err(Error::custom(format!(
+                "expected function {} to have {} elements, got {}",
+                head, arity, length
+            )));
+        }
+
+        Ok(())
+    }
+
     /// *WSTP C API Documentation:* [`WSGetArgCount()`](https://reference.wolfram.com/language/ref/c/WSGetArgCount.html)
     pub fn get_arg_count(&mut self) -> Result<usize, Error> {
         let mut arg_count = 0;
@@ -424,9 +754,143 @@ impl Link {
             // numeric environment handling issue.
             .expect("WSTKFUNC argument count could not be converted to usize");
 
+        self.check_arg_count_limit(arg_count)?;
+
         Ok(arg_count)
     }
 
+    //==================================
+    // Lists
+    //==================================
+
+    /// Get a `List` of strings.
+    ///
+    /// See also [`Link::put_str_list()`].
+    pub fn get_string_list(&mut self) -> Result<Vec<String>, Error> {
+        let length = self.test_head("System`List")?;
+
+        let mut strings = Vec::with_capacity(length);
+
+        for _ in 0..length {
+            strings.push(self.get_string()?);
+        }
+
+        Ok(strings)
+    }
+
+    /// Get a `List` of symbols.
+    ///
+    /// See also [`Link::put_symbol_list()`].
+    pub fn get_symbol_list(&mut self) -> Result<Vec<String>, Error> {
+        let length = self.test_head("System`List")?;
+
+        let mut symbols = Vec::with_capacity(length);
+
+        for _ in 0..length {
+            symbols.push(self.get_symbol_ref()?.as_str().to_owned());
+        }
+
+        Ok(symbols)
+    }
+
+    /// Get a `List` of `List`s of integers, whose rows may have different lengths.
+    ///
+    /// See also [`Link::put_jagged()`].
+    pub fn get_jagged(&mut self) -> Result<Vec<Vec<i64>>, Error> {
+        let length = self.test_head_cstr(SYSTEM_LIST)?;
+
+        let mut rows = Vec::with_capacity(length);
+
+        for _ in 0..length {
+            let row_length = self.test_head_cstr(SYSTEM_LIST)?;
+
+            let mut row = Vec::with_capacity(row_length);
+
+            for _ in 0..row_length {
+                row.push(self.get_i64()?);
+            }
+
+            rows.push(row);
+        }
+
+        Ok(rows)
+    }
+
+    //==================================
+    // Lists into buffers
+    //==================================
+
+    /// Get a `List` of reals into `buf`, returning the length of the list.
+    ///
+    /// The elements are stored in `buf[..len]`. This function does not allocate, so it
+    /// can be used in a read loop that must not allocate in its steady state, e.g. when
+    /// processing audio or telemetry samples.
+    ///
+    /// An error is returned if the list is longer than `buf`. In that case, the
+    /// elements of the list are left unread on the link.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// let mut buf = [0.0; 8];
+    ///
+    /// link.put_f64_array(&[1.0, 2.0, 3.0], &[3]).unwrap();
+    ///
+    /// let len = link.get_f64_into(&mut buf).unwrap();
+    ///
+    /// assert_eq!(&buf[..len], &[1.0, 2.0, 3.0]);
+    /// ```
+    pub fn get_f64_into(&mut self, buf: &mut [f64]) -> Result<usize, Error> {
+        self.get_list_into(buf, Link::get_f64)
+    }
+
+    /// Get a `List` of reals into `buf`, returning the length of the list.
+    ///
+    /// See [`Link::get_f64_into()`].
+    pub fn get_f32_into(&mut self, buf: &mut [f32]) -> Result<usize, Error> {
+        self.get_list_into(buf, Link::get_f32)
+    }
+
+    /// Get a `List` of integers into `buf`, returning the length of the list.
+    ///
+    /// See [`Link::get_f64_into()`].
+    pub fn get_i64_into(&mut self, buf: &mut [i64]) -> Result<usize, Error> {
+        self.get_list_into(buf, Link::get_i64)
+    }
+
+    /// Get a `List` of integers into `buf`, returning the length of the list.
+    ///
+    /// See [`Link::get_f64_into()`].
+    pub fn get_i32_into(&mut self, buf: &mut [i32]) -> Result<usize, Error> {
+        self.get_list_into(buf, Link::get_i32)
+    }
+
+    fn get_list_into<T>(
+        &mut self,
+        buf: &mut [T],
+        get: fn(&mut Link) -> Result<T, Error>,
+    ) -> Result<usize, Error> {
+        let len = self.test_head_cstr(SYSTEM_LIST)?;
+
+        if len > buf.len() {
+            return Err(Error::custom(format!(
+                "list of length {} does not fit in buffer of length {}",
+                len,
+                buf.len()
+            )));
+        }
+
+        for elem in &mut buf[..len] {
+            *elem = get(self)?;
+        }
+
+        Ok(len)
+    }
+
     //==================================
     // Numerics
     //==================================
@@ -485,6 +949,111 @@ impl Link {
         Ok(real)
     }
 
+    /// Get the next integer or real number as a string of digits.
+    ///
+    /// Unlike [`Link::get_i64()`] and [`Link::get_f64()`], this function can read
+    /// numbers of any size and precision. Arbitrary-precision reals include their
+    /// precision mark, e.g. ``"3.14159265358979323846`20."``.
+    ///
+    /// *WSTP C API Documentation:* [`WSGetNumberAsUTF8String()`](https://reference.wolfram.com/language/ref/c/WSGetNumberAsString.html)
+    pub fn get_number_string(&mut self) -> Result<String, Error> {
+        self.check_string_limit()?;
+
+        let mut c_string: *const u8 = std::ptr::null();
+        let mut num_bytes: i32 = 0;
+        let mut num_chars = 0;
+
+        if unsafe {
+            sys::WSGetNumberAsUTF8String(
+                self.raw_link,
+                &mut c_string,
+                &mut num_bytes,
+                &mut num_chars,
+            )
+        } == 0
+        {
+            return Err(self.error_or_unknown());
+        }
+
+        let len = usize::try_from(num_bytes).unwrap();
+
+        let string = unsafe {
+            let bytes = std::slice::from_raw_parts(c_string, len);
+            let string = String::from_utf8_lossy(bytes).into_owned();
+
+            WSReleaseUTF8String(self.raw_link, c_string, num_bytes);
+
+            string
+        };
+
+        Ok(string)
+    }
+
+    /// Get a real number, converting an `Integer` token to [`f64`] if necessary.
+    ///
+    /// Kernel output can contain either `2` or `2.` for the same quantity. Unlike
+    /// [`Link::get_f64()`], this accepts both.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_i64(2).unwrap();
+    /// link.put_f64(2.5).unwrap();
+    ///
+    /// assert_eq!(link.get_f64_coercing().unwrap(), 2.0);
+    /// assert_eq!(link.get_f64_coercing().unwrap(), 2.5);
+    /// ```
+    pub fn get_f64_coercing(&mut self) -> Result<f64, Error> {
+        match self.get_type()? {
+            TokenType::Integer => Ok(self.get_i64()? as f64),
+            _ => self.get_f64(),
+        }
+    }
+
+    /// Get an integer, converting a `Real` token with an integral value to [`i64`] if
+    /// necessary.
+    ///
+    /// If the next token is a `Real` whose value is not an integer, or which is out of
+    /// the range of [`i64`], the token is consumed and an error is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_f64(2.0).unwrap();
+    /// link.put_f64(2.5).unwrap();
+    ///
+    /// assert_eq!(link.get_i64_coercing().unwrap(), 2);
+    /// assert!(link.get_i64_coercing().is_err());
+    /// ```
+    pub fn get_i64_coercing(&mut self) -> Result<i64, Error> {
+        match self.get_type()? {
+            TokenType::Real => {
+                let real = self.get_f64()?;
+
+                // Note: `i64::MAX as f64` rounds up to 2^63, which is out of range.
+                if real.fract() != 0.0
+                    || !(real >= i64::MIN as f64 && real < i64::MAX as f64)
+                {
+                    return Err(Error::custom(format!(
+                        "Real value {} cannot be converted to an integer",
+                        real
+                    )));
+                }
+
+                Ok(real as i64)
+            },
+            _ => self.get_i64(),
+        }
+    }
+
     //==================================
     // Integer numeric arrays
     //==================================
@@ -559,8 +1128,8 @@ impl Link {
     }
 
     #[allow(non_snake_case)]
-    unsafe fn get_array<T: 'static>(
-        &mut self,
+    pub(crate) unsafe fn get_array<T: 'static>(
+        &self,
         WSGetTArray: unsafe extern "C" fn(
             sys::WSLINK,
             *mut *mut T,
@@ -576,6 +1145,8 @@ impl Link {
             i32,
         ),
     ) -> Result<Array<T>, Error> {
+        self.check_array_limit()?;
+
         let Link { raw_link } = *self;
 
         let mut data_ptr: *mut T = std::ptr::null_mut();
@@ -606,7 +1177,7 @@ impl Link {
                 .expect("WSGetInteger64Array dimension size overflows usize")
         }));
 
-        Ok(Array {
+        let array = Array {
             link: self,
             data_ptr,
             release_callback: Box::new(move |link: &Link| {
@@ -619,7 +1190,9 @@ impl Link {
                 );
             }),
             dimensions: dims,
-        })
+        };
+
+        Ok(array)
     }
 }
 
@@ -681,6 +1254,16 @@ impl<'link, T: ?Sized + LinkStrType> Drop for LinkStr<'link, T> {
     }
 }
 
+/// Returns `true` if `symbol` is the symbol `name`, with `name` matching symbols in
+/// the ``System` `` context if it has no context.
+pub(crate) fn symbol_matches(symbol: &str, name: &str) -> bool {
+    if symbol == name {
+        return true;
+    }
+
+    !name.contains('`') && symbol.strip_prefix("System`") == Some(name)
+}
+
 //======================================
 // LinkStrType impls
 //======================================
@@ -795,6 +1378,7 @@ unsafe impl LinkStrType for Utf32Str {
 /// * [`Link::get_u8_array()`]
 /// * [`Link::get_f64_array()`]
 /// * [`Link::get_f32_array()`]
+/// * [`Link::get_byte_array()`]
 pub struct Array<'link, T> {
     link: &'link Link,
 
@@ -836,6 +1420,26 @@ impl<'link, T> Array<'link, T> {
     }
 }
 
+impl<'link, T: 'static> Array<'link, T> {
+    /// Construct a one-dimensional [`Array`] that owns `data`, instead of borrowing it
+    /// from `link`.
+    pub(crate) fn from_vec(link: &'link Link, data: Vec<T>) -> Self {
+        let len = data.len();
+        let data_ptr = Box::into_raw(data.into_boxed_slice()) as *mut T;
+
+        Array {
+            link,
+            data_ptr,
+            release_callback: Box::new(move |_: &Link| unsafe {
+                drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+                    data_ptr, len,
+                )));
+            }),
+            dimensions: vec![len],
+        }
+    }
+}
+
 impl<'link, T> Drop for Array<'link, T> {
     fn drop(&mut self) {
         let Array {
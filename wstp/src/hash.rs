@@ -0,0 +1,97 @@
+//! Structural hashing of the expressions available on a link.
+
+use std::hash::Hasher;
+
+use crate::{sys, Error, Link, TokenType};
+
+impl Link {
+    /// Read the next expression on this link, and compute a structural hash of it
+    /// using the hasher `H`.
+    ///
+    /// The hash depends only on the structure and contents of the expression, and not
+    /// on how it was written to the link, so equal expressions always produce equal
+    /// hashes. Each token contributes its [`TokenType`] and contents to the hash, so
+    /// e.g. the integer `1` and the real `1.` hash differently.
+    ///
+    /// Whether a hash can be compared across processes or program versions depends on
+    /// the stability of `H`. In particular, the output of
+    /// [`DefaultHasher`][std::collections::hash_map::DefaultHasher] may change between
+    /// Rust releases.
+    ///
+    /// Use [`Link::peek()`] to hash an expression without consuming it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::hash_map::DefaultHasher;
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// // Put {1, 2}
+    /// link.put_function("System`List", 2).unwrap();
+    /// link.put_i64(1).unwrap();
+    /// link.put_i64(2).unwrap();
+    ///
+    /// // Hash the expression, without consuming it.
+    /// let peeked = link.peek(|link| link.hash_next_expr::<DefaultHasher>()).unwrap();
+    ///
+    /// assert_eq!(link.hash_next_expr::<DefaultHasher>().unwrap(), peeked);
+    /// ```
+    pub fn hash_next_expr<H: Hasher + Default>(&mut self) -> Result<u64, Error> {
+        let mut hasher = H::default();
+
+        self.hash_next_expr_into(&mut hasher)?;
+
+        Ok(hasher.finish())
+    }
+
+    /// Read the next expression on this link, writing its structural hash into
+    /// `hasher`.
+    ///
+    /// See [`Link::hash_next_expr()`].
+    pub fn hash_next_expr_into<H: Hasher>(
+        &mut self,
+        hasher: &mut H,
+    ) -> Result<(), Error> {
+        match self.get_type()? {
+            // Note: Numbers are hashed using their textual form, so that integers and
+            //       reals of any size and precision can be hashed.
+            TokenType::Integer => {
+                hasher.write_u8(sys::WSTKINT);
+                hash_str(hasher, &self.get_number_string()?);
+            },
+            TokenType::Real => {
+                hasher.write_u8(sys::WSTKREAL);
+                hash_str(hasher, &self.get_number_string()?);
+            },
+            TokenType::String => {
+                hasher.write_u8(sys::WSTKSTR);
+                hash_str(hasher, self.get_string_ref()?.as_str());
+            },
+            TokenType::Symbol => {
+                hasher.write_u8(sys::WSTKSYM);
+                hash_str(hasher, self.get_symbol_ref()?.as_str());
+            },
+            TokenType::Function => {
+                let argc = self.get_arg_count()?;
+
+                hasher.write_u8(sys::WSTKFUNC);
+                hasher.write_u64(u64::try_from(argc).unwrap());
+
+                // Hash the head, then each of the arguments.
+                for _ in 0..=argc {
+                    self.hash_next_expr_into(hasher)?;
+                }
+            },
+        }
+
+        Ok(())
+    }
+}
+
+/// Hash `string` in a way that is independent of the platform pointer width.
+fn hash_str<H: Hasher>(hasher: &mut H, string: &str) {
+    hasher.write_u64(u64::try_from(string.len()).unwrap());
+    hasher.write(string.as_bytes());
+}
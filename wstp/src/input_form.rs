@@ -0,0 +1,187 @@
+//! Rendering of expressions as [`InputForm`][InputForm] text.
+//!
+//! [InputForm]: https://reference.wolfram.com/language/ref/InputForm.html
+
+use wolfram_expr::{Expr, ExprKind};
+
+use crate::{Error, Link};
+
+impl Link {
+    /// Get an expression, and render it as [`InputForm`][InputForm] text.
+    ///
+    /// This is useful for including the expression received from a link in logs and
+    /// error messages, when the [`Expr`] value itself is not needed.
+    ///
+    /// The rendering is done without a Wolfram Language kernel, so only the following
+    /// forms are written using their special syntax:
+    ///
+    /// Expression                 | InputForm
+    /// ---------------------------|----------
+    /// `List[a, b]`               | `{a, b}`
+    /// `Rule[a, b]`               | `a -> b`
+    /// `RuleDelayed[a, b]`        | `a :> b`
+    /// `Association[a -> b]`      | `<\|a -> b\|>`
+    ///
+    /// Every other function is written as `f[a, b]`. Symbols in the ``System` `` and
+    /// ``Global` `` contexts are written without their context, and reals are written
+    /// using the `*^` exponent syntax, e.g. `1.5*^-7`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wolfram_expr::{Expr, Symbol};
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_expr(&Expr::normal(Symbol::new("Global`f"), vec![
+    ///     Expr::list(vec![Expr::from(1), Expr::real(2.5)]),
+    ///     Expr::rule("key", Expr::symbol(Symbol::new("System`True"))),
+    /// ]))
+    /// .unwrap();
+    ///
+    /// assert_eq!(
+    ///     link.get_expr_input_form().unwrap(),
+    ///     r#"f[{1, 2.5}, "key" -> True]"#
+    /// );
+    /// ```
+    ///
+    /// [InputForm]: https://reference.wolfram.com/language/ref/InputForm.html
+    pub fn get_expr_input_form(&mut self) -> Result<String, Error> {
+        let expr = self.get_expr()?;
+
+        let mut out = String::new();
+        write_input_form(&mut out, &expr);
+
+        Ok(out)
+    }
+}
+
+fn write_input_form(out: &mut String, expr: &Expr) {
+    match expr.kind() {
+        ExprKind::Integer(int) => out.push_str(&int.to_string()),
+        ExprKind::Real(real) => write_real(out, **real),
+        ExprKind::String(string) => write_string(out, string),
+        ExprKind::Symbol(symbol) => {
+            let name = symbol.as_str();
+
+            out.push_str(
+                name.strip_prefix("System`")
+                    .or_else(|| name.strip_prefix("Global`"))
+                    .filter(|name| !name.contains('`'))
+                    .unwrap_or(name),
+            );
+        },
+        ExprKind::Normal(normal) => {
+            let head = match normal.head().kind() {
+                ExprKind::Symbol(head) => Some(head.as_str()),
+                _ => None,
+            };
+
+            match (head, normal.elements()) {
+                (Some("System`List"), elements) => {
+                    write_sequence(out, "{", elements, "}");
+                },
+                (Some("System`Association"), elements) => {
+                    write_sequence(out, "<|", elements, "|>");
+                },
+                (Some("System`Rule"), [lhs, rhs]) => write_rule(out, lhs, " -> ", rhs),
+                (Some("System`RuleDelayed"), [lhs, rhs]) => {
+                    write_rule(out, lhs, " :> ", rhs)
+                },
+                (_, elements) => {
+                    write_input_form(out, normal.head());
+                    write_sequence(out, "[", elements, "]");
+                },
+            }
+        },
+    }
+}
+
+fn write_sequence(out: &mut String, open: &str, elements: &[Expr], close: &str) {
+    out.push_str(open);
+
+    for (index, element) in elements.iter().enumerate() {
+        if index > 0 {
+            out.push_str(", ");
+        }
+
+        write_input_form(out, element);
+    }
+
+    out.push_str(close);
+}
+
+fn write_rule(out: &mut String, lhs: &Expr, operator: &str, rhs: &Expr) {
+    // Rules are right-associative, so only a rule on the left-hand side needs to be
+    // parenthesized.
+    let is_rule = match lhs.kind() {
+        ExprKind::Normal(normal) => matches!(
+            normal.head().kind(),
+            ExprKind::Symbol(head)
+                if matches!(head.as_str(), "System`Rule" | "System`RuleDelayed")
+        ),
+        _ => false,
+    };
+
+    if is_rule {
+        out.push('(');
+        write_input_form(out, lhs);
+        out.push(')');
+    } else {
+        write_input_form(out, lhs);
+    }
+
+    out.push_str(operator);
+    write_input_form(out, rhs);
+}
+
+fn write_real(out: &mut String, real: f64) {
+    if real.is_infinite() {
+        out.push_str(if real > 0.0 { "Infinity" } else { "-Infinity" });
+        return;
+    }
+
+    // The Debug format of f64 is the shortest representation that round-trips, e.g.
+    // "2.0", "0.1", or "1.5e-7".
+    let formatted = format!("{:?}", real);
+
+    let (mantissa, exponent) = match formatted.split_once('e') {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (formatted.as_str(), None),
+    };
+
+    match mantissa.strip_suffix(".0") {
+        Some(integral) => {
+            out.push_str(integral);
+            out.push('.');
+        },
+        None if !mantissa.contains('.') => {
+            out.push_str(mantissa);
+            out.push('.');
+        },
+        None => out.push_str(mantissa),
+    }
+
+    if let Some(exponent) = exponent {
+        out.push_str("*^");
+        out.push_str(exponent);
+    }
+}
+
+fn write_string(out: &mut String, string: &str) {
+    out.push('"');
+
+    for c in string.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+}
@@ -0,0 +1,143 @@
+//! Transfer of [`Interval`][Interval] expressions.
+//!
+//! [Interval]: https://reference.wolfram.com/language/ref/Interval.html
+
+use crate::{Error, InfinityDirection, Link, RealValue};
+
+impl Link {
+    /// Put the interval from `lo` to `hi` as `Interval[{lo, hi}]`.
+    ///
+    /// Infinite endpoints are put as `DirectedInfinity[1]` or `DirectedInfinity[-1]`,
+    /// regardless of the [`NanPolicy`][crate::NanPolicy] of this link.
+    ///
+    /// An error is returned if either endpoint is NaN, or if `lo` is greater than `hi`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// // Put Interval[{0.5, 1.5}]
+    /// link.put_interval(0.5, 1.5).unwrap();
+    ///
+    /// assert_eq!(link.get_interval().unwrap(), (0.5, 1.5));
+    /// ```
+    pub fn put_interval(&mut self, lo: f64, hi: f64) -> Result<(), Error> {
+        self.put_intervals(&[(lo, hi)])
+    }
+
+    /// Put the union of `spans` as `Interval[{lo1, hi1}, {lo2, hi2}, ...]`.
+    ///
+    /// The spans are put in the order given. The Wolfram Language sorts and merges
+    /// overlapping spans when the expression is evaluated.
+    ///
+    /// See [`Link::put_interval()`] for how the endpoints are put.
+    pub fn put_intervals(&mut self, spans: &[(f64, f64)]) -> Result<(), Error> {
+        for &(lo, hi) in spans {
+            if lo.is_nan() || hi.is_nan() || lo > hi {
+                return Err(Error::custom(format!(
+                    "invalid Interval span: {{{}, {}}}",
+                    lo, hi
+                )));
+            }
+        }
+
+        self.put_guarded(|link| {
+            link.put_function("System`Interval", spans.len())?;
+
+            for &(lo, hi) in spans {
+                link.put_function("System`List", 2)?;
+                link.put_interval_endpoint(lo)?;
+                link.put_interval_endpoint(hi)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Get an `Interval[{lo, hi}]` expression with a single span.
+    ///
+    /// Endpoints can be integers, reals, or `Infinity` and `-Infinity`, which are
+    /// returned as [`f64::INFINITY`] and [`f64::NEG_INFINITY`].
+    ///
+    /// An error is returned if the interval does not have exactly one span. Use
+    /// [`Link::get_intervals()`] to get an interval with any number of spans.
+    pub fn get_interval(&mut self) -> Result<(f64, f64), Error> {
+        let spans = self.get_intervals()?;
+
+        match spans.as_slice() {
+            [span] => Ok(*span),
+            _ => Err(Error::custom(format!(
+                "expected Interval with 1 span, got {} spans",
+                spans.len()
+            ))),
+        }
+    }
+
+    /// Get an `Interval[{lo1, hi1}, {lo2, hi2}, ...]` expression, returning its spans.
+    ///
+    /// See [`Link::get_interval()`] for the endpoints that are accepted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// // Put Interval[{-Infinity, 0.}, {1., 2.}]
+    /// link.put_intervals(&[(f64::NEG_INFINITY, 0.0), (1.0, 2.0)]).unwrap();
+    ///
+    /// assert_eq!(
+    ///     link.get_intervals().unwrap(),
+    ///     vec![(f64::NEG_INFINITY, 0.0), (1.0, 2.0)]
+    /// );
+    /// ```
+    pub fn get_intervals(&mut self) -> Result<Vec<(f64, f64)>, Error> {
+        let count = self.test_head("System`Interval")?;
+
+        let mut spans = Vec::with_capacity(count);
+
+        for _ in 0..count {
+            match self.test_head("System`List")? {
+                2 => (),
+                len => {
+                    return Err(Error::custom(format!(
+                        "expected Interval span to be a List of length 2, got length {}",
+                        len
+                    )))
+                },
+            }
+
+            let lo = self.get_interval_endpoint()?;
+            let hi = self.get_interval_endpoint()?;
+
+            spans.push((lo, hi));
+        }
+
+        Ok(spans)
+    }
+
+    fn put_interval_endpoint(&mut self, value: f64) -> Result<(), Error> {
+        if value.is_infinite() {
+            self.put_function("System`DirectedInfinity", 1)?;
+            return self.put_i64(if value > 0.0 { 1 } else { -1 });
+        }
+
+        self.put_f64(value)
+    }
+
+    fn get_interval_endpoint(&mut self) -> Result<f64, Error> {
+        match self.get_real_value()? {
+            RealValue::Finite(value) => Ok(value),
+            RealValue::Infinity(InfinityDirection::Positive) => Ok(f64::INFINITY),
+            RealValue::Infinity(InfinityDirection::Negative) => Ok(f64::NEG_INFINITY),
+            other => Err(Error::custom(format!(
+                "expected real Interval endpoint, got {:?}",
+                other
+            ))),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! Issuing Wolfram Language messages from programs called by the kernel.
+
+use wolfram_expr::Expr;
+
+use crate::{Error, Link};
+
+impl Link {
+    /// Issue the Wolfram Language message `symbol::tag`, with arguments `args`, and
+    /// wait for the kernel to finish evaluating it.
+    ///
+    /// This is intended for use by programs installed into a kernel using
+    /// [`Install`][Install], while they are handling a call from the kernel. It puts
+    ///
+    /// ```text
+    /// EvaluatePacket[Message[MessageName[symbol, "tag"], args...]]
+    /// ```
+    ///
+    /// on the link, and then reads and discards packets up to and including the
+    /// `ReturnPacket` sent by the kernel after it evaluates the message. This is the
+    /// same convention used by the `MLEvaluate()` function of MathLink template
+    /// programs, and lets a handler raise a proper message, like `MyFunc::badarg`,
+    /// before returning its result (e.g. `$Failed`) to the kernel.
+    ///
+    /// The message text is looked up by the kernel, so the message should be defined
+    /// in the Wolfram Language, e.g. using `MyFunc::badarg = "Bad argument: `1`."`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use wolfram_expr::Expr;
+    /// use wstp::Link;
+    ///
+    /// fn handle_call(link: &mut Link, arg: i64) -> Result<(), wstp::Error> {
+    ///     if arg < 0 {
+    ///         // Issues MyFunc::badarg, then returns $Failed.
+    ///         link.issue_message("Global`MyFunc", "badarg", &[Expr::from(arg)])?;
+    ///
+    ///         return link.put_symbol("System`$Failed");
+    ///     }
+    ///
+    ///     link.put_i64(arg * 2)
+    /// }
+    /// ```
+    ///
+    /// [Install]: https://reference.wolfram.com/language/ref/Install.html
+    pub fn issue_message(
+        &mut self,
+        symbol: &str,
+        tag: &str,
+        args: &[Expr],
+    ) -> Result<(), Error> {
+        self.put_guarded(|link| {
+            link.put_function("System`EvaluatePacket", 1)?;
+            link.put_function("System`Message", 1 + args.len())?;
+
+            link.put_function("System`MessageName", 2)?;
+            link.put_symbol(symbol)?;
+            link.put_str(tag)?;
+
+            for arg in args {
+                link.put_expr(arg)?;
+            }
+
+            Ok(())
+        })?;
+
+        self.end_packet()?;
+        self.flush()?;
+
+        while !self.skip_packet()? {}
+
+        Ok(())
+    }
+}
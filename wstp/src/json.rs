@@ -0,0 +1,234 @@
+//! Transfer of JSON values as expressions.
+
+use std::io::BufRead;
+
+use serde_json::{Map, Number, Value};
+
+use crate::{Error, Link, ListWriter, TokenType};
+
+impl Link {
+    /// Put a [`serde_json::Value`] as an expression.
+    ///
+    /// JSON values are converted to expressions using the following mapping:
+    ///
+    /// JSON                 | Expression
+    /// ---------------------|-----------
+    /// `null`               | `Null`
+    /// `true`, `false`      | `True`, `False`
+    /// number               | `Integer` or `Real`
+    /// string               | `String`
+    /// array                | `List[...]`
+    /// object               | `Association[key -> value, ...]`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use serde_json::json;
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// let value = json!({ "id": 7, "tags": ["a", "b"], "parent": null });
+    ///
+    /// // Put <|"id" -> 7, "tags" -> {"a", "b"}, "parent" -> Null|>
+    /// link.put_json(&value).unwrap();
+    ///
+    /// assert_eq!(link.get_json().unwrap(), value);
+    /// ```
+    pub fn put_json(&mut self, value: &Value) -> Result<(), Error> {
+        self.put_guarded(|link| link.put_json_unguarded(value))
+    }
+
+    fn put_json_unguarded(&mut self, value: &Value) -> Result<(), Error> {
+        match value {
+            Value::Null => self.put_symbol("System`Null"),
+            Value::Bool(true) => self.put_symbol("System`True"),
+            Value::Bool(false) => self.put_symbol("System`False"),
+            Value::Number(number) => {
+                if let Some(integer) = number.as_i64() {
+                    self.put_i64(integer)
+                } else if let Some(integer) = number.as_u64() {
+                    self.put_integer_digits(&integer.to_string())
+                } else {
+                    // Non-integer JSON numbers are always representable as an f64.
+                    self.put_f64(number.as_f64().unwrap())
+                }
+            },
+            Value::String(string) => self.put_str(string),
+            Value::Array(elements) => {
+                self.put_function("System`List", elements.len())?;
+
+                for element in elements {
+                    self.put_json_unguarded(element)?;
+                }
+
+                Ok(())
+            },
+            Value::Object(fields) => {
+                self.put_function("System`Association", fields.len())?;
+
+                for (key, value) in fields {
+                    self.put_function("System`Rule", 2)?;
+                    self.put_str(key)?;
+                    self.put_json_unguarded(value)?;
+                }
+
+                Ok(())
+            },
+        }
+    }
+
+    /// Get an expression as a [`serde_json::Value`].
+    ///
+    /// This is the inverse of the mapping described in [`Link::put_json()`].
+    ///
+    /// An error is returned if the incoming expression has no JSON representation, e.g.
+    /// because it contains a symbol other than `Null`, `True`, or `False`, or an
+    /// `Association` with keys that are not strings.
+    pub fn get_json(&mut self) -> Result<Value, Error> {
+        let value = match self.get_type()? {
+            TokenType::Integer => {
+                let digits = self.get_number_string()?;
+
+                let number: Option<Number> = match digits.parse::<i64>() {
+                    Ok(integer) => Some(Number::from(integer)),
+                    Err(_) => digits.parse::<u64>().ok().map(Number::from),
+                };
+
+                match number {
+                    Some(number) => Value::Number(number),
+                    None => {
+                        return Err(Error::custom(format!(
+                            "integer {} is too large to be represented in JSON",
+                            digits
+                        )))
+                    },
+                }
+            },
+            TokenType::Real => {
+                let real = self.get_f64()?;
+
+                match Number::from_f64(real) {
+                    Some(number) => Value::Number(number),
+                    None => {
+                        return Err(Error::custom(format!(
+                            "real number {} cannot be represented in JSON",
+                            real
+                        )))
+                    },
+                }
+            },
+            TokenType::String => Value::String(self.get_string()?),
+            TokenType::Symbol => {
+                let symbol = self.get_symbol_ref()?;
+
+                match symbol.as_str() {
+                    "System`Null" => Value::Null,
+                    "System`True" => Value::Bool(true),
+                    "System`False" => Value::Bool(false),
+                    other => {
+                        return Err(Error::custom(format!(
+                            "symbol {} cannot be represented in JSON",
+                            other
+                        )))
+                    },
+                }
+            },
+            TokenType::Function => {
+                let argc = self.get_arg_count()?;
+
+                let is_list = {
+                    let head = self.get_symbol_ref()?;
+
+                    match head.as_str() {
+                        "System`List" => true,
+                        "System`Association" => false,
+                        other => {
+                            return Err(Error::custom(format!(
+                                "expression with head {} cannot be represented in JSON",
+                                other
+                            )))
+                        },
+                    }
+                };
+
+                if is_list {
+                    let mut elements = Vec::with_capacity(argc);
+
+                    for _ in 0..argc {
+                        elements.push(self.get_json()?);
+                    }
+
+                    Value::Array(elements)
+                } else {
+                    let mut fields = Map::new();
+
+                    for _ in 0..argc {
+                        let rule_argc = self.test_head("System`Rule")?;
+
+                        if rule_argc != 2 {
+                            return Err(Error::custom(format!(
+                                "expected Rule in Association to have 2 arguments, got {}",
+                                rule_argc
+                            )));
+                        }
+
+                        let key = self.get_string()?;
+                        let value = self.get_json()?;
+
+                        fields.insert(key, value);
+                    }
+
+                    Value::Object(fields)
+                }
+            },
+        };
+
+        Ok(value)
+    }
+
+    /// Put the [JSON Lines](https://jsonlines.org/) data read from `reader` as a `List`
+    /// of expressions.
+    ///
+    /// Each non-empty line of `reader` must contain a single JSON value. Lines are
+    /// parsed and converted to expressions one at a time, and staged using
+    /// [`ListWriter`] until the total number of lines is known.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let ndjson = "{\"level\": \"info\"}\n{\"level\": \"error\"}\n";
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// // Put {<|"level" -> "info"|>, <|"level" -> "error"|>}
+    /// link.put_json_lines(ndjson.as_bytes()).unwrap();
+    /// ```
+    pub fn put_json_lines<R: BufRead>(&mut self, reader: R) -> Result<(), Error> {
+        let mut list = ListWriter::new()?;
+
+        for (index, line) in reader.lines().enumerate() {
+            let line = line.map_err(|err| {
+                Error::custom(format!("error reading JSON Lines data: {}", err))
+            })?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let value: Value = serde_json::from_str(&line).map_err(|err| {
+                Error::custom(format!(
+                    "invalid JSON on line {} of JSON Lines data: {}",
+                    index + 1,
+                    err
+                ))
+            })?;
+
+            list.push(|link| link.put_json(&value))?;
+        }
+
+        list.finish(self)
+    }
+}
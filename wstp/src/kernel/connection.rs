@@ -0,0 +1,343 @@
+//! Connections to a kernel using a main link and a preemptive link.
+
+use std::{
+    ops::ControlFlow,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use wolfram_expr::{Expr, Symbol};
+
+use super::{
+    stream::{self, EvaluationOutput},
+    Error,
+};
+use crate::{Link, Protocol, UrgentMessage};
+
+const POISONED: &str = "preemptive link lock was poisoned";
+
+/// Connection to a Wolfram Kernel using a main link and an optional preemptive link.
+///
+/// The official front end communicates with a kernel using two links:
+///
+/// * The *main* link, used for ordinary evaluations. Only one evaluation can be in
+///   progress on it at a time.
+/// * The *preemptive* link, used for evaluations like `Dynamic` updates, which the
+///   kernel can perform while an evaluation on the main link is still in progress.
+///
+/// [`KernelConnection::evaluate()`] blocks the calling thread until the evaluation on
+/// the main link is finished. Other threads interact with that evaluation using
+/// handles, which are [`Send`] and [`Sync`], and can be cloned:
+///
+/// * An [`InterruptHandle`], from [`KernelConnection::interrupt_handle()`], interrupts
+///   or aborts the evaluation in progress on the main link.
+/// * A [`PreemptiveHandle`], from [`KernelConnection::preemptive_handle()`], evaluates
+///   expressions on the preemptive link.
+///
+/// The preemptive link can be passed to [`KernelConnection::with_preemptive()`] if it
+/// is already connected to the kernel, or set up by the kernel itself using
+/// [`KernelConnection::connect_preemptive()`].
+///
+/// # Example
+///
+/// ```no_run
+/// use wolfram_expr::{Expr, Symbol};
+/// use wstp::{kernel::KernelConnection, Link, Protocol};
+///
+/// # let name = "";
+/// let mut main = Link::connect(Protocol::SharedMemory, name).unwrap();
+/// main.activate().unwrap();
+///
+/// let mut kernel = KernelConnection::new(main);
+/// kernel.connect_preemptive(Protocol::SharedMemory).unwrap();
+///
+/// let interrupt = kernel.interrupt_handle();
+/// let preemptive = kernel.preemptive_handle().unwrap();
+///
+/// let thread = std::thread::spawn(move || {
+///     let pause = Expr::normal(Symbol::new("System`Pause"), vec![Expr::from(60)]);
+///     kernel.evaluate(&pause)
+/// });
+///
+/// // Can be evaluated while the evaluation on the main link is in progress.
+/// let now = Expr::normal(Symbol::new("System`DateString"), vec![]);
+/// let date = preemptive.evaluate(&now).unwrap();
+///
+/// interrupt.abort();
+///
+/// let result = thread.join().unwrap().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct KernelConnection {
+    main: Link,
+    preemptive: Option<Arc<Mutex<Link>>>,
+    pending: Arc<Pending>,
+}
+
+/// Handle used to interrupt or abort the evaluation in progress on the main link of a
+/// [`KernelConnection`], from any thread.
+///
+/// Use [`KernelConnection::interrupt_handle()`] to get an [`InterruptHandle`].
+#[derive(Debug, Clone)]
+pub struct InterruptHandle {
+    pending: Arc<Pending>,
+}
+
+/// Handle used to evaluate expressions on the preemptive link of a
+/// [`KernelConnection`], from any thread.
+///
+/// Use [`KernelConnection::preemptive_handle()`] to get a [`PreemptiveHandle`].
+#[derive(Debug, Clone)]
+pub struct PreemptiveHandle {
+    link: Arc<Mutex<Link>>,
+}
+
+/// Urgent messages requested using an [`InterruptHandle`], which have not yet been
+/// sent on the main link.
+#[derive(Debug, Default)]
+struct Pending {
+    interrupt: AtomicBool,
+    abort: AtomicBool,
+}
+
+impl KernelConnection {
+    /// Construct a connection to a kernel that uses only a main link.
+    pub fn new(main: Link) -> Self {
+        KernelConnection {
+            main,
+            preemptive: None,
+            pending: Arc::default(),
+        }
+    }
+
+    /// Construct a connection to a kernel that uses a main link and a preemptive link.
+    ///
+    /// Both links must already be connected to the kernel.
+    pub fn with_preemptive(main: Link, preemptive: Link) -> Self {
+        KernelConnection {
+            main,
+            preemptive: Some(Arc::new(Mutex::new(preemptive))),
+            pending: Arc::default(),
+        }
+    }
+
+    /// Ask the kernel to connect a new preemptive link to this connection.
+    ///
+    /// This creates a link listening using `protocol`, and evaluates
+    /// ``MathLink`AddSharingLink`` on the main link, so that the kernel connects to it
+    /// and services evaluations sent on it preemptively. Any preemptive link this
+    /// connection already had is replaced.
+    ///
+    /// `protocol` must be usable by the kernel to connect to this process. In
+    /// particular, [`Protocol::IntraProcess`] can only be used if the kernel is running
+    /// in the same process.
+    ///
+    /// This blocks until the kernel has connected to the new link.
+    pub fn connect_preemptive(&mut self, protocol: Protocol) -> Result<(), Error> {
+        let (mut preemptive, name) = Link::listen_any(protocol.clone())?;
+
+        let connect = Expr::normal(
+            Symbol::new("System`LinkConnect"),
+            vec![
+                Expr::string(name),
+                Expr::rule(
+                    Symbol::new("System`LinkProtocol"),
+                    Expr::string(protocol.to_string()),
+                ),
+            ],
+        );
+
+        let enabled = |option: &str| {
+            Expr::rule(
+                Symbol::new(option),
+                Expr::symbol(Symbol::new("System`True")),
+            )
+        };
+        let disabled = |option: &str| {
+            Expr::rule(
+                Symbol::new(option),
+                Expr::symbol(Symbol::new("System`False")),
+            )
+        };
+
+        let add_sharing_link = Expr::normal(
+            Symbol::new("MathLink`AddSharingLink"),
+            vec![
+                connect,
+                enabled("MathLink`AllowPreemptive"),
+                disabled("MathLink`SendInputNamePacket"),
+                enabled("MathLink`Terminating"),
+            ],
+        );
+
+        self.main.put_eval_packet(&add_sharing_link)?;
+        self.main.flush()?;
+
+        // The kernel connects to the new link before it returns the result of the
+        // evaluation, so activating the link must not wait for that result.
+        preemptive.activate()?;
+
+        let _: Expr = self.main.get_return_packet()?;
+
+        self.preemptive = Some(Arc::new(Mutex::new(preemptive)));
+
+        Ok(())
+    }
+
+    /// Get the main link of this connection.
+    pub fn main(&mut self) -> &mut Link {
+        &mut self.main
+    }
+
+    /// Get a handle used to interrupt or abort evaluations on the main link, from any
+    /// thread.
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        InterruptHandle {
+            pending: Arc::clone(&self.pending),
+        }
+    }
+
+    /// Get a handle used to evaluate expressions on the preemptive link of this
+    /// connection, from any thread, if it has one.
+    pub fn preemptive_handle(&self) -> Option<PreemptiveHandle> {
+        let link = Arc::clone(self.preemptive.as_ref()?);
+
+        Some(PreemptiveHandle { link })
+    }
+
+    /// Unwrap the main and preemptive links of this connection.
+    ///
+    /// The preemptive link is only returned if no [`PreemptiveHandle`] to it is still
+    /// alive. Otherwise it is closed when the last handle is dropped.
+    pub fn into_links(self) -> (Link, Option<Link>) {
+        let KernelConnection {
+            main,
+            preemptive,
+            pending: _,
+        } = self;
+
+        let preemptive = preemptive
+            .and_then(|link| Arc::try_unwrap(link).ok())
+            .map(|link| link.into_inner().expect(POISONED));
+
+        (main, preemptive)
+    }
+
+    /// Evaluate `expr` on the main link, and return the result.
+    ///
+    /// This blocks until the result is received. While it is blocked, an
+    /// [`InterruptHandle`] can be used from another thread to interrupt or abort the
+    /// evaluation; the urgent message is then sent on the main link by this thread.
+    ///
+    /// Interrupts and aborts requested before this is called are discarded.
+    pub fn evaluate(&mut self, expr: &Expr) -> Result<Expr, Error> {
+        let KernelConnection { main, pending, .. } = self;
+
+        pending.clear();
+
+        main.put_eval_packet(expr)?;
+        main.flush()?;
+
+        loop {
+            pending.send(main)?;
+
+            // Wait for a packet, or until an interrupt or abort is requested.
+            let is_ready = main.wait_with_callback(|_: &mut Link| {
+                if pending.is_set() {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            })?;
+
+            if !is_ready {
+                continue;
+            }
+
+            if let EvaluationOutput::Result(result) = stream::get_output(main)? {
+                return Ok(result);
+            }
+        }
+    }
+
+    /// Interrupt the evaluation in progress on the main link.
+    ///
+    /// This sends an [`UrgentMessage::INTERRUPT`] message to the kernel. It can't be
+    /// called while [`KernelConnection::evaluate()`] is blocked; use an
+    /// [`InterruptHandle`] to interrupt an evaluation from another thread.
+    pub fn interrupt(&mut self) -> Result<(), Error> {
+        self.main.put_message(UrgentMessage::INTERRUPT)?;
+
+        Ok(())
+    }
+
+    /// Abort the evaluation in progress on the main link.
+    ///
+    /// This sends an [`UrgentMessage::ABORT`] message to the kernel. It can't be called
+    /// while [`KernelConnection::evaluate()`] is blocked; use an [`InterruptHandle`] to
+    /// abort an evaluation from another thread.
+    pub fn abort(&mut self) -> Result<(), Error> {
+        self.main.put_message(UrgentMessage::ABORT)?;
+
+        Ok(())
+    }
+}
+
+impl InterruptHandle {
+    /// Interrupt the evaluation in progress in [`KernelConnection::evaluate()`].
+    ///
+    /// An [`UrgentMessage::INTERRUPT`] message is sent to the kernel by the thread
+    /// blocked in [`KernelConnection::evaluate()`]. If no evaluation is in progress,
+    /// the request is discarded when the next evaluation starts.
+    pub fn interrupt(&self) {
+        self.pending.interrupt.store(true, Ordering::SeqCst);
+    }
+
+    /// Abort the evaluation in progress in [`KernelConnection::evaluate()`].
+    ///
+    /// An [`UrgentMessage::ABORT`] message is sent to the kernel by the thread blocked
+    /// in [`KernelConnection::evaluate()`]. If no evaluation is in progress, the request
+    /// is discarded when the next evaluation starts.
+    pub fn abort(&self) {
+        self.pending.abort.store(true, Ordering::SeqCst);
+    }
+}
+
+impl PreemptiveHandle {
+    /// Evaluate `expr` on the preemptive link, and return the result.
+    ///
+    /// This can be called while an evaluation on the main link is in progress.
+    /// Evaluations made using handles to the same preemptive link are performed one at
+    /// a time.
+    pub fn evaluate(&self, expr: &Expr) -> Result<Expr, Error> {
+        let mut link = self.link.lock().expect(POISONED);
+
+        link.evaluate(expr)
+    }
+}
+
+impl Pending {
+    fn is_set(&self) -> bool {
+        self.interrupt.load(Ordering::SeqCst) || self.abort.load(Ordering::SeqCst)
+    }
+
+    fn clear(&self) {
+        self.interrupt.store(false, Ordering::SeqCst);
+        self.abort.store(false, Ordering::SeqCst);
+    }
+
+    /// Send the urgent messages that have been requested since they were last sent.
+    fn send(&self, main: &mut Link) -> Result<(), Error> {
+        if self.interrupt.swap(false, Ordering::SeqCst) {
+            main.put_message(UrgentMessage::INTERRUPT)?;
+        }
+
+        if self.abort.swap(false, Ordering::SeqCst) {
+            main.put_message(UrgentMessage::ABORT)?;
+        }
+
+        Ok(())
+    }
+}
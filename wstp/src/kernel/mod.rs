@@ -55,12 +55,30 @@
 //! #### Link packet methods
 //!
 //! * [`Link::put_eval_packet()`]
+//! * [`Link::evaluate()`]
+//! * [`Link::evaluate_with()`]
+//! * [`Link::get_symbol_value()`]
+//! * [`Link::set_symbol_value()`]
+//! * [`Link::scoped_context()`]
+//! * [`Link::evaluate_streaming()`]
+
+#[cfg(feature = "expr")]
+mod connection;
+#[cfg(feature = "expr")]
+mod stream;
 
 use std::{path::PathBuf, process};
 
-use wolfram_expr::Expr;
+#[cfg(feature = "expr")]
+use wolfram_expr::{Expr, Symbol};
+
+use crate::{ActivateError, Error as WstpError, Link, Protocol};
 
-use crate::{Error as WstpError, Link, Protocol};
+#[cfg(feature = "expr")]
+pub use self::{
+    connection::{InterruptHandle, KernelConnection, PreemptiveHandle},
+    stream::{EvaluationOutput, EvaluationStream},
+};
 
 /// Handle to a Wolfram Kernel process connected via WSTP.
 ///
@@ -85,6 +103,12 @@ impl From<WstpError> for Error {
     }
 }
 
+impl From<ActivateError> for Error {
+    fn from(err: ActivateError) -> Error {
+        Error(format!("WSTP error: {err}"))
+    }
+}
+
 impl From<std::io::Error> for Error {
     fn from(err: std::io::Error) -> Error {
         Error(format!("IO error: {err}"))
@@ -129,7 +153,7 @@ impl WolframKernelProcess {
         //
         //        TODO: Set a yield function that will abort if a timeout
         //              duration is reached.
-        let () = link.activate()?;
+        let () = link.try_activate()?;
 
         Ok(WolframKernelProcess {
             process: kernel_process,
@@ -145,15 +169,258 @@ impl WolframKernelProcess {
     }
 }
 
+#[cfg(feature = "expr")]
 impl Link {
     /// Put an [`EvaluatePacket[expr]`][EvaluatePacket] onto the link.
     ///
     /// [EvaluatePacket]: https://reference.wolfram.com/language/ref/EvaluatePacket.html
     pub fn put_eval_packet(&mut self, expr: &Expr) -> Result<(), Error> {
-        self.put_function("System`EvaluatePacket", 1)?;
-        self.put_expr(expr)?;
+        self.put_guarded(|link| {
+            link.put_function("System`EvaluatePacket", 1)?;
+            link.put_expr(expr)
+        })?;
+        self.end_packet()?;
+
+        Ok(())
+    }
+}
+
+/// # Kernel evaluation
+#[cfg(feature = "expr")]
+impl Link {
+    /// Evaluate `expr` in the Wolfram Kernel connected to this link, and return the
+    /// result.
+    ///
+    /// This puts an [`EvaluatePacket[expr]`][EvaluatePacket] on the link, and then
+    /// reads packets until a [`ReturnPacket[result]`][ReturnPacket] is received. Any
+    /// other packets sent by the kernel before the result, like `TextPacket` or
+    /// `MessagePacket`, are discarded.
+    ///
+    /// [EvaluatePacket]: https://reference.wolfram.com/language/ref/EvaluatePacket.html
+    /// [ReturnPacket]: https://reference.wolfram.com/language/ref/ReturnPacket.html
+    pub fn evaluate(&mut self, expr: &Expr) -> Result<Expr, Error> {
+        self.put_eval_packet(expr)?;
+        self.flush()?;
+
+        self.get_return_packet()
+    }
+
+    /// Evaluate the Wolfram Language source `code`, with the slots `#1`, `#2`, etc.
+    /// replaced by the elements of `args`, and return the result.
+    ///
+    /// `code` is parsed as the body of a pure [`Function`][Function], which is then
+    /// applied to `args`. The arguments are spliced in as expressions, not as source
+    /// text, so values that came from an untrusted source can't change the meaning of
+    /// `code`, and don't need to be escaped.
+    ///
+    /// The arguments are not evaluated before they are spliced in.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::path::PathBuf;
+    /// use wolfram_expr::Expr;
+    /// use wstp::kernel::WolframKernelProcess;
+    ///
+    /// # let exe = PathBuf::new();
+    /// let mut kernel = WolframKernelProcess::launch(&exe).unwrap();
+    ///
+    /// // A string from a user is only ever a string.
+    /// let name = Expr::string("\"]; DeleteFile[\"important.txt");
+    ///
+    /// let greeting = kernel
+    ///     .link()
+    ///     .evaluate_with("StringJoin[\"Hello, \", #1]", &[name])
+    ///     .unwrap();
+    /// ```
+    ///
+    /// Evaluation is performed as if by:
+    ///
+    /// ```text
+    /// ToExpression[code, InputForm, Function][Unevaluated[arg1], Unevaluated[arg2], ...]
+    /// ```
+    ///
+    /// [Function]: https://reference.wolfram.com/language/ref/Function.html
+    pub fn evaluate_with(&mut self, code: &str, args: &[Expr]) -> Result<Expr, Error> {
+        self.put_guarded(|link| {
+            link.put_function("System`EvaluatePacket", 1)?;
+
+            // Put the application `ToExpression[code, InputForm, Function][args...]`.
+            link.put_raw_type(i32::from(crate::sys::WSTKFUNC))?;
+            link.put_arg_count(args.len())?;
+
+            link.put_function("System`ToExpression", 3)?;
+            link.put_str(code)?;
+            link.put_symbol("System`InputForm")?;
+            link.put_symbol("System`Function")?;
+
+            for arg in args {
+                link.put_function("System`Unevaluated", 1)?;
+                link.put_expr(arg)?;
+            }
+
+            Ok(())
+        })?;
+
+        self.end_packet()?;
+        self.flush()?;
+
+        self.get_return_packet()
+    }
+
+    /// Get the value of the kernel symbol `name`.
+    ///
+    /// `name` may be an absolute name like ``"System`$Version"``, or a relative name,
+    /// which is resolved using the [default context][Link::set_default_context] of this
+    /// link, or by the kernel if no default context is set.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::path::PathBuf;
+    /// use wstp::kernel::WolframKernelProcess;
+    ///
+    /// # let exe = PathBuf::new();
+    /// let mut kernel = WolframKernelProcess::launch(&exe).unwrap();
+    ///
+    /// let version = kernel.link().get_symbol_value("$Version").unwrap();
+    /// ```
+    pub fn get_symbol_value(&mut self, name: &str) -> Result<Expr, Error> {
+        self.put_guarded(|link| {
+            link.put_function("System`EvaluatePacket", 1)?;
+            link.put_symbol(name)
+        })?;
+        self.end_packet()?;
+        self.flush()?;
+
+        self.get_return_packet()
+    }
+
+    /// Set the value of the kernel symbol `name` to `value`.
+    ///
+    /// This evaluates `Set[name, Unevaluated[value]]`, so `value` is assigned exactly
+    /// as given, without being evaluated first. Data from an untrusted source can be
+    /// assigned without it being run as code.
+    ///
+    /// `name` is resolved in the same way as in [`Link::get_symbol_value()`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::path::PathBuf;
+    /// use wolfram_expr::Expr;
+    /// use wstp::kernel::WolframKernelProcess;
+    ///
+    /// # let exe = PathBuf::new();
+    /// let mut kernel = WolframKernelProcess::launch(&exe).unwrap();
+    ///
+    /// kernel.link().set_symbol_value("x", &Expr::from(5)).unwrap();
+    /// ```
+    pub fn set_symbol_value(&mut self, name: &str, value: &Expr) -> Result<(), Error> {
+        self.put_guarded(|link| {
+            link.put_function("System`EvaluatePacket", 1)?;
+            // Return Null instead of sending `value` back.
+            link.put_function("System`CompoundExpression", 2)?;
+            link.put_function("System`Set", 2)?;
+            link.put_symbol(name)?;
+            link.put_function("System`Unevaluated", 1)?;
+            link.put_expr(value)?;
+            link.put_symbol("System`Null")
+        })?;
         self.end_packet()?;
+        self.flush()?;
+
+        let _: Expr = self.get_return_packet()?;
 
         Ok(())
     }
+
+    /// Call `f` with `context` as the current context of the kernel connected to this
+    /// link, so that symbols created by `f` don't affect other users of the kernel.
+    ///
+    /// Before calling `f`, the current values of [`$Context`][$Context] and
+    /// [`$ContextPath`][$ContextPath] are saved, and then set to `context` and
+    /// ``{context, "System`"}``. After `f` returns, the saved values are restored, even
+    /// if `f` returned an error. If `clear` is `true`, all symbols in `context` are
+    /// then removed.
+    ///
+    /// `context` may be given with or without a trailing `` ` ``.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::path::PathBuf;
+    /// use wolfram_expr::Expr;
+    /// use wstp::kernel::WolframKernelProcess;
+    ///
+    /// # let exe = PathBuf::new();
+    /// let mut kernel = WolframKernelProcess::launch(&exe).unwrap();
+    ///
+    /// // Global`x is unaffected by the assignment to User1`x.
+    /// let x = kernel
+    ///     .link()
+    ///     .scoped_context("User1`", true, |link| {
+    ///         link.set_symbol_value("x", &Expr::from(5))?;
+    ///         link.get_symbol_value("x")
+    ///     })
+    ///     .unwrap();
+    /// ```
+    ///
+    /// [$Context]: https://reference.wolfram.com/language/ref/$Context.html
+    /// [$ContextPath]: https://reference.wolfram.com/language/ref/$ContextPath.html
+    pub fn scoped_context<T, F>(
+        &mut self,
+        context: &str,
+        clear: bool,
+        f: F,
+    ) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Link) -> Result<T, Error>,
+    {
+        let context = if context.ends_with('`') {
+            context.to_owned()
+        } else {
+            format!("{context}`")
+        };
+
+        let old_context = self.get_symbol_value("System`$Context")?;
+        let old_context_path = self.get_symbol_value("System`$ContextPath")?;
+
+        self.set_symbol_value(
+            "System`$ContextPath",
+            &Expr::list(vec![
+                Expr::string(context.as_str()),
+                Expr::string("System`"),
+            ]),
+        )?;
+        self.set_symbol_value("System`$Context", &Expr::string(context.as_str()))?;
+
+        let result = f(self);
+
+        let restored = self
+            .set_symbol_value("System`$Context", &old_context)
+            .and_then(|()| {
+                self.set_symbol_value("System`$ContextPath", &old_context_path)
+            });
+
+        let value = result?;
+        let () = restored?;
+
+        if clear {
+            let _: Expr = self.evaluate(&Expr::normal(
+                Symbol::new("System`Quiet"),
+                vec![Expr::normal(
+                    Symbol::new("System`Remove"),
+                    vec![Expr::string(format!("{context}*"))],
+                )],
+            ))?;
+        }
+
+        Ok(value)
+    }
+
+    /// Read packets until a `ReturnPacket[result]` is received, and return `result`.
+    fn get_return_packet(&mut self) -> Result<Expr, Error> {
+        EvaluationStream::new(self).result()
+    }
 }
@@ -0,0 +1,138 @@
+//! Streaming of the packets sent by a kernel during an evaluation.
+
+use wolfram_expr::{Expr, ExprKind};
+
+use super::Error;
+use crate::{get::symbol_matches, Link};
+
+/// A packet received from a kernel during an evaluation.
+///
+/// See [`EvaluationStream`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum EvaluationOutput {
+    /// A packet sent before the result, like `TextPacket[..]`, `MessagePacket[..]`, or
+    /// a packet written by the evaluation using `LinkWrite`.
+    Intermediate(Expr),
+    /// The result of the evaluation, from the final `ReturnPacket[result]`.
+    Result(Expr),
+}
+
+/// Iterator over the packets sent by a kernel while it evaluates an expression.
+///
+/// Long-running evaluations can send partial results or progress output before they
+/// finish. This iterator yields each of those packets as an
+/// [`EvaluationOutput::Intermediate`], and then ends after yielding the evaluation
+/// result as an [`EvaluationOutput::Result`].
+///
+/// Use [`Link::evaluate_streaming()`] to start an evaluation and get an
+/// [`EvaluationStream`] for it.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::path::PathBuf;
+/// use wolfram_expr::{Expr, Symbol};
+/// use wstp::kernel::{EvaluationOutput, WolframKernelProcess};
+///
+/// # let exe = PathBuf::new();
+/// let mut kernel = WolframKernelProcess::launch(&exe).unwrap();
+///
+/// let expr = Expr::normal(Symbol::new("Global`longComputation"), vec![]);
+///
+/// for output in kernel.link().evaluate_streaming(&expr).unwrap() {
+///     match output.unwrap() {
+///         EvaluationOutput::Intermediate(packet) => println!("partial: {packet}"),
+///         EvaluationOutput::Result(result) => println!("result: {result}"),
+///         _ => (),
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct EvaluationStream<'link> {
+    link: &'link mut Link,
+    done: bool,
+}
+
+impl Link {
+    /// Evaluate `expr` in the Wolfram Kernel connected to this link, returning an
+    /// iterator over the packets sent by the kernel during the evaluation.
+    ///
+    /// See [`EvaluationStream`].
+    pub fn evaluate_streaming(
+        &mut self,
+        expr: &Expr,
+    ) -> Result<EvaluationStream<'_>, Error> {
+        self.put_eval_packet(expr)?;
+        self.flush()?;
+
+        Ok(EvaluationStream::new(self))
+    }
+}
+
+impl<'link> EvaluationStream<'link> {
+    /// Read the packets of an evaluation whose `EvaluatePacket` has already been sent
+    /// on `link`.
+    pub(super) fn new(link: &'link mut Link) -> Self {
+        EvaluationStream { link, done: false }
+    }
+
+    /// Discard any remaining intermediate packets, and return the result of the
+    /// evaluation.
+    pub fn result(self) -> Result<Expr, Error> {
+        for output in self {
+            if let EvaluationOutput::Result(result) = output? {
+                return Ok(result);
+            }
+        }
+
+        Err(Error("evaluation stream ended without a result".to_owned()))
+    }
+}
+
+impl<'link> Iterator for EvaluationStream<'link> {
+    type Item = Result<EvaluationOutput, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let output = get_output(self.link);
+
+        // Stop after the result, or after any error, since the position of the link
+        // within the evaluation is then unknown.
+        if !matches!(output, Ok(EvaluationOutput::Intermediate(_))) {
+            self.done = true;
+        }
+
+        Some(output)
+    }
+}
+
+/// Read the next packet sent by the kernel during an evaluation.
+pub(super) fn get_output(link: &mut Link) -> Result<EvaluationOutput, Error> {
+    let packet = link.get_expr()?;
+
+    let normal = match packet.kind() {
+        ExprKind::Normal(normal) => normal,
+        _ => return Ok(EvaluationOutput::Intermediate(packet)),
+    };
+
+    let is_return = match normal.head().kind() {
+        ExprKind::Symbol(head) => symbol_matches(head.as_str(), "ReturnPacket"),
+        _ => false,
+    };
+
+    if !is_return {
+        return Ok(EvaluationOutput::Intermediate(packet));
+    }
+
+    match normal.elements() {
+        [result] => Ok(EvaluationOutput::Result(result.clone())),
+        elements => Err(Error(format!(
+            "expected ReturnPacket with 1 argument, got {}",
+            elements.len()
+        ))),
+    }
+}
@@ -118,6 +118,29 @@
 //!
 // TODO: Mention package manager downloads of WolframEngine.
 //!
+//! ### Optional features
+//!
+//! The `expr` [Cargo feature][cargo-features] is enabled by default. It enables reading
+//! and writing [`wolfram_expr::Expr`][wolfram-expr-crate] values using
+//! [`Link::get_expr()`] and [`Link::put_expr()`], and the APIs built on them. Programs
+//! that only use the token-level APIs, like [`Link::get_i64()`] and
+//! [`Link::put_function()`], can disable default features to drop the `wolfram-expr`
+//! dependency.
+//!
+//! The following Cargo features enable integrations with other crates. None of them
+//! are enabled by default.
+//!
+//! * `bytes` — transfer [`bytes::Bytes`][bytes-crate] buffers as `ByteArray` expressions.
+//! * `rust_decimal` and `bigdecimal` — transfer exact decimal values from the
+//!   [`rust_decimal`][rust_decimal-crate] and [`bigdecimal`][bigdecimal-crate] crates.
+//! * `uom` — transfer [`uom`][uom-crate] typed quantities as `Quantity` expressions.
+//! * `csv` — stream CSV data onto a link using the [`csv`][csv-crate] crate.
+//! * `serde_json` — transfer [`serde_json::Value`][serde_json-crate] values, and stream
+//!   JSON Lines data onto a link.
+//! * `rmpv` — transfer MessagePack values using the [`rmpv`][rmpv-crate] crate.
+//! * `prost-reflect` — transfer protobuf messages as `Association` expressions using
+//!   the [`prost-reflect`][prost-reflect-crate] crate.
+//!
 //!
 //! # Related Links
 //!
@@ -136,21 +159,109 @@
 //! [CFunctions]: https://reference.wolfram.com/language/guide/AlphabeticalListingOfWSTPCFunctions.html
 //!
 //! [wolfram-app-discovery]: https://crates.io/crates/wolfram-app-discovery
+//! [cargo-features]: https://doc.rust-lang.org/cargo/reference/features.html
+//! [wolfram-expr-crate]: https://crates.io/crates/wolfram-expr
+//! [bytes-crate]: https://crates.io/crates/bytes
+//! [csv-crate]: https://crates.io/crates/csv
+//! [prost-reflect-crate]: https://crates.io/crates/prost-reflect
+//! [rmpv-crate]: https://crates.io/crates/rmpv
+//! [serde_json-crate]: https://crates.io/crates/serde_json
+//! [rust_decimal-crate]: https://crates.io/crates/rust_decimal
+//! [bigdecimal-crate]: https://crates.io/crates/bigdecimal
+//! [uom-crate]: https://crates.io/crates/uom
 
 #![warn(missing_docs)]
 
 
+mod auto_flush;
 mod env;
 mod error;
+mod lifecycle;
+mod limits;
 mod link_server;
+mod list_writer;
+mod macros;
+mod mark;
+mod poison;
+mod settings;
 mod wait;
+mod watcher;
 
 mod get;
 mod put;
 
+mod bool_list;
+mod byte_array;
+#[cfg(feature = "expr")]
+mod call;
+mod channel_pool;
+mod compare;
+#[cfg(feature = "flate2")]
+mod compressed_link;
+#[cfg(feature = "expr")]
+mod correlation;
+#[cfg(feature = "csv")]
+mod csv_reader;
+mod dataset;
+#[cfg(any(feature = "rust_decimal", feature = "bigdecimal"))]
+mod decimal;
+mod dispatcher;
+#[cfg(feature = "expr")]
+mod expr_view;
+mod fixed_array;
+mod flaky_link;
+mod hash;
+#[cfg(feature = "expr")]
+mod input_form;
+mod interval;
+#[cfg(feature = "expr")]
+mod issue_message;
+#[cfg(feature = "serde_json")]
+mod json;
+mod link_kind;
+mod link_listener;
+mod link_name;
+mod link_service;
+#[cfg(feature = "rmpv")]
+mod msgpack;
+mod number_digits;
+mod numeric_array;
+#[cfg(feature = "expr")]
+mod options;
+mod path;
+mod ping;
+#[cfg(feature = "expr")]
+mod progress;
+#[cfg(feature = "prost-reflect")]
+mod protobuf;
+#[cfg(feature = "uom")]
+mod quantity;
+mod quantity_array;
+#[cfg(feature = "expr")]
+mod queued_link;
+mod reader;
+mod real_value;
+#[cfg(feature = "expr")]
+mod router;
+mod rule;
+mod schema;
+mod shared_memory;
+mod sparse_array;
+mod status;
+mod symbol;
+mod temporal;
+mod throttled_link;
+#[cfg(feature = "expr")]
+mod token_filter;
+mod transaction;
+mod version;
+mod wxf;
+
 mod strx;
+mod user_data;
 
 pub mod kernel;
+pub mod symbols;
 
 /// Ensure that doc tests in the README.md file get run.
 #[cfg(doctest)]
@@ -165,6 +276,7 @@ use std::ffi::{c_char, CStr, CString};
 use std::fmt::{self, Display};
 use std::net;
 
+#[cfg(feature = "expr")]
 use wolfram_expr::{Expr, ExprKind, Number, Symbol};
 use wstp_sys::{WSErrorMessage, WSReady, WSReleaseErrorMessage, WSLINK};
 
@@ -178,14 +290,72 @@ use wstp_sys::{WSErrorMessage, WSReady, WSReleaseErrorMessage, WSLINK};
 #[doc(inline)]
 pub use wstp_sys as sys;
 
+#[cfg(feature = "flate2")]
+pub use crate::compressed_link::CompressedLink;
+#[cfg(feature = "csv")]
+pub use crate::csv_reader::{CsvOptions, CsvOutput};
+#[cfg(any(feature = "rust_decimal", feature = "bigdecimal"))]
+pub use crate::decimal::DecimalPolicy;
+#[cfg(feature = "uom")]
+pub use crate::quantity::WolframQuantity;
+
 pub use crate::{
-    env::shutdown,
-    error::Error,
+    bool_list::BoolEncoding,
+    channel_pool::ChannelPool,
+    compare::{compare_exprs, exprs_equal, ExprDifference},
+    dataset::DatasetWriter,
+    dispatcher::Dispatcher,
+    env::{
+        disable_signal_handlers, set_text_encoding, shutdown, text_encoding, TextEncoding,
+    },
+    error::{ActivateError, Error, ErrorRecovery, HandshakeStage},
+    fixed_array::FixedArrayType,
+    flaky_link::{FaultConfig, FlakyLink},
     get::{Array, LinkStr, Token, TokenType},
+    limits::LinkLimits,
+    link_kind::LinkKind,
+    link_listener::LinkListener,
+    link_name::unique_link_name,
     link_server::LinkServer,
+    link_service::{discover_services, ServiceInfo, ServiceRegistration},
+    list_writer::ListWriter,
+    number_digits::{NumberDigits, RealPrecision},
+    numeric_array::{NumericArray, NumericArrayKind, NumericArrayType},
+    path::{ExprPath, PathStep},
+    quantity_array::QuantityVector,
+    reader::LinkReader,
+    real_value::{InfinityDirection, NanPolicy, RealValue},
+    schema::ExprSchema,
+    shared_memory::{clean_stale_shared_memory, shared_memory_link_name},
+    sparse_array::SparseMatrix,
+    status::LinkStatus,
     strx::{Ucs2Str, Utf16Str, Utf32Str, Utf8Str},
+    symbol::SymbolStr,
+    temporal::TimeSeriesTime,
+    throttled_link::ThrottledLink,
+    transaction::Transaction,
+    version::InterfaceVersion,
+    watcher::{LinkWatcher, WatchId},
+};
+
+#[cfg(feature = "expr")]
+pub use crate::{
+    correlation::{PendingResponse, RequestClient},
+    expr_view::{AsExprView, ExprView},
+    options::{Options, OptionsParser, UnknownOptionPolicy},
+    progress::Progress,
+    queued_link::{OverflowPolicy, QueuedLink},
+    router::{LinkRouter, Subscription},
+    token_filter::{copy_expr_filtered, FilterAction, TokenFilter},
 };
 
+#[doc(hidden)]
+pub mod __private {
+    //! Implementation details of the exported macros.
+
+    pub use crate::macros::{put_with, PutValue};
+}
+
 // TODO: Make this function public from `wstp`?
 pub(crate) use env::stdenv;
 
@@ -202,7 +372,6 @@ pub(crate) use env::stdenv;
 /// *WSTP C API Documentation:* [`WSLINK`](https://reference.wolfram.com/language/ref/c/WSLINK.html)
 ///
 /// *Wolfram Language Documentation:* [`LinkObject`](https://reference.wolfram.com/language/ref/LinkObject.html)
-#[derive(Debug)]
 #[derive(ref_cast::RefCastCustom)]
 #[repr(transparent)]
 pub struct Link {
@@ -388,6 +557,36 @@ impl Link {
         }
     }
 
+    /// Create a new Loopback type link containing `exprs`.
+    ///
+    /// This is a convenient way to provide fixture data to code that reads from a
+    /// link. See also [`Link::loopback_with_wxf()`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wolfram_expr::Expr;
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::loopback_with(&[Expr::from(1), Expr::string("two")]).unwrap();
+    ///
+    /// assert_eq!(link.get_i64().unwrap(), 1);
+    /// assert_eq!(link.get_string().unwrap(), "two");
+    /// ```
+    #[cfg(feature = "expr")]
+    pub fn loopback_with<'e, I>(exprs: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = &'e Expr>,
+    {
+        let mut link = Link::new_loopback()?;
+
+        for expr in exprs {
+            link.put_expr(expr)?;
+        }
+
+        Ok(link)
+    }
+
     /// Create a new named WSTP link using `protocol`.
     pub fn listen(protocol: Protocol, name: &str) -> Result<Self, Error> {
         let protocol_string = protocol.to_string();
@@ -504,6 +703,82 @@ impl Link {
         Link::open_with_args(&strings)
     }
 
+    /// Connect to the program that launched this one, using the link named `name`.
+    ///
+    /// This opens the link in `parentconnect` mode, instead of the `connect` mode used
+    /// by [`Link::connect()`]. This is the mode expected by a Wolfram front end or
+    /// kernel that launches a program and waits for it to connect back to a link it
+    /// created.
+    ///
+    /// As with [`Link::connect()`], call [`Link::activate()`] to complete the
+    /// connection.
+    ///
+    /// Use [`Link::parent_connect_from_args()`] to get the link name and protocol from
+    /// the command-line arguments passed to this program by its parent.
+    pub fn parent_connect(protocol: Protocol, name: &str) -> Result<Self, Error> {
+        let protocol_string = protocol.to_string();
+
+        let strings: &[&str] = &[
+            "-wstp",
+            "-linkmode",
+            "parentconnect",
+            "-linkprotocol",
+            protocol_string.as_str(),
+            "-linkname",
+            name,
+        ];
+
+        Link::open_with_args(strings)
+    }
+
+    /// Connect to the program that launched this one, using the `-linkname` and
+    /// `-linkprotocol` given in the command-line arguments `args`.
+    ///
+    /// Other arguments are ignored. If `-linkprotocol` is not given, the default
+    /// protocol chosen by WSTP is used. An error is returned if `-linkname` is not
+    /// given.
+    ///
+    /// See [`Link::parent_connect()`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use wstp::Link;
+    ///
+    /// let args: Vec<String> = std::env::args().collect();
+    ///
+    /// let mut link = Link::parent_connect_from_args(&args).unwrap();
+    /// link.activate().unwrap();
+    /// ```
+    pub fn parent_connect_from_args<S: AsRef<str>>(args: &[S]) -> Result<Self, Error> {
+        let mut name: Option<&str> = None;
+        let mut protocol: Option<&str> = None;
+
+        let mut args = args.iter().map(AsRef::as_ref);
+
+        while let Some(arg) = args.next() {
+            match arg.to_ascii_lowercase().as_str() {
+                "-linkname" => name = args.next(),
+                "-linkprotocol" => protocol = args.next(),
+                _ => (),
+            }
+        }
+
+        let name = name.ok_or_else(|| {
+            Error::custom("no -linkname argument given by parent program".to_owned())
+        })?;
+
+        let mut strings: Vec<&str> = vec!["-wstp", "-linkmode", "parentconnect"];
+
+        if let Some(protocol) = protocol {
+            strings.extend(["-linkprotocol", protocol]);
+        }
+
+        strings.extend(["-linkname", name]);
+
+        Link::open_with_args(&strings)
+    }
+
     /// *WSTP C API Documentation:* [`WSOpenArgcArgv()`](https://reference.wolfram.com/language/ref/c/WSOpenArgcArgv.html)
     ///
     /// This function can be used to create a [`Link`] of any protocol and mode. Prefer
@@ -515,8 +790,8 @@ impl Link {
     /// * [`Link::tcpip_listen()`]
     /// * [`Link::tcpip_connect()`]
     /// * [`Link::connect_to_link_server()`]
+    /// * [`Link::parent_connect()`]
     // * [`Link::launch()`]
-    // * [`Link::parent_connect()`]
     pub fn open_with_args(args: &[&str]) -> Result<Self, Error> {
         // NOTE: Before returning, we must convert these back into CString's to
         //       deallocate them.
@@ -552,7 +827,12 @@ impl Link {
             return Err(Error::from_code(err));
         }
 
-        Ok(Link { raw_link })
+        let link = Link { raw_link };
+
+        let kind = LinkKind::from_args(args);
+        link.update_settings(|settings| settings.kind = Some(kind));
+
+        Ok(link)
     }
 
     /// Construct a [`Link`] from a raw [`WSLINK`] pointer.
@@ -560,14 +840,33 @@ impl Link {
         Link { raw_link }
     }
 
+    /// Activate this link, performing the handshake with the link end point at the
+    /// other side of the connection.
+    ///
+    /// Use [`Link::try_activate()`] to find out which stage of the handshake failed if
+    /// activation fails.
+    ///
     /// *WSTP C API Documentation:* [`WSActivate()`](https://reference.wolfram.com/language/ref/c/WSActivate.html)
     pub fn activate(&mut self) -> Result<(), Error> {
+        self.try_activate().map_err(ActivateError::into_error)
+    }
+
+    /// Activate this link, like [`Link::activate()`], returning an [`ActivateError`]
+    /// if activation fails.
+    ///
+    /// The returned [`ActivateError`] records which stage of the handshake failed,
+    /// which helps to diagnose connections between incompatible WSTP versions.
+    ///
+    /// *WSTP C API Documentation:* [`WSActivate()`](https://reference.wolfram.com/language/ref/c/WSActivate.html)
+    pub fn try_activate(&mut self) -> Result<(), ActivateError> {
         // Note: WSActivate() returns 0 in the event of an error, and sets an error
         //       code retrievable by WSError().
         if unsafe { sys::WSActivate(self.raw_link) } == 0 {
-            return Err(self.error_or_unknown());
+            return Err(ActivateError::new(self.error_or_unknown()));
         }
 
+        self.update_settings(|settings| settings.activated = true);
+
         Ok(())
     }
 
@@ -632,6 +931,42 @@ impl Link {
         }
     }
 
+    /// Set the name of this link.
+    ///
+    /// The name is returned by subsequent calls to [`Link::link_name()`], and can be
+    /// used to give links human-meaningful names in logs and diagnostics.
+    ///
+    /// Renaming a listening link does not change the name that connecting links must
+    /// use to connect to it. Use [`Link::link_name()`] to get the name needed to
+    /// connect before renaming the link.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.set_name("request-queue").unwrap();
+    ///
+    /// assert_eq!(link.link_name(), "request-queue");
+    /// ```
+    ///
+    /// *WSTP C API Documentation:* [`WSSetName()`](https://reference.wolfram.com/language/ref/c/WSSetName.html)
+    pub fn set_name(&mut self, name: &str) -> Result<(), Error> {
+        let name = CString::new(name).map_err(|err| {
+            Error::custom(format!("link name contains a nul byte: {}", err))
+        })?;
+
+        let result: *mut c_char = unsafe { sys::WSSetName(self.raw_link, name.as_ptr()) };
+
+        if result.is_null() {
+            return Err(self.error_or_unknown());
+        }
+
+        Ok(())
+    }
+
     /// Check if there is data ready to be read from this link.
     ///
     /// *WSTP C API Documentation:* [`WSReady()`](https://reference.wolfram.com/language/ref/c/WSReady.html)
@@ -641,6 +976,32 @@ impl Link {
         unsafe { WSReady(raw_link) != 0 }
     }
 
+    /// Get the number of bytes that have been put on this link, but not yet sent.
+    ///
+    /// *WSTP C API Documentation:* [`WSBytesToPut()`](https://reference.wolfram.com/language/ref/c/WSBytesToPut.html)
+    pub fn bytes_to_put(&self) -> Result<usize, Error> {
+        let mut bytes: i32 = 0;
+
+        if unsafe { sys::WSBytesToPut(self.raw_link, &mut bytes) } == 0 {
+            return Err(self.error_or_unknown());
+        }
+
+        Ok(usize::try_from(bytes).unwrap_or(0))
+    }
+
+    /// Get the number of bytes that have been received on this link, but not yet read.
+    ///
+    /// *WSTP C API Documentation:* [`WSBytesToGet()`](https://reference.wolfram.com/language/ref/c/WSBytesToGet.html)
+    pub fn bytes_to_get(&self) -> Result<usize, Error> {
+        let mut bytes: i32 = 0;
+
+        if unsafe { sys::WSBytesToGet(self.raw_link, &mut bytes) } == 0 {
+            return Err(self.error_or_unknown());
+        }
+
+        Ok(usize::try_from(bytes).unwrap_or(0))
+    }
+
     /// *WSTP C API Documentation:* [`WSIsLinkLoopback()`](https://reference.wolfram.com/language/ref/c/WSIsLinkLoopback.html)
     pub fn is_loopback(&self) -> bool {
         let Link { raw_link } = *self;
@@ -678,6 +1039,7 @@ impl Link {
         return Some(Error {
             code: Some(code),
             message: string,
+            poisoned: false,
         });
     }
 
@@ -688,14 +1050,22 @@ impl Link {
     ///
     /// *WSTP C API Documentation:* [`WSErrorMessage()`](https://reference.wolfram.com/language/ref/c/WSErrorMessage.html)
     pub fn error_message(&self) -> Option<String> {
-        self.error().map(|Error { message, code: _ }| message)
+        self.error().map(|Error { message, .. }| message)
     }
 
     /// Helper to create an [`Error`] instance even if the underlying link does not have
     /// an error code set.
+    ///
+    /// This also notifies the [`Link::on_closed()`] callbacks of this link if the error
+    /// indicates that the link has been closed.
     pub(crate) fn error_or_unknown(&self) -> Error {
-        self.error()
-            .unwrap_or_else(|| Error::custom("unknown error occurred on WSLINK".into()))
+        let err = self
+            .error()
+            .unwrap_or_else(|| Error::custom("unknown error occurred on WSLINK".into()));
+
+        self.notify_error(&err);
+
+        err
     }
 
     /// Clear errors on this link.
@@ -715,8 +1085,13 @@ impl Link {
         raw_link
     }
 
+    /// Get the raw user data object and function associated with this link.
+    ///
+    /// See also [`Link::typed_user_data()`], which provides safe access to typed user
+    /// data values.
+    ///
     /// *WSTP C API Documentation:* [`WSUserData`](https://reference.wolfram.com/language/ref/c/WSUserData.html)
-    pub unsafe fn user_data(&self) -> (*mut std::ffi::c_void, sys::WSUserFunction) {
+    pub unsafe fn raw_user_data(&self) -> (*mut std::ffi::c_void, sys::WSUserFunction) {
         let Link { raw_link } = *self;
 
         let mut user_func: sys::WSUserFunction = None;
@@ -726,16 +1101,46 @@ impl Link {
         (data_obj, user_func)
     }
 
+    /// Set the raw user data object and function associated with this link.
+    ///
+    /// Any typed user data value set using [`Link::set_typed_user_data()`] is dropped.
+    ///
     /// *WSTP C API Documentation:* [`WSSetUserData`](https://reference.wolfram.com/language/ref/c/WSSetUserData.html)
-    pub unsafe fn set_user_data(
+    pub unsafe fn set_raw_user_data(
         &mut self,
         data_obj: *mut std::ffi::c_void,
         user_func: sys::WSUserFunction,
     ) {
+        self.drop_user_data();
+
         let Link { raw_link } = *self;
 
         sys::WSSetUserData(raw_link, data_obj, user_func);
     }
+
+    /// Get the raw user data object and function associated with this link.
+    ///
+    /// # Safety
+    ///
+    /// See [`Link::raw_user_data()`].
+    #[deprecated(note = "Use Link::raw_user_data() instead")]
+    pub unsafe fn user_data(&self) -> (*mut std::ffi::c_void, sys::WSUserFunction) {
+        self.raw_user_data()
+    }
+
+    /// Set the raw user data object and function associated with this link.
+    ///
+    /// # Safety
+    ///
+    /// See [`Link::set_raw_user_data()`].
+    #[deprecated(note = "Use Link::set_raw_user_data() instead")]
+    pub unsafe fn set_user_data(
+        &mut self,
+        data_obj: *mut std::ffi::c_void,
+        user_func: sys::WSUserFunction,
+    ) {
+        self.set_raw_user_data(data_obj, user_func)
+    }
 }
 
 /// # Urgent messages
@@ -797,11 +1202,17 @@ impl Link {
         let param =
             u32::try_from(param).expect("WSTP urgent message param doesn't fit into u32");
 
-        if result != 0 {
-            Some(UrgentMessage { code, param })
-        } else {
-            None
+        if result == 0 {
+            return None;
         }
+
+        let message = UrgentMessage { code, param };
+
+        if message.code == UrgentMessage::TERMINATE.code {
+            self.notify_closed();
+        }
+
+        Some(message)
     }
 }
 
@@ -811,6 +1222,8 @@ impl Link {
     ///
     /// *WSTP C API Documentation:* [`WSFlush()`](https://reference.wolfram.com/language/ref/c/WSFlush.html)
     pub fn flush(&mut self) -> Result<(), Error> {
+        self.check_poisoned()?;
+
         if unsafe { sys::WSFlush(self.raw_link) } == 0 {
             return Err(self.error_or_unknown());
         }
@@ -850,15 +1263,140 @@ impl Link {
     }
 
     /// Read an expression off of this link.
+    #[cfg(feature = "expr")]
     pub fn get_expr(&mut self) -> Result<Expr, Error> {
         self.get_expr_with_resolver(&mut |_| None)
     }
 
+    /// Read every remaining expression off of this loopback link.
+    ///
+    /// This is useful when a loopback link is used as a queue of expressions. The
+    /// expressions are returned in the order they were written, and the link is empty
+    /// afterwards.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wolfram_expr::Expr;
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::loopback_with(&[Expr::from(1), Expr::string("two")]).unwrap();
+    ///
+    /// assert_eq!(
+    ///     link.get_all_exprs().unwrap(),
+    ///     vec![Expr::from(1), Expr::string("two")]
+    /// );
+    /// assert!(link.get_all_exprs().unwrap().is_empty());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `!self.is_loopback()`.
+    #[cfg(feature = "expr")]
+    pub fn get_all_exprs(&mut self) -> Result<Vec<Expr>, Error> {
+        if !self.is_loopback() {
+            panic!("get_all_exprs(): self must be a loopback link");
+        }
+
+        let mut exprs = Vec::new();
+
+        while self.is_ready() {
+            exprs.push(self.get_expr()?);
+        }
+
+        Ok(exprs)
+    }
+
+    /// Returns `true` if this loopback link does not contain any expressions.
+    ///
+    /// This does not read anything from the link, so it can be used to poll a loopback
+    /// link that is used as a buffer of expressions.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    /// assert!(link.is_loopback_empty());
+    ///
+    /// link.put_i64(1).unwrap();
+    /// assert!(!link.is_loopback_empty());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `!self.is_loopback()`.
+    pub fn is_loopback_empty(&self) -> bool {
+        if !self.is_loopback() {
+            panic!("is_loopback_empty(): self must be a loopback link");
+        }
+
+        !self.is_ready()
+    }
+
+    /// Get the number of expressions contained in this loopback link.
+    ///
+    /// WSTP does not keep track of the number of expressions on a loopback link, so
+    /// this moves every expression to a temporary loopback link while counting them,
+    /// and then moves them back. The contents of the link are left unchanged, but the
+    /// cost is proportional to the size of the contents. Prefer
+    /// [`Link::is_loopback_empty()`] when only emptiness is needed.
+    ///
+    /// If an expression can't be moved, the expressions already moved are put back
+    /// before the error is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wolfram_expr::Expr;
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::loopback_with(&[Expr::from(1), Expr::from(2)]).unwrap();
+    ///
+    /// assert_eq!(link.loopback_expr_count().unwrap(), 2);
+    /// assert_eq!(link.get_i64().unwrap(), 1);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `!self.is_loopback()`.
+    pub fn loopback_expr_count(&mut self) -> Result<usize, Error> {
+        if !self.is_loopback() {
+            panic!("loopback_expr_count(): self must be a loopback link");
+        }
+
+        let mut temp = Link::new_loopback()?;
+        let mut count = 0;
+
+        while self.is_ready() {
+            if let Err(err) = self.transfer_expr_to(&mut temp) {
+                // Move the expressions that were already counted back onto this link,
+                // ahead of the ones that were not, so that their order is preserved.
+                // These transfers are best effort: `err` describes the original
+                // failure, which is more useful than any error they might return.
+                let _ = self.transfer_to_end_of_loopback_link(&mut temp);
+                let _ = temp.transfer_to_end_of_loopback_link(self);
+
+                return Err(err);
+            }
+
+            count += 1;
+        }
+
+        if count > 0 {
+            temp.transfer_to_end_of_loopback_link(self)?;
+        }
+
+        Ok(count)
+    }
+
     // TODO: This needs a bit more design work before being made public. For starters,
     //       you have to pass a closure to it using `get_expr_with_resolver(&mut |_| ...)`
     //       which looks out of place. Using `dyn FnMut()` is to avoid having to
     //       monomorphize different copies of `get_expr_with_resolver()`
     #[doc(hidden)]
+    #[cfg(feature = "expr")]
     pub fn get_expr_with_resolver(
         &mut self,
         mut resolver: &mut dyn FnMut(&str) -> Option<Symbol>,
@@ -917,16 +1455,27 @@ impl Link {
     }
 
     /// Write an expression to this link.
+    ///
+    /// If [auto-flush][Link::set_auto_flush] is enabled, the link is flushed after the
+    /// expression has been written.
+    #[cfg(feature = "expr")]
     pub fn put_expr(&mut self, expr: &Expr) -> Result<(), Error> {
+        self.put_guarded(|link| link.put_expr_unflushed(expr))?;
+
+        self.flush_if_auto()
+    }
+
+    #[cfg(feature = "expr")]
+    fn put_expr_unflushed(&mut self, expr: &Expr) -> Result<(), Error> {
         match expr.kind() {
             ExprKind::Normal(normal) => {
                 self.put_raw_type(i32::from(sys::WSTKFUNC))?;
                 self.put_arg_count(normal.elements().len())?;
 
-                let _: () = self.put_expr(normal.head())?;
+                let _: () = self.put_expr_unflushed(normal.head())?;
 
                 for elem in normal.elements() {
-                    let _: () = self.put_expr(elem)?;
+                    let _: () = self.put_expr_unflushed(elem)?;
                 }
             },
             ExprKind::Symbol(symbol) => {
@@ -969,6 +1518,8 @@ impl Link {
     ///
     /// *WSTP C API Documentation:* [`WSTransferExpression()`](https://reference.wolfram.com/language/ref/c/WSTransferExpression.html)
     pub fn transfer_expr_to(&mut self, dest: &mut Link) -> Result<(), Error> {
+        dest.check_poisoned()?;
+
         let result = unsafe { sys::WSTransferExpression(dest.raw_link, self.raw_link) };
 
         if result == 0 {
@@ -1061,7 +1612,11 @@ impl Drop for Link {
         let Link { raw_link } = *self;
 
         unsafe {
+            self.drop_user_data();
+
             sys::WSClose(raw_link);
         }
+
+        let _ = settings::remove_settings(raw_link);
     }
 }
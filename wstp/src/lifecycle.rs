@@ -0,0 +1,91 @@
+//! Notification of link lifecycle events.
+
+use crate::{Error, Link};
+
+/// Callback registered using [`Link::on_closed()`].
+pub(crate) type ClosedCallback = Box<dyn FnOnce() + Send>;
+
+/// # Lifecycle events
+impl Link {
+    /// Register `callback` to be called when this link is closed by its peer.
+    ///
+    /// `callback` is called at most once, from the thread that detects that the link
+    /// has been closed. This happens when:
+    ///
+    /// * an operation on the link fails with an error for which
+    ///   [`Error::is_peer_closed()`] is `true`, or
+    /// * a [`UrgentMessage::TERMINATE`][crate::UrgentMessage::TERMINATE] message is
+    ///   received by [`Link::get_message()`].
+    ///
+    /// Callbacks that have not been called when the link is dropped are dropped without
+    /// being called.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use wstp::Protocol;
+    ///
+    /// let (a, mut b) = wstp::channel(Protocol::IntraProcess).unwrap();
+    ///
+    /// b.on_closed(|| println!("peer disconnected"));
+    ///
+    /// drop(a);
+    ///
+    /// // Prints "peer disconnected".
+    /// assert!(b.get_i64().is_err());
+    /// ```
+    pub fn on_closed<F>(&mut self, callback: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.update_settings(|settings| settings.on_closed.push(Box::new(callback)))
+    }
+
+    /// Returns `false` if the peer of this link has closed it, or the link has died.
+    ///
+    /// A link is known to be closed once an operation on it has failed with an error
+    /// for which [`Error::is_peer_closed()`] is `true`, or a
+    /// [`UrgentMessage::TERMINATE`][crate::UrgentMessage::TERMINATE] message has been
+    /// received on it. This can be used by a server to remove dead connections from
+    /// the set of links it is serving.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let link = Link::new_loopback().unwrap();
+    ///
+    /// assert!(link.is_open());
+    /// ```
+    pub fn is_open(&self) -> bool {
+        let is_closed = self.error().map_or(false, |err| err.is_peer_closed())
+            || self.with_settings(|settings| settings.peer_closed);
+
+        !is_closed
+    }
+
+    /// Notify the [`Link::on_closed()`] callbacks of this link if `err` indicates that
+    /// the link has been closed.
+    pub(crate) fn notify_error(&self, err: &Error) {
+        if err.is_peer_closed() {
+            self.notify_closed();
+        }
+    }
+
+    /// Mark this link as closed, and call and remove its [`Link::on_closed()`]
+    /// callbacks.
+    pub(crate) fn notify_closed(&self) {
+        // Note: The callbacks are removed from the settings before they are called, so
+        //       that they can use this link without deadlocking.
+        let callbacks = self.update_settings(|settings| {
+            settings.peer_closed = true;
+
+            std::mem::take(&mut settings.on_closed)
+        });
+
+        for callback in callbacks {
+            callback();
+        }
+    }
+}
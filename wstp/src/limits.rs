@@ -0,0 +1,199 @@
+//! Resource limits for data read from untrusted links.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{sys, Error, Link};
+
+/// Whether [`Link::set_limits()`] has ever been called.
+///
+/// This lets the getters skip looking up the settings of a link when no link has
+/// limits.
+static ANY_LIMITS: AtomicBool = AtomicBool::new(false);
+
+/// Limits on the size of the data read from a link.
+///
+/// Configured using [`Link::set_limits()`]. A limit of `None` means that size is not
+/// limited, which is the default.
+///
+/// # Example
+///
+/// ```
+/// use wstp::{Link, LinkLimits};
+///
+/// let mut link = Link::new_loopback().unwrap();
+///
+/// link.set_limits(LinkLimits {
+///     max_string_bytes: Some(4),
+///     ..LinkLimits::default()
+/// });
+///
+/// link.put_str("abc").unwrap();
+/// link.put_str("abcde").unwrap();
+///
+/// assert_eq!(link.get_string().unwrap(), "abc");
+/// assert!(link.get_string().is_err());
+/// ```
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct LinkLimits {
+    /// Maximum size in bytes of a string, symbol, or number, as stored on the link.
+    pub max_string_bytes: Option<usize>,
+    /// Maximum number of elements in a numeric array.
+    pub max_array_elems: Option<usize>,
+    /// Maximum number of arguments of a function expression.
+    pub max_arg_count: Option<usize>,
+}
+
+/// # Resource limits
+impl Link {
+    /// Set limits on the size of the strings, arrays, and function expressions read
+    /// from this link.
+    ///
+    /// The limits are enforced by the getters of this crate, including
+    /// [`Link::get_string()`], [`Link::get_arg_count()`], [`Link::get_i64_array()`],
+    /// and [`Link::get_expr()`], which return an error if a limit is exceeded.
+    ///
+    /// Every limit is checked against the size declared by the incoming data, before
+    /// it is read:
+    ///
+    /// * the size of a string, symbol, or number is the number of bytes WSTP reports
+    ///   for the token using [`WSBytesToGet()`][crate::sys::WSBytesToGet],
+    /// * the number of elements of an array is computed from the argument counts of
+    ///   its nested `List`s, and
+    /// * the argument count of a function is the one reported by WSTP.
+    ///
+    /// So data that exceeds a limit is rejected before WSTP or this crate allocate
+    /// memory to hold it, and is left unread on the link. The data has still been
+    /// received by the link, so the limits do not bound the size of the buffers used
+    /// by the link itself.
+    ///
+    /// The limits apply to all [`Link`] values referring to the same underlying
+    /// [`WSLINK`][crate::sys::WSLINK].
+    pub fn set_limits(&mut self, limits: LinkLimits) {
+        ANY_LIMITS.store(true, Ordering::Relaxed);
+
+        self.update_settings(|settings| settings.limits = limits)
+    }
+
+    /// Get the limits on the size of the data read from this link.
+    ///
+    /// See [`Link::set_limits()`].
+    pub fn limits(&self) -> LinkLimits {
+        if !ANY_LIMITS.load(Ordering::Relaxed) {
+            return LinkLimits::default();
+        }
+
+        self.with_settings(|settings| settings.limits)
+    }
+
+    /// Check the size of the next textual token against the string limit, before the
+    /// token is read.
+    ///
+    /// The size is the number of bytes reported by
+    /// [`WSBytesToGet()`][sys::WSBytesToGet] for the token.
+    pub(crate) fn check_string_limit(&self) -> Result<(), Error> {
+        let limit = match self.limits().max_string_bytes {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+
+        let type_ = u8::try_from(self.get_raw_type()?).unwrap_or(0);
+
+        match type_ {
+            sys::WSTKSTR | sys::WSTKSYM => {
+                check(self.bytes_to_get()?, Some(limit), "string", "bytes")
+            },
+            sys::WSTKINT | sys::WSTKREAL => match self.bytes_to_get() {
+                Ok(bytes) => check(bytes, Some(limit), "string", "bytes"),
+                // Note: A number that isn't stored as text is a fixed-size machine
+                //       number, so it doesn't need to be limited.
+                Err(_) => {
+                    unsafe { sys::WSClearError(self.raw_link) };
+                    Ok(())
+                },
+            },
+            // Note: Other tokens are rejected by the getter that reads them.
+            _ => Ok(()),
+        }
+    }
+
+    /// Check the dimensions of the next array against the array limit, before the
+    /// array is read.
+    ///
+    /// The dimensions are found by reading only the function headers along the first
+    /// element of each level of the array, and then seeking back to the start of the
+    /// array.
+    pub(crate) fn check_array_limit(&self) -> Result<(), Error> {
+        let limit = match self.limits().max_array_elems {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+
+        let raw_link = self.raw_link;
+
+        let mark: sys::WSMARK = unsafe { sys::WSCreateMark(raw_link) };
+
+        if mark.is_null() {
+            return Err(self.error_or_unknown());
+        }
+
+        let mut elems: usize = 1;
+
+        unsafe {
+            let mut type_ = sys::WSGetNext(raw_link);
+
+            while type_ == i32::from(sys::WSTKFUNC) {
+                let mut arg_count: i32 = 0;
+
+                if sys::WSGetArgCount(raw_link, &mut arg_count) == 0 {
+                    type_ = sys::WSTKERR;
+                    break;
+                }
+
+                elems = elems.saturating_mul(usize::try_from(arg_count).unwrap_or(0));
+
+                if elems == 0 {
+                    break;
+                }
+
+                // Skip the head, and move to the first element of this level.
+                let _: i32 = sys::WSGetNext(raw_link);
+                type_ = sys::WSGetNext(raw_link);
+            }
+
+            // Note: An expression that isn't an array is rejected by the getter that
+            //       reads it, so errors from reading ahead are discarded.
+            if type_ == sys::WSTKERR {
+                sys::WSClearError(raw_link);
+            }
+
+            let _: sys::WSMARK = sys::WSSeekToMark(raw_link, mark, 0);
+            sys::WSDestroyMark(raw_link, mark);
+        }
+
+        check(elems, Some(limit), "array", "elements")
+    }
+
+    pub(crate) fn check_arg_count_limit(&self, arg_count: usize) -> Result<(), Error> {
+        check(
+            arg_count,
+            self.limits().max_arg_count,
+            "function",
+            "arguments",
+        )
+    }
+}
+
+fn check(
+    size: usize,
+    limit: Option<usize>,
+    what: &str,
+    units: &str,
+) -> Result<(), Error> {
+    match limit {
+        Some(limit) if size > limit => Err(Error::custom(format!(
+            "{} with {} {} exceeds link limit of {} {}",
+            what, size, units, limit, units
+        ))),
+        _ => Ok(()),
+    }
+}
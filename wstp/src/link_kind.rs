@@ -0,0 +1,96 @@
+//! Introspection of how a link was created.
+
+use crate::{Link, Protocol};
+
+/// How a [`Link`] was created.
+///
+/// Returned by [`Link::kind()`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LinkKind {
+    /// Loopback link, created by [`Link::new_loopback()`].
+    Loopback,
+    /// Link created in `listen` mode, e.g. by [`Link::listen()`].
+    Listener(Protocol),
+    /// Link created in `connect` mode, e.g. by [`Link::connect()`].
+    Connector(Protocol),
+    /// Link created in `launch` mode, which launches a program and connects to it.
+    Launch,
+    /// Link created in `parentconnect` mode, which connects to the program that
+    /// launched this one, e.g. by [`Link::parent_connect()`].
+    ParentConnect,
+    /// The way the link was created is not known.
+    ///
+    /// This is the case for links created using [`Link::unchecked_new()`], including
+    /// links accepted by a [`LinkServer`][crate::LinkServer], and links whose protocol
+    /// is not a [`Protocol`].
+    Unknown,
+}
+
+impl LinkKind {
+    /// Determine the kind of link that will be created by [`Link::open_with_args()`]
+    /// from `args`.
+    pub(crate) fn from_args(args: &[&str]) -> LinkKind {
+        let mut mode: Option<&str> = None;
+        let mut protocol: Option<&str> = None;
+
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            match arg.to_ascii_lowercase().as_str() {
+                "-linkmode" => mode = args.next().copied(),
+                "-linkprotocol" => protocol = args.next().copied(),
+                "-linkcreate" => mode = Some("listen"),
+                "-linkconnect" => mode = Some("connect"),
+                "-linklaunch" => mode = Some("launch"),
+                _ => (),
+            }
+        }
+
+        let protocol = protocol.and_then(|protocol| {
+            [
+                Protocol::IntraProcess,
+                Protocol::SharedMemory,
+                Protocol::TCPIP,
+            ]
+            .into_iter()
+            .find(|known| known.to_string().eq_ignore_ascii_case(protocol))
+        });
+
+        match (mode.map(str::to_ascii_lowercase).as_deref(), protocol) {
+            (Some("loopback"), _) => LinkKind::Loopback,
+            (Some("launch"), _) => LinkKind::Launch,
+            (Some("parentconnect"), _) => LinkKind::ParentConnect,
+            (Some("listen" | "create"), Some(protocol)) => LinkKind::Listener(protocol),
+            (Some("connect"), Some(protocol)) => LinkKind::Connector(protocol),
+            _ => LinkKind::Unknown,
+        }
+    }
+}
+
+impl Link {
+    /// Get the kind of this link, which describes how it was created.
+    ///
+    /// Generic code can use this to adapt its behavior to the link, e.g. by not calling
+    /// [`Link::activate()`] on loopback links.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{Link, LinkKind, Protocol};
+    ///
+    /// let loopback = Link::new_loopback().unwrap();
+    /// assert_eq!(loopback.kind(), LinkKind::Loopback);
+    ///
+    /// let listener = Link::listen(Protocol::IntraProcess, "").unwrap();
+    /// assert_eq!(listener.kind(), LinkKind::Listener(Protocol::IntraProcess));
+    /// ```
+    pub fn kind(&self) -> LinkKind {
+        if self.is_loopback() {
+            return LinkKind::Loopback;
+        }
+
+        self.with_settings(|settings| settings.kind.clone())
+            .unwrap_or(LinkKind::Unknown)
+    }
+}
@@ -0,0 +1,181 @@
+//! Queue of accepted [`TCPIP`][crate::Protocol::TCPIP] connections.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use crate::{Error, Link, LinkServer};
+
+/// Serving socket that accepts any number of [`TCPIP`][crate::Protocol::TCPIP] link
+/// connections, and queues them until they are [accepted][LinkListener::accept].
+///
+/// A link created using [`Link::listen()`] or [`Link::tcpip_listen()`] is a single
+/// link end point that waits for *one* connection. Once a peer has connected, other
+/// peers that try to connect to the same port are refused.
+///
+/// A [`LinkListener`] is instead a serving socket: each connection made to it
+/// creates a new [`Link`]. Connections are accepted by a background thread as soon as
+/// they arrive, and are held in a queue until they are retrieved using
+/// [`LinkListener::accept()`], so peers that connect at nearly the same time are not
+/// refused while the application is busy handling an earlier connection.
+///
+/// At most `backlog` connections are queued. Connections that arrive while the queue
+/// is full are closed immediately, and counted by [`LinkListener::refused()`].
+///
+/// Use [`Link::connect_to_link_server()`] to connect to a [`LinkListener`].
+///
+/// # Example
+///
+/// ```no_run
+/// use wstp::{Link, LinkListener};
+///
+/// let listener = LinkListener::new(11235, 16).unwrap();
+///
+/// let mut client = Link::connect_to_link_server("127.0.0.1:11235").unwrap();
+///
+/// let mut conn = listener.accept();
+///
+/// conn.put_i64(5).unwrap();
+/// conn.flush().unwrap();
+///
+/// assert_eq!(client.get_i64().unwrap(), 5);
+/// ```
+#[derive(Debug)]
+pub struct LinkListener {
+    server: LinkServer,
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<State>,
+    /// Notified when a connection is added to the queue.
+    not_empty: Condvar,
+    backlog: usize,
+}
+
+#[derive(Debug)]
+struct State {
+    queue: VecDeque<Link>,
+    refused: usize,
+}
+
+impl LinkListener {
+    /// Create a new listener that accepts connections on `port`, queuing up to
+    /// `backlog` connections.
+    pub fn new(port: u16, backlog: usize) -> Result<Self, Error> {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                queue: VecDeque::with_capacity(backlog),
+                refused: 0,
+            }),
+            not_empty: Condvar::new(),
+            backlog,
+        });
+
+        let server = {
+            let shared = Arc::clone(&shared);
+
+            LinkServer::new_with_callback(port, move |link| {
+                let mut state = shared.lock();
+
+                if state.queue.len() >= shared.backlog {
+                    // Closes the connection.
+                    drop(link);
+                    state.refused += 1;
+                    return;
+                }
+
+                state.queue.push_back(link);
+                drop(state);
+
+                shared.not_empty.notify_one();
+            })?
+        };
+
+        Ok(LinkListener { server, shared })
+    }
+
+    /// Take the oldest queued connection, blocking the current thread until a
+    /// connection is made if the queue is empty.
+    pub fn accept(&self) -> Link {
+        let mut state = self.shared.lock();
+
+        loop {
+            if let Some(link) = state.queue.pop_front() {
+                return link;
+            }
+
+            state = self.shared.not_empty.wait(state).expect(POISONED);
+        }
+    }
+
+    /// Take the oldest queued connection, waiting up to `timeout` for a connection to
+    /// be made if the queue is empty.
+    ///
+    /// Returns `None` if no connection was made before `timeout` elapsed.
+    pub fn accept_timeout(&self, timeout: Duration) -> Option<Link> {
+        let deadline = Instant::now() + timeout;
+
+        let mut state = self.shared.lock();
+
+        loop {
+            if let Some(link) = state.queue.pop_front() {
+                return Some(link);
+            }
+
+            let remaining = deadline.checked_duration_since(Instant::now())?;
+
+            state = self
+                .shared
+                .not_empty
+                .wait_timeout(state, remaining)
+                .expect(POISONED)
+                .0;
+        }
+    }
+
+    /// Take the oldest queued connection, without blocking.
+    ///
+    /// Returns `None` if the queue is empty.
+    pub fn try_accept(&self) -> Option<Link> {
+        self.shared.lock().queue.pop_front()
+    }
+
+    /// Get the number of connections currently waiting in the queue.
+    pub fn pending(&self) -> usize {
+        self.shared.lock().queue.len()
+    }
+
+    /// Get the number of connections that were closed because the queue was full.
+    pub fn refused(&self) -> usize {
+        self.shared.lock().refused
+    }
+
+    /// Get the maximum number of connections held in the queue.
+    pub fn backlog(&self) -> usize {
+        self.shared.backlog
+    }
+
+    /// Returns the TCPIP port number used by this listener.
+    ///
+    /// See [`LinkServer::port()`].
+    pub fn port(&self) -> u16 {
+        self.server.port()
+    }
+
+    /// Get the [`LinkServer`] that accepts connections for this listener.
+    pub fn link_server(&self) -> &LinkServer {
+        &self.server
+    }
+}
+
+const POISONED: &str = "LinkListener state lock was poisoned";
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect(POISONED)
+    }
+}
@@ -0,0 +1,183 @@
+//! Generation of unique link names.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash, Hasher},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{sys, Error, Link, Protocol};
+
+/// Prefix of the names generated by [`unique_link_name()`].
+pub(crate) const NAME_PREFIX: &str = "wstprs";
+
+/// Number of names tried by [`Link::listen_shared_memory()`] and
+/// [`Link::listen_any()`] before giving up.
+const LISTEN_ATTEMPTS: usize = 8;
+
+/// Generate a link name for `protocol` that is unique to this process and call.
+///
+/// The generated name combines the ID of the current process, the current time, and a
+/// random value, and has the form:
+///
+/// ```text
+/// wstprs-<process id>-<time>-<random>
+/// ```
+///
+/// The name is adjusted for and validated against the naming rules of `protocol`:
+///
+/// * [`Protocol::IntraProcess`] and [`Protocol::SharedMemory`] names are returned in
+///   the form above.
+/// * [`Protocol::TCPIP`] names are port numbers, which can't be generated this way,
+///   so an error is always returned. Use [`Link::listen_any()`] to listen on ports
+///   chosen automatically.
+///
+/// See also [`Link::listen_any()`],
+/// [`shared_memory_link_name()`][crate::shared_memory_link_name], and
+/// [`Link::listen_shared_memory()`].
+///
+/// # Example
+///
+/// ```
+/// use wstp::{Link, Protocol};
+///
+/// let name = wstp::unique_link_name(Protocol::SharedMemory).unwrap();
+///
+/// let _listener = Link::listen(Protocol::SharedMemory, &name).unwrap();
+/// ```
+pub fn unique_link_name(protocol: Protocol) -> Result<String, Error> {
+    let name = match protocol {
+        Protocol::IntraProcess | Protocol::SharedMemory => generate_name(),
+        Protocol::TCPIP => return Err(tcpip_name_error()),
+    };
+
+    validate_link_name(protocol, &name)?;
+
+    Ok(name)
+}
+
+fn tcpip_name_error() -> Error {
+    Error::custom(
+        "unique_link_name(): TCPIP link names are port numbers, which can't be \
+         generated; use Link::listen_any() to listen on automatically chosen ports"
+            .to_owned(),
+    )
+}
+
+/// Generate a name of the form `wstprs-<process id>-<time>-<random>`.
+pub(crate) fn generate_name() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or(0);
+
+    // `RandomState` is seeded with random keys, so hashing gives a random value
+    // without depending on a random number generator crate.
+    let mut hasher = RandomState::new().build_hasher();
+    time.hash(&mut hasher);
+    COUNTER.fetch_add(1, Ordering::Relaxed).hash(&mut hasher);
+    let random = hasher.finish() as u32;
+
+    format!(
+        "{}-{}-{:x}-{:08x}",
+        NAME_PREFIX,
+        std::process::id(),
+        time,
+        random
+    )
+}
+
+/// Check that `name` follows the naming rules of `protocol`.
+fn validate_link_name(protocol: Protocol, name: &str) -> Result<(), Error> {
+    let invalid = |reason: &str| {
+        Err(Error::custom(format!(
+            "unique_link_name(): invalid {:?} link name \"{}\": {}",
+            protocol, name, reason
+        )))
+    };
+
+    if name.is_empty() {
+        return invalid("name is empty");
+    }
+
+    // Link names are passed to WSTP as C strings.
+    if name.contains('\0') {
+        return invalid("name contains a NUL byte");
+    }
+
+    match protocol {
+        Protocol::IntraProcess | Protocol::SharedMemory => {
+            if name.contains(['/', '\\']) {
+                return invalid("name contains a path separator");
+            }
+        },
+        Protocol::TCPIP => {
+            if name.parse::<u16>().is_err() {
+                return invalid("name is not a port number");
+            }
+        },
+    }
+
+    Ok(())
+}
+
+/// # Listening with generated names
+impl Link {
+    /// Create a new link in `listen` mode using `protocol`, with an automatically
+    /// chosen name, returning the link and the name needed to connect to it.
+    ///
+    /// The name is chosen as is appropriate for `protocol`:
+    ///
+    /// * [`Protocol::IntraProcess`] links ignore the name given to [`Link::listen()`]
+    ///   and generate their own, so the generated name is used.
+    /// * [`Protocol::SharedMemory`] links use [`Link::listen_shared_memory()`].
+    /// * [`Protocol::TCPIP`] links listen on ports chosen by WSTP.
+    ///
+    /// The returned name is the [`Link::link_name()`] of the listener, and can be
+    /// passed unchanged to [`Link::connect()`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{Link, Protocol};
+    ///
+    /// let (_listener, name) = Link::listen_any(Protocol::IntraProcess).unwrap();
+    ///
+    /// let _connector = Link::connect(Protocol::IntraProcess, &name).unwrap();
+    /// ```
+    pub fn listen_any(protocol: Protocol) -> Result<(Link, String), Error> {
+        let link = match protocol {
+            Protocol::IntraProcess | Protocol::TCPIP => Link::listen(protocol, "")?,
+            Protocol::SharedMemory => Link::listen_shared_memory()?,
+        };
+
+        let name = link.link_name();
+
+        Ok((link, name))
+    }
+}
+
+/// Create a new link in `listen` mode using `protocol` and a name returned by
+/// `generate_name`, retrying with a new name if the name is taken.
+pub(crate) fn listen_with_generated_name<F>(
+    protocol: Protocol,
+    mut generate_name: F,
+) -> Result<Link, Error>
+where
+    F: FnMut() -> Result<String, Error>,
+{
+    let mut last_error = None;
+
+    for _ in 0..LISTEN_ATTEMPTS {
+        match Link::listen(protocol.clone(), &generate_name()?) {
+            Ok(link) => return Ok(link),
+            Err(err) if err.code() == Some(sys::WSENAMETAKEN) => last_error = Some(err),
+            Err(err) => return Err(err),
+        }
+    }
+
+    Err(last_error.expect("LISTEN_ATTEMPTS is zero"))
+}
@@ -0,0 +1,448 @@
+//! Advertising and discovering links on the local network using ZeroConf.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::fmt;
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::{stdenv, sys, Error, Link, Protocol};
+
+/// Registration of a link service on the local network.
+///
+/// Returned by [`Link::register_service()`]. The service is advertised until this value
+/// is dropped or [`ServiceRegistration::stop()`] is called.
+pub struct ServiceRegistration {
+    raw_ref: sys::WSServiceRef,
+    /// Pointer to a `Box<Mutex<Option<String>>>` holding the registered name, which is
+    /// set by [`register_trampoline()`].
+    registered_name: *mut Mutex<Option<String>>,
+    requested_name: String,
+}
+
+// SAFETY: `raw_ref` is only used to stop the registration, and `registered_name` is
+//         only accessed while holding the lock it contains.
+unsafe impl Send for ServiceRegistration {}
+
+/// A link service discovered on the local network.
+///
+/// Returned by [`discover_services()`]. Use [`Link::connect_to_service()`] to connect to
+/// the link advertising this service.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct ServiceInfo {
+    /// The name the service is registered with.
+    pub name: String,
+    /// The name of the link advertising the service.
+    pub link_name: String,
+    /// The protocol of the link advertising the service, e.g. `"TCPIP"`.
+    pub protocol: String,
+}
+
+/// State shared with [`browse_trampoline()`] and [`resolve_trampoline()`].
+#[derive(Default)]
+struct Discovery {
+    names: BTreeSet<String>,
+    /// The most recent resolution of each service, by service name.
+    resolved: BTreeMap<String, ServiceInfo>,
+}
+
+type DiscoveryContext = (Mutex<Discovery>, Condvar);
+
+/// Browse and resolve operations started by [`discover_services()`].
+///
+/// Dropping this stops every operation that was started, and then frees the
+/// [`DiscoveryContext`] they use, so that the context is released on every exit path
+/// of [`discover_services()`], including early returns.
+struct DiscoveryOperations {
+    raw_env: sys::WSENV,
+    context: *mut DiscoveryContext,
+    browse_ref: Option<sys::WSServiceRef>,
+    resolve_refs: Vec<sys::WSServiceRef>,
+}
+
+/// Discover the link services advertised on the local network, e.g. using
+/// [`Link::register_service()`].
+///
+/// This browses for services for `timeout`, and then waits up to `timeout` again for
+/// the link names of the services found to be resolved. Services which are not
+/// resolved in time, e.g. because they have stopped being advertised, are not
+/// returned.
+///
+/// This function blocks the current thread for at least `timeout`, and at most twice
+/// `timeout`, even if every service is found sooner. Asynchronous programs should
+/// call it from a blocking task.
+///
+/// *WSTP C API Documentation:* [`WSBrowseForLinkServices()`](https://reference.wolfram.com/language/ref/c/WSBrowseForLinkServices.html),
+/// [`WSResolveLinkService()`](https://reference.wolfram.com/language/ref/c/WSResolveLinkService.html)
+///
+/// # Example
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use wstp::Link;
+///
+/// let services = wstp::discover_services(Duration::from_secs(2)).unwrap();
+///
+/// if let Some(service) = services.iter().find(|service| service.name == "MyService") {
+///     let mut link = Link::connect_to_service(service).unwrap();
+///     link.activate().unwrap();
+/// }
+/// ```
+pub fn discover_services(timeout: Duration) -> Result<Vec<ServiceInfo>, Error> {
+    let protocol = CString::new(Protocol::TCPIP.to_string())
+        .expect("failed to create CString from link service protocol");
+
+    let mut ops = DiscoveryOperations {
+        raw_env: stdenv()?.raw_env,
+        context: Box::into_raw(Box::new((
+            Mutex::new(Discovery::default()),
+            Condvar::new(),
+        ))),
+        browse_ref: None,
+        resolve_refs: Vec::new(),
+    };
+
+    // SAFETY: `ops.context` is only freed when `ops` is dropped, after browsing and
+    //         resolving have been stopped.
+    let (discovery, condvar) = unsafe { &*ops.context };
+
+    //==================================
+    // Browse for service names
+    //==================================
+
+    let mut browse_ref: sys::WSServiceRef = std::ptr::null_mut();
+
+    let err = unsafe {
+        sys::WSBrowseForLinkServices(
+            ops.raw_env,
+            Some(browse_trampoline),
+            protocol.as_ptr(),
+            std::ptr::null(),
+            ops.context as *mut c_void,
+            &mut browse_ref,
+        )
+    };
+
+    if err != sys::MLEOK {
+        return Err(Error::from_code(err));
+    }
+
+    ops.browse_ref = Some(browse_ref);
+
+    std::thread::sleep(timeout);
+
+    ops.stop_browsing();
+
+    let names: BTreeSet<String> = lock(discovery).names.clone();
+
+    //==================================
+    // Resolve the link of each service
+    //==================================
+
+    // Names of the services being resolved.
+    let mut resolving: Vec<&str> = Vec::new();
+
+    for name in &names {
+        let c_name = match CString::new(name.as_str()) {
+            Ok(c_name) => c_name,
+            Err(_) => continue,
+        };
+
+        let mut resolve_ref: sys::WSServiceRef = std::ptr::null_mut();
+
+        let err = unsafe {
+            sys::WSResolveLinkService(
+                ops.raw_env,
+                Some(resolve_trampoline),
+                protocol.as_ptr(),
+                c_name.as_ptr(),
+                ops.context as *mut c_void,
+                &mut resolve_ref,
+            )
+        };
+
+        if err == sys::MLEOK {
+            ops.resolve_refs.push(resolve_ref);
+            resolving.push(name.as_str());
+        }
+    }
+
+    let deadline = Instant::now() + timeout;
+    let mut state = lock(discovery);
+
+    // Note: A service may be resolved more than once, so completion is tracked per
+    //       service, instead of by counting resolutions.
+    while !resolving
+        .iter()
+        .all(|&name| state.resolved.contains_key(name))
+    {
+        let now = Instant::now();
+        if now >= deadline {
+            break;
+        }
+
+        state = condvar
+            .wait_timeout(state, deadline - now)
+            .expect("failed to acquire lock on link service discovery")
+            .0;
+    }
+
+    let resolved: Vec<ServiceInfo> = state.resolved.values().cloned().collect();
+
+    drop(state);
+
+    Ok(resolved)
+}
+
+impl DiscoveryOperations {
+    fn stop_browsing(&mut self) {
+        if let Some(browse_ref) = self.browse_ref.take() {
+            unsafe { sys::WSStopBrowsingForLinkServices(self.raw_env, browse_ref) };
+        }
+    }
+}
+
+impl Drop for DiscoveryOperations {
+    fn drop(&mut self) {
+        self.stop_browsing();
+
+        for resolve_ref in self.resolve_refs.drain(..) {
+            unsafe { sys::WSStopResolvingLinkService(self.raw_env, resolve_ref) };
+        }
+
+        // SAFETY: Browsing and resolving have been stopped, so the callbacks will not
+        //         access `context` again.
+        drop(unsafe { Box::from_raw(self.context) });
+    }
+}
+
+/// # Link services
+impl Link {
+    /// Create a new [`TCPIP`][Protocol::TCPIP] link, and advertise it on the local
+    /// network as a service named `name`.
+    ///
+    /// Programs on the local network can discover the service using ZeroConf (Bonjour),
+    /// the same way Wolfram front ends discover kernels, and connect to the returned
+    /// link. The returned link must be activated with [`Link::activate()`] before it
+    /// can be used.
+    ///
+    /// *WSTP C API Documentation:* [`WSRegisterLinkService()`](https://reference.wolfram.com/language/ref/c/WSRegisterLinkService.html)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use wstp::Link;
+    ///
+    /// let (mut link, registration) = Link::register_service("MyService").unwrap();
+    ///
+    /// // Wait for a peer that discovered the service to connect.
+    /// link.activate().unwrap();
+    ///
+    /// // Stop advertising the service.
+    /// registration.stop();
+    /// ```
+    pub fn register_service(name: &str) -> Result<(Link, ServiceRegistration), Error> {
+        let protocol = CString::new(Protocol::TCPIP.to_string())
+            .expect("failed to create CString from link service protocol");
+        let c_name = CString::new(name).map_err(|err| {
+            Error::custom(format!("invalid link service name: {}", err))
+        })?;
+
+        let registered_name: *mut Mutex<Option<String>> =
+            Box::into_raw(Box::new(Mutex::new(None)));
+
+        let mut raw_ref: sys::WSServiceRef = std::ptr::null_mut();
+        let mut err: c_int = sys::MLEOK;
+
+        let raw_link = unsafe {
+            sys::WSRegisterLinkService(
+                stdenv()?.raw_env,
+                protocol.as_ptr(),
+                c_name.as_ptr(),
+                Some(register_trampoline),
+                std::ptr::null(),
+                registered_name as *mut c_void,
+                &mut raw_ref,
+                &mut err,
+            )
+        };
+
+        if raw_link.is_null() || err != sys::MLEOK {
+            if !raw_ref.is_null() {
+                unsafe { sys::WSStopRegisteringLinkService(stdenv()?.raw_env, raw_ref) };
+            }
+
+            // SAFETY: The registration has been stopped, so the callback will not
+            //         access `registered_name` again.
+            drop(unsafe { Box::from_raw(registered_name) });
+
+            return Err(Error::from_code(err));
+        }
+
+        let link = unsafe { Link::unchecked_new(raw_link) };
+
+        let registration = ServiceRegistration {
+            raw_ref,
+            registered_name,
+            requested_name: name.to_owned(),
+        };
+
+        Ok((link, registration))
+    }
+
+    /// Connect to the link advertising `service`.
+    ///
+    /// The returned link must be activated with [`Link::activate()`] before it can be
+    /// used.
+    pub fn connect_to_service(service: &ServiceInfo) -> Result<Link, Error> {
+        Link::open_with_args(&[
+            "-wstp",
+            "-linkmode",
+            "connect",
+            "-linkprotocol",
+            service.protocol.as_str(),
+            "-linkname",
+            service.link_name.as_str(),
+        ])
+    }
+}
+
+impl ServiceRegistration {
+    /// Get the name the service was registered with.
+    ///
+    /// If another service on the network already uses the requested name, ZeroConf may
+    /// register the service under a different name. This returns `None` if the
+    /// registration has not completed yet.
+    pub fn registered_name(&self) -> Option<String> {
+        let registered_name = unsafe { &*self.registered_name };
+
+        registered_name
+            .lock()
+            .expect("failed to acquire lock on link service name")
+            .clone()
+    }
+
+    /// Get the name passed to [`Link::register_service()`].
+    pub fn requested_name(&self) -> &str {
+        &self.requested_name
+    }
+
+    /// Stop advertising the service.
+    ///
+    /// This does not close the link the service was registered for.
+    ///
+    /// *WSTP C API Documentation:* [`WSStopRegisteringLinkService()`](https://reference.wolfram.com/language/ref/c/WSStopRegisteringLinkService.html)
+    pub fn stop(self) {
+        // Note: The registration is stopped when `self` is dropped.
+    }
+}
+
+unsafe extern "C" fn register_trampoline(
+    _env: sys::WSENV,
+    _ref: sys::WSServiceRef,
+    _flag: c_int,
+    service_name: *const c_char,
+    context: *mut c_void,
+) {
+    if service_name.is_null() || context.is_null() {
+        return;
+    }
+
+    let registered_name = &*(context as *const Mutex<Option<String>>);
+    let service_name = CStr::from_ptr(service_name).to_string_lossy().into_owned();
+
+    if let Ok(mut registered_name) = registered_name.lock() {
+        *registered_name = Some(service_name);
+    }
+}
+
+// Note: The browse callback is called both when a service is added and when it is
+//       removed. Every service name seen is recorded, and names of services which have
+//       been removed are expected to fail to resolve.
+unsafe extern "C" fn browse_trampoline(
+    _env: sys::WSENV,
+    _ref: sys::WSServiceRef,
+    _flag: c_int,
+    service_name: *const c_char,
+    context: *mut c_void,
+) {
+    if service_name.is_null() || context.is_null() {
+        return;
+    }
+
+    let (discovery, _) = &*(context as *const DiscoveryContext);
+    let service_name = CStr::from_ptr(service_name).to_string_lossy().into_owned();
+
+    if let Ok(mut discovery) = discovery.lock() {
+        discovery.names.insert(service_name);
+    }
+}
+
+unsafe extern "C" fn resolve_trampoline(
+    _env: sys::WSENV,
+    _ref: sys::WSServiceRef,
+    service_name: *const c_char,
+    link_name: *const c_char,
+    protocol: *const c_char,
+    _options: c_int,
+    context: *mut c_void,
+) {
+    if service_name.is_null() || link_name.is_null() || context.is_null() {
+        return;
+    }
+
+    let (discovery, condvar) = &*(context as *const DiscoveryContext);
+
+    let protocol = if protocol.is_null() {
+        Protocol::TCPIP.to_string()
+    } else {
+        CStr::from_ptr(protocol).to_string_lossy().into_owned()
+    };
+
+    let service = ServiceInfo {
+        name: CStr::from_ptr(service_name).to_string_lossy().into_owned(),
+        link_name: CStr::from_ptr(link_name).to_string_lossy().into_owned(),
+        protocol,
+    };
+
+    if let Ok(mut discovery) = discovery.lock() {
+        discovery.resolved.insert(service.name.clone(), service);
+        condvar.notify_all();
+    }
+}
+
+fn lock(discovery: &Mutex<Discovery>) -> std::sync::MutexGuard<'_, Discovery> {
+    discovery
+        .lock()
+        .expect("failed to acquire lock on link service discovery")
+}
+
+impl Drop for ServiceRegistration {
+    fn drop(&mut self) {
+        let ServiceRegistration {
+            raw_ref,
+            registered_name,
+            requested_name: _,
+        } = *self;
+
+        // If the environment is unavailable, leak `registered_name` rather than risk
+        // the registration callback using it after it has been freed.
+        if let Ok(env) = stdenv() {
+            unsafe {
+                sys::WSStopRegisteringLinkService(env.raw_env, raw_ref);
+
+                drop(Box::from_raw(registered_name));
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ServiceRegistration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ServiceRegistration")
+            .field("requested_name", &self.requested_name)
+            .field("registered_name", &self.registered_name())
+            .finish()
+    }
+}
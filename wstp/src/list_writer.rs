@@ -0,0 +1,94 @@
+//! Writing `List` expressions whose length is not known in advance.
+
+use crate::{Error, Link};
+
+/// Builder for a `List` expression whose length is not known until the last element
+/// has been written.
+///
+/// WSTP requires the argument count of a function to be written before its
+/// arguments. `ListWriter` stages each element in a loopback link as it is written,
+/// and then writes the complete `List` to the destination link in
+/// [`ListWriter::finish()`]. Elements are staged in their serialized WSTP form, so no
+/// [`Expr`][wolfram_expr::Expr] tree is ever constructed.
+///
+/// # Example
+///
+/// ```
+/// use wstp::{Link, ListWriter};
+///
+/// let mut list = ListWriter::new().unwrap();
+///
+/// for value in (1..).take_while(|value| value * value < 20) {
+///     list.push(|link| link.put_i64(value * value)).unwrap();
+/// }
+///
+/// let mut link = Link::new_loopback().unwrap();
+///
+/// // Put {1, 4, 9, 16}
+/// list.finish(&mut link).unwrap();
+///
+/// assert_eq!(link.get_i64_array().unwrap().data(), &[1, 4, 9, 16]);
+/// ```
+#[derive(Debug)]
+pub struct ListWriter {
+    staging: Link,
+    length: usize,
+}
+
+impl ListWriter {
+    /// Construct a new, empty `ListWriter`.
+    pub fn new() -> Result<Self, Error> {
+        Ok(ListWriter {
+            staging: Link::new_loopback()?,
+            length: 0,
+        })
+    }
+
+    /// Append an element to the list.
+    ///
+    /// `put_element` must put exactly one complete expression onto the link it is
+    /// given.
+    ///
+    /// If `put_element` returns an error, the contents of this `ListWriter` are
+    /// unspecified, and it should be discarded.
+    pub fn push<F>(&mut self, put_element: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut Link) -> Result<(), Error>,
+    {
+        let () = self.staging.put_guarded(put_element)?;
+
+        self.length += 1;
+
+        Ok(())
+    }
+
+    /// Append the next expression available on `source` to the list.
+    pub fn push_from(&mut self, source: &mut Link) -> Result<(), Error> {
+        self.push(|staging| source.transfer_expr_to(staging))
+    }
+
+    /// Get the number of elements written to the list so far.
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    /// Returns `true` if no elements have been written to the list.
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Put the completed `List` expression onto `dest`.
+    pub fn finish(mut self, dest: &mut Link) -> Result<(), Error> {
+        self.staging.check_poisoned()?;
+
+        dest.put_guarded(|dest| {
+            dest.put_function("System`List", self.length)?;
+
+            while self.staging.is_ready() {
+                self.staging.transfer_expr_to(dest)?;
+            }
+
+            Ok(())
+        })
+    }
+}
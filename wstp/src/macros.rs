@@ -0,0 +1,190 @@
+//! The [`put_expr!`][crate::put_expr] macro.
+
+#[cfg(feature = "expr")]
+use wolfram_expr::Expr;
+
+use crate::{Error, Link};
+
+/// Put an expression written in Wolfram Language-like syntax onto a link.
+///
+/// `put_expr!(link, expr)` expands to the sequence of [`Link`] put calls needed to
+/// write `expr`, computing the argument count of each function automatically. The
+/// macro evaluates to a `Result<(), wstp::Error>`. `link` must be an expression of
+/// type `&mut Link`.
+///
+/// The following syntax is supported in `expr`:
+///
+/// Syntax                   | Expression
+/// -------------------------|-----------
+/// `f[arg1, arg2, ...]`     | function with symbol head `f`
+/// `x`                      | symbol `x`
+/// `Global::x`              | symbol ``Global`x``
+/// `5`, `-2.5`, `"text"`    | `Integer`, `Real`, or `String` literal
+/// `true`, `false`          | ``System`True``, ``System`False``
+/// `{value}`                | the Rust expression `value`
+///
+/// Symbols written without a context, such as `x`, are put without one, and will be
+/// resolved by the receiving program (e.g. a Wolfram Language kernel).
+///
+/// A Rust expression interpolated using `{value}` can be any integer, floating point,
+/// string, or `bool` value, or a [`wolfram_expr::Expr`].
+///
+/// # Example
+///
+/// ```
+/// use wstp::{put_expr, Link};
+///
+/// let mut link = Link::new_loopback().unwrap();
+///
+/// let n = 4;
+///
+/// // Put {1, "two", System`Sin[Global`x], 4}
+/// put_expr!(&mut link, System::List[1, "two", System::Sin[Global::x], {n}]).unwrap();
+///
+/// assert_eq!(
+///     link.get_expr().unwrap().to_string(),
+///     "System`List[1, \"two\", System`Sin[Global`x], 4]"
+/// );
+/// ```
+#[macro_export]
+macro_rules! put_expr {
+    //------------------------------------
+    // Count the comma-separated arguments
+    //------------------------------------
+
+    (@count) => { 0usize };
+    (@count $lit:literal $(, $($rest:tt)*)?) => {
+        1usize + $crate::put_expr!(@count $($($rest)*)?)
+    };
+    (@count { $($value:tt)* } $(, $($rest:tt)*)?) => {
+        1usize + $crate::put_expr!(@count $($($rest)*)?)
+    };
+    (@count $name:ident $(:: $more:ident)* [ $($args:tt)* ] $(, $($rest:tt)*)?) => {
+        1usize + $crate::put_expr!(@count $($($rest)*)?)
+    };
+    (@count $name:ident $(:: $more:ident)* $(, $($rest:tt)*)?) => {
+        1usize + $crate::put_expr!(@count $($($rest)*)?)
+    };
+
+    //------------------------------------
+    // Put the comma-separated arguments
+    //------------------------------------
+
+    (@put $link:ident;) => {};
+    (@put $link:ident; $lit:literal $(, $($rest:tt)*)?) => {
+        $crate::__private::PutValue::put_value(&$lit, $link)?;
+        $crate::put_expr!(@put $link; $($($rest)*)?);
+    };
+    (@put $link:ident; { $value:expr } $(, $($rest:tt)*)?) => {
+        $crate::__private::PutValue::put_value(&$value, $link)?;
+        $crate::put_expr!(@put $link; $($($rest)*)?);
+    };
+    (@put $link:ident; $name:ident $(:: $more:ident)* [ $($args:tt)* ] $(, $($rest:tt)*)?) => {
+        $link.put_function(
+            $crate::put_expr!(@symbol $name $(:: $more)*),
+            $crate::put_expr!(@count $($args)*),
+        )?;
+        $crate::put_expr!(@put $link; $($args)*);
+        $crate::put_expr!(@put $link; $($($rest)*)?);
+    };
+    (@put $link:ident; $name:ident $(:: $more:ident)* $(, $($rest:tt)*)?) => {
+        $link.put_symbol($crate::put_expr!(@symbol $name $(:: $more)*))?;
+        $crate::put_expr!(@put $link; $($($rest)*)?);
+    };
+
+    (@symbol $name:ident $(:: $more:ident)*) => {
+        concat!(stringify!($name) $(, "`", stringify!($more))*)
+    };
+
+    //------------------------------------
+    // Entry point
+    //------------------------------------
+
+    ($link:expr, $($expr:tt)+) => {
+        $crate::__private::put_with($link, |link: &mut $crate::Link| {
+            $crate::put_expr!(@put link; $($expr)+);
+            Ok(())
+        })
+    };
+}
+
+//======================================
+// Macro support
+//======================================
+
+/// Value that can be put onto a link by [`put_expr!`][crate::put_expr].
+pub trait PutValue {
+    /// Put this value onto `link`.
+    fn put_value(&self, link: &mut Link) -> Result<(), Error>;
+}
+
+/// Call `put` with `link`, using [`Link::put_guarded()`].
+///
+/// This gives the expansion of [`put_expr!`][crate::put_expr] a closure body in which
+/// the `?` operator can be used.
+pub fn put_with<F>(link: &mut Link, put: F) -> Result<(), Error>
+where
+    F: FnOnce(&mut Link) -> Result<(), Error>,
+{
+    link.put_guarded(put)
+}
+
+impl<T: PutValue + ?Sized> PutValue for &T {
+    fn put_value(&self, link: &mut Link) -> Result<(), Error> {
+        T::put_value(self, link)
+    }
+}
+
+macro_rules! impl_put_value_integer {
+    ($($type:ty),*) => {
+        $(
+            impl PutValue for $type {
+                fn put_value(&self, link: &mut Link) -> Result<(), Error> {
+                    match i64::try_from(*self) {
+                        Ok(value) => link.put_i64(value),
+                        Err(_) => link.put_integer_digits(&self.to_string()),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_put_value_integer!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl PutValue for f32 {
+    fn put_value(&self, link: &mut Link) -> Result<(), Error> {
+        link.put_f32(*self)
+    }
+}
+
+impl PutValue for f64 {
+    fn put_value(&self, link: &mut Link) -> Result<(), Error> {
+        link.put_f64(*self)
+    }
+}
+
+impl PutValue for bool {
+    fn put_value(&self, link: &mut Link) -> Result<(), Error> {
+        link.put_symbol(if *self { "System`True" } else { "System`False" })
+    }
+}
+
+impl PutValue for str {
+    fn put_value(&self, link: &mut Link) -> Result<(), Error> {
+        link.put_str(self)
+    }
+}
+
+impl PutValue for String {
+    fn put_value(&self, link: &mut Link) -> Result<(), Error> {
+        link.put_str(self)
+    }
+}
+
+#[cfg(feature = "expr")]
+impl PutValue for Expr {
+    fn put_value(&self, link: &mut Link) -> Result<(), Error> {
+        link.put_expr(self)
+    }
+}
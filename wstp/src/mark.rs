@@ -0,0 +1,220 @@
+//! Look-ahead reading of incoming expressions using link marks.
+
+#[cfg(feature = "expr")]
+use wolfram_expr::Expr;
+
+use crate::{sys, Error, Link, TokenType};
+
+/// # Look-ahead
+impl Link {
+    /// Read ahead on this link without consuming any data.
+    ///
+    /// `read` is called with this link, and may read any amount of data from it. After
+    /// `read` returns, the link is reset so that the next data read from it is the data
+    /// that was available before `peek()` was called.
+    ///
+    /// If `read` returns an error, that error is cleared from the link before it is
+    /// reset, and then returned from `peek()`.
+    ///
+    /// # Example
+    ///
+    /// Check the head of an incoming expression before deciding how to read it:
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// // Put foo[1, 2]
+    /// link.put_function("Global`foo", 2).unwrap();
+    /// link.put_i64(1).unwrap();
+    /// link.put_i64(2).unwrap();
+    ///
+    /// let head = link
+    ///     .peek(|link| {
+    ///         let _ = link.get_arg_count()?;
+    ///         Ok(link.get_symbol_ref()?.as_str().to_owned())
+    ///     })
+    ///     .unwrap();
+    ///
+    /// assert_eq!(head, "Global`foo");
+    ///
+    /// // The expression is still available to read.
+    /// assert_eq!(link.test_head("Global`foo").unwrap(), 2);
+    /// ```
+    ///
+    /// *WSTP C API Documentation:*
+    /// [`WSCreateMark()`](https://reference.wolfram.com/language/ref/c/WSCreateMark.html),
+    /// [`WSSeekToMark()`](https://reference.wolfram.com/language/ref/c/WSSeekToMark.html),
+    /// [`WSDestroyMark()`](https://reference.wolfram.com/language/ref/c/WSDestroyMark.html)
+    pub fn peek<T, F>(&mut self, read: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Link) -> Result<T, Error>,
+    {
+        let mark: sys::WSMARK = unsafe { sys::WSCreateMark(self.raw_link) };
+
+        if mark.is_null() {
+            return Err(self.error_or_unknown());
+        }
+
+        let result = read(self);
+
+        if result.is_err() {
+            self.clear_error();
+        }
+
+        unsafe {
+            let _: sys::WSMARK = sys::WSSeekToMark(self.raw_link, mark, 0);
+            sys::WSDestroyMark(self.raw_link, mark);
+        }
+
+        result
+    }
+
+    /// Read a copy of the next expression on this link, without consuming it.
+    ///
+    /// The link is left positioned as if nothing had been read, so the next read
+    /// returns the same expression again. This is useful for logging and inspection
+    /// code that should not affect the reader that actually consumes the expression.
+    ///
+    /// This is equivalent to `link.peek(Link::get_expr)`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_function("Global`foo", 1).unwrap();
+    /// link.put_i64(1).unwrap();
+    ///
+    /// let copy = link.clone_expr().unwrap();
+    /// println!("received: {}", copy);
+    ///
+    /// assert_eq!(link.get_expr().unwrap(), copy);
+    /// ```
+    #[cfg(feature = "expr")]
+    pub fn clone_expr(&mut self) -> Result<Expr, Error> {
+        self.peek(Link::get_expr)
+    }
+
+    /// Attempt to read from this link using `read`, rolling back if it fails.
+    ///
+    /// If `read` succeeds, the data it read is consumed. If `read` returns an error,
+    /// that error is cleared from the link, the link is reset so that the next data
+    /// read from it is the data that was available before `try_get()` was called,
+    /// and the error is returned.
+    ///
+    /// This can be used to decode data that may have one of several forms, by trying
+    /// the decoder for each form in turn.
+    ///
+    /// # Example
+    ///
+    /// Read a value that is either a number or a list of numbers:
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// enum Value {
+    ///     Number(f64),
+    ///     List(Vec<f64>),
+    /// }
+    ///
+    /// fn get_value(link: &mut Link) -> Result<Value, wstp::Error> {
+    ///     link.try_get(|link| link.get_f64().map(Value::Number))
+    ///         .or_else(|_| {
+    ///             link.try_get(|link| {
+    ///                 let length = link.test_head("System`List")?;
+    ///                 let values = (0..length)
+    ///                     .map(|_| link.get_f64())
+    ///                     .collect::<Result<_, _>>()?;
+    ///                 Ok(Value::List(values))
+    ///             })
+    ///         })
+    /// }
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_f64_array(&[1.5, 2.5], &[2]).unwrap();
+    ///
+    /// assert_eq!(get_value(&mut link).unwrap(), Value::List(vec![1.5, 2.5]));
+    /// ```
+    ///
+    /// *WSTP C API Documentation:*
+    /// [`WSCreateMark()`](https://reference.wolfram.com/language/ref/c/WSCreateMark.html),
+    /// [`WSSeekToMark()`](https://reference.wolfram.com/language/ref/c/WSSeekToMark.html),
+    /// [`WSDestroyMark()`](https://reference.wolfram.com/language/ref/c/WSDestroyMark.html)
+    pub fn try_get<T, F>(&mut self, read: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Link) -> Result<T, Error>,
+    {
+        let mark: sys::WSMARK = unsafe { sys::WSCreateMark(self.raw_link) };
+
+        if mark.is_null() {
+            return Err(self.error_or_unknown());
+        }
+
+        let result = read(self);
+
+        if result.is_err() {
+            self.clear_error();
+
+            unsafe {
+                let _: sys::WSMARK = sys::WSSeekToMark(self.raw_link, mark, 0);
+            }
+        }
+
+        unsafe {
+            sys::WSDestroyMark(self.raw_link, mark);
+        }
+
+        result
+    }
+
+    /// Read and discard the next expression on this link.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// // Put {"a", "b"}, then 5
+    /// link.put_function("System`List", 2).unwrap();
+    /// link.put_str("a").unwrap();
+    /// link.put_str("b").unwrap();
+    /// link.put_i64(5).unwrap();
+    ///
+    /// link.skip_expr().unwrap();
+    ///
+    /// assert_eq!(link.get_i64().unwrap(), 5);
+    /// ```
+    pub fn skip_expr(&mut self) -> Result<(), Error> {
+        match self.get_type()? {
+            // Note: Reading numbers as strings means integers and reals of any size
+            //       can be skipped.
+            TokenType::Integer | TokenType::Real => {
+                let _ = self.get_number_string()?;
+            },
+            TokenType::String => {
+                let _ = self.get_string_ref()?;
+            },
+            TokenType::Symbol => {
+                let _ = self.get_symbol_ref()?;
+            },
+            TokenType::Function => {
+                let argc = self.get_arg_count()?;
+
+                // Skip the head, then each of the arguments.
+                for _ in 0..=argc {
+                    self.skip_expr()?;
+                }
+            },
+        }
+
+        Ok(())
+    }
+}
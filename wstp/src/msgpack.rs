@@ -0,0 +1,227 @@
+//! Transfer of MessagePack values as expressions.
+
+use rmpv::Value;
+
+use crate::{Error, Link, TokenType};
+
+impl Link {
+    /// Put a MessagePack [`rmpv::Value`] as an expression.
+    ///
+    /// MessagePack values are converted to expressions using the following mapping:
+    ///
+    /// MessagePack          | Expression
+    /// ---------------------|-----------
+    /// nil                  | `Null`
+    /// boolean              | `True`, `False`
+    /// integer              | `Integer`
+    /// float 32, float 64   | `Real`
+    /// string               | `String`
+    /// binary               | `ByteArray[...]`
+    /// array                | `List[...]`
+    /// map                  | `Association[key -> value, ...]`
+    ///
+    /// An error is returned if `value` contains a string that is not valid UTF-8, or an
+    /// extension type value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rmpv::Value;
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// let value = Value::Map(vec![
+    ///     (Value::from("temp"), Value::from(21.5)),
+    ///     (Value::from("raw"), Value::Binary(vec![0xCA, 0xFE])),
+    /// ]);
+    ///
+    /// // Put <|"temp" -> 21.5, "raw" -> ByteArray[{202, 254}]|>
+    /// link.put_msgpack(&value).unwrap();
+    ///
+    /// assert_eq!(link.get_msgpack().unwrap(), value);
+    /// ```
+    pub fn put_msgpack(&mut self, value: &Value) -> Result<(), Error> {
+        self.put_guarded(|link| link.put_msgpack_unguarded(value))
+    }
+
+    fn put_msgpack_unguarded(&mut self, value: &Value) -> Result<(), Error> {
+        match value {
+            Value::Nil => self.put_symbol("System`Null"),
+            Value::Boolean(true) => self.put_symbol("System`True"),
+            Value::Boolean(false) => self.put_symbol("System`False"),
+            Value::Integer(integer) => match (integer.as_i64(), integer.as_u64()) {
+                (Some(integer), _) => self.put_i64(integer),
+                (None, Some(integer)) => self.put_integer_digits(&integer.to_string()),
+                (None, None) => unreachable!("MessagePack integer is not an i64 or u64"),
+            },
+            Value::F32(real) => self.put_f32(*real),
+            Value::F64(real) => self.put_f64(*real),
+            Value::String(string) => match string.as_str() {
+                Some(string) => self.put_str(string),
+                None => Err(Error::custom(
+                    "MessagePack string is not valid UTF-8".to_owned(),
+                )),
+            },
+            Value::Binary(data) => self.put_byte_array(data),
+            Value::Array(elements) => {
+                self.put_function("System`List", elements.len())?;
+
+                for element in elements {
+                    self.put_msgpack_unguarded(element)?;
+                }
+
+                Ok(())
+            },
+            Value::Map(entries) => {
+                self.put_function("System`Association", entries.len())?;
+
+                for (key, value) in entries {
+                    self.put_function("System`Rule", 2)?;
+                    self.put_msgpack_unguarded(key)?;
+                    self.put_msgpack_unguarded(value)?;
+                }
+
+                Ok(())
+            },
+            Value::Ext(type_, _) => Err(Error::custom(format!(
+                "MessagePack extension type {} cannot be represented as an expression",
+                type_
+            ))),
+        }
+    }
+
+    /// Get an expression as a MessagePack [`rmpv::Value`].
+    ///
+    /// This is the inverse of the mapping described in [`Link::put_msgpack()`]. Reals
+    /// are always converted to MessagePack float 64 values.
+    pub fn get_msgpack(&mut self) -> Result<Value, Error> {
+        let value = match self.get_type()? {
+            TokenType::Integer => {
+                let digits = self.get_number_string()?;
+
+                if let Ok(integer) = digits.parse::<i64>() {
+                    Value::from(integer)
+                } else if let Ok(integer) = digits.parse::<u64>() {
+                    Value::from(integer)
+                } else {
+                    return Err(Error::custom(format!(
+                        "integer {} is too large to be represented in MessagePack",
+                        digits
+                    )));
+                }
+            },
+            TokenType::Real => Value::F64(self.get_f64()?),
+            TokenType::String => Value::from(self.get_string()?),
+            TokenType::Symbol => {
+                let symbol = self.get_symbol_ref()?;
+
+                match symbol.as_str() {
+                    "System`Null" => Value::Nil,
+                    "System`True" => Value::Boolean(true),
+                    "System`False" => Value::Boolean(false),
+                    other => {
+                        return Err(Error::custom(format!(
+                            "symbol {} cannot be represented in MessagePack",
+                            other
+                        )))
+                    },
+                }
+            },
+            TokenType::Function => {
+                let argc = self.get_arg_count()?;
+
+                let head = self.get_symbol_ref()?.as_str().to_owned();
+
+                match head.as_str() {
+                    "System`List" => {
+                        let mut elements = Vec::with_capacity(argc);
+
+                        for _ in 0..argc {
+                            elements.push(self.get_msgpack()?);
+                        }
+
+                        Value::Array(elements)
+                    },
+                    "System`Association" => {
+                        let mut entries = Vec::with_capacity(argc);
+
+                        for _ in 0..argc {
+                            let rule_argc = self.test_head("System`Rule")?;
+
+                            if rule_argc != 2 {
+                                return Err(Error::custom(format!(
+                                    "expected Rule in Association to have 2 arguments, \
+                                     got {}",
+                                    rule_argc
+                                )));
+                            }
+
+                            let key = self.get_msgpack()?;
+                            let value = self.get_msgpack()?;
+
+                            entries.push((key, value));
+                        }
+
+                        Value::Map(entries)
+                    },
+                    "System`ByteArray" if argc == 1 => {
+                        let array = self.get_u8_array()?;
+
+                        if array.rank() != 1 {
+                            return Err(Error::custom(format!(
+                                "expected ByteArray data to have rank 1, got rank {}",
+                                array.rank()
+                            )));
+                        }
+
+                        Value::Binary(array.data().to_vec())
+                    },
+                    other => {
+                        return Err(Error::custom(format!(
+                            "expression with head {} cannot be represented in \
+                             MessagePack",
+                            other
+                        )))
+                    },
+                }
+            },
+        };
+
+        Ok(value)
+    }
+
+    /// Decode a MessagePack-encoded payload and put it as an expression.
+    ///
+    /// See [`Link::put_msgpack()`] for how MessagePack values are represented as
+    /// expressions.
+    pub fn put_msgpack_bytes(&mut self, mut payload: &[u8]) -> Result<(), Error> {
+        let value = rmpv::decode::read_value(&mut payload).map_err(|err| {
+            Error::custom(format!("invalid MessagePack payload: {}", err))
+        })?;
+
+        if !payload.is_empty() {
+            return Err(Error::custom(format!(
+                "MessagePack payload has {} trailing bytes",
+                payload.len()
+            )));
+        }
+
+        self.put_msgpack(&value)
+    }
+
+    /// Get an expression as a MessagePack-encoded payload.
+    ///
+    /// See [`Link::get_msgpack()`].
+    pub fn get_msgpack_bytes(&mut self) -> Result<Vec<u8>, Error> {
+        let value = self.get_msgpack()?;
+
+        let mut payload = Vec::new();
+
+        rmpv::encode::write_value(&mut payload, &value).map_err(|err| {
+            Error::custom(format!("error encoding MessagePack payload: {}", err))
+        })?;
+
+        Ok(payload)
+    }
+}
@@ -0,0 +1,167 @@
+//! Lossless access to the digits and precision of numbers read from a link.
+
+use crate::{Error, Link, TokenType};
+
+/// The digits, exponent, and precision of a number read using
+/// [`Link::get_number_digits()`].
+///
+/// The value of the number is `digits × 10^exponent`, negated if `negative` is `true`.
+/// For example, ``-12.50`20.`` has the digits `"1250"`, and an exponent of `-2`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumberDigits {
+    /// Whether the number is negative.
+    pub negative: bool,
+    /// Decimal digits of the number, without leading zeros.
+    ///
+    /// This is `"0"` if the number is zero.
+    pub digits: String,
+    /// Base 10 exponent applied to [`NumberDigits::digits`].
+    pub exponent: i64,
+    /// Precision or accuracy of the number.
+    pub precision: RealPrecision,
+}
+
+/// Precision of a [`NumberDigits`] value.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum RealPrecision {
+    /// The number is an exact integer.
+    Exact,
+    /// The number is a machine-precision real.
+    Machine,
+    /// The number is an arbitrary-precision real with the given
+    /// [`Precision`][Precision], i.e. number of significant digits.
+    ///
+    /// [Precision]: https://reference.wolfram.com/language/ref/Precision.html
+    Precision(f64),
+    /// The number is an arbitrary-precision real with the given
+    /// [`Accuracy`][Accuracy], i.e. number of significant digits after the decimal
+    /// point.
+    ///
+    /// [Accuracy]: https://reference.wolfram.com/language/ref/Accuracy.html
+    Accuracy(f64),
+}
+
+impl NumberDigits {
+    /// Convert this number to the nearest [`f64`].
+    ///
+    /// This loses any digits beyond the precision of [`f64`].
+    pub fn to_f64(&self) -> f64 {
+        let sign = if self.negative { "-" } else { "" };
+
+        format!("{sign}{}e{}", self.digits, self.exponent)
+            .parse()
+            .unwrap_or(f64::NAN)
+    }
+}
+
+impl Link {
+    /// Get the next integer or real number, preserving all of its digits and its
+    /// precision.
+    ///
+    /// Unlike [`Link::get_f64()`], this doesn't round arbitrary-precision reals to
+    /// machine precision, and records the precision or accuracy of the number, so it
+    /// can be put back on a link without losing the exactness guarantees of the kernel.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{Link, NumberDigits, RealPrecision};
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_real_number_str("3.14159265358979323846264`25.").unwrap();
+    ///
+    /// assert_eq!(link.get_number_digits().unwrap(), NumberDigits {
+    ///     negative: false,
+    ///     digits: "314159265358979323846264".to_owned(),
+    ///     exponent: -23,
+    ///     precision: RealPrecision::Precision(25.0),
+    /// });
+    /// ```
+    ///
+    /// *WSTP C API Documentation:* [`WSGetNumberAsUTF8String()`](https://reference.wolfram.com/language/ref/c/WSGetNumberAsString.html)
+    pub fn get_number_digits(&mut self) -> Result<NumberDigits, Error> {
+        let is_integer = match self.get_type()? {
+            TokenType::Integer => true,
+            TokenType::Real => false,
+            other => {
+                return Err(Error::custom(format!(
+                    "expected Integer or Real number, got {:?}",
+                    other
+                )))
+            },
+        };
+
+        let string = self.get_number_string()?;
+
+        parse_number_digits(&string, is_integer)
+    }
+}
+
+/// Parse the WSTP textual form of a number, e.g. ``"-1.25`20.*^-3"``.
+fn parse_number_digits(string: &str, is_integer: bool) -> Result<NumberDigits, Error> {
+    let invalid = || Error::custom(format!("unsupported number format: '{string}'"));
+
+    if string.contains("^^") {
+        return Err(invalid());
+    }
+
+    let (negative, unsigned) = match string.strip_prefix('-') {
+        Some(unsigned) => (true, unsigned),
+        None => (false, string),
+    };
+
+    let (number, mut exponent) = match unsigned.split_once("*^") {
+        Some((number, exponent)) => (number, exponent.parse().map_err(|_| invalid())?),
+        None => (unsigned, 0i64),
+    };
+
+    let parse_mark =
+        |mark: &str| -> Result<f64, Error> { mark.parse().map_err(|_| invalid()) };
+
+    let (mantissa, precision) =
+        if let Some((mantissa, accuracy)) = number.split_once("``") {
+            (mantissa, RealPrecision::Accuracy(parse_mark(accuracy)?))
+        } else if let Some((mantissa, precision)) = number.split_once('`') {
+            let precision = if precision.is_empty() {
+                RealPrecision::Machine
+            } else {
+                RealPrecision::Precision(parse_mark(precision)?)
+            };
+
+            (mantissa, precision)
+        } else if is_integer {
+            (number, RealPrecision::Exact)
+        } else {
+            (number, RealPrecision::Machine)
+        };
+
+    let (integer_part, fraction_part) =
+        mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+    let all_digits = integer_part.chars().chain(fraction_part.chars());
+
+    if integer_part.is_empty() && fraction_part.is_empty()
+        || !all_digits.clone().all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+
+    exponent -= i64::try_from(fraction_part.len()).map_err(|_| invalid())?;
+
+    let digits: String = all_digits.skip_while(|&c| c == '0').collect();
+
+    let digits = if digits.is_empty() {
+        "0".to_owned()
+    } else {
+        digits
+    };
+
+    Ok(NumberDigits {
+        negative,
+        digits,
+        exponent,
+        precision,
+    })
+}
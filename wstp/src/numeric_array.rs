@@ -0,0 +1,537 @@
+//! Transfer of [`NumericArray`][NumericArray] expressions with explicit element types.
+//!
+//! A `NumericArray` is written to a link as the expression
+//! `NumericArray[data, "Type"]`, where `data` is a rectangular nested list of
+//! elements, and `"Type"` is the Wolfram Language name of the element type (e.g.
+//! `"Integer16"`).
+//!
+//! Where WSTP has a typed array function that can represent every value of the
+//! element type, `data` is transferred as a single packed array. Otherwise, `data`
+//! is transferred element by element.
+//!
+//! [NumericArray]: https://reference.wolfram.com/language/ref/NumericArray.html
+
+use crate::{Error, Link, TokenType};
+
+/// Element type of a [`NumericArray`][NumericArray] expression.
+///
+/// The discriminant of each variant is equal to the corresponding
+/// `MNumericArray_Type` value used by LibraryLink, so this type can be converted
+/// to and from the `NumericArrayDataType` type from the
+/// [`wolfram-library-link`](https://crates.io/crates/wolfram-library-link) crate
+/// using an integer cast.
+///
+/// [NumericArray]: https://reference.wolfram.com/language/ref/NumericArray.html
+#[allow(missing_docs)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum NumericArrayKind {
+    Integer8 = 1,
+    UnsignedInteger8 = 2,
+    Integer16 = 3,
+    UnsignedInteger16 = 4,
+    Integer32 = 5,
+    UnsignedInteger32 = 6,
+    Integer64 = 7,
+    UnsignedInteger64 = 8,
+    Real32 = 9,
+    Real64 = 10,
+    ComplexReal32 = 11,
+    ComplexReal64 = 12,
+}
+
+/// Rust type that can be used as the element type of a `NumericArray` transferred
+/// using [`Link::put_numeric_array()`] and [`Link::get_numeric_array()`].
+///
+/// Complex numbers are represented as `[re, im]` pairs, which have the same layout as
+/// the `mcomplex` type used by LibraryLink.
+///
+/// This trait is sealed and cannot be implemented outside of `wstp`.
+pub trait NumericArrayType: Copy + private::Sealed + 'static {
+    /// The element type of `NumericArray` expressions containing elements of this type.
+    const KIND: NumericArrayKind;
+}
+
+/// Multidimensional rectangular array read by [`Link::get_numeric_array()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumericArray<T> {
+    /// The elements of the array, as a flat buffer in row-major order.
+    pub data: Vec<T>,
+    /// The dimensions of the array.
+    pub dimensions: Vec<usize>,
+}
+
+impl NumericArrayKind {
+    /// Get the Wolfram Language name of this element type, e.g. `"Integer8"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NumericArrayKind::Integer8 => "Integer8",
+            NumericArrayKind::UnsignedInteger8 => "UnsignedInteger8",
+            NumericArrayKind::Integer16 => "Integer16",
+            NumericArrayKind::UnsignedInteger16 => "UnsignedInteger16",
+            NumericArrayKind::Integer32 => "Integer32",
+            NumericArrayKind::UnsignedInteger32 => "UnsignedInteger32",
+            NumericArrayKind::Integer64 => "Integer64",
+            NumericArrayKind::UnsignedInteger64 => "UnsignedInteger64",
+            NumericArrayKind::Real32 => "Real32",
+            NumericArrayKind::Real64 => "Real64",
+            NumericArrayKind::ComplexReal32 => "ComplexReal32",
+            NumericArrayKind::ComplexReal64 => "ComplexReal64",
+        }
+    }
+
+    /// Get the element type with the Wolfram Language name `name`.
+    ///
+    /// ```
+    /// use wstp::NumericArrayKind;
+    ///
+    /// assert_eq!(
+    ///     NumericArrayKind::from_name("Real32"),
+    ///     Some(NumericArrayKind::Real32)
+    /// );
+    /// assert_eq!(NumericArrayKind::from_name("Real16"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<Self> {
+        let kind = match name {
+            "Integer8" => NumericArrayKind::Integer8,
+            "UnsignedInteger8" => NumericArrayKind::UnsignedInteger8,
+            "Integer16" => NumericArrayKind::Integer16,
+            "UnsignedInteger16" => NumericArrayKind::UnsignedInteger16,
+            "Integer32" => NumericArrayKind::Integer32,
+            "UnsignedInteger32" => NumericArrayKind::UnsignedInteger32,
+            "Integer64" => NumericArrayKind::Integer64,
+            "UnsignedInteger64" => NumericArrayKind::UnsignedInteger64,
+            "Real32" => NumericArrayKind::Real32,
+            "Real64" => NumericArrayKind::Real64,
+            "ComplexReal32" => NumericArrayKind::ComplexReal32,
+            "ComplexReal64" => NumericArrayKind::ComplexReal64,
+            _ => return None,
+        };
+
+        Some(kind)
+    }
+}
+
+impl Link {
+    /// Put a [`NumericArray`][NumericArray] expression with element type `T::KIND`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// // Put NumericArray[{{1, 2}, {3, 4}}, "UnsignedInteger16"]
+    /// link.put_numeric_array::<u16>(&[1, 2, 3, 4], &[2, 2]).unwrap();
+    ///
+    /// let array = link.get_numeric_array::<u16>().unwrap();
+    ///
+    /// assert_eq!(array.data, vec![1, 2, 3, 4]);
+    /// assert_eq!(array.dimensions, vec![2, 2]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the product of `dimensions` is not equal to `data.len()`.
+    ///
+    /// [NumericArray]: https://reference.wolfram.com/language/ref/NumericArray.html
+    pub fn put_numeric_array<T: NumericArrayType>(
+        &mut self,
+        data: &[T],
+        dimensions: &[usize],
+    ) -> Result<(), Error> {
+        assert_eq!(
+            data.len(),
+            dimensions.iter().product::<usize>(),
+            "data length does not equal product of dimensions"
+        );
+
+        T::check_data(self, data)?;
+
+        self.put_guarded(|link| {
+            link.put_function("System`NumericArray", 2)?;
+            T::put_data(link, data, dimensions)?;
+            link.put_str(T::KIND.as_str())
+        })
+    }
+
+    /// Get a [`NumericArray`][NumericArray] expression with element type `T::KIND`.
+    ///
+    /// An error is returned if the element type of the incoming `NumericArray` is not
+    /// `T::KIND`.
+    ///
+    /// [NumericArray]: https://reference.wolfram.com/language/ref/NumericArray.html
+    pub fn get_numeric_array<T: NumericArrayType>(
+        &mut self,
+    ) -> Result<NumericArray<T>, Error> {
+        let argc = self.test_head("System`NumericArray")?;
+
+        self.get_numeric_array_arguments(argc)
+    }
+
+    /// Get the arguments of a `NumericArray[data, "Type"]` expression whose head has
+    /// already been read.
+    pub(crate) fn get_numeric_array_arguments<T: NumericArrayType>(
+        &mut self,
+        argc: usize,
+    ) -> Result<NumericArray<T>, Error> {
+        if argc != 2 {
+            return Err(Error::custom(format!(
+                "expected NumericArray expression to have 2 arguments, got {}",
+                argc
+            )));
+        }
+
+        let (data, dimensions) = T::get_data(self)?;
+
+        let kind = self.get_string_ref()?;
+
+        if kind.as_str() != T::KIND.as_str() {
+            return Err(Error::custom(format!(
+                "expected NumericArray of type \"{}\", got type \"{}\"",
+                T::KIND.as_str(),
+                kind.as_str()
+            )));
+        }
+
+        Ok(NumericArray { data, dimensions })
+    }
+
+    //==================================
+    // Element-wise transfer
+    //==================================
+
+    /// Put `data` as a rectangular nested `List` of elements, each written by
+    /// `put_element`.
+    pub(crate) fn put_nested_list<T>(
+        &mut self,
+        data: &[T],
+        dimensions: &[usize],
+        put_element: &mut dyn FnMut(&mut Link, &T) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let (length, rest) = match dimensions.split_first() {
+            Some(split) => split,
+            None => return put_element(self, &data[0]),
+        };
+
+        let stride: usize = rest.iter().product();
+
+        self.put_function("System`List", *length)?;
+
+        for index in 0..*length {
+            let chunk = &data[index * stride..(index + 1) * stride];
+            self.put_nested_list(chunk, rest, put_element)?;
+        }
+
+        Ok(())
+    }
+
+    /// Get a rectangular nested `List` of elements, each read by `get_element`.
+    ///
+    /// If `element_head` is specified, each element is expected to be a function with
+    /// that head and argument count, and `get_element` is called after the head has
+    /// been read.
+    fn get_nested_list<T>(
+        &mut self,
+        element_head: Option<(&str, usize)>,
+        get_element: &mut dyn FnMut(&mut Link) -> Result<T, Error>,
+    ) -> Result<(Vec<T>, Vec<usize>), Error> {
+        let mut nested = NestedList {
+            data: Vec::new(),
+            dimensions: Vec::new(),
+            rank: None,
+        };
+
+        self.get_nested_level(&mut nested, 0, element_head, get_element)?;
+
+        Ok((nested.data, nested.dimensions))
+    }
+
+    fn get_nested_level<T>(
+        &mut self,
+        nested: &mut NestedList<T>,
+        depth: usize,
+        element_head: Option<(&str, usize)>,
+        get_element: &mut dyn FnMut(&mut Link) -> Result<T, Error>,
+    ) -> Result<(), Error> {
+        let list_length: Option<usize> = match self.get_type()? {
+            TokenType::Function => {
+                let argc = self.get_arg_count()?;
+                let head = self.get_symbol_ref()?;
+
+                match head.as_str() {
+                    "System`List" => Some(argc),
+                    head if Some((head, argc)) == element_head => None,
+                    head => {
+                        return Err(Error::custom(format!(
+                            "unexpected {}[...] expression in NumericArray data",
+                            head
+                        )))
+                    },
+                }
+            },
+            other => {
+                if let Some((element_head, _)) = element_head {
+                    return Err(Error::custom(format!(
+                        "expected {}[...] element in NumericArray data, got {:?}",
+                        element_head, other
+                    )));
+                }
+
+                None
+            },
+        };
+
+        let length = match list_length {
+            Some(length) => length,
+            None => {
+                match nested.rank {
+                    None => nested.rank = Some(depth),
+                    Some(rank) if rank == depth => (),
+                    Some(_) => return Err(non_rectangular_error()),
+                }
+
+                nested.data.push(get_element(self)?);
+
+                return Ok(());
+            },
+        };
+
+        if nested.rank.map_or(false, |rank| depth >= rank) {
+            return Err(non_rectangular_error());
+        }
+
+        match nested.dimensions.get(depth) {
+            Some(&expected) if expected == length => (),
+            Some(_) => return Err(non_rectangular_error()),
+            None => nested.dimensions.push(length),
+        }
+
+        for _ in 0..length {
+            self.get_nested_level(nested, depth + 1, element_head, get_element)?;
+        }
+
+        // Handle empty lists, which contain no elements that set the rank.
+        if depth + 1 == nested.dimensions.len() && length == 0 && nested.rank.is_none() {
+            nested.rank = Some(depth + 1);
+        }
+
+        Ok(())
+    }
+}
+
+/// Accumulated state of [`Link::get_nested_list()`].
+struct NestedList<T> {
+    data: Vec<T>,
+    dimensions: Vec<usize>,
+    /// Depth at which elements occur, once the first element has been read.
+    rank: Option<usize>,
+}
+
+fn non_rectangular_error() -> Error {
+    Error::custom("NumericArray data is not a rectangular array".to_owned())
+}
+
+//======================================
+// NumericArrayType impls
+//======================================
+
+mod private {
+    use crate::{Error, Link};
+
+    pub trait Sealed: Sized {
+        fn put_data(
+            link: &mut Link,
+            data: &[Self],
+            dimensions: &[usize],
+        ) -> Result<(), Error>;
+
+        fn get_data(link: &mut Link) -> Result<(Vec<Self>, Vec<usize>), Error>;
+
+        /// Check that `data` can be put on `link`, before anything has been put.
+        fn check_data(_link: &Link, _data: &[Self]) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+}
+
+/// Implement [`NumericArrayType`] for a type that can be converted losslessly to and
+/// from the element type of a packed WSTP array.
+macro_rules! packed_numeric_array_type {
+    ($($type:ty => $kind:ident via $wire:ty: $put:ident, $get:ident;)*) => {
+        $(
+            impl NumericArrayType for $type {
+                const KIND: NumericArrayKind = NumericArrayKind::$kind;
+            }
+
+            impl private::Sealed for $type {
+                fn put_data(
+                    link: &mut Link,
+                    data: &[Self],
+                    dimensions: &[usize],
+                ) -> Result<(), Error> {
+                    let wire: Vec<$wire> = data.iter().map(|&elem| <$wire>::from(elem)).collect();
+
+                    link.$put(&wire, dimensions)
+                }
+
+                fn get_data(link: &mut Link) -> Result<(Vec<Self>, Vec<usize>), Error> {
+                    let array = link.$get()?;
+
+                    let data = array
+                        .data()
+                        .iter()
+                        .map(|&elem| {
+                            <$type>::try_from(elem).map_err(|_| {
+                                Error::custom(format!(
+                                    "NumericArray element {} is out of range for type \"{}\"",
+                                    elem,
+                                    NumericArrayKind::$kind.as_str()
+                                ))
+                            })
+                        })
+                        .collect::<Result<Vec<$type>, Error>>()?;
+
+                    Ok((data, array.dimensions().to_vec()))
+                }
+            }
+        )*
+    };
+}
+
+packed_numeric_array_type! {
+    i8 => Integer8 via i16: put_i16_array, get_i16_array;
+    u8 => UnsignedInteger8 via u8: put_u8_array, get_u8_array;
+    i16 => Integer16 via i16: put_i16_array, get_i16_array;
+    u16 => UnsignedInteger16 via i32: put_i32_array, get_i32_array;
+    i32 => Integer32 via i32: put_i32_array, get_i32_array;
+    u32 => UnsignedInteger32 via i64: put_i64_array, get_i64_array;
+    i64 => Integer64 via i64: put_i64_array, get_i64_array;
+}
+
+impl NumericArrayType for f32 {
+    const KIND: NumericArrayKind = NumericArrayKind::Real32;
+}
+
+impl private::Sealed for f32 {
+    fn put_data(
+        link: &mut Link,
+        data: &[Self],
+        dimensions: &[usize],
+    ) -> Result<(), Error> {
+        link.put_f32_array(data, dimensions)
+    }
+
+    fn get_data(link: &mut Link) -> Result<(Vec<Self>, Vec<usize>), Error> {
+        let array = link.get_f32_array()?;
+
+        Ok((array.data().to_vec(), array.dimensions().to_vec()))
+    }
+
+    fn check_data(link: &Link, data: &[Self]) -> Result<(), Error> {
+        link.check_finite(data.iter().map(|&elem| f64::from(elem)))
+    }
+}
+
+impl NumericArrayType for f64 {
+    const KIND: NumericArrayKind = NumericArrayKind::Real64;
+}
+
+impl private::Sealed for f64 {
+    fn put_data(
+        link: &mut Link,
+        data: &[Self],
+        dimensions: &[usize],
+    ) -> Result<(), Error> {
+        link.put_f64_array(data, dimensions)
+    }
+
+    fn get_data(link: &mut Link) -> Result<(Vec<Self>, Vec<usize>), Error> {
+        let array = link.get_f64_array()?;
+
+        Ok((array.data().to_vec(), array.dimensions().to_vec()))
+    }
+
+    fn check_data(link: &Link, data: &[Self]) -> Result<(), Error> {
+        link.check_finite(data.iter().copied())
+    }
+}
+
+impl NumericArrayType for u64 {
+    const KIND: NumericArrayKind = NumericArrayKind::UnsignedInteger64;
+}
+
+// WSTP has no typed array function for unsigned 64-bit integers, so values are put
+// individually, using big integers where the value does not fit in an i64.
+impl private::Sealed for u64 {
+    fn put_data(
+        link: &mut Link,
+        data: &[Self],
+        dimensions: &[usize],
+    ) -> Result<(), Error> {
+        link.put_nested_list(data, dimensions, &mut |link, &elem| match i64::try_from(
+            elem,
+        ) {
+            Ok(elem) => link.put_i64(elem),
+            Err(_) => link.put_integer_digits(&elem.to_string()),
+        })
+    }
+
+    fn get_data(link: &mut Link) -> Result<(Vec<Self>, Vec<usize>), Error> {
+        link.get_nested_list(None, &mut |link| {
+            let digits = link.get_number_string()?;
+
+            digits.parse::<u64>().map_err(|err| {
+                Error::custom(format!(
+                    "NumericArray element {} is out of range for type \
+                     \"UnsignedInteger64\": {}",
+                    digits, err
+                ))
+            })
+        })
+    }
+}
+
+/// Implement [`NumericArrayType`] for a `[re, im]` complex number type, whose
+/// elements are transferred individually as `Complex[re, im]` expressions.
+macro_rules! complex_numeric_array_type {
+    ($($type:ty => $kind:ident: $put:ident, $get:ident;)*) => {
+        $(
+            impl NumericArrayType for [$type; 2] {
+                const KIND: NumericArrayKind = NumericArrayKind::$kind;
+            }
+
+            impl private::Sealed for [$type; 2] {
+                fn put_data(
+                    link: &mut Link,
+                    data: &[Self],
+                    dimensions: &[usize],
+                ) -> Result<(), Error> {
+                    link.put_nested_list(data, dimensions, &mut |link, &[re, im]| {
+                        link.put_function("System`Complex", 2)?;
+                        link.$put(re)?;
+                        link.$put(im)
+                    })
+                }
+
+                fn get_data(link: &mut Link) -> Result<(Vec<Self>, Vec<usize>), Error> {
+                    link.get_nested_list(Some(("System`Complex", 2)), &mut |link| {
+                        Ok([link.$get()?, link.$get()?])
+                    })
+                }
+
+                fn check_data(link: &Link, data: &[Self]) -> Result<(), Error> {
+                    link.check_finite(
+                        data.iter().flat_map(|&[re, im]| [f64::from(re), f64::from(im)]),
+                    )
+                }
+            }
+        )*
+    };
+}
+
+complex_numeric_array_type! {
+    f32 => ComplexReal32: put_f32, get_f32;
+    f64 => ComplexReal64: put_f64, get_f64;
+}
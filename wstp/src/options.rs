@@ -0,0 +1,247 @@
+//! Parsing of trailing option rules, like those accepted by Wolfram Language functions.
+
+use std::collections::{HashMap, HashSet};
+
+use wolfram_expr::{Expr, ExprKind};
+
+use crate::{Error, Link};
+
+/// What [`OptionsParser::parse()`] does with options that have not been declared
+/// using [`OptionsParser::option()`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum UnknownOptionPolicy {
+    /// Return an error.
+    ///
+    /// This is the default policy, and matches the `OptionValue::nodef` message
+    /// issued by Wolfram Language functions.
+    #[default]
+    Error,
+    /// Discard the option.
+    Ignore,
+    /// Keep the option, so it can be retrieved using [`Options::unknown()`].
+    Collect,
+}
+
+/// Parser for a sequence of `name -> value` option rules.
+///
+/// Wolfram Language functions accept options as rules following their positional
+/// arguments, e.g. `f[x, Method -> "Newton", MaxIterations -> 100]`. An
+/// `OptionsParser` declares the options accepted by a function, and their default
+/// values, and then reads the option rules from a link using
+/// [`OptionsParser::parse()`].
+///
+/// Option names can be given as symbols, in any context, or as strings. Option rules
+/// can be `Rule` or `RuleDelayed` expressions, and can be grouped into (nested)
+/// `List`s. If the same option is given more than once, the first value is used, as is
+/// the case in the Wolfram Language.
+///
+/// # Example
+///
+/// ```
+/// use wolfram_expr::{Expr, Symbol};
+/// use wstp::{Link, OptionsParser};
+///
+/// let parser = OptionsParser::new()
+///     .option("Method", Expr::string("Automatic"))
+///     .option("MaxIterations", Expr::from(100));
+///
+/// let mut link = Link::new_loopback().unwrap();
+///
+/// // Put Global`f[5, Method -> "Newton"]
+/// link.put_function("Global`f", 2).unwrap();
+/// link.put_symbol("Global`f").unwrap();
+/// link.put_i64(5).unwrap();
+/// link.put_expr(&Expr::rule(Symbol::new("Global`Method"), Expr::string("Newton")))
+///     .unwrap();
+///
+/// let argc = link.test_head("Global`f").unwrap();
+/// assert_eq!(link.get_i64().unwrap(), 5);
+/// let options = parser.parse(&mut link, argc - 1).unwrap();
+///
+/// assert_eq!(options.get_str("Method").unwrap(), "Newton");
+/// assert_eq!(options.get_i64("MaxIterations").unwrap(), 100);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct OptionsParser {
+    defaults: Vec<(String, Expr)>,
+    unknown: UnknownOptionPolicy,
+}
+
+/// Option values read by [`OptionsParser::parse()`].
+#[derive(Debug, Clone)]
+pub struct Options {
+    values: HashMap<String, Expr>,
+    explicit: HashSet<String>,
+    unknown: Vec<(String, Expr)>,
+}
+
+impl OptionsParser {
+    /// Construct a new `OptionsParser` that accepts no options.
+    pub fn new() -> Self {
+        OptionsParser::default()
+    }
+
+    /// Declare an option named `name`, whose value is `default` if it is not given.
+    ///
+    /// `name` should not include a context.
+    pub fn option(mut self, name: &str, default: Expr) -> Self {
+        self.defaults.push((name.to_owned(), default));
+        self
+    }
+
+    /// Set the policy for options that have not been declared.
+    pub fn unknown_options(mut self, policy: UnknownOptionPolicy) -> Self {
+        self.unknown = policy;
+        self
+    }
+
+    /// Read `count` option expressions from `link`.
+    ///
+    /// This is typically called after reading the positional arguments of a function,
+    /// with `count` equal to the number of remaining arguments.
+    pub fn parse(&self, link: &mut Link, count: usize) -> Result<Options, Error> {
+        let mut options = Options {
+            values: self.defaults.iter().cloned().collect(),
+            explicit: HashSet::new(),
+            unknown: Vec::new(),
+        };
+
+        for _ in 0..count {
+            let expr = link.get_expr()?;
+
+            self.add(&mut options, &expr)?;
+        }
+
+        Ok(options)
+    }
+
+    fn add(&self, options: &mut Options, expr: &Expr) -> Result<(), Error> {
+        let normal = match expr.kind() {
+            ExprKind::Normal(normal) => normal,
+            _ => return Err(not_an_option(expr)),
+        };
+
+        let head = match normal.head().kind() {
+            ExprKind::Symbol(head) => head.as_str(),
+            _ => return Err(not_an_option(expr)),
+        };
+
+        match (head, normal.elements()) {
+            ("System`List", elements) => {
+                for element in elements {
+                    self.add(options, element)?;
+                }
+            },
+            ("System`Rule" | "System`RuleDelayed", [name, value]) => {
+                let name = match name.kind() {
+                    ExprKind::Symbol(symbol) => symbol.symbol_name().as_str().to_owned(),
+                    ExprKind::String(string) => string.clone(),
+                    _ => {
+                        return Err(Error::custom(format!(
+                            "expected option name to be a symbol or string, got {}",
+                            name
+                        )))
+                    },
+                };
+
+                if options.explicit.contains(&name) {
+                    return Ok(());
+                }
+
+                if options.values.contains_key(&name) {
+                    options.values.insert(name.clone(), value.clone());
+                    options.explicit.insert(name);
+                    return Ok(());
+                }
+
+                match self.unknown {
+                    UnknownOptionPolicy::Error => {
+                        return Err(Error::custom(format!("unknown option: {}", name)))
+                    },
+                    UnknownOptionPolicy::Ignore => (),
+                    UnknownOptionPolicy::Collect => {
+                        if !options.unknown.iter().any(|(other, _)| *other == name) {
+                            options.unknown.push((name, value.clone()));
+                        }
+                    },
+                }
+            },
+            _ => return Err(not_an_option(expr)),
+        }
+
+        Ok(())
+    }
+}
+
+impl Options {
+    /// Get the value of the option `name`, or its default value if it was not given.
+    ///
+    /// Returns `None` if `name` is not a declared option.
+    pub fn get(&self, name: &str) -> Option<&Expr> {
+        self.values.get(name)
+    }
+
+    /// Returns `true` if the option `name` was given explicitly, instead of taking its
+    /// default value.
+    pub fn is_explicit(&self, name: &str) -> bool {
+        self.explicit.contains(name)
+    }
+
+    /// Get the options that were not declared, in the order they were given.
+    ///
+    /// This is empty unless the parser used [`UnknownOptionPolicy::Collect`].
+    pub fn unknown(&self) -> &[(String, Expr)] {
+        &self.unknown
+    }
+
+    /// Get the value of the option `name`, which must be an integer.
+    pub fn get_i64(&self, name: &str) -> Result<i64, Error> {
+        match self.expect(name)?.kind() {
+            ExprKind::Integer(value) => Ok(*value),
+            _ => Err(self.wrong_type(name, "an integer")),
+        }
+    }
+
+    /// Get the value of the option `name`, which must be a real number or an integer.
+    pub fn get_f64(&self, name: &str) -> Result<f64, Error> {
+        match self.expect(name)?.kind() {
+            ExprKind::Real(value) => Ok(**value),
+            ExprKind::Integer(value) => Ok(*value as f64),
+            _ => Err(self.wrong_type(name, "a real number")),
+        }
+    }
+
+    /// Get the value of the option `name`, which must be a string.
+    pub fn get_str(&self, name: &str) -> Result<&str, Error> {
+        match self.expect(name)?.kind() {
+            ExprKind::String(value) => Ok(value.as_str()),
+            _ => Err(self.wrong_type(name, "a string")),
+        }
+    }
+
+    /// Get the value of the option `name`, which must be `True` or `False`.
+    pub fn get_bool(&self, name: &str) -> Result<bool, Error> {
+        match self.expect(name)?.kind() {
+            ExprKind::Symbol(symbol) if symbol.as_str() == "System`True" => Ok(true),
+            ExprKind::Symbol(symbol) if symbol.as_str() == "System`False" => Ok(false),
+            _ => Err(self.wrong_type(name, "True or False")),
+        }
+    }
+
+    fn expect(&self, name: &str) -> Result<&Expr, Error> {
+        self.get(name)
+            .ok_or_else(|| Error::custom(format!("undeclared option: {}", name)))
+    }
+
+    fn wrong_type(&self, name: &str, expected: &str) -> Error {
+        Error::custom(format!(
+            "expected value of option {} to be {}, got {}",
+            name, expected, self.values[name]
+        ))
+    }
+}
+
+fn not_an_option(expr: &Expr) -> Error {
+    Error::custom(format!("expected option rule, got {}", expr))
+}
@@ -0,0 +1,397 @@
+//! Extraction of sub-expressions from an incoming expression using path selectors.
+
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+#[cfg(feature = "expr")]
+use wolfram_expr::Expr;
+
+use crate::Error;
+#[cfg(feature = "expr")]
+use crate::{Link, TokenType};
+
+/// Selector identifying a sub-expression of an expression.
+///
+/// A path is a sequence of [`PathStep`]s, each of which selects a part of the
+/// expression selected by the previous step. Paths can be constructed using the
+/// builder methods [`ExprPath::head()`], [`ExprPath::arg()`], and [`ExprPath::key()`],
+/// or parsed from a string of `/`-separated steps:
+///
+/// Syntax           | Step                   | Selects
+/// -----------------|------------------------|--------
+/// `head`           | [`PathStep::Head`]     | the head of a normal expression
+/// `args[n]`        | [`PathStep::Arg`]      | the `n`-th argument of a normal expression, counting from 1
+/// `key "Name"`     | [`PathStep::Key`]      | the value associated with the string key `"Name"` in an `Association`
+///
+/// Paths are evaluated directly against the incoming tokens using
+/// [`Link::get_at_path()`] or [`Link::peek_at_path()`], so only the selected
+/// sub-expression is ever constructed in memory.
+///
+/// # Example
+///
+/// ```
+/// use wstp::{ExprPath, Link};
+///
+/// let path: ExprPath = "args[2]/key \"Name\"".parse().unwrap();
+///
+/// assert_eq!(path, ExprPath::new().arg(2).key("Name"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ExprPath {
+    steps: Vec<PathStep>,
+}
+
+/// A single step of an [`ExprPath`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathStep {
+    /// Select the head of a normal expression.
+    Head,
+    /// Select an argument of a normal expression, counting from 1.
+    Arg(usize),
+    /// Select the value associated with a string key in an `Association`.
+    Key(String),
+}
+
+impl ExprPath {
+    /// Construct an empty path, which selects the entire expression.
+    pub fn new() -> Self {
+        ExprPath { steps: Vec::new() }
+    }
+
+    /// Append a [`PathStep::Head`] step to this path.
+    pub fn head(mut self) -> Self {
+        self.steps.push(PathStep::Head);
+        self
+    }
+
+    /// Append a [`PathStep::Arg`] step to this path.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `index` is 0.
+    pub fn arg(mut self, index: usize) -> Self {
+        assert!(index > 0, "ExprPath::arg(): argument indices start at 1");
+        self.steps.push(PathStep::Arg(index));
+        self
+    }
+
+    /// Append a [`PathStep::Key`] step to this path.
+    pub fn key(mut self, key: &str) -> Self {
+        self.steps.push(PathStep::Key(key.to_owned()));
+        self
+    }
+
+    /// Get the steps of this path.
+    pub fn steps(&self) -> &[PathStep] {
+        &self.steps
+    }
+}
+
+impl Display for PathStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PathStep::Head => write!(f, "head"),
+            PathStep::Arg(index) => write!(f, "args[{}]", index),
+            PathStep::Key(key) => write!(f, "key {:?}", key),
+        }
+    }
+}
+
+impl Display for ExprPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, step) in self.steps.iter().enumerate() {
+            if index > 0 {
+                write!(f, "/")?;
+            }
+
+            write!(f, "{}", step)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for ExprPath {
+    type Err = Error;
+
+    fn from_str(string: &str) -> Result<Self, Error> {
+        let mut steps = Vec::new();
+        let mut rest = string.trim_start();
+
+        while !rest.is_empty() {
+            let (step, remaining) = parse_step(rest).ok_or_else(|| {
+                Error::custom(format!(
+                    "invalid path step at \"{}\" in expression path \"{}\"",
+                    rest, string
+                ))
+            })?;
+
+            steps.push(step);
+
+            rest = remaining.trim_start();
+
+            if let Some(remaining) = rest.strip_prefix('/') {
+                rest = remaining.trim_start();
+
+                if rest.is_empty() {
+                    return Err(Error::custom(format!(
+                        "expression path \"{}\" ends with \"/\"",
+                        string
+                    )));
+                }
+            } else if !rest.is_empty() {
+                return Err(Error::custom(format!(
+                    "expected \"/\" at \"{}\" in expression path \"{}\"",
+                    rest, string
+                )));
+            }
+        }
+
+        Ok(ExprPath { steps })
+    }
+}
+
+/// Parse a single path step from the start of `input`, returning the step and the
+/// remaining input.
+fn parse_step(input: &str) -> Option<(PathStep, &str)> {
+    if let Some(rest) = input.strip_prefix("args[") {
+        let end = rest.find(']')?;
+        let index: usize = rest[..end].trim().parse().ok()?;
+
+        if index == 0 {
+            return None;
+        }
+
+        return Some((PathStep::Arg(index), &rest[end + 1..]));
+    }
+
+    if let Some(rest) = input.strip_prefix("key") {
+        let rest = rest.trim_start().strip_prefix('"')?;
+
+        let mut key = String::new();
+        let mut chars = rest.char_indices();
+
+        while let Some((offset, char)) = chars.next() {
+            match char {
+                '"' => return Some((PathStep::Key(key), &rest[offset + 1..])),
+                '\\' => key.push(chars.next()?.1),
+                _ => key.push(char),
+            }
+        }
+
+        // Unterminated string.
+        return None;
+    }
+
+    if let Some(rest) = input.strip_prefix("head") {
+        return Some((PathStep::Head, rest));
+    }
+
+    None
+}
+
+#[cfg(feature = "expr")]
+impl Link {
+    /// Read the next expression on this link, returning only the sub-expression
+    /// selected by `path`.
+    ///
+    /// The parts of the expression that are not selected by `path` are skipped over
+    /// without being constructed in memory. The full expression is consumed from the
+    /// link, even if an error is returned because `path` does not exist in the
+    /// expression.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wolfram_expr::Expr;
+    /// use wstp::{ExprPath, Link};
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// // Put Response[200, <|"Name" -> "wstp", "Size" -> 5|>]
+    /// link.put_function("Global`Response", 2).unwrap();
+    /// link.put_i64(200).unwrap();
+    /// link.put_function("System`Association", 2).unwrap();
+    /// link.put_function("System`Rule", 2).unwrap();
+    /// link.put_str("Name").unwrap();
+    /// link.put_str("wstp").unwrap();
+    /// link.put_function("System`Rule", 2).unwrap();
+    /// link.put_str("Size").unwrap();
+    /// link.put_i64(5).unwrap();
+    ///
+    /// let path: ExprPath = "args[2]/key \"Name\"".parse().unwrap();
+    ///
+    /// assert_eq!(link.get_at_path(&path).unwrap(), Expr::string("wstp"));
+    /// ```
+    pub fn get_at_path(&mut self, path: &ExprPath) -> Result<Expr, Error> {
+        let mut remaining = Vec::with_capacity(path.steps.len());
+
+        let result = self
+            .seek_path(path, &mut remaining)
+            .and_then(|()| self.get_expr());
+
+        // Skip the rest of the expression, from the innermost selected part outwards.
+        let mut skipped = Ok(());
+
+        for count in remaining.into_iter().rev() {
+            for _ in 0..count {
+                if skipped.is_ok() {
+                    skipped = self.skip_expr();
+                }
+            }
+        }
+
+        let expr = result?;
+        let () = skipped?;
+
+        Ok(expr)
+    }
+
+    /// Get the sub-expression selected by `path` from the next expression on this
+    /// link, without consuming the expression.
+    ///
+    /// See [`Link::get_at_path()`] and [`Link::peek()`].
+    pub fn peek_at_path(&mut self, path: &ExprPath) -> Result<Expr, Error> {
+        self.peek(|link| {
+            link.seek_path(path, &mut Vec::new())?;
+            link.get_expr()
+        })
+    }
+
+    /// Advance this link to the start of the sub-expression selected by `path`.
+    ///
+    /// For each step, the number of sibling expressions following the selected part is
+    /// pushed onto `remaining`. If an error occurs, the link is positioned at the start
+    /// of an expression, so that the remainder of the expression can still be skipped.
+    fn seek_path(
+        &mut self,
+        path: &ExprPath,
+        remaining: &mut Vec<usize>,
+    ) -> Result<(), Error> {
+        for (index, step) in path.steps.iter().enumerate() {
+            self.seek_path_step(step)
+                .map_err(|err| match err.code {
+                    // Errors from the link itself are returned unchanged.
+                    Some(_) => err,
+                    None => Error::custom(format!(
+                        "step {} ({}) of expression path \"{}\": {}",
+                        index + 1,
+                        step,
+                        path,
+                        err.message
+                    )),
+                })
+                .map(|count| remaining.push(count))?;
+        }
+
+        Ok(())
+    }
+
+    fn seek_path_step(&mut self, step: &PathStep) -> Result<usize, Error> {
+        let type_ = self.get_type()?;
+
+        if type_ != TokenType::Function {
+            // Consume the atom, so that the link is positioned after the whole
+            // expression.
+            self.skip_expr()?;
+
+            return Err(Error::custom(format!(
+                "expected a normal expression, got {:?}",
+                type_
+            )));
+        }
+
+        let argc = self.get_arg_count()?;
+
+        match *step {
+            PathStep::Head => Ok(argc),
+            PathStep::Arg(index) => {
+                if index > argc {
+                    // Skip the head and all of the arguments.
+                    for _ in 0..=argc {
+                        self.skip_expr()?;
+                    }
+
+                    return Err(Error::custom(format!(
+                        "argument {} does not exist in an expression with {} arguments",
+                        index, argc
+                    )));
+                }
+
+                // Skip the head, and every argument before `index`.
+                for _ in 0..index {
+                    self.skip_expr()?;
+                }
+
+                Ok(argc - index)
+            },
+            PathStep::Key(ref key) => {
+                let is_association = self.peek(|link| {
+                    Ok(link.get_type()? == TokenType::Symbol
+                        && link.get_symbol_ref()?.as_str() == "System`Association")
+                })?;
+
+                // Skip the head.
+                self.skip_expr()?;
+
+                if !is_association {
+                    for _ in 0..argc {
+                        self.skip_expr()?;
+                    }
+
+                    return Err(Error::custom("expected an Association".to_owned()));
+                }
+
+                for rule_index in 0..argc {
+                    if !self.peek(is_rule)? {
+                        for _ in rule_index..argc {
+                            self.skip_expr()?;
+                        }
+
+                        return Err(Error::custom(format!(
+                            "element {} of Association is not a rule",
+                            rule_index + 1
+                        )));
+                    }
+
+                    let _ = self.get_arg_count()?;
+                    self.skip_expr()?;
+
+                    let is_match = if self.get_type()? == TokenType::String {
+                        self.get_string_ref()?.as_str() == key
+                    } else {
+                        self.skip_expr()?;
+                        false
+                    };
+
+                    if is_match {
+                        return Ok(argc - rule_index - 1);
+                    }
+
+                    // Skip the value.
+                    self.skip_expr()?;
+                }
+
+                Err(Error::custom(format!("key {:?} does not exist", key)))
+            },
+        }
+    }
+}
+
+#[cfg(feature = "expr")]
+fn is_rule(link: &mut Link) -> Result<bool, Error> {
+    if link.get_type()? != TokenType::Function || link.get_arg_count()? != 2 {
+        return Ok(false);
+    }
+
+    if link.get_type()? != TokenType::Symbol {
+        return Ok(false);
+    }
+
+    let head = link.get_symbol_ref()?;
+
+    Ok(matches!(
+        head.as_str(),
+        "System`Rule" | "System`RuleDelayed"
+    ))
+}
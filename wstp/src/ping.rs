@@ -0,0 +1,105 @@
+//! Liveness checks of connected links.
+
+use std::time::{Duration, Instant};
+
+use crate::{get::symbol_matches, symbols, sys, Error, Link, TokenType};
+
+impl Link {
+    /// Check that the peer of this link is alive and responding, returning the
+    /// round-trip time.
+    ///
+    /// This puts `EvaluatePacket[Null]` on the link, and then waits up to `timeout`
+    /// for the `ReturnPacket[...]` sent in reply, discarding any other packets the peer
+    /// sends first. A Wolfram Language kernel replies to this packet without any side
+    /// effects.
+    ///
+    /// The peer must therefore be a Wolfram Language kernel, or another program that
+    /// replies to every `EvaluatePacket` with a `ReturnPacket`. A peer that does not
+    /// will never reply, so the ping times out, and the peer is left with an
+    /// `EvaluatePacket[Null]` it does not expect.
+    ///
+    /// Loopback links have no peer, so for loopback links this only checks that the
+    /// link is open, without putting anything on it.
+    ///
+    /// This can be used by pool managers and load balancers to validate an idle link
+    /// before handing it out. The link should not be used by anything else while it is
+    /// being pinged, because any `ReturnPacket` received is taken to be the reply.
+    ///
+    /// An error is returned if the link has been closed, or if no reply is received
+    /// within `timeout`. If a ping times out, the reply may still arrive later, so the
+    /// link should be discarded.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::connect(wstp::Protocol::TCPIP, "8000").unwrap();
+    /// link.activate().unwrap();
+    ///
+    /// match link.ping(Duration::from_secs(1)) {
+    ///     Ok(rtt) => println!("peer responded in {:?}", rtt),
+    ///     Err(err) => println!("peer is not responding: {}", err),
+    /// }
+    /// ```
+    pub fn ping(&mut self, timeout: Duration) -> Result<Duration, Error> {
+        let start = Instant::now();
+
+        if !self.is_open() {
+            return Err(Error::from_code(sys::WSECLOSED));
+        }
+
+        if self.is_loopback() {
+            return Ok(start.elapsed());
+        }
+
+        self.put_guarded(|link| {
+            link.put_function(symbols::EVALUATE_PACKET.as_str(), 1)?;
+            link.put_symbol(symbols::NULL.as_str())
+        })?;
+        self.end_packet()?;
+        self.flush()?;
+
+        let deadline = start + timeout;
+
+        loop {
+            if !self.wait_until(deadline)? {
+                return Err(Error::custom(format!(
+                    "ping: no reply received within {:?}",
+                    timeout
+                )));
+            }
+
+            if self.skip_packet()? {
+                return Ok(start.elapsed());
+            }
+        }
+    }
+
+    /// Read and discard the next packet, returning `true` if it was a `ReturnPacket`.
+    pub(crate) fn skip_packet(&mut self) -> Result<bool, Error> {
+        if self.get_type()? != TokenType::Function {
+            self.skip_expr()?;
+            return Ok(false);
+        }
+
+        let argc = self.get_arg_count()?;
+
+        let is_return = match self.get_type()? {
+            TokenType::Symbol => {
+                symbol_matches(self.get_symbol_ref()?.as_str(), "ReturnPacket")
+            },
+            _ => {
+                self.skip_expr()?;
+                false
+            },
+        };
+
+        for _ in 0..argc {
+            self.skip_expr()?;
+        }
+
+        Ok(is_return)
+    }
+}
@@ -0,0 +1,129 @@
+//! Detection of put sequences that were abandoned partway through an expression.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{settings::update_raw_settings, sys::WSLINK, Error, Link};
+
+/// Whether any link has ever been poisoned.
+///
+/// This lets [`Link::flush()`] and other operations skip looking up the settings of a
+/// link when no link has been poisoned.
+static ANY_POISONED: AtomicBool = AtomicBool::new(false);
+
+/// # Poisoning
+///
+/// An expression is put on a link one token at a time, so a put sequence that is
+/// abandoned partway through, because of an error or a panic, leaves an incomplete
+/// expression in the outgoing buffer of the link. Sending that data would cause the
+/// peer to read a malformed expression, or to misinterpret everything that is put on
+/// the link afterwards.
+///
+/// To prevent this, a link is *poisoned* when a put sequence run using
+/// [`Link::put_guarded()`] fails or panics. Every function of this crate that puts
+/// more than one token, from [`Link::put_function()`] and [`Link::put_str_list()`] to
+/// [`Link::put_expr()`], [`Link::put_sparse_array()`], and the
+/// [`put_expr!`][crate::put_expr] macro, uses [`Link::put_guarded()`] internally.
+/// Once a link is poisoned, every put function, [`Link::end_packet()`], and
+/// [`Link::flush()`] return an error for which [`Error::is_poisoned()`] is `true`,
+/// instead of sending any more data to the peer.
+///
+/// A poisoned link should usually be closed. [`Link::repair()`] can be used to keep
+/// using the link when the application protocol has another way to resynchronize
+/// with the peer.
+impl Link {
+    /// Run `put`, poisoning this link if it returns an error or panics.
+    ///
+    /// `put` should put zero or more complete expressions on the link it is given.
+    /// If `put` fails partway through an expression, the link is left containing an
+    /// incomplete expression, so it is [poisoned][Link::is_poisoned].
+    ///
+    /// Because `put` is a closure rather than a guard value, a put sequence cannot
+    /// skip this check by being [forgotten][std::mem::forget].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{Link, NanPolicy};
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    /// link.set_nan_policy(NanPolicy::Error);
+    ///
+    /// let result = link.put_guarded(|link| {
+    ///     link.put_function("System`List", 2)?;
+    ///     link.put_f64(1.0)?;
+    ///     // Fails, abandoning the list before its second element is put.
+    ///     link.put_f64(f64::NAN)
+    /// });
+    ///
+    /// assert!(result.is_err());
+    /// assert!(link.is_poisoned());
+    /// assert!(link.flush().unwrap_err().is_poisoned());
+    /// ```
+    pub fn put_guarded<T, F>(&mut self, put: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Link) -> Result<T, Error>,
+    {
+        self.check_poisoned()?;
+
+        let guard = PoisonGuard {
+            raw_link: self.raw_link,
+        };
+
+        let result = put(self);
+
+        if result.is_ok() {
+            std::mem::forget(guard);
+        }
+
+        result
+    }
+
+    /// Returns `true` if this link has been poisoned by a put sequence that was
+    /// abandoned partway through an expression.
+    ///
+    /// See [`Link::put_guarded()`].
+    pub fn is_poisoned(&self) -> bool {
+        if !ANY_POISONED.load(Ordering::Relaxed) {
+            return false;
+        }
+
+        self.with_settings(|settings| settings.poisoned)
+    }
+
+    /// Clear the poisoned state and any error on this link, so that it can be used
+    /// again.
+    ///
+    /// This does not remove the incomplete expression that poisoned the link, which
+    /// may already have been sent to the peer. Only call this if the application
+    /// protocol has a way to resynchronize with the peer, e.g. by sending a new
+    /// packet that the peer is known to use to discard partially received data.
+    pub fn repair(&mut self) {
+        if self.is_poisoned() {
+            self.update_settings(|settings| settings.poisoned = false);
+        }
+
+        self.clear_error();
+    }
+
+    /// Return an error if this link is poisoned.
+    pub(crate) fn check_poisoned(&self) -> Result<(), Error> {
+        if self.is_poisoned() {
+            return Err(Error::poisoned());
+        }
+
+        Ok(())
+    }
+}
+
+/// Poisons a link when dropped, unless forgotten.
+struct PoisonGuard {
+    raw_link: WSLINK,
+}
+
+impl Drop for PoisonGuard {
+    fn drop(&mut self) {
+        ANY_POISONED.store(true, Ordering::Relaxed);
+
+        update_raw_settings(self.raw_link, |settings| settings.poisoned = true)
+    }
+}
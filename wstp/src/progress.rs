@@ -0,0 +1,153 @@
+//! Conventions for streaming progress updates over a link.
+//!
+//! A progress update is an expression of the form:
+//!
+//! ```text
+//! Global`progress[fraction, message]
+//! ```
+//!
+//! where `fraction` is a real number between `0.0` and `1.0`, and `message` is a
+//! string describing the current stage of the computation.
+//!
+//! Rust programs send progress updates using [`Link::put_progress()`], and receive
+//! them using [`Link::get_progress()`], or by converting the intermediate packets of a
+//! kernel evaluation using [`Progress::from_expr()`].
+//!
+//! Wolfram Language code sends progress updates by writing them to the link, for
+//! example from a function called by an [installed][Install] program:
+//!
+//! ```text
+//! LinkWrite[$ParentLink, progress[0.5, "Halfway there"]]
+//! ```
+//!
+//! [Install]: https://reference.wolfram.com/language/ref/Install.html
+
+use wolfram_expr::{Expr, ExprKind, Symbol};
+
+use crate::{Error, Link};
+
+const PROGRESS_HEAD: &str = "Global`progress";
+
+/// A progress update, sent as `progress[fraction, message]`.
+///
+/// See the [module documentation][self] for the conventions used.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    /// Fraction of the computation that is complete, between `0.0` and `1.0`.
+    pub fraction: f64,
+    /// Description of the current stage of the computation.
+    pub message: String,
+}
+
+impl Progress {
+    /// Construct a new progress update.
+    pub fn new(fraction: f64, message: &str) -> Self {
+        Progress {
+            fraction,
+            message: message.to_owned(),
+        }
+    }
+
+    /// Convert a `progress[fraction, message]` expression into a [`Progress`].
+    ///
+    /// Returns `None` if `expr` is not a valid progress update. `fraction` may be an
+    /// integer or a real.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wolfram_expr::{Expr, Symbol};
+    /// use wstp::Progress;
+    ///
+    /// let expr = Expr::normal(
+    ///     Symbol::new("Global`progress"),
+    ///     vec![Expr::real(0.25), Expr::string("Loading")],
+    /// );
+    ///
+    /// assert_eq!(Progress::from_expr(&expr), Some(Progress::new(0.25, "Loading")));
+    /// ```
+    pub fn from_expr(expr: &Expr) -> Option<Progress> {
+        let normal = match expr.kind() {
+            ExprKind::Normal(normal) => normal,
+            _ => return None,
+        };
+
+        match normal.head().kind() {
+            ExprKind::Symbol(head) if head.as_str() == PROGRESS_HEAD => (),
+            _ => return None,
+        }
+
+        let (fraction, message) = match normal.elements() {
+            [fraction, message] => (fraction, message),
+            _ => return None,
+        };
+
+        let fraction = match fraction.kind() {
+            ExprKind::Integer(int) => *int as f64,
+            ExprKind::Real(real) => **real,
+            _ => return None,
+        };
+
+        match message.kind() {
+            ExprKind::String(message) => Some(Progress::new(fraction, message)),
+            _ => None,
+        }
+    }
+
+    /// Convert this progress update into a `progress[fraction, message]` expression.
+    pub fn to_expr(&self) -> Expr {
+        Expr::normal(
+            Symbol::new(PROGRESS_HEAD),
+            vec![
+                Expr::real(self.fraction),
+                Expr::string(self.message.as_str()),
+            ],
+        )
+    }
+}
+
+/// # Progress updates
+impl Link {
+    /// Put a `progress[fraction, message]` update, and then flush this link so that
+    /// the update is seen promptly.
+    ///
+    /// An error is returned if `fraction` is not between `0.0` and `1.0`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{Link, Progress};
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_progress(0.5, "Halfway there").unwrap();
+    ///
+    /// assert_eq!(link.get_progress().unwrap(), Progress::new(0.5, "Halfway there"));
+    /// ```
+    pub fn put_progress(&mut self, fraction: f64, message: &str) -> Result<(), Error> {
+        if !(0.0..=1.0).contains(&fraction) {
+            return Err(Error::custom(format!(
+                "progress fraction must be between 0 and 1, got {}",
+                fraction
+            )));
+        }
+
+        self.put_guarded(|link| {
+            link.put_function(PROGRESS_HEAD, 2)?;
+            link.put_f64(fraction)?;
+            link.put_str(message)
+        })?;
+
+        self.flush()
+    }
+
+    /// Get a `progress[fraction, message]` update.
+    pub fn get_progress(&mut self) -> Result<Progress, Error> {
+        let _: () = self.expect_head_arity(PROGRESS_HEAD, 2)?;
+
+        let fraction = self.get_f64()?;
+        let message = self.get_string()?;
+
+        Ok(Progress { fraction, message })
+    }
+}
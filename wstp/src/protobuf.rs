@@ -0,0 +1,365 @@
+//! Transfer of protobuf messages as [`Association`][Association] expressions.
+//!
+//! Messages are converted using the runtime type information provided by
+//! [`prost_reflect`], so any message type generated by `prost` that implements
+//! [`ReflectMessage`] can be transferred without a hand-written converter.
+//!
+//! [Association]: https://reference.wolfram.com/language/ref/Association.html
+
+use std::collections::HashMap;
+
+use prost_reflect::{
+    bytes::Bytes, DynamicMessage, FieldDescriptor, Kind, MapKey, MessageDescriptor,
+    ReflectMessage, Value,
+};
+
+use crate::{Error, Link, TokenType};
+
+impl Link {
+    /// Put a protobuf message as an [`Association`][Association] expression.
+    ///
+    /// `M` can be any message type that implements [`ReflectMessage`]. Message types
+    /// generated by `prost` can implement `ReflectMessage` using the
+    /// `#[derive(ReflectMessage)]` macro provided by the `derive` feature of
+    /// `prost-reflect`, or using the `prost-reflect-build` crate.
+    ///
+    /// See [`Link::put_dynamic_message()`] for how messages are represented as
+    /// expressions.
+    ///
+    /// [Association]: https://reference.wolfram.com/language/ref/Association.html
+    pub fn put_protobuf_message<M: ReflectMessage>(
+        &mut self,
+        message: &M,
+    ) -> Result<(), Error> {
+        self.put_dynamic_message(&message.transcode_to_dynamic())
+    }
+
+    /// Get an [`Association`][Association] expression as a protobuf message.
+    ///
+    /// See [`Link::get_dynamic_message()`].
+    ///
+    /// [Association]: https://reference.wolfram.com/language/ref/Association.html
+    pub fn get_protobuf_message<M: ReflectMessage + Default>(
+        &mut self,
+    ) -> Result<M, Error> {
+        let descriptor = M::default().descriptor();
+
+        let message = self.get_dynamic_message(descriptor)?;
+
+        message.transcode_to::<M>().map_err(|err| {
+            Error::custom(format!(
+                "unable to convert {} message: {}",
+                message.descriptor().full_name(),
+                err
+            ))
+        })
+    }
+
+    /// Put a [`DynamicMessage`] as an [`Association`][Association] expression.
+    ///
+    /// Each field that is set on `message` is put as a `"name" -> value` rule, where
+    /// `"name"` is the name of the field in the message definition. Field values are
+    /// converted using the following mapping:
+    ///
+    /// Protobuf type                      | Expression
+    /// -----------------------------------|-----------
+    /// `bool`                             | `True`, `False`
+    /// integer types                      | `Integer`
+    /// `float`, `double`                  | `Real`
+    /// `string`                           | `String`
+    /// `bytes`                            | `ByteArray[...]`
+    /// enum                               | `String` name of the enum value
+    /// message                            | `Association[...]`
+    /// `repeated` field                   | `List[...]`
+    /// `map` field                        | `Association[key -> value, ...]`
+    ///
+    /// [Association]: https://reference.wolfram.com/language/ref/Association.html
+    pub fn put_dynamic_message(&mut self, message: &DynamicMessage) -> Result<(), Error> {
+        self.put_guarded(|link| link.put_dynamic_message_unguarded(message))
+    }
+
+    fn put_dynamic_message_unguarded(
+        &mut self,
+        message: &DynamicMessage,
+    ) -> Result<(), Error> {
+        let fields: Vec<(FieldDescriptor, &Value)> = message.fields().collect();
+
+        self.put_function("System`Association", fields.len())?;
+
+        for (field, value) in fields {
+            self.put_function("System`Rule", 2)?;
+            self.put_str(field.name())?;
+            self.put_protobuf_value(&field.kind(), value)?;
+        }
+
+        Ok(())
+    }
+
+    /// Get an [`Association`][Association] expression as a [`DynamicMessage`] of type
+    /// `descriptor`.
+    ///
+    /// This is the inverse of the mapping described in [`Link::put_dynamic_message()`].
+    /// Enum values may be given either by name or by number, and `float` and `double`
+    /// fields also accept `Integer` values.
+    ///
+    /// An error is returned if the incoming expression contains a key that is not the
+    /// name of a field of `descriptor`, or a value of the wrong type.
+    ///
+    /// [Association]: https://reference.wolfram.com/language/ref/Association.html
+    pub fn get_dynamic_message(
+        &mut self,
+        descriptor: MessageDescriptor,
+    ) -> Result<DynamicMessage, Error> {
+        let argc = self.test_head("System`Association")?;
+
+        let mut message = DynamicMessage::new(descriptor);
+
+        for _ in 0..argc {
+            expect_rule(self)?;
+
+            let name = self.get_string()?;
+
+            let field = match message.descriptor().get_field_by_name(&name) {
+                Some(field) => field,
+                None => {
+                    return Err(Error::custom(format!(
+                        "{} message has no field named \"{}\"",
+                        message.descriptor().full_name(),
+                        name
+                    )))
+                },
+            };
+
+            let value = self.get_protobuf_field(&field)?;
+
+            message.set_field(&field, value);
+        }
+
+        Ok(message)
+    }
+
+    //==================================
+    // Field values
+    //==================================
+
+    fn put_protobuf_value(&mut self, kind: &Kind, value: &Value) -> Result<(), Error> {
+        match value {
+            Value::Bool(true) => self.put_symbol("System`True"),
+            Value::Bool(false) => self.put_symbol("System`False"),
+            Value::I32(value) => self.put_i64(i64::from(*value)),
+            Value::I64(value) => self.put_i64(*value),
+            Value::U32(value) => self.put_i64(i64::from(*value)),
+            Value::U64(value) => match i64::try_from(*value) {
+                Ok(value) => self.put_i64(value),
+                Err(_) => self.put_integer_digits(&value.to_string()),
+            },
+            Value::F32(value) => self.put_f32(*value),
+            Value::F64(value) => self.put_f64(*value),
+            Value::String(value) => self.put_str(value),
+            Value::Bytes(value) => self.put_byte_array(value),
+            Value::EnumNumber(number) => {
+                let name = kind
+                    .as_enum()
+                    .and_then(|enum_| enum_.get_value(*number))
+                    .map(|value| value.name().to_owned());
+
+                match name {
+                    Some(name) => self.put_str(&name),
+                    // Unknown enum values are preserved as numbers.
+                    None => self.put_i64(i64::from(*number)),
+                }
+            },
+            Value::Message(message) => self.put_dynamic_message_unguarded(message),
+            Value::List(elements) => {
+                self.put_function("System`List", elements.len())?;
+
+                for element in elements {
+                    self.put_protobuf_value(kind, element)?;
+                }
+
+                Ok(())
+            },
+            Value::Map(entries) => {
+                // The kind of a map field is the synthetic map entry message type.
+                let value_kind = match kind.as_message() {
+                    Some(entry) => entry.map_entry_value_field().kind(),
+                    None => kind.clone(),
+                };
+
+                self.put_function("System`Association", entries.len())?;
+
+                for (key, value) in entries {
+                    self.put_function("System`Rule", 2)?;
+                    self.put_protobuf_map_key(key)?;
+                    self.put_protobuf_value(&value_kind, value)?;
+                }
+
+                Ok(())
+            },
+        }
+    }
+
+    fn put_protobuf_map_key(&mut self, key: &MapKey) -> Result<(), Error> {
+        match key {
+            MapKey::Bool(true) => self.put_symbol("System`True"),
+            MapKey::Bool(false) => self.put_symbol("System`False"),
+            MapKey::I32(key) => self.put_i64(i64::from(*key)),
+            MapKey::I64(key) => self.put_i64(*key),
+            MapKey::U32(key) => self.put_i64(i64::from(*key)),
+            MapKey::U64(key) => match i64::try_from(*key) {
+                Ok(key) => self.put_i64(key),
+                Err(_) => self.put_integer_digits(&key.to_string()),
+            },
+            MapKey::String(key) => self.put_str(key),
+        }
+    }
+
+    fn get_protobuf_field(&mut self, field: &FieldDescriptor) -> Result<Value, Error> {
+        let kind = field.kind();
+
+        if field.is_map() {
+            let entry = kind.as_message().cloned().ok_or_else(|| {
+                Error::custom(format!("map field {} has no entry type", field.name()))
+            })?;
+            let key_kind = entry.map_entry_key_field().kind();
+            let value_kind = entry.map_entry_value_field().kind();
+
+            let argc = self.test_head("System`Association")?;
+
+            let mut entries = HashMap::with_capacity(argc);
+
+            for _ in 0..argc {
+                expect_rule(self)?;
+
+                let key = match self.get_protobuf_value(&key_kind)? {
+                    Value::Bool(key) => MapKey::Bool(key),
+                    Value::I32(key) => MapKey::I32(key),
+                    Value::I64(key) => MapKey::I64(key),
+                    Value::U32(key) => MapKey::U32(key),
+                    Value::U64(key) => MapKey::U64(key),
+                    Value::String(key) => MapKey::String(key),
+                    other => {
+                        return Err(Error::custom(format!(
+                            "invalid protobuf map key: {:?}",
+                            other
+                        )))
+                    },
+                };
+
+                let value = self.get_protobuf_value(&value_kind)?;
+
+                entries.insert(key, value);
+            }
+
+            Ok(Value::Map(entries))
+        } else if field.is_list() {
+            let argc = self.test_head("System`List")?;
+
+            let mut elements = Vec::with_capacity(argc);
+
+            for _ in 0..argc {
+                elements.push(self.get_protobuf_value(&kind)?);
+            }
+
+            Ok(Value::List(elements))
+        } else {
+            self.get_protobuf_value(&kind)
+        }
+    }
+
+    fn get_protobuf_value(&mut self, kind: &Kind) -> Result<Value, Error> {
+        let value = match kind {
+            Kind::Double => Value::F64(self.get_protobuf_real()?),
+            Kind::Float => Value::F32(self.get_protobuf_real()? as f32),
+            Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => {
+                Value::I32(protobuf_integer(self.get_i64()?)?)
+            },
+            Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => Value::I64(self.get_i64()?),
+            Kind::Uint32 | Kind::Fixed32 => {
+                Value::U32(protobuf_integer(self.get_i64()?)?)
+            },
+            Kind::Uint64 | Kind::Fixed64 => {
+                let digits = self.get_number_string()?;
+
+                Value::U64(digits.parse().map_err(|_| {
+                    Error::custom(format!(
+                        "integer {} is out of range for protobuf uint64 field",
+                        digits
+                    ))
+                })?)
+            },
+            Kind::Bool => {
+                let symbol = self.get_symbol_ref()?;
+
+                match symbol.as_str() {
+                    "System`True" => Value::Bool(true),
+                    "System`False" => Value::Bool(false),
+                    other => {
+                        return Err(Error::custom(format!(
+                            "expected True or False for protobuf bool field, got {}",
+                            other
+                        )))
+                    },
+                }
+            },
+            Kind::String => Value::String(self.get_string()?),
+            Kind::Bytes => {
+                Value::Bytes(Bytes::copy_from_slice(self.get_byte_array()?.data()))
+            },
+            Kind::Message(descriptor) => {
+                Value::Message(self.get_dynamic_message(descriptor.clone())?)
+            },
+            Kind::Enum(descriptor) => match self.get_type()? {
+                TokenType::Integer => {
+                    Value::EnumNumber(protobuf_integer(self.get_i64()?)?)
+                },
+                _ => {
+                    let name = self.get_string()?;
+
+                    match descriptor.get_value_by_name(&name) {
+                        Some(value) => Value::EnumNumber(value.number()),
+                        None => {
+                            return Err(Error::custom(format!(
+                                "{} enum has no value named \"{}\"",
+                                descriptor.full_name(),
+                                name
+                            )))
+                        },
+                    }
+                },
+            },
+        };
+
+        Ok(value)
+    }
+
+    fn get_protobuf_real(&mut self) -> Result<f64, Error> {
+        match self.get_type()? {
+            TokenType::Integer => Ok(self.get_i64()? as f64),
+            _ => self.get_f64(),
+        }
+    }
+}
+
+fn expect_rule(link: &mut Link) -> Result<(), Error> {
+    let argc = link.test_head("System`Rule")?;
+
+    if argc != 2 {
+        return Err(Error::custom(format!(
+            "expected Rule in Association to have 2 arguments, got {}",
+            argc
+        )));
+    }
+
+    Ok(())
+}
+
+fn protobuf_integer<T: TryFrom<i64>>(value: i64) -> Result<T, Error> {
+    T::try_from(value).map_err(|_| {
+        Error::custom(format!(
+            "integer {} is out of range for protobuf field of type {}",
+            value,
+            std::any::type_name::<T>()
+        ))
+    })
+}
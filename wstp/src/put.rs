@@ -1,6 +1,9 @@
 use std::convert::TryFrom;
 use std::ffi::CString;
 
+#[cfg(feature = "expr")]
+use wolfram_expr::Expr;
+
 use crate::{
     sys::{
         self, WSPutArgCount, WSPutInteger16, WSPutInteger32, WSPutInteger64,
@@ -16,6 +19,8 @@ impl Link {
     ///
     /// *WSTP C API Documentation:* [`WSPutType()`](https://reference.wolfram.com/language/ref/c/WSPutType.html)
     pub fn put_raw_type(&mut self, type_: i32) -> Result<(), Error> {
+        self.check_poisoned()?;
+
         if unsafe { sys::WSPutType(self.raw_link, type_) } == 0 {
             return Err(self.error_or_unknown());
         }
@@ -23,13 +28,38 @@ impl Link {
         Ok(())
     }
 
+    /// Mark the end of the current packet.
+    ///
+    /// A Wolfram Language kernel connected in packet mode does not begin evaluating a
+    /// packet like `EvaluatePacket[..]` until the end of the packet has been marked.
+    /// Call this after the last part of each packet sent to a kernel.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_function("System`EvaluatePacket", 1).unwrap();
+    /// link.put_i64(5).unwrap();
+    /// link.end_packet().unwrap();
+    ///
+    /// assert_eq!(link.get_expr().unwrap().to_string(), "System`EvaluatePacket[5]");
+    /// ```
+    ///
+    /// If [auto-flush][Link::set_auto_flush] is enabled, the link is flushed after the
+    /// end of the packet has been marked.
+    ///
     /// *WSTP C API Documentation:* [`WSEndPacket()`](https://reference.wolfram.com/language/ref/c/WSEndPacket.html)
     pub fn end_packet(&mut self) -> Result<(), Error> {
+        self.check_poisoned()?;
+
         if unsafe { sys::WSEndPacket(self.raw_link) } == 0 {
             return Err(self.error_or_unknown());
         }
 
-        Ok(())
+        self.flush_if_auto()
     }
 
     //==================================
@@ -38,6 +68,8 @@ impl Link {
 
     /// *WSTP C API Documentation:* [`WSPutUTF8String()`](https://reference.wolfram.com/language/ref/c/WSPutUTF8String.html)
     pub fn put_str(&mut self, string: &str) -> Result<(), Error> {
+        self.check_poisoned()?;
+
         // TODO: Optimization:
         //     This intermediate CString allocation may not actually be necessary. Because
         //     WSPutUTF8String() takes a pointer + length pair, it's possible it doesn't
@@ -61,13 +93,22 @@ impl Link {
         Ok(())
     }
 
+    /// Put a symbol.
+    ///
+    /// If `symbol` is a relative symbol name, it is resolved against the
+    /// [default context][Link::set_default_context] of this link, if one is set.
+    ///
     /// *WSTP C API Documentation:* [`WSPutUTF8Symbol()`](https://reference.wolfram.com/language/ref/c/WSPutUTF8Symbol.html)
     pub fn put_symbol(&mut self, symbol: &str) -> Result<(), Error> {
+        self.check_poisoned()?;
+
+        let symbol = self.resolve_symbol(symbol);
+
         // FIXME:
         //     Is this extra allocation necessary?WSPutUTF8Symbol doesn't seem to require
         //     that the data contains a NULL terminator, so we should be able to just
         //     pass a pointer to `symbol`'s data.
-        let c_string = CString::new(symbol).unwrap();
+        let c_string = CString::new(symbol.as_bytes()).unwrap();
 
         let len = i32::try_from(c_string.as_bytes().len()).expect("usize overflows i32");
         let ptr = c_string.as_ptr() as *const u8;
@@ -88,6 +129,8 @@ impl Link {
     /// This function will return a WSTP error if `utf8` is not a valid UTF-8 encoded
     /// string.
     pub fn put_utf8_str(&mut self, utf8: &[u8]) -> Result<(), Error> {
+        self.check_poisoned()?;
+
         let len = i32::try_from(utf8.len()).expect("usize overflows i32");
 
         if unsafe { WSPutUTF8String(self.raw_link, utf8.as_ptr(), len) } == 0 {
@@ -104,6 +147,8 @@ impl Link {
     /// *WSTP C API Documentation:* [`WSPutUTF16String()`](https://reference.wolfram.com/language/ref/c/WSPutUTF16String.html)
     ///
     pub fn put_utf16_str(&mut self, utf16: &[u16]) -> Result<(), Error> {
+        self.check_poisoned()?;
+
         let len = i32::try_from(utf16.len()).expect("usize overflows i32");
 
         if unsafe { WSPutUTF16String(self.raw_link, utf16.as_ptr(), len) } == 0 {
@@ -119,6 +164,8 @@ impl Link {
     ///
     /// *WSTP C API Documentation:* [`WSPutUTF32String()`](https://reference.wolfram.com/language/ref/c/WSPutUTF32String.html)
     pub fn put_utf32_str(&mut self, utf32: &[u32]) -> Result<(), Error> {
+        self.check_poisoned()?;
+
         let len = i32::try_from(utf32.len()).expect("usize overflows i32");
 
         if unsafe { WSPutUTF32String(self.raw_link, utf32.as_ptr(), len) } == 0 {
@@ -175,18 +222,83 @@ impl Link {
         head: H,
         count: usize,
     ) -> Result<(), Error> {
-        self.put_raw_type(i32::from(sys::WSTKFUNC))?;
-        self.put_arg_count(count)?;
+        let head: Option<&str> = head.into();
 
-        if let Some(head) = head.into() {
-            self.put_symbol(head)?;
-        }
+        self.put_guarded(|link| {
+            link.put_raw_type(i32::from(sys::WSTKFUNC))?;
+            link.put_arg_count(count)?;
 
-        Ok(())
+            if let Some(head) = head {
+                link.put_symbol(head)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Begin putting a function onto this link, whose head is put by `put_head`.
+    ///
+    /// `put_head` must put exactly one expression, which becomes the head of the
+    /// function. The `count` arguments of the function should be put after this
+    /// method returns.
+    ///
+    /// # Example
+    ///
+    /// Put the expression `foo["a"]["b"]` on the link:
+    ///
+    /// ```
+    /// # use wstp::Link;
+    /// # fn test() -> Result<wolfram_expr::Expr, wstp::Error> {
+    /// let mut link = Link::new_loopback()?;
+    ///
+    /// link.put_function_with_head(
+    ///     |link| {
+    ///         link.put_function("Global`foo", 1)?;
+    ///         link.put_str("a")
+    ///     },
+    ///     1,
+    /// )?;
+    /// link.put_str("b")?;
+    /// # link.get_expr()
+    /// # }
+    ///
+    /// # use wolfram_expr::{Expr, Symbol};
+    /// # assert_eq!(test().unwrap(), Expr::normal(
+    /// #     Expr::normal(Symbol::new("Global`foo"), vec![Expr::string("a")]),
+    /// #     vec![Expr::string("b")]
+    /// # ))
+    /// ```
+    pub fn put_function_with_head<F>(
+        &mut self,
+        put_head: F,
+        count: usize,
+    ) -> Result<(), Error>
+    where
+        F: FnOnce(&mut Link) -> Result<(), Error>,
+    {
+        self.put_guarded(|link| {
+            link.put_function(None, count)?;
+
+            put_head(link)
+        })
+    }
+
+    /// Begin putting a function onto this link, whose head is the expression `head`.
+    ///
+    /// See [`Link::put_function_with_head()`].
+    #[cfg(feature = "expr")]
+    pub fn put_function_with_expr_head(
+        &mut self,
+        head: &Expr,
+        count: usize,
+    ) -> Result<(), Error> {
+        self.put_function_with_head(|link| link.put_expr(head), count)
     }
 
     /// *WSTP C API Documentation:* [`WSPutArgCount()`](https://reference.wolfram.com/language/ref/c/WSPutArgCount.html)
     pub fn put_arg_count(&mut self, count: usize) -> Result<(), Error> {
+        self.check_poisoned()?;
+
         let count: i32 = i32::try_from(count).map_err(|err| {
             Error::custom(format!(
                 "put_arg_count: Error converting usize to i32: {}",
@@ -201,12 +313,105 @@ impl Link {
         Ok(())
     }
 
+    //==================================
+    // Lists
+    //==================================
+
+    /// Put a `List` of strings.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// // Put {"red", "green", "blue"}
+    /// link.put_str_list(&["red", "green", "blue"]).unwrap();
+    ///
+    /// assert_eq!(link.get_string_list().unwrap(), vec!["red", "green", "blue"]);
+    /// ```
+    pub fn put_str_list<S: AsRef<str>>(&mut self, strings: &[S]) -> Result<(), Error> {
+        self.put_guarded(|link| {
+            link.put_function("System`List", strings.len())?;
+
+            for string in strings {
+                link.put_str(string.as_ref())?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Put a `List` of symbols.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// // Put {True, False}
+    /// link.put_symbol_list(&["System`True", "System`False"]).unwrap();
+    ///
+    /// assert_eq!(
+    ///     link.get_symbol_list().unwrap(),
+    ///     vec!["System`True", "System`False"]
+    /// );
+    /// ```
+    pub fn put_symbol_list<S: AsRef<str>>(&mut self, symbols: &[S]) -> Result<(), Error> {
+        self.put_guarded(|link| {
+            link.put_function("System`List", symbols.len())?;
+
+            for symbol in symbols {
+                link.put_symbol(symbol.as_ref())?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Put a `List` of `List`s of integers, whose rows may have different lengths.
+    ///
+    /// Use [`Link::put_i64_array()`] instead when every row has the same length.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// // Put {{1, 2, 3}, {}, {4}}
+    /// link.put_jagged(&[vec![1, 2, 3], vec![], vec![4]]).unwrap();
+    ///
+    /// assert_eq!(link.get_jagged().unwrap(), vec![vec![1, 2, 3], vec![], vec![4]]);
+    /// ```
+    pub fn put_jagged(&mut self, rows: &[Vec<i64>]) -> Result<(), Error> {
+        self.put_guarded(|link| {
+            link.put_function("System`List", rows.len())?;
+
+            for row in rows {
+                link.put_function("System`List", row.len())?;
+
+                for &value in row {
+                    link.put_i64(value)?;
+                }
+            }
+
+            Ok(())
+        })
+    }
+
     //==================================
     // Numerics
     //==================================
 
     /// *WSTP C API Documentation:* [`WSPutInteger64()`](https://reference.wolfram.com/language/ref/c/WSPutInteger64.html)
     pub fn put_i64(&mut self, value: i64) -> Result<(), Error> {
+        self.check_poisoned()?;
+
         if unsafe { WSPutInteger64(self.raw_link, value) } == 0 {
             return Err(self.error_or_unknown());
         }
@@ -215,6 +420,8 @@ impl Link {
 
     /// *WSTP C API Documentation:* [`WSPutInteger32()`](https://reference.wolfram.com/language/ref/c/WSPutInteger32.html)
     pub fn put_i32(&mut self, value: i32) -> Result<(), Error> {
+        self.check_poisoned()?;
+
         if unsafe { WSPutInteger32(self.raw_link, value) } == 0 {
             return Err(self.error_or_unknown());
         }
@@ -223,6 +430,8 @@ impl Link {
 
     /// *WSTP C API Documentation:* [`WSPutInteger16()`](https://reference.wolfram.com/language/ref/c/WSPutInteger16.html)
     pub fn put_i16(&mut self, value: i16) -> Result<(), Error> {
+        self.check_poisoned()?;
+
         // Note: This conversion is necessary due to the declaration of WSPutInteger16,
         //       which takes an int for legacy reasons.
         let value = i32::from(value);
@@ -233,34 +442,137 @@ impl Link {
         Ok(())
     }
 
+    /// Put a signed 8-bit integer.
+    ///
+    /// [`WSPutInteger8()`][sys::WSPutInteger8] puts an *unsigned* 8-bit integer, so
+    /// `value` is put using [`WSPutInteger16()`][sys::WSPutInteger16] to preserve its
+    /// sign.
+    ///
+    /// *WSTP C API Documentation:* [`WSPutInteger16()`](https://reference.wolfram.com/language/ref/c/WSPutInteger16.html)
+    pub fn put_i8(&mut self, value: i8) -> Result<(), Error> {
+        self.put_i16(i16::from(value))
+    }
+
     /// *WSTP C API Documentation:* [`WSPutInteger8()`](https://reference.wolfram.com/language/ref/c/WSPutInteger8.html)
     pub fn put_u8(&mut self, value: u8) -> Result<(), Error> {
+        self.check_poisoned()?;
+
         if unsafe { WSPutInteger8(self.raw_link, value) } == 0 {
             return Err(self.error_or_unknown());
         }
         Ok(())
     }
 
+    /// Put a real number.
+    ///
+    /// NaN and infinite values are put according to the [`NanPolicy`][crate::NanPolicy]
+    /// of this link. See [`Link::set_nan_policy()`].
+    ///
     /// *WSTP C API Documentation:* [`WSPutReal64()`](https://reference.wolfram.com/language/ref/c/WSPutReal64.html)
     pub fn put_f64(&mut self, value: f64) -> Result<(), Error> {
+        self.check_poisoned()?;
+
+        if !value.is_finite() && self.put_non_finite(value)? {
+            return Ok(());
+        }
+
         if unsafe { WSPutReal64(self.raw_link, value) } == 0 {
             return Err(self.error_or_unknown());
         }
         Ok(())
     }
 
+    /// Put a real number.
+    ///
+    /// NaN and infinite values are put according to the [`NanPolicy`][crate::NanPolicy]
+    /// of this link. See [`Link::set_nan_policy()`].
+    ///
     /// *WSTP C API Documentation:* [`WSPutReal32()`](https://reference.wolfram.com/language/ref/c/WSPutReal32.html)
     pub fn put_f32(&mut self, value: f32) -> Result<(), Error> {
+        self.check_poisoned()?;
+
         // Note: This conversion is necessary due to the declaration of WSPutReal32,
         //       which takes a double for legacy reasons.
         let value = f64::from(value);
 
+        if !value.is_finite() && self.put_non_finite(value)? {
+            return Ok(());
+        }
+
         if unsafe { WSPutReal32(self.raw_link, value) } == 0 {
             return Err(self.error_or_unknown());
         }
         Ok(())
     }
 
+    /// Put an integer of arbitrary size, given as a string of decimal digits.
+    ///
+    /// `digits` may optionally begin with a `-` sign. This can be used to put integers
+    /// that are too large to be represented by any Rust integer type.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_integer_digits("123456789012345678901234567890").unwrap();
+    ///
+    /// assert_eq!(link.get_number_string().unwrap(), "123456789012345678901234567890");
+    /// ```
+    ///
+    /// *WSTP C API Documentation:* [`WSPutData()`](https://reference.wolfram.com/language/ref/c/WSPutData.html)
+    pub fn put_integer_digits(&mut self, digits: &str) -> Result<(), Error> {
+        let body = digits.strip_prefix('-').unwrap_or(digits);
+
+        if body.is_empty() || !body.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(Error::custom(format!(
+                "put_integer_digits: invalid integer digits: '{}'",
+                digits
+            )));
+        }
+
+        let len = i32::try_from(digits.len()).expect("usize overflows i32");
+
+        self.put_guarded(|link| {
+            link.put_raw_type(i32::from(sys::WSTKINT))?;
+
+            if unsafe { sys::WSPutSize(link.raw_link, len) } == 0 {
+                return Err(link.error_or_unknown());
+            }
+
+            if unsafe { sys::WSPutData(link.raw_link, digits.as_ptr() as *const _, len) }
+                == 0
+            {
+                return Err(link.error_or_unknown());
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Put a real number given in the Wolfram Language textual number syntax.
+    ///
+    /// This can be used to put arbitrary-precision reals, e.g. ``"3.14159`20"`` or
+    /// ``"1.5*^-300"``.
+    ///
+    /// *WSTP C API Documentation:* [`WSPutRealNumberAsUTF8String()`](https://reference.wolfram.com/language/ref/c/WSPutRealNumberAsString.html)
+    pub fn put_real_number_str(&mut self, number: &str) -> Result<(), Error> {
+        self.check_poisoned()?;
+
+        let len = i32::try_from(number.len()).expect("usize overflows i32");
+
+        if unsafe {
+            sys::WSPutRealNumberAsUTF8String(self.raw_link, number.as_ptr(), len)
+        } == 0
+        {
+            return Err(self.error_or_unknown());
+        }
+
+        Ok(())
+    }
+
     //==================================
     // Integer numeric arrays
     //==================================
@@ -277,9 +589,11 @@ impl Link {
         data: &[i64],
         dimensions: &[usize],
     ) -> Result<(), Error> {
+        self.check_poisoned()?;
+
         assert_eq!(
             data.len(),
-            dimensions.iter().product(),
+            dimensions.iter().product::<usize>(),
             "data length does not equal product of dimensions"
         );
 
@@ -314,9 +628,11 @@ impl Link {
         data: &[i32],
         dimensions: &[usize],
     ) -> Result<(), Error> {
+        self.check_poisoned()?;
+
         assert_eq!(
             data.len(),
-            dimensions.iter().product(),
+            dimensions.iter().product::<usize>(),
             "data length does not equal product of dimensions"
         );
 
@@ -351,9 +667,11 @@ impl Link {
         data: &[i16],
         dimensions: &[usize],
     ) -> Result<(), Error> {
+        self.check_poisoned()?;
+
         assert_eq!(
             data.len(),
-            dimensions.iter().product(),
+            dimensions.iter().product::<usize>(),
             "data length does not equal product of dimensions"
         );
 
@@ -382,9 +700,11 @@ impl Link {
         data: &[u8],
         dimensions: &[usize],
     ) -> Result<(), Error> {
+        self.check_poisoned()?;
+
         assert_eq!(
             data.len(),
-            dimensions.iter().product(),
+            dimensions.iter().product::<usize>(),
             "data length does not equal product of dimensions"
         );
 
@@ -413,6 +733,10 @@ impl Link {
 
     /// Put a multidimensional array of [`f64`].
     ///
+    /// NaN and infinite values are put according to the [`NanPolicy`][crate::NanPolicy]
+    /// of this link. If the policy replaces any of them, the array is put as a nested
+    /// `List` of elements instead of as a packed array. See [`Link::set_nan_policy()`].
+    ///
     /// # Panics
     ///
     /// This function will panic if the product of `dimensions` is not equal to `data.len()`.
@@ -423,12 +747,18 @@ impl Link {
         data: &[f64],
         dimensions: &[usize],
     ) -> Result<(), Error> {
+        self.check_poisoned()?;
+
         assert_eq!(
             data.len(),
-            dimensions.iter().product(),
+            dimensions.iter().product::<usize>(),
             "data length does not equal product of dimensions"
         );
 
+        if self.put_non_finite_array(data, dimensions, Link::put_f64)? {
+            return Ok(());
+        }
+
         let dimensions: Vec<i32> = abi_array_dimensions(dimensions)?;
 
         let result = unsafe {
@@ -450,6 +780,10 @@ impl Link {
 
     /// Put a multidimensional array of [`f32`].
     ///
+    /// NaN and infinite values are put according to the [`NanPolicy`][crate::NanPolicy]
+    /// of this link. If the policy replaces any of them, the array is put as a nested
+    /// `List` of elements instead of as a packed array. See [`Link::set_nan_policy()`].
+    ///
     /// # Panics
     ///
     /// This function will panic if the product of `dimensions` is not equal to `data.len()`.
@@ -460,12 +794,18 @@ impl Link {
         data: &[f32],
         dimensions: &[usize],
     ) -> Result<(), Error> {
+        self.check_poisoned()?;
+
         assert_eq!(
             data.len(),
-            dimensions.iter().product(),
+            dimensions.iter().product::<usize>(),
             "data length does not equal product of dimensions"
         );
 
+        if self.put_non_finite_array(data, dimensions, Link::put_f32)? {
+            return Ok(());
+        }
+
         let dimensions: Vec<i32> = abi_array_dimensions(dimensions)?;
 
         let result = unsafe {
@@ -484,6 +824,83 @@ impl Link {
 
         Ok(())
     }
+
+    /// Put a matrix of [`f64`] whose rows are stored in separate buffers.
+    ///
+    /// The matrix is put as a `List` of rows, each of which is put directly from its
+    /// buffer, so the rows are never concatenated into one contiguous buffer.
+    ///
+    /// An error is returned if a row does not have length `ncols`, or if `rows` yields
+    /// a different number of rows than its [`ExactSizeIterator::len()`]. Because rows
+    /// are put as they are read from `rows`, the part of the matrix before the invalid
+    /// row has already been put when the error is returned, so the link is
+    /// [poisoned][Link::is_poisoned].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// let rows: Vec<Vec<f64>> = vec![vec![1.0, 2.0], vec![3.0, 4.0], vec![5.0, 6.0]];
+    ///
+    /// link.put_matrix_from_rows(rows.iter().map(Vec::as_slice), 2).unwrap();
+    ///
+    /// let matrix = link.get_f64_array().unwrap();
+    ///
+    /// assert_eq!(matrix.dimensions(), &[3, 2]);
+    /// assert_eq!(matrix.data(), &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    /// ```
+    pub fn put_matrix_from_rows<'r, I>(
+        &mut self,
+        rows: I,
+        ncols: usize,
+    ) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'r [f64]>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        self.put_guarded(|link| {
+            let rows = rows.into_iter();
+            let nrows = rows.len();
+
+            link.put_function("System`List", nrows)?;
+
+            let mut count = 0;
+
+            for row in rows {
+                if count == nrows {
+                    return Err(Error::custom(format!(
+                        "put_matrix_from_rows: iterator yielded more than {} rows",
+                        nrows
+                    )));
+                }
+
+                if row.len() != ncols {
+                    return Err(Error::custom(format!(
+                        "put_matrix_from_rows: row {} has length {}, expected {}",
+                        count,
+                        row.len(),
+                        ncols
+                    )));
+                }
+
+                link.put_f64_array(row, &[ncols])?;
+
+                count += 1;
+            }
+
+            if count != nrows {
+                return Err(Error::custom(format!(
+                    "put_matrix_from_rows: iterator yielded {} rows, expected {}",
+                    count, nrows
+                )));
+            }
+
+            Ok(())
+        })
+    }
 }
 
 /// Convert `dimensions` to a `Vec<i32>`, which can further be converted to a
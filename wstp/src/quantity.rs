@@ -0,0 +1,240 @@
+//! Transfer of [`uom`] typed quantities as [`Quantity`][Quantity] expressions.
+//!
+//! A `uom` quantity is written to a link as `Quantity[magnitude, "Unit"]`, where
+//! `"Unit"` is the canonical Wolfram Language unit name of the quantity kind (e.g.
+//! `"Meters"` for [`Length`][uom::si::f64::Length]).
+//!
+//! When reading a quantity, any of the unit names in the translation table for that
+//! quantity kind are accepted, and the magnitude is converted by `uom`.
+//!
+//! [Quantity]: https://reference.wolfram.com/language/ref/Quantity.html
+
+use crate::{Error, Link, TokenType};
+
+/// A [`uom`] quantity type that can be transferred as a Wolfram Language
+/// [`Quantity`][Quantity] expression.
+///
+/// This trait is implemented for the [`uom::si::f64`] quantity types listed in the
+/// translation table in the [`Link::put_quantity()`] documentation.
+///
+/// [Quantity]: https://reference.wolfram.com/language/ref/Quantity.html
+pub trait WolframQuantity: Sized {
+    /// Wolfram Language name of the unit used when putting this quantity.
+    const UNIT: &'static str;
+
+    /// Wolfram Language names of all the units accepted when getting this quantity.
+    const UNITS: &'static [&'static str];
+
+    /// The magnitude of this quantity, in [`WolframQuantity::UNIT`] units.
+    fn magnitude(&self) -> f64;
+
+    /// Construct a quantity from a magnitude in the Wolfram Language `unit`.
+    ///
+    /// Returns `None` if `unit` is not in [`WolframQuantity::UNITS`].
+    fn from_wolfram_unit(magnitude: f64, unit: &str) -> Option<Self>;
+}
+
+impl Link {
+    /// Put a [`uom`] quantity as a [`Quantity`][Quantity] expression.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use uom::si::{f64::Length, length::kilometer};
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// // Put Quantity[2500., "Meters"]
+    /// link.put_quantity(&Length::new::<kilometer>(2.5)).unwrap();
+    ///
+    /// let length: Length = link.get_quantity().unwrap();
+    ///
+    /// assert_eq!(length.get::<kilometer>(), 2.5);
+    /// ```
+    ///
+    /// # Unit translation table
+    ///
+    /// The first unit listed for each quantity type is used when putting a quantity.
+    ///
+    /// Quantity type                 | Wolfram Language units
+    /// ------------------------------|-----------------------
+    /// `Length`                      | `"Meters"`, `"Kilometers"`, `"Centimeters"`, `"Millimeters"`, `"Micrometers"`, `"Nanometers"`, `"Inches"`, `"Feet"`, `"Miles"`
+    /// `Area`                        | `"SquareMeters"`, `"SquareKilometers"`, `"SquareCentimeters"`
+    /// `Volume`                      | `"CubicMeters"`, `"Liters"`, `"Milliliters"`
+    /// `Mass`                        | `"Kilograms"`, `"Grams"`, `"Milligrams"`, `"Pounds"`
+    /// `Time`                        | `"Seconds"`, `"Milliseconds"`, `"Microseconds"`, `"Nanoseconds"`, `"Minutes"`, `"Hours"`, `"Days"`
+    /// `Velocity`                    | `"MetersPerSecond"`, `"KilometersPerHour"`, `"MilesPerHour"`
+    /// `Acceleration`                | `"MetersPerSecondSquared"`
+    /// `Force`                       | `"Newtons"`, `"Kilonewtons"`
+    /// `Energy`                      | `"Joules"`, `"Kilojoules"`, `"KilowattHours"`
+    /// `Power`                       | `"Watts"`, `"Kilowatts"`, `"Megawatts"`
+    /// `Pressure`                    | `"Pascals"`, `"Kilopascals"`, `"Bars"`
+    /// `Frequency`                   | `"Hertz"`, `"Kilohertz"`, `"Megahertz"`
+    /// `ElectricCurrent`             | `"Amperes"`, `"Milliamperes"`
+    /// `ElectricPotential`           | `"Volts"`, `"Millivolts"`, `"Kilovolts"`
+    /// `ThermodynamicTemperature`    | `"Kelvins"`, `"DegreesCelsius"`, `"DegreesFahrenheit"`
+    ///
+    /// [Quantity]: https://reference.wolfram.com/language/ref/Quantity.html
+    pub fn put_quantity<Q: WolframQuantity>(
+        &mut self,
+        quantity: &Q,
+    ) -> Result<(), Error> {
+        self.put_guarded(|link| {
+            link.put_function("System`Quantity", 2)?;
+            link.put_f64(quantity.magnitude())?;
+            link.put_str(Q::UNIT)
+        })
+    }
+
+    /// Get a [`Quantity`][Quantity] expression as a [`uom`] quantity.
+    ///
+    /// The unit of the incoming quantity must be one of the units listed in
+    /// [`WolframQuantity::UNITS`] for `Q`. See [`Link::put_quantity()`] for the full
+    /// unit translation table.
+    ///
+    /// [Quantity]: https://reference.wolfram.com/language/ref/Quantity.html
+    pub fn get_quantity<Q: WolframQuantity>(&mut self) -> Result<Q, Error> {
+        let argc = self.test_head("System`Quantity")?;
+
+        if argc != 2 {
+            return Err(Error::custom(format!(
+                "expected Quantity expression to have 2 arguments, got {}",
+                argc
+            )));
+        }
+
+        let magnitude = match self.get_type()? {
+            TokenType::Integer => self.get_i64()? as f64,
+            _ => self.get_f64()?,
+        };
+
+        let unit = self.get_string_ref()?;
+
+        match Q::from_wolfram_unit(magnitude, unit.as_str()) {
+            Some(quantity) => Ok(quantity),
+            None => Err(Error::custom(format!(
+                "unsupported unit for {}: \"{}\" (expected one of: {:?})",
+                std::any::type_name::<Q>(),
+                unit.as_str(),
+                Q::UNITS
+            ))),
+        }
+    }
+}
+
+macro_rules! wolfram_quantities {
+    ($(
+        $kind:ident in $module:ident {
+            $first_unit:ident => $first_name:literal
+            $(, $unit:ident => $name:literal)* $(,)?
+        }
+    )*) => {
+        $(
+            impl WolframQuantity for uom::si::f64::$kind {
+                const UNIT: &'static str = $first_name;
+
+                const UNITS: &'static [&'static str] = &[$first_name $(, $name)*];
+
+                fn magnitude(&self) -> f64 {
+                    self.get::<uom::si::$module::$first_unit>()
+                }
+
+                fn from_wolfram_unit(magnitude: f64, unit: &str) -> Option<Self> {
+                    let quantity = match unit {
+                        $first_name => Self::new::<uom::si::$module::$first_unit>(magnitude),
+                        $($name => Self::new::<uom::si::$module::$unit>(magnitude),)*
+                        _ => return None,
+                    };
+
+                    Some(quantity)
+                }
+            }
+        )*
+    };
+}
+
+wolfram_quantities! {
+    Length in length {
+        meter => "Meters",
+        kilometer => "Kilometers",
+        centimeter => "Centimeters",
+        millimeter => "Millimeters",
+        micrometer => "Micrometers",
+        nanometer => "Nanometers",
+        inch => "Inches",
+        foot => "Feet",
+        mile => "Miles",
+    }
+    Area in area {
+        square_meter => "SquareMeters",
+        square_kilometer => "SquareKilometers",
+        square_centimeter => "SquareCentimeters",
+    }
+    Volume in volume {
+        cubic_meter => "CubicMeters",
+        liter => "Liters",
+        milliliter => "Milliliters",
+    }
+    Mass in mass {
+        kilogram => "Kilograms",
+        gram => "Grams",
+        milligram => "Milligrams",
+        pound => "Pounds",
+    }
+    Time in time {
+        second => "Seconds",
+        millisecond => "Milliseconds",
+        microsecond => "Microseconds",
+        nanosecond => "Nanoseconds",
+        minute => "Minutes",
+        hour => "Hours",
+        day => "Days",
+    }
+    Velocity in velocity {
+        meter_per_second => "MetersPerSecond",
+        kilometer_per_hour => "KilometersPerHour",
+        mile_per_hour => "MilesPerHour",
+    }
+    Acceleration in acceleration {
+        meter_per_second_squared => "MetersPerSecondSquared",
+    }
+    Force in force {
+        newton => "Newtons",
+        kilonewton => "Kilonewtons",
+    }
+    Energy in energy {
+        joule => "Joules",
+        kilojoule => "Kilojoules",
+        kilowatt_hour => "KilowattHours",
+    }
+    Power in power {
+        watt => "Watts",
+        kilowatt => "Kilowatts",
+        megawatt => "Megawatts",
+    }
+    Pressure in pressure {
+        pascal => "Pascals",
+        kilopascal => "Kilopascals",
+        bar => "Bars",
+    }
+    Frequency in frequency {
+        hertz => "Hertz",
+        kilohertz => "Kilohertz",
+        megahertz => "Megahertz",
+    }
+    ElectricCurrent in electric_current {
+        ampere => "Amperes",
+        milliampere => "Milliamperes",
+    }
+    ElectricPotential in electric_potential {
+        volt => "Volts",
+        millivolt => "Millivolts",
+        kilovolt => "Kilovolts",
+    }
+    ThermodynamicTemperature in thermodynamic_temperature {
+        kelvin => "Kelvins",
+        degree_celsius => "DegreesCelsius",
+        degree_fahrenheit => "DegreesFahrenheit",
+    }
+}
@@ -0,0 +1,249 @@
+//! Transfer of arrays of [`Quantity`][Quantity] values that share a single unit.
+//!
+//! Wrapping each element of a large array in its own `Quantity[magnitude, "Unit"]`
+//! expression multiplies the number of tokens that must be transferred. A
+//! [`QuantityArray`][QuantityArray] instead stores the magnitudes as a packed array,
+//! and the unit once:
+//!
+//! ```text
+//! QuantityArray[{m1, m2, ...}, "Unit"]
+//! ```
+//!
+//! [Quantity]: https://reference.wolfram.com/language/ref/Quantity.html
+//! [QuantityArray]: https://reference.wolfram.com/language/ref/QuantityArray.html
+
+use crate::{symbols, Error, Link, TokenType};
+
+/// Head of the structured data of an evaluated `QuantityArray`.
+const STRUCTURED_DATA: &str = "StructuredArray`StructuredData";
+
+/// One-dimensional array of magnitudes that share a single unit.
+///
+/// `QuantityVector` is returned by [`Link::get_quantity_array()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantityVector {
+    /// The magnitude of each element.
+    pub magnitudes: Vec<f64>,
+    /// The Wolfram Language name of the unit shared by every element, e.g.
+    /// `"Meters"`.
+    pub unit: String,
+}
+
+impl Link {
+    /// Put a one-dimensional [`QuantityArray`][QuantityArray] expression with
+    /// magnitudes `values`, all in `unit`.
+    ///
+    /// The magnitudes are put as a packed array, so the cost of putting a quantity
+    /// array is close to that of putting [`Link::put_f64_array()`]. The expression is
+    /// put in its constructor form, `QuantityArray[{...}, "Unit"]`, which becomes a
+    /// `QuantityArray` object when it is evaluated by the Wolfram Language.
+    ///
+    /// Use [`Link::put_quantity_list()`] if the receiver expects a list of
+    /// individual `Quantity` expressions instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{Link, QuantityVector};
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// // Put QuantityArray[{1.5, 2.5}, "Meters"]
+    /// link.put_quantity_array(&[1.5, 2.5], "Meters").unwrap();
+    ///
+    /// assert_eq!(
+    ///     link.get_quantity_array(),
+    ///     Ok(QuantityVector {
+    ///         magnitudes: vec![1.5, 2.5],
+    ///         unit: "Meters".into(),
+    ///     })
+    /// );
+    /// ```
+    ///
+    /// [QuantityArray]: https://reference.wolfram.com/language/ref/QuantityArray.html
+    pub fn put_quantity_array(
+        &mut self,
+        values: &[f64],
+        unit: &str,
+    ) -> Result<(), Error> {
+        self.put_guarded(|link| {
+            link.put_function("System`QuantityArray", 2)?;
+            link.put_f64_array(values, &[values.len()])?;
+            link.put_str(unit)
+        })
+    }
+
+    /// Put a list of [`Quantity`][Quantity] expressions with magnitudes `values`, all
+    /// in `unit`.
+    ///
+    /// This puts `{Quantity[m1, "Unit"], Quantity[m2, "Unit"], ...}`, which is
+    /// understood by receivers that don't support [`QuantityArray`][QuantityArray], but
+    /// is much larger than the equivalent [`Link::put_quantity_array()`].
+    ///
+    /// [Quantity]: https://reference.wolfram.com/language/ref/Quantity.html
+    /// [QuantityArray]: https://reference.wolfram.com/language/ref/QuantityArray.html
+    pub fn put_quantity_list(&mut self, values: &[f64], unit: &str) -> Result<(), Error> {
+        self.put_guarded(|link| {
+            link.put_function(symbols::LIST.as_str(), values.len())?;
+
+            for &value in values {
+                link.put_function(symbols::QUANTITY.as_str(), 2)?;
+                link.put_f64(value)?;
+                link.put_str(unit)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Get a one-dimensional array of quantities that share a single unit.
+    ///
+    /// The incoming expression can be any of:
+    ///
+    /// * `QuantityArray[{m1, m2, ...}, "Unit"]`, as put by
+    ///   [`Link::put_quantity_array()`].
+    /// * An evaluated `QuantityArray` object, in its
+    ///   ``StructuredArray[QuantityArray, {n}, StructuredArray`StructuredData[...]]``
+    ///   form.
+    /// * `{Quantity[m1, "Unit"], Quantity[m2, "Unit"], ...}`, as put by
+    ///   [`Link::put_quantity_list()`], where every element has the same unit.
+    pub fn get_quantity_array(&mut self) -> Result<QuantityVector, Error> {
+        let head = self.peek(|link| {
+            if link.get_type()? != TokenType::Function {
+                return Ok(None);
+            }
+
+            let _ = link.get_arg_count()?;
+
+            if link.get_type()? != TokenType::Symbol {
+                return Ok(None);
+            }
+
+            Ok(Some(link.get_symbol_ref()?.as_str().to_owned()))
+        })?;
+
+        match head.as_deref() {
+            Some("System`QuantityArray") => {
+                expect_argc("QuantityArray", self.test_head("System`QuantityArray")?, 2)?;
+
+                self.get_magnitudes_and_unit()
+            },
+            Some("System`StructuredArray") => {
+                expect_argc(
+                    "StructuredArray",
+                    self.test_head("System`StructuredArray")?,
+                    3,
+                )?;
+
+                {
+                    let kind = self.get_symbol_ref()?;
+
+                    if kind.as_str() != "System`QuantityArray" {
+                        return Err(Error::custom(format!(
+                            "expected StructuredArray[QuantityArray, ...], got \
+                             StructuredArray[{}, ...]",
+                            kind.as_str()
+                        )));
+                    }
+                }
+
+                // Skip the dimensions, which are implied by the magnitudes.
+                self.skip_expr()?;
+
+                let argc = self.test_head(STRUCTURED_DATA)?;
+
+                if argc < 3 {
+                    return Err(Error::custom(format!(
+                        "expected StructuredData expression to have at least 3 \
+                         arguments, got {}",
+                        argc
+                    )));
+                }
+
+                let _ = self.get_symbol_ref()?;
+
+                let quantities = self.get_magnitudes_and_unit()?;
+
+                for _ in 3..argc {
+                    self.skip_expr()?;
+                }
+
+                Ok(quantities)
+            },
+            Some("System`List") => self.get_quantity_list(),
+            _ => Err(Error::custom(
+                "expected QuantityArray expression or list of Quantity expressions"
+                    .to_owned(),
+            )),
+        }
+    }
+
+    fn get_magnitudes_and_unit(&mut self) -> Result<QuantityVector, Error> {
+        let magnitudes = {
+            let array = self.get_f64_array()?;
+
+            if array.rank() != 1 {
+                return Err(Error::custom(format!(
+                    "expected one-dimensional QuantityArray, got dimensions {:?}",
+                    array.dimensions()
+                )));
+            }
+
+            array.data().to_vec()
+        };
+
+        let unit = self.get_string()?;
+
+        Ok(QuantityVector { magnitudes, unit })
+    }
+
+    fn get_quantity_list(&mut self) -> Result<QuantityVector, Error> {
+        let len = self.test_head(symbols::LIST.as_str())?;
+
+        let mut magnitudes = Vec::with_capacity(len);
+        let mut unit: Option<String> = None;
+
+        for index in 0..len {
+            expect_argc("Quantity", self.test_head(symbols::QUANTITY.as_str())?, 2)?;
+
+            let magnitude = match self.get_type()? {
+                TokenType::Integer => self.get_i64()? as f64,
+                _ => self.get_f64()?,
+            };
+
+            magnitudes.push(magnitude);
+
+            let element_unit = self.get_string_ref()?;
+
+            match unit {
+                Some(ref unit) if unit.as_str() != element_unit.as_str() => {
+                    return Err(Error::custom(format!(
+                        "element {} of Quantity list has unit \"{}\", expected \"{}\"",
+                        index + 1,
+                        element_unit.as_str(),
+                        unit
+                    )))
+                },
+                Some(_) => (),
+                None => unit = Some(element_unit.as_str().to_owned()),
+            }
+        }
+
+        let unit = unit.ok_or_else(|| {
+            Error::custom("unable to determine the unit of an empty Quantity list".into())
+        })?;
+
+        Ok(QuantityVector { magnitudes, unit })
+    }
+}
+
+fn expect_argc(head: &str, argc: usize, expected: usize) -> Result<(), Error> {
+    if argc != expected {
+        return Err(Error::custom(format!(
+            "expected {} expression to have {} arguments, got {}",
+            head, expected, argc
+        )));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,269 @@
+//! Bounded outgoing queue of expressions, drained to a link by a writer thread.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    thread::{self, JoinHandle},
+};
+
+use wolfram_expr::Expr;
+
+use crate::{Error, Link};
+
+/// What [`QueuedLink::send()`] does when the queue is full.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum OverflowPolicy {
+    /// Block the producer until there is room in the queue.
+    #[default]
+    Block,
+    /// Discard the oldest queued expression to make room for the new one.
+    ///
+    /// The number of discarded expressions is returned by [`QueuedLink::dropped()`].
+    DropOldest,
+    /// Return an error, without queueing the new expression.
+    Error,
+}
+
+/// Wrapper around a [`Link`] that puts expressions from a bounded queue, using a
+/// dedicated writer thread.
+///
+/// Producers add expressions to the queue using [`QueuedLink::send()`], which returns
+/// as soon as the expression has been queued. The writer thread puts the queued
+/// expressions on the link in order, and flushes the link each time the queue has been
+/// drained. This decouples latency-sensitive producers from a slow peer.
+///
+/// At most `capacity` expressions are queued at a time. When the queue is full, the
+/// [`OverflowPolicy`] of the [`QueuedLink`] determines what happens to new expressions.
+///
+/// [`QueuedLink`] is [`Sync`], so it can be shared between producer threads, e.g.
+/// using an [`Arc`].
+///
+/// # Example
+///
+/// ```
+/// use wolfram_expr::Expr;
+/// use wstp::{OverflowPolicy, Protocol, QueuedLink};
+///
+/// let (a, mut b) = wstp::channel(Protocol::IntraProcess).unwrap();
+///
+/// let queue = QueuedLink::new(a, 100, OverflowPolicy::Block);
+///
+/// queue.send(Expr::from(1)).unwrap();
+/// queue.send(Expr::from(2)).unwrap();
+///
+/// assert_eq!(b.get_i64().unwrap(), 1);
+/// assert_eq!(b.get_i64().unwrap(), 2);
+///
+/// // Wait for the queue to drain, and get the link back.
+/// let _a = queue.close().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct QueuedLink {
+    shared: Arc<Shared>,
+    writer: Option<JoinHandle<Link>>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<State>,
+    /// Notified when an expression is queued, or the queue is closed.
+    not_empty: Condvar,
+    /// Notified when an expression is removed from the queue, or the writer fails.
+    not_full: Condvar,
+    capacity: usize,
+    policy: OverflowPolicy,
+}
+
+#[derive(Debug)]
+struct State {
+    queue: VecDeque<Expr>,
+    closed: bool,
+    /// Error that stopped the writer thread.
+    error: Option<Error>,
+    dropped: u64,
+}
+
+impl QueuedLink {
+    /// Start a writer thread that puts expressions queued using [`QueuedLink::send()`]
+    /// on `link`.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `capacity` is zero.
+    pub fn new(link: Link, capacity: usize, policy: OverflowPolicy) -> Self {
+        assert!(capacity > 0, "QueuedLink capacity must be non-zero");
+
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                queue: VecDeque::with_capacity(capacity),
+                closed: false,
+                error: None,
+                dropped: 0,
+            }),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            capacity,
+            policy,
+        });
+
+        let writer = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || write_queued(link, &shared))
+        };
+
+        QueuedLink {
+            shared,
+            writer: Some(writer),
+        }
+    }
+
+    /// Queue `expr` to be put on the link.
+    ///
+    /// If the queue is full, the [`OverflowPolicy`] of this link determines whether
+    /// this blocks, discards the oldest queued expression, or returns an error.
+    ///
+    /// An error is also returned if the writer thread has stopped because putting a
+    /// previous expression failed.
+    pub fn send(&self, expr: Expr) -> Result<(), Error> {
+        let shared = &*self.shared;
+
+        let mut state = shared.lock();
+
+        loop {
+            if let Some(ref err) = state.error {
+                return Err(Error::custom(format!("QueuedLink writer failed: {}", err)));
+            }
+
+            if state.queue.len() < shared.capacity {
+                break;
+            }
+
+            match shared.policy {
+                OverflowPolicy::Block => {
+                    state = shared.not_full.wait(state).expect(POISONED);
+                },
+                OverflowPolicy::DropOldest => {
+                    let _: Option<Expr> = state.queue.pop_front();
+                    state.dropped += 1;
+                },
+                OverflowPolicy::Error => {
+                    return Err(Error::custom(format!(
+                        "QueuedLink queue is full (capacity: {})",
+                        shared.capacity
+                    )))
+                },
+            }
+        }
+
+        state.queue.push_back(expr);
+        shared.not_empty.notify_one();
+
+        Ok(())
+    }
+
+    /// Get the number of expressions waiting in the queue.
+    pub fn len(&self) -> usize {
+        self.shared.lock().queue.len()
+    }
+
+    /// Returns `true` if there are no expressions waiting in the queue.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the number of expressions discarded by [`OverflowPolicy::DropOldest`].
+    pub fn dropped(&self) -> u64 {
+        self.shared.lock().dropped
+    }
+
+    /// Wait for the queued expressions to be put on the link, stop the writer thread,
+    /// and return the link.
+    ///
+    /// If the writer thread stopped because putting an expression failed, that error
+    /// is returned instead.
+    pub fn close(mut self) -> Result<Link, Error> {
+        let link = self.stop_writer();
+
+        match self.shared.lock().error.take() {
+            Some(err) => Err(err),
+            None => Ok(link),
+        }
+    }
+
+    fn stop_writer(&mut self) -> Link {
+        {
+            let mut state = self.shared.lock();
+            state.closed = true;
+            self.shared.not_empty.notify_one();
+        }
+
+        let writer = self
+            .writer
+            .take()
+            .expect("QueuedLink writer thread was already stopped");
+
+        match writer.join() {
+            Ok(link) => link,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+}
+
+impl Drop for QueuedLink {
+    fn drop(&mut self) {
+        if self.writer.is_some() {
+            let _: Link = self.stop_writer();
+        }
+    }
+}
+
+const POISONED: &str = "QueuedLink state lock was poisoned";
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect(POISONED)
+    }
+}
+
+/// Body of the writer thread of a [`QueuedLink`].
+fn write_queued(mut link: Link, shared: &Shared) -> Link {
+    loop {
+        let (expr, drained) = {
+            let mut state = shared.lock();
+
+            while state.queue.is_empty() && !state.closed {
+                state = shared.not_empty.wait(state).expect(POISONED);
+            }
+
+            let expr = match state.queue.pop_front() {
+                Some(expr) => expr,
+                // The queue is empty and closed.
+                None => return link,
+            };
+
+            shared.not_full.notify_one();
+
+            (expr, state.queue.is_empty())
+        };
+
+        let result = link.put_expr(&expr).and_then(|()| {
+            // Batch the expressions put while the queue is non-empty into one flush.
+            if drained {
+                link.flush()
+            } else {
+                Ok(())
+            }
+        });
+
+        if let Err(err) = result {
+            let mut state = shared.lock();
+            state.error = Some(err);
+            state.queue.clear();
+            // Wake producers blocked on a full queue so they see the error.
+            shared.not_full.notify_all();
+
+            return link;
+        }
+    }
+}
@@ -0,0 +1,144 @@
+//! Reading adaptor with token and expression lookahead.
+
+#[cfg(feature = "expr")]
+use wolfram_expr::Expr;
+
+use crate::{sys, Error, Link, LinkStr, Token, TokenType};
+
+/// Adaptor for reading from a [`Link`] with one token or one expression of
+/// lookahead.
+///
+/// The `peek_*()` methods read the upcoming data using a link mark, and then reset the
+/// link to that mark, so that the data is not consumed. This lets recursive-descent
+/// decoders branch on what will be read next.
+///
+/// # Example
+///
+/// ```
+/// use wstp::{Link, LinkReader, Token};
+///
+/// let mut link = Link::new_loopback().unwrap();
+///
+/// link.put_i64(5).unwrap();
+///
+/// let mut reader = LinkReader::new(&mut link);
+///
+/// assert!(matches!(reader.peek_token().unwrap(), Token::Integer(5)));
+///
+/// // Peeking did not consume the token.
+/// assert!(matches!(reader.next_token().unwrap(), Token::Integer(5)));
+/// ```
+#[derive(Debug)]
+pub struct LinkReader<'link> {
+    link: &'link mut Link,
+}
+
+impl<'link> LinkReader<'link> {
+    /// Construct a reader for `link`.
+    pub fn new(link: &'link mut Link) -> Self {
+        LinkReader { link }
+    }
+
+    /// Get the link being read from.
+    pub fn link(&mut self) -> &mut Link {
+        self.link
+    }
+
+    /// Return the link being read from.
+    pub fn into_inner(self) -> &'link mut Link {
+        self.link
+    }
+
+    //==================================
+    // Lookahead
+    //==================================
+
+    /// Get the type of the next token, without consuming it.
+    ///
+    /// See also [`Link::get_type()`].
+    pub fn peek_type(&self) -> Result<TokenType, Error> {
+        self.link.get_type()
+    }
+
+    /// Get the next token, without consuming it.
+    ///
+    /// See also [`Link::get_token()`].
+    pub fn peek_token(&mut self) -> Result<Token<'_>, Error> {
+        let token = match self.link.get_type()? {
+            TokenType::String => Token::String(self.peek_str(false)?),
+            TokenType::Symbol => Token::Symbol(self.peek_str(true)?),
+            TokenType::Integer => Token::Integer(self.link.peek(Link::get_i64)?),
+            TokenType::Real => Token::Real(self.link.peek(Link::get_f64)?),
+            TokenType::Function => Token::Function {
+                length: self.link.peek(Link::get_arg_count)?,
+            },
+        };
+
+        Ok(token)
+    }
+
+    /// Get the next expression, without consuming it.
+    ///
+    /// See also [`Link::get_expr()`].
+    #[cfg(feature = "expr")]
+    pub fn peek_expr(&mut self) -> Result<Expr, Error> {
+        self.link.peek(Link::get_expr)
+    }
+
+    fn peek_str(&mut self, is_symbol: bool) -> Result<LinkStr<'_>, Error> {
+        let raw_link = self.link.raw_link;
+
+        let mark: sys::WSMARK = unsafe { sys::WSCreateMark(raw_link) };
+
+        if mark.is_null() {
+            return Err(self.link.error_or_unknown());
+        }
+
+        let link: &Link = self.link;
+
+        let result = if is_symbol {
+            link.get_symbol_ref_shared()
+        } else {
+            link.get_string_ref_shared()
+        };
+
+        // Note: The string data returned by WSTP remains valid until it is released,
+        //       independent of the current read position of the link.
+        unsafe {
+            if result.is_err() {
+                sys::WSClearError(raw_link);
+            }
+
+            let _: sys::WSMARK = sys::WSSeekToMark(raw_link, mark, 0);
+            sys::WSDestroyMark(raw_link, mark);
+        }
+
+        result
+    }
+
+    //==================================
+    // Reading
+    //==================================
+
+    /// Read the next token.
+    ///
+    /// See [`Link::get_token()`].
+    pub fn next_token(&mut self) -> Result<Token<'_>, Error> {
+        self.link.get_token()
+    }
+
+    /// Read the next expression.
+    ///
+    /// See [`Link::get_expr()`].
+    #[cfg(feature = "expr")]
+    pub fn next_expr(&mut self) -> Result<Expr, Error> {
+        self.link.get_expr()
+    }
+
+    /// Read and discard the next expression.
+    ///
+    /// See [`Link::skip_expr()`].
+    pub fn skip_expr(&mut self) -> Result<(), Error> {
+        self.link.skip_expr()
+    }
+}
@@ -0,0 +1,492 @@
+//! Reading and writing real numbers that may be symbolic numeric specials.
+
+#[cfg(feature = "expr")]
+use wolfram_expr::{Expr, ExprKind, Symbol};
+
+use crate::{symbols, Error, Link, TokenType};
+
+/// Real number value that may be one of the symbolic numeric specials used by the
+/// Wolfram Language.
+///
+/// Returned by [`Link::get_real_value()`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RealValue {
+    /// Finite machine real number.
+    Finite(f64),
+    /// Infinite quantity, corresponding to
+    /// [`DirectedInfinity`](https://reference.wolfram.com/language/ref/DirectedInfinity.html).
+    Infinity(InfinityDirection),
+    /// [`Indeterminate`](https://reference.wolfram.com/language/ref/Indeterminate.html)
+    /// numeric quantity.
+    Indeterminate,
+    /// [`Missing`](https://reference.wolfram.com/language/ref/Missing.html) value.
+    Missing,
+    /// [`Overflow[]`](https://reference.wolfram.com/language/ref/Overflow.html), a
+    /// number too large to be represented.
+    Overflow,
+    /// [`Underflow[]`](https://reference.wolfram.com/language/ref/Underflow.html), a
+    /// number too small to be represented.
+    Underflow,
+}
+
+/// Direction of a [`RealValue::Infinity`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum InfinityDirection {
+    /// `Infinity`, or `DirectedInfinity[1]`.
+    Positive,
+    /// `-Infinity`, or `DirectedInfinity[-1]`.
+    Negative,
+    /// `ComplexInfinity`, or any other `DirectedInfinity[..]` expression.
+    Complex,
+}
+
+/// Policy for putting NaN and infinite real values on a link.
+///
+/// Configured using [`Link::set_nan_policy()`].
+///
+/// The policy applies to real numbers put using [`Link::put_f64()`] and
+/// [`Link::put_f32()`], and to the elements of real arrays put using
+/// [`Link::put_f64_array()`] and [`Link::put_f32_array()`], including the arrays put
+/// by functions like [`Link::put_numeric_array()`] and [`Link::put_sparse_array()`].
+/// If a policy replaces an array element, the whole array is put as a nested `List`
+/// of elements instead of as a packed array.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum NanPolicy {
+    /// Pass NaN and infinite values to WSTP unchanged.
+    ///
+    /// This is the default policy.
+    #[default]
+    Forward,
+    /// Return an error when putting a NaN or infinite value.
+    Error,
+    /// Put NaN values as ``System`Indeterminate``, and pass infinite values to WSTP
+    /// unchanged.
+    AsIndeterminate,
+    /// Put NaN values as ``System`Indeterminate``, and infinite values as
+    /// ``System`DirectedInfinity[1]`` or ``System`DirectedInfinity[-1]``.
+    AsDirectedInfinity,
+}
+
+impl RealValue {
+    /// Convert this value to an [`f64`].
+    ///
+    /// [`RealValue::Infinity`] values with a real direction are converted to
+    /// [`f64::INFINITY`] or [`f64::NEG_INFINITY`]. [`RealValue::Overflow`] is
+    /// converted to [`f64::INFINITY`], and [`RealValue::Underflow`] to `0.0`. Every
+    /// other non-finite value is converted to [`f64::NAN`].
+    pub fn to_f64(&self) -> f64 {
+        match *self {
+            RealValue::Finite(value) => value,
+            RealValue::Infinity(InfinityDirection::Positive) | RealValue::Overflow => {
+                f64::INFINITY
+            },
+            RealValue::Infinity(InfinityDirection::Negative) => f64::NEG_INFINITY,
+            RealValue::Underflow => 0.0,
+            RealValue::Infinity(InfinityDirection::Complex)
+            | RealValue::Indeterminate
+            | RealValue::Missing => f64::NAN,
+        }
+    }
+
+    /// Convert an expression into a [`RealValue`].
+    ///
+    /// This recognizes the same expressions as [`Link::get_real_value()`], and returns
+    /// `None` for any other expression.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wolfram_expr::{Expr, Symbol};
+    /// use wstp::RealValue;
+    ///
+    /// let overflow = Expr::normal(Symbol::new("System`Overflow"), vec![]);
+    ///
+    /// assert_eq!(RealValue::from_expr(&overflow), Some(RealValue::Overflow));
+    /// assert_eq!(RealValue::from_expr(&Expr::string("text")), None);
+    /// ```
+    #[cfg(feature = "expr")]
+    pub fn from_expr(expr: &Expr) -> Option<RealValue> {
+        let value = match expr.kind() {
+            ExprKind::Integer(value) => RealValue::Finite(*value as f64),
+            ExprKind::Real(value) => RealValue::Finite(**value),
+            ExprKind::Symbol(symbol) => match symbol.as_str() {
+                "System`Infinity" => RealValue::Infinity(InfinityDirection::Positive),
+                "System`ComplexInfinity" => {
+                    RealValue::Infinity(InfinityDirection::Complex)
+                },
+                "System`Indeterminate" => RealValue::Indeterminate,
+                _ => return None,
+            },
+            ExprKind::Normal(normal) => {
+                let head = match normal.head().kind() {
+                    ExprKind::Symbol(head) => head.as_str(),
+                    _ => return None,
+                };
+
+                match (head, normal.elements()) {
+                    ("System`DirectedInfinity", []) => {
+                        RealValue::Infinity(InfinityDirection::Complex)
+                    },
+                    ("System`DirectedInfinity", [direction]) => {
+                        let direction = match direction.kind() {
+                            ExprKind::Integer(value) => *value as f64,
+                            ExprKind::Real(value) => **value,
+                            _ => 0.0,
+                        };
+
+                        RealValue::Infinity(infinity_direction(direction))
+                    },
+                    ("System`Missing", _) => RealValue::Missing,
+                    ("System`Overflow", []) => RealValue::Overflow,
+                    ("System`Underflow", []) => RealValue::Underflow,
+                    _ => return None,
+                }
+            },
+            _ => return None,
+        };
+
+        Some(value)
+    }
+
+    /// Convert this value into an expression.
+    ///
+    /// This is the expression put by [`Link::put_real_value()`].
+    #[cfg(feature = "expr")]
+    pub fn to_expr(&self) -> Expr {
+        let directed_infinity =
+            |args: Vec<Expr>| Expr::normal(Symbol::new("System`DirectedInfinity"), args);
+
+        match *self {
+            RealValue::Finite(value) => Expr::real(value),
+            RealValue::Infinity(InfinityDirection::Positive) => {
+                directed_infinity(vec![Expr::from(1)])
+            },
+            RealValue::Infinity(InfinityDirection::Negative) => {
+                directed_infinity(vec![Expr::from(-1)])
+            },
+            RealValue::Infinity(InfinityDirection::Complex) => directed_infinity(vec![]),
+            RealValue::Indeterminate => Expr::symbol(Symbol::new("System`Indeterminate")),
+            RealValue::Missing => Expr::normal(Symbol::new("System`Missing"), vec![]),
+            RealValue::Overflow => Expr::normal(Symbol::new("System`Overflow"), vec![]),
+            RealValue::Underflow => Expr::normal(Symbol::new("System`Underflow"), vec![]),
+        }
+    }
+}
+
+impl Link {
+    /// Set the policy used to put NaN and infinite real values, including the elements
+    /// of real arrays. See [`NanPolicy`].
+    ///
+    /// The policy applies to all [`Link`] values referring to the same underlying
+    /// [`WSLINK`][crate::sys::WSLINK].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{Link, NanPolicy};
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.set_nan_policy(NanPolicy::AsDirectedInfinity);
+    ///
+    /// link.put_f64(f64::NAN).unwrap();
+    /// link.put_f64(f64::NEG_INFINITY).unwrap();
+    ///
+    /// assert_eq!(link.get_expr().unwrap().to_string(), "System`Indeterminate");
+    /// assert_eq!(link.get_expr().unwrap().to_string(), "System`DirectedInfinity[-1]");
+    /// ```
+    pub fn set_nan_policy(&mut self, policy: NanPolicy) {
+        self.update_settings(|settings| settings.nan_policy = policy)
+    }
+
+    /// Get the policy used to put NaN and infinite values on this link.
+    ///
+    /// See [`Link::set_nan_policy()`].
+    pub fn nan_policy(&self) -> NanPolicy {
+        self.with_settings(|settings| settings.nan_policy)
+    }
+
+    /// Put `value`, which is NaN or infinite, according to the [`NanPolicy`] of this
+    /// link.
+    ///
+    /// Returns `false` if `value` should be passed to WSTP unchanged.
+    pub(crate) fn put_non_finite(&mut self, value: f64) -> Result<bool, Error> {
+        debug_assert!(!value.is_finite());
+
+        match self.nan_policy() {
+            NanPolicy::Forward => Ok(false),
+            NanPolicy::Error => Err(non_finite_error(value)),
+            NanPolicy::AsIndeterminate if !value.is_nan() => Ok(false),
+            NanPolicy::AsIndeterminate | NanPolicy::AsDirectedInfinity => {
+                if value.is_nan() {
+                    self.put_symbol(symbols::INDETERMINATE.as_str())?;
+                } else {
+                    self.put_guarded(|link| {
+                        link.put_function(symbols::DIRECTED_INFINITY.as_str(), 1)?;
+                        link.put_i64(if value > 0.0 { 1 } else { -1 })
+                    })?;
+                }
+
+                Ok(true)
+            },
+        }
+    }
+
+    /// Put `data`, an array of real numbers with the given `dimensions`, according to
+    /// the [`NanPolicy`] of this link.
+    ///
+    /// If the policy replaces any of the NaN or infinite values in `data`, the array is
+    /// put as a nested `List`, with each element put by `put_element`.
+    ///
+    /// Returns `false` if `data` should be passed to WSTP unchanged.
+    pub(crate) fn put_non_finite_array<T: Copy + Into<f64>>(
+        &mut self,
+        data: &[T],
+        dimensions: &[usize],
+        put_element: fn(&mut Link, T) -> Result<(), Error>,
+    ) -> Result<bool, Error> {
+        let is_replaced: fn(f64) -> bool = match self.nan_policy() {
+            NanPolicy::Forward => return Ok(false),
+            NanPolicy::Error => {
+                self.check_finite(data.iter().map(|&value| value.into()))?;
+                return Ok(false);
+            },
+            NanPolicy::AsIndeterminate => f64::is_nan,
+            NanPolicy::AsDirectedInfinity => |value| !value.is_finite(),
+        };
+
+        if !data.iter().any(|&value| is_replaced(value.into())) {
+            return Ok(false);
+        }
+
+        self.put_guarded(|link| {
+            link.put_nested_list(data, dimensions, &mut |link, &value| {
+                put_element(link, value)
+            })
+        })?;
+
+        Ok(true)
+    }
+
+    /// Return an error if the [`NanPolicy`] of this link is [`NanPolicy::Error`], and
+    /// `values` contains a NaN or infinite value.
+    ///
+    /// Functions that put several real values call this before putting anything, so
+    /// that rejected values don't [poison][Link::is_poisoned] the link.
+    pub(crate) fn check_finite<I>(&self, values: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = f64>,
+    {
+        if self.nan_policy() != NanPolicy::Error {
+            return Ok(());
+        }
+
+        match values.into_iter().find(|value| !value.is_finite()) {
+            Some(value) => Err(non_finite_error(value)),
+            None => Ok(()),
+        }
+    }
+
+    /// Put a real number, which may be a symbolic numeric special.
+    ///
+    /// Special values are put as the following expressions, regardless of the
+    /// [`NanPolicy`] of this link:
+    ///
+    /// Value                                              | Expression
+    /// ---------------------------------------------------|-----------
+    /// `RealValue::Infinity(InfinityDirection::Positive)` | `DirectedInfinity[1]`
+    /// `RealValue::Infinity(InfinityDirection::Negative)` | `DirectedInfinity[-1]`
+    /// `RealValue::Infinity(InfinityDirection::Complex)`  | `DirectedInfinity[]`
+    /// `RealValue::Indeterminate`                         | `Indeterminate`
+    /// `RealValue::Missing`                               | `Missing[]`
+    /// `RealValue::Overflow`                              | `Overflow[]`
+    /// `RealValue::Underflow`                             | `Underflow[]`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{Link, RealValue};
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_real_value(RealValue::Overflow).unwrap();
+    /// link.put_real_value(RealValue::Finite(1.5)).unwrap();
+    ///
+    /// assert_eq!(link.get_real_value().unwrap(), RealValue::Overflow);
+    /// assert_eq!(link.get_real_value().unwrap(), RealValue::Finite(1.5));
+    /// ```
+    pub fn put_real_value(&mut self, value: RealValue) -> Result<(), Error> {
+        match value {
+            RealValue::Finite(value) => self.put_f64(value),
+            RealValue::Infinity(direction) => {
+                let direction = match direction {
+                    InfinityDirection::Positive => Some(1),
+                    InfinityDirection::Negative => Some(-1),
+                    InfinityDirection::Complex => None,
+                };
+
+                match direction {
+                    Some(direction) => self.put_guarded(|link| {
+                        link.put_function(symbols::DIRECTED_INFINITY.as_str(), 1)?;
+                        link.put_i64(direction)
+                    }),
+                    None => self.put_function(symbols::DIRECTED_INFINITY.as_str(), 0),
+                }
+            },
+            RealValue::Indeterminate => self.put_symbol(symbols::INDETERMINATE.as_str()),
+            RealValue::Missing => self.put_function(symbols::MISSING.as_str(), 0),
+            RealValue::Overflow => self.put_function(symbols::OVERFLOW.as_str(), 0),
+            RealValue::Underflow => self.put_function(symbols::UNDERFLOW.as_str(), 0),
+        }
+    }
+
+    /// Get a real number, which may be a symbolic numeric special.
+    ///
+    /// In addition to machine `Integer` and `Real` tokens, this accepts the following
+    /// expressions:
+    ///
+    /// Expression                                   | Value
+    /// ---------------------------------------------|------
+    /// `Infinity`, `DirectedInfinity[1]`            | `RealValue::Infinity(InfinityDirection::Positive)`
+    /// `DirectedInfinity[-1]`                       | `RealValue::Infinity(InfinityDirection::Negative)`
+    /// `ComplexInfinity`, `DirectedInfinity[..]`    | `RealValue::Infinity(InfinityDirection::Complex)`
+    /// `Indeterminate`                              | `RealValue::Indeterminate`
+    /// `Missing[..]`                                | `RealValue::Missing`
+    /// `Overflow[]`                                 | `RealValue::Overflow`
+    /// `Underflow[]`                                | `RealValue::Underflow`
+    ///
+    /// Any other expression is consumed, and an error is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{InfinityDirection, Link, RealValue};
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// // Put DirectedInfinity[-1], then Indeterminate, then 2.5
+    /// link.put_function("System`DirectedInfinity", 1).unwrap();
+    /// link.put_i64(-1).unwrap();
+    /// link.put_symbol("System`Indeterminate").unwrap();
+    /// link.put_f64(2.5).unwrap();
+    ///
+    /// assert_eq!(
+    ///     link.get_real_value().unwrap(),
+    ///     RealValue::Infinity(InfinityDirection::Negative)
+    /// );
+    /// assert_eq!(link.get_real_value().unwrap(), RealValue::Indeterminate);
+    /// assert_eq!(link.get_real_value().unwrap(), RealValue::Finite(2.5));
+    /// ```
+    pub fn get_real_value(&mut self) -> Result<RealValue, Error> {
+        let value = match self.get_type()? {
+            TokenType::Integer => RealValue::Finite(self.get_i64()? as f64),
+            TokenType::Real => RealValue::Finite(self.get_f64()?),
+            TokenType::Symbol => {
+                let symbol = self.get_symbol_ref()?;
+
+                match symbol.as_str() {
+                    "System`Infinity" => RealValue::Infinity(InfinityDirection::Positive),
+                    "System`ComplexInfinity" => {
+                        RealValue::Infinity(InfinityDirection::Complex)
+                    },
+                    "System`Indeterminate" => RealValue::Indeterminate,
+                    other => {
+                        return Err(Error::custom(format!(
+                            "expected real value, got symbol: {}",
+                            other
+                        )))
+                    },
+                }
+            },
+            TokenType::Function => {
+                let argc = self.get_arg_count()?;
+
+                let head = match self.get_type()? {
+                    TokenType::Symbol => Some(self.get_symbol_ref()?.as_str().to_owned()),
+                    _ => {
+                        self.skip_expr()?;
+                        None
+                    },
+                };
+
+                match head.as_deref() {
+                    Some("System`DirectedInfinity") if argc <= 1 => {
+                        let direction = if argc == 0 {
+                            InfinityDirection::Complex
+                        } else {
+                            self.get_infinity_direction()?
+                        };
+
+                        RealValue::Infinity(direction)
+                    },
+                    Some("System`Overflow") if argc == 0 => RealValue::Overflow,
+                    Some("System`Underflow") if argc == 0 => RealValue::Underflow,
+                    Some("System`Missing") => {
+                        for _ in 0..argc {
+                            self.skip_expr()?;
+                        }
+
+                        RealValue::Missing
+                    },
+                    _ => {
+                        for _ in 0..argc {
+                            self.skip_expr()?;
+                        }
+
+                        return Err(Error::custom(format!(
+                            "expected real value, got function expression with head {}",
+                            head.as_deref().unwrap_or("<non-symbol>")
+                        )));
+                    },
+                }
+            },
+            TokenType::String => {
+                let _ = self.get_string_ref()?;
+
+                return Err(Error::custom("expected real value, got String".to_owned()));
+            },
+        };
+
+        Ok(value)
+    }
+
+    /// Get the direction argument of a `DirectedInfinity[..]` expression.
+    fn get_infinity_direction(&mut self) -> Result<InfinityDirection, Error> {
+        let direction = match self.get_type()? {
+            TokenType::Integer | TokenType::Real => {
+                let value = match self.get_type()? {
+                    TokenType::Integer => self.get_i64()? as f64,
+                    _ => self.get_f64()?,
+                };
+
+                infinity_direction(value)
+            },
+            _ => {
+                self.skip_expr()?;
+                InfinityDirection::Complex
+            },
+        };
+
+        Ok(direction)
+    }
+}
+
+/// Get the direction of `DirectedInfinity[direction]`.
+fn infinity_direction(direction: f64) -> InfinityDirection {
+    if direction > 0.0 {
+        InfinityDirection::Positive
+    } else if direction < 0.0 {
+        InfinityDirection::Negative
+    } else {
+        InfinityDirection::Complex
+    }
+}
+
+/// Error returned when putting `value` on a link with [`NanPolicy::Error`].
+fn non_finite_error(value: f64) -> Error {
+    Error::custom(format!(
+        "cannot put non-finite real value {} on link with NanPolicy::Error",
+        value
+    ))
+}
@@ -0,0 +1,295 @@
+//! Publish/subscribe routing of incoming expressions based on their head.
+
+use std::{
+    collections::HashMap,
+    ops::ControlFlow,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex, MutexGuard,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use wolfram_expr::{Expr, ExprKind};
+
+use crate::{Error, Link};
+
+const POISONED: &str = "LinkRouter state lock was poisoned";
+
+/// Reads expressions from a link on a background thread, and delivers each one to the
+/// [`Subscription`]s for its head.
+///
+/// This is a publish/subscribe layer for event-style traffic, e.g. a Wolfram Language
+/// kernel sending ``MyApp`Event[...]`` expressions to a Rust program. Each call to
+/// [`LinkRouter::subscribe()`] returns a new [`Subscription`], which receives every
+/// incoming expression whose head is the given symbol. Incoming expressions are
+/// delivered to all current subscriptions for their head. Expressions with no
+/// subscribers are discarded, and counted by [`LinkRouter::discarded()`].
+///
+/// Compare [`Dispatcher`][crate::Dispatcher], which calls a handler for each incoming
+/// expression on the thread reading from the link.
+///
+/// [`LinkRouter`] is [`Sync`], so it can be shared between threads, e.g. using an
+/// [`Arc`].
+///
+/// # Example
+///
+/// ```
+/// use wolfram_expr::{Expr, Symbol};
+/// use wstp::{LinkRouter, Protocol};
+///
+/// let (mut kernel, link) = wstp::channel(Protocol::IntraProcess).unwrap();
+///
+/// let router = LinkRouter::new(link);
+///
+/// let events = router.subscribe("MyApp`Event");
+///
+/// kernel
+///     .put_expr(&Expr::normal(Symbol::new("MyApp`Event"), vec![Expr::from(1)]))
+///     .unwrap();
+/// kernel.flush().unwrap();
+///
+/// assert_eq!(
+///     events.recv().unwrap(),
+///     Expr::normal(Symbol::new("MyApp`Event"), vec![Expr::from(1)])
+/// );
+///
+/// let _link = router.close().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct LinkRouter {
+    shared: Arc<Shared>,
+    reader: Option<JoinHandle<Link>>,
+}
+
+/// Stream of the incoming expressions with a particular head, created by
+/// [`LinkRouter::subscribe()`].
+///
+/// Dropping a [`Subscription`] unsubscribes it.
+///
+/// [`Subscription`] implements [`Iterator`], blocking until the next expression is
+/// received. Iteration ends when the [`LinkRouter`] is closed, or its link fails.
+#[derive(Debug)]
+pub struct Subscription {
+    head: String,
+    receiver: Receiver<Expr>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<State>,
+    /// Set when the reader thread should stop.
+    closed: AtomicBool,
+    discarded: AtomicU64,
+}
+
+#[derive(Debug)]
+struct State {
+    subscribers: HashMap<String, Vec<Sender<Expr>>>,
+    /// Set when the reader thread has stopped.
+    stopped: bool,
+    /// Error that stopped the reader thread.
+    error: Option<Error>,
+}
+
+impl LinkRouter {
+    /// Start a reader thread that routes the expressions received on `link`.
+    pub fn new(link: Link) -> Self {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                subscribers: HashMap::new(),
+                stopped: false,
+                error: None,
+            }),
+            closed: AtomicBool::new(false),
+            discarded: AtomicU64::new(0),
+        });
+
+        let reader = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || run_reader(link, &shared))
+        };
+
+        LinkRouter {
+            shared,
+            reader: Some(reader),
+        }
+    }
+
+    /// Subscribe to incoming expressions whose head is the symbol `head`.
+    ///
+    /// `head` must be a fully qualified symbol name, e.g. ``"MyApp`Event"``.
+    ///
+    /// Only expressions received after this call are delivered to the subscription.
+    pub fn subscribe(&self, head: &str) -> Subscription {
+        let (sender, receiver) = mpsc::channel();
+
+        let mut state = self.shared.lock();
+
+        // If the reader thread has already stopped, don't register the sender, so
+        // that the subscription is immediately disconnected.
+        if !state.stopped {
+            state
+                .subscribers
+                .entry(head.to_owned())
+                .or_default()
+                .push(sender);
+        }
+
+        Subscription {
+            head: head.to_owned(),
+            receiver,
+        }
+    }
+
+    /// Get the number of incoming expressions that were discarded because they had
+    /// no subscribers.
+    pub fn discarded(&self) -> u64 {
+        self.shared.discarded.load(Ordering::Relaxed)
+    }
+
+    /// Stop the reader thread, and return the link.
+    ///
+    /// All subscriptions are disconnected. If the reader thread stopped because of a
+    /// link error, that error is returned instead.
+    pub fn close(mut self) -> Result<Link, Error> {
+        let link = self.stop_reader();
+
+        match self.shared.lock().error.take() {
+            Some(err) => Err(err),
+            None => Ok(link),
+        }
+    }
+
+    fn stop_reader(&mut self) -> Link {
+        self.shared.closed.store(true, Ordering::SeqCst);
+
+        let reader = self
+            .reader
+            .take()
+            .expect("LinkRouter reader thread was already stopped");
+
+        match reader.join() {
+            Ok(link) => link,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+}
+
+impl Drop for LinkRouter {
+    fn drop(&mut self) {
+        if self.reader.is_some() {
+            let _: Link = self.stop_reader();
+        }
+    }
+}
+
+impl Subscription {
+    /// Get the head this subscription receives expressions for.
+    pub fn head(&self) -> &str {
+        &self.head
+    }
+
+    /// Block until the next expression is received.
+    ///
+    /// Returns `None` if the [`LinkRouter`] has been closed, or its link failed.
+    pub fn recv(&self) -> Option<Expr> {
+        self.receiver.recv().ok()
+    }
+
+    /// Block until the next expression is received, or `timeout` elapses.
+    ///
+    /// Returns `None` if no expression was received within `timeout`, or if the
+    /// [`LinkRouter`] has been closed, or its link failed.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<Expr> {
+        self.receiver.recv_timeout(timeout).ok()
+    }
+
+    /// Get the next expression if one has already been received, without blocking.
+    pub fn try_recv(&self) -> Option<Expr> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Iterator for Subscription {
+    type Item = Expr;
+
+    fn next(&mut self) -> Option<Expr> {
+        self.recv()
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect(POISONED)
+    }
+
+    /// Deliver `expr` to the subscribers for its head, returning `false` if it has no
+    /// subscribers.
+    fn publish(&self, expr: Expr) -> bool {
+        let head = match expr.kind() {
+            ExprKind::Normal(normal) => match normal.head().kind() {
+                ExprKind::Symbol(symbol) => symbol.as_str().to_owned(),
+                _ => return false,
+            },
+            _ => return false,
+        };
+
+        let mut state = self.lock();
+        let subscribers = &mut state.subscribers;
+
+        let senders = match subscribers.get_mut(&head) {
+            Some(senders) => senders,
+            None => return false,
+        };
+
+        // Sending fails if the subscription has been dropped.
+        senders.retain(|sender| sender.send(expr.clone()).is_ok());
+
+        if senders.is_empty() {
+            subscribers.remove(&head);
+            return false;
+        }
+
+        true
+    }
+}
+
+/// Body of the reader thread of a [`LinkRouter`].
+fn run_reader(mut link: Link, shared: &Shared) -> Link {
+    let result = (|| -> Result<(), Error> {
+        loop {
+            let is_ready = link.wait_with_callback(|_: &mut Link| {
+                if shared.closed.load(Ordering::SeqCst) {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            })?;
+
+            if !is_ready {
+                return Ok(());
+            }
+
+            let expr = link.get_expr()?;
+
+            if !shared.publish(expr) {
+                shared.discarded.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    })();
+
+    let mut state = shared.lock();
+
+    state.stopped = true;
+    state.error = result.err();
+
+    // Disconnect all subscriptions.
+    state.subscribers.clear();
+
+    drop(state);
+
+    link
+}
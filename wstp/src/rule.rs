@@ -0,0 +1,107 @@
+//! Reading [`Rule`][Rule] and [`RuleDelayed`][RuleDelayed] expressions.
+//!
+//! [Rule]: https://reference.wolfram.com/language/ref/Rule.html
+//! [RuleDelayed]: https://reference.wolfram.com/language/ref/RuleDelayed.html
+
+#[cfg(feature = "expr")]
+use wolfram_expr::Expr;
+
+use crate::{Error, Link};
+
+impl Link {
+    /// Get a `Rule[key, value]` expression, returning its key and value.
+    ///
+    /// An error describing the incoming expression is returned if it is not a `Rule`
+    /// with 2 elements. See [`Link::expect_head_arity()`].
+    ///
+    /// Use [`Link::get_rule_with()`] to read the key and value into other types.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wolfram_expr::Expr;
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// // Put "Method" -> "Newton"
+    /// link.put_function("System`Rule", 2).unwrap();
+    /// link.put_str("Method").unwrap();
+    /// link.put_str("Newton").unwrap();
+    ///
+    /// assert_eq!(
+    ///     link.get_rule().unwrap(),
+    ///     (Expr::string("Method"), Expr::string("Newton"))
+    /// );
+    /// ```
+    #[cfg(feature = "expr")]
+    pub fn get_rule(&mut self) -> Result<(Expr, Expr), Error> {
+        self.get_rule_with(Link::get_expr, Link::get_expr)
+    }
+
+    /// Get a `RuleDelayed[key, value]` expression, returning its key and value.
+    ///
+    /// See [`Link::get_rule()`].
+    #[cfg(feature = "expr")]
+    pub fn get_rule_delayed(&mut self) -> Result<(Expr, Expr), Error> {
+        self.get_rule_delayed_with(Link::get_expr, Link::get_expr)
+    }
+
+    /// Get a `Rule[key, value]` expression, reading its key using `get_key` and its
+    /// value using `get_value`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// // Put "MaxIterations" -> 100
+    /// link.put_function("System`Rule", 2).unwrap();
+    /// link.put_str("MaxIterations").unwrap();
+    /// link.put_i64(100).unwrap();
+    ///
+    /// let (key, value) = link.get_rule_with(Link::get_string, Link::get_i64).unwrap();
+    ///
+    /// assert_eq!(key, "MaxIterations");
+    /// assert_eq!(value, 100);
+    /// ```
+    pub fn get_rule_with<K, V, GK, GV>(
+        &mut self,
+        get_key: GK,
+        get_value: GV,
+    ) -> Result<(K, V), Error>
+    where
+        GK: FnOnce(&mut Link) -> Result<K, Error>,
+        GV: FnOnce(&mut Link) -> Result<V, Error>,
+    {
+        self.expect_head_arity("Rule", 2)?;
+
+        let key = get_key(self)?;
+        let value = get_value(self)?;
+
+        Ok((key, value))
+    }
+
+    /// Get a `RuleDelayed[key, value]` expression, reading its key using `get_key` and
+    /// its value using `get_value`.
+    ///
+    /// See [`Link::get_rule_with()`].
+    pub fn get_rule_delayed_with<K, V, GK, GV>(
+        &mut self,
+        get_key: GK,
+        get_value: GV,
+    ) -> Result<(K, V), Error>
+    where
+        GK: FnOnce(&mut Link) -> Result<K, Error>,
+        GV: FnOnce(&mut Link) -> Result<V, Error>,
+    {
+        self.expect_head_arity("RuleDelayed", 2)?;
+
+        let key = get_key(self)?;
+        let value = get_value(self)?;
+
+        Ok((key, value))
+    }
+}
@@ -0,0 +1,292 @@
+//! Declarative validation of incoming expressions.
+
+#[cfg(feature = "expr")]
+use wolfram_expr::Expr;
+
+use crate::{dispatcher::peek_head, Error, Link, TokenType};
+
+/// Description of the expected structure of an incoming expression.
+///
+/// A schema is checked against an incoming expression using [`Link::validate()`] or
+/// [`Link::get_validated()`]. If the expression does not match, the returned error
+/// describes where in the expression the mismatch occurred, e.g.
+/// ``argument 2 of Global`Foo: expected Integer, got String``.
+///
+/// # Example
+///
+/// Describe the expression `Foo[_Integer, {___String}]`:
+///
+/// ```
+/// use wstp::{ExprSchema, Link};
+///
+/// let schema = ExprSchema::function("Global`Foo")
+///     .arg(ExprSchema::integer())
+///     .arg(ExprSchema::list_of(ExprSchema::string()));
+///
+/// let mut link = Link::new_loopback().unwrap();
+///
+/// // Put Foo[5, "a"]
+/// link.put_function("Global`Foo", 2).unwrap();
+/// link.put_i64(5).unwrap();
+/// link.put_str("a").unwrap();
+///
+/// let err = link.validate(&schema).unwrap_err();
+///
+/// assert_eq!(
+///     err.to_string(),
+///     "WSTP error: argument 2 of Global`Foo: expected expression with head \
+///      System`List, got String"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExprSchema {
+    kind: SchemaKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum SchemaKind {
+    Any,
+    Atom(TokenType),
+    Function {
+        head: String,
+        arity: Option<usize>,
+        args: Vec<ExprSchema>,
+        rest: Option<Box<ExprSchema>>,
+    },
+}
+
+impl ExprSchema {
+    /// Schema matching any expression.
+    pub fn any() -> Self {
+        ExprSchema::from_kind(SchemaKind::Any)
+    }
+
+    /// Schema matching any expression whose first token has type `token_type`.
+    ///
+    /// [`TokenType::Function`] matches any non-atomic expression.
+    pub fn of_type(token_type: TokenType) -> Self {
+        ExprSchema::from_kind(SchemaKind::Atom(token_type))
+    }
+
+    /// Schema matching an `Integer`.
+    pub fn integer() -> Self {
+        ExprSchema::of_type(TokenType::Integer)
+    }
+
+    /// Schema matching a `Real`.
+    pub fn real() -> Self {
+        ExprSchema::of_type(TokenType::Real)
+    }
+
+    /// Schema matching a `String`.
+    pub fn string() -> Self {
+        ExprSchema::of_type(TokenType::String)
+    }
+
+    /// Schema matching a `Symbol`.
+    pub fn symbol() -> Self {
+        ExprSchema::of_type(TokenType::Symbol)
+    }
+
+    /// Schema matching an expression with head `head`.
+    ///
+    /// `head` must be a fully qualified symbol name, e.g. ``"Global`Foo"``.
+    ///
+    /// By default any number of arguments is accepted. Use [`ExprSchema::arg()`],
+    /// [`ExprSchema::arity()`], and [`ExprSchema::rest()`] to constrain the arguments.
+    pub fn function(head: &str) -> Self {
+        ExprSchema::from_kind(SchemaKind::Function {
+            head: head.to_owned(),
+            arity: None,
+            args: Vec::new(),
+            rest: None,
+        })
+    }
+
+    /// Schema matching a `List` whose elements all match `element`.
+    pub fn list_of(element: ExprSchema) -> Self {
+        ExprSchema::function("System`List").rest(element)
+    }
+
+    /// Add a constraint on the next argument of a function schema.
+    ///
+    /// Unless [`ExprSchema::rest()`] is also specified, a schema with argument
+    /// constraints only matches expressions with exactly that many arguments.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `self` was not constructed using
+    /// [`ExprSchema::function()`] or [`ExprSchema::list_of()`].
+    pub fn arg(mut self, schema: ExprSchema) -> Self {
+        self.function_parts("arg").args.push(schema);
+        self
+    }
+
+    /// Require that a function schema has exactly `arity` arguments.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `self` was not constructed using
+    /// [`ExprSchema::function()`] or [`ExprSchema::list_of()`].
+    pub fn arity(mut self, arity: usize) -> Self {
+        *self.function_parts("arity").arity = Some(arity);
+        self
+    }
+
+    /// Add a constraint on every argument following those specified using
+    /// [`ExprSchema::arg()`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `self` was not constructed using
+    /// [`ExprSchema::function()`] or [`ExprSchema::list_of()`].
+    pub fn rest(mut self, schema: ExprSchema) -> Self {
+        *self.function_parts("rest").rest = Some(Box::new(schema));
+        self
+    }
+
+    fn from_kind(kind: SchemaKind) -> Self {
+        ExprSchema { kind }
+    }
+
+    fn function_parts(&mut self, method: &str) -> FunctionParts<'_> {
+        match self.kind {
+            SchemaKind::Function {
+                ref mut arity,
+                ref mut args,
+                ref mut rest,
+                ..
+            } => FunctionParts { arity, args, rest },
+            _ => panic!(
+                "ExprSchema::{}(): schema does not describe a function expression",
+                method
+            ),
+        }
+    }
+
+    //==================================
+    // Validation
+    //==================================
+
+    /// Read the next expression from `link`, checking that it matches this schema.
+    fn check(&self, link: &mut Link) -> Result<(), Error> {
+        let (head, arity, args, rest) = match self.kind {
+            SchemaKind::Any => return link.skip_expr(),
+            SchemaKind::Atom(expected) => {
+                if link.get_type()? != expected {
+                    return Err(mismatch(link, &format!("{:?}", expected)));
+                }
+
+                return link.skip_expr();
+            },
+            SchemaKind::Function {
+                ref head,
+                arity,
+                ref args,
+                ref rest,
+            } => (head, arity, args, rest),
+        };
+
+        let expected_head = || format!("expression with head {}", head);
+
+        if link.get_type()? != TokenType::Function {
+            return Err(mismatch(link, &expected_head()));
+        }
+
+        match link.peek(peek_head)? {
+            Some((ref actual, _)) if actual == head => (),
+            _ => return Err(mismatch(link, &expected_head())),
+        }
+
+        let argc = link.get_arg_count()?;
+
+        // Skip the head, which was checked above.
+        link.skip_expr()?;
+
+        let expected_argc = match (arity, rest) {
+            (Some(arity), _) => Some(arity),
+            (None, None) if !args.is_empty() => Some(args.len()),
+            (None, _) => None,
+        };
+
+        if let Some(expected_argc) = expected_argc {
+            if argc != expected_argc {
+                return Err(Error::custom(format!(
+                    "{}: expected {} arguments, got {}",
+                    head, expected_argc, argc
+                )));
+            }
+        } else if argc < args.len() {
+            return Err(Error::custom(format!(
+                "{}: expected at least {} arguments, got {}",
+                head,
+                args.len(),
+                argc
+            )));
+        }
+
+        for index in 0..argc {
+            let schema = args.get(index).or(rest.as_deref());
+
+            match schema {
+                Some(schema) => schema.check(link).map_err(|err| match err.code {
+                    // Errors from the link itself are returned unchanged.
+                    Some(_) => err,
+                    None => Error::custom(format!(
+                        "argument {} of {}: {}",
+                        index + 1,
+                        head,
+                        err.message
+                    )),
+                })?,
+                None => link.skip_expr()?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct FunctionParts<'s> {
+    arity: &'s mut Option<usize>,
+    args: &'s mut Vec<ExprSchema>,
+    rest: &'s mut Option<Box<ExprSchema>>,
+}
+
+/// Construct an error describing the next expression on `link`.
+fn mismatch(link: &mut Link, expected: &str) -> Error {
+    let actual = match link.get_type() {
+        Ok(TokenType::Function) => match link.peek(peek_head) {
+            Ok(Some((head, _))) => format!("expression with head {}", head),
+            _ => "expression with a non-symbol head".to_owned(),
+        },
+        Ok(token_type) => format!("{:?}", token_type),
+        Err(err) => return err,
+    };
+
+    Error::custom(format!("expected {}, got {}", expected, actual))
+}
+
+impl Link {
+    /// Check that the next expression on this link matches `schema`, without
+    /// consuming it.
+    ///
+    /// See [`ExprSchema`].
+    pub fn validate(&mut self, schema: &ExprSchema) -> Result<(), Error> {
+        self.peek(|link| schema.check(link))
+    }
+
+    /// Read the next expression on this link, if it matches `schema`.
+    ///
+    /// The expression is checked before any of it is consumed, so if an error is
+    /// returned because the expression does not match `schema`, the link is left
+    /// positioned at the start of the expression.
+    ///
+    /// See [`ExprSchema`].
+    #[cfg(feature = "expr")]
+    pub fn get_validated(&mut self, schema: &ExprSchema) -> Result<Expr, Error> {
+        self.validate(schema)?;
+
+        self.get_expr()
+    }
+}
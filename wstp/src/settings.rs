@@ -0,0 +1,87 @@
+//! Per-link settings.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    lifecycle::ClosedCallback, sys::WSLINK, Link, LinkKind, LinkLimits, NanPolicy,
+};
+
+struct ForceSend<T>(T);
+
+unsafe impl<T> Send for ForceSend<T> {}
+
+/// Settings associated with a link, configured using methods like
+/// [`Link::set_nan_policy()`].
+#[derive(Default)]
+pub(crate) struct LinkSettings {
+    pub nan_policy: NanPolicy,
+    pub limits: LinkLimits,
+    pub on_closed: Vec<ClosedCallback>,
+    pub peer_closed: bool,
+    pub kind: Option<LinkKind>,
+    pub activated: bool,
+    pub default_context: Option<String>,
+    pub auto_flush: bool,
+    pub poisoned: bool,
+}
+
+/// Hash map used to store the settings of each link.
+///
+/// [`Link`] is a transparent wrapper around a [`WSLINK`], so there is nowhere to
+/// store settings in the link value itself. Links using the default settings have no
+/// entry in this map.
+static LINK_SETTINGS: Lazy<Mutex<ForceSend<HashMap<WSLINK, LinkSettings>>>> =
+    Lazy::new(|| Mutex::new(ForceSend(HashMap::new())));
+
+fn lock() -> MutexGuard<'static, ForceSend<HashMap<WSLINK, LinkSettings>>> {
+    LINK_SETTINGS
+        .lock()
+        .expect("failed to acquire lock on LINK_SETTINGS")
+}
+
+impl Link {
+    /// Call `read` with the settings of this link.
+    ///
+    /// `read` must not call any other settings function, or it will deadlock.
+    pub(crate) fn with_settings<T, F>(&self, read: F) -> T
+    where
+        F: FnOnce(&LinkSettings) -> T,
+    {
+        let lock = lock();
+
+        match lock.0.get(&self.raw_link) {
+            Some(settings) => read(settings),
+            None => read(&LinkSettings::default()),
+        }
+    }
+
+    /// Call `update` with a mutable reference to the settings of this link.
+    ///
+    /// `update` must not call any other settings function, or it will deadlock.
+    pub(crate) fn update_settings<T, F>(&self, update: F) -> T
+    where
+        F: FnOnce(&mut LinkSettings) -> T,
+    {
+        update_raw_settings(self.raw_link, update)
+    }
+}
+
+/// Call `update` with a mutable reference to the settings of `raw_link`.
+///
+/// This is used by code that holds a [`WSLINK`] but no [`Link`], like drop guards.
+pub(crate) fn update_raw_settings<T, F>(raw_link: WSLINK, update: F) -> T
+where
+    F: FnOnce(&mut LinkSettings) -> T,
+{
+    let mut lock = lock();
+
+    update(lock.0.entry(raw_link).or_default())
+}
+
+/// Remove the settings of `raw_link`, which is being closed.
+pub(crate) fn remove_settings(raw_link: WSLINK) -> Option<LinkSettings> {
+    lock().0.remove(&raw_link)
+}
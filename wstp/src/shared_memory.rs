@@ -0,0 +1,130 @@
+//! Naming utilities for [`SharedMemory`][crate::Protocol::SharedMemory] links.
+
+use crate::{
+    link_name::{generate_name, listen_with_generated_name},
+    Error, Link, Protocol,
+};
+
+/// Generate a collision-resistant name for a [`SharedMemory`][Protocol::SharedMemory]
+/// link.
+///
+/// `SharedMemory` links are named by the system-wide synchronization objects that
+/// back them, so two programs, or two parts of one program, that choose the same name
+/// conflict, and [`Link::listen()`] fails with the
+/// [`WSENAMETAKEN`][crate::sys::WSENAMETAKEN] error code. Names generated by this
+/// function have the form:
+///
+/// ```text
+/// wstprs-<process id>-<time>-<random>
+/// ```
+///
+/// Including the ID of the process that generated the name also allows
+/// [`clean_stale_shared_memory()`] to identify objects left behind by processes that
+/// have exited.
+///
+/// This is equivalent to
+/// [`unique_link_name(Protocol::SharedMemory)`][crate::unique_link_name].
+///
+/// See also [`Link::listen_shared_memory()`], which retries with a new name if the
+/// generated name is taken.
+pub fn shared_memory_link_name() -> String {
+    generate_name()
+}
+
+/// Remove the `SharedMemory` synchronization objects that were created for names
+/// generated by [`shared_memory_link_name()`] by processes that are no longer running,
+/// returning the number of objects removed.
+///
+/// A process that crashes while it has a `SharedMemory` link open can leave the
+/// objects backing that link behind, which uses system resources until they are
+/// removed.
+///
+/// Only objects whose names contain a name generated by [`shared_memory_link_name()`]
+/// are considered, so objects created for other link names are never removed, even
+/// if they are stale.
+///
+/// On Linux, the objects are the entries in `/dev/shm`. On other platforms, the
+/// objects can't be enumerated, and this function does nothing and returns `0`.
+pub fn clean_stale_shared_memory() -> Result<usize, Error> {
+    #[cfg(target_os = "linux")]
+    {
+        let entries = std::fs::read_dir("/dev/shm").map_err(|err| {
+            Error::custom(format!("unable to read /dev/shm directory: {}", err))
+        })?;
+
+        let mut removed = 0;
+
+        for entry in entries.flatten() {
+            let file_name = entry.file_name();
+
+            let pid = match file_name.to_str().and_then(generated_name_pid) {
+                Some(pid) => pid,
+                None => continue,
+            };
+
+            let is_running = std::path::Path::new(&format!("/proc/{}", pid)).exists();
+
+            if !is_running && std::fs::remove_file(entry.path()).is_ok() {
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        Ok(0)
+    }
+}
+
+/// Get the process ID embedded in the name generated by [`shared_memory_link_name()`]
+/// contained in `object_name`, if any.
+#[cfg(target_os = "linux")]
+fn generated_name_pid(object_name: &str) -> Option<u32> {
+    use crate::link_name::NAME_PREFIX;
+
+    let start = object_name.find(NAME_PREFIX)?;
+    let rest = object_name[start + NAME_PREFIX.len()..].strip_prefix('-')?;
+
+    let mut parts = rest.splitn(3, '-');
+    let pid = parts.next()?.parse().ok()?;
+
+    // Require the time and random parts, to avoid matching unrelated names that
+    // happen to contain the prefix.
+    let time = parts.next()?;
+    let random = parts.next()?;
+
+    let is_hex =
+        |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_hexdigit());
+
+    if !is_hex(time) || !random.get(..8).map_or(false, is_hex) {
+        return None;
+    }
+
+    Some(pid)
+}
+
+/// # Shared memory links
+impl Link {
+    /// Create a new [`SharedMemory`][Protocol::SharedMemory] link in `listen` mode,
+    /// with a name generated by [`shared_memory_link_name()`].
+    ///
+    /// If the generated name is already taken, a new name is generated and the listen
+    /// is retried, so callers don't need to handle
+    /// [`WSENAMETAKEN`][crate::sys::WSENAMETAKEN] errors themselves. Use
+    /// [`Link::link_name()`] to get the name needed to connect to the returned link.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{Link, Protocol};
+    ///
+    /// let listener = Link::listen_shared_memory().unwrap();
+    ///
+    /// let _connector = Link::connect(Protocol::SharedMemory, &listener.link_name()).unwrap();
+    /// ```
+    pub fn listen_shared_memory() -> Result<Link, Error> {
+        listen_with_generated_name(Protocol::SharedMemory, || Ok(generate_name()))
+    }
+}
@@ -0,0 +1,261 @@
+//! Transfer of two-dimensional [`SparseArray`][SparseArray] expressions.
+//!
+//! Sparse matrices are transferred using the structural form of `SparseArray` used
+//! internally by the Wolfram Language, which stores the non-zero elements in
+//! [compressed sparse row (CSR)][CSR] format:
+//!
+//! ```text
+//! SparseArray[Automatic, {rows, cols}, 0., {1, {rowPointers, columnIndices}, values}]
+//! ```
+//!
+//! [SparseArray]: https://reference.wolfram.com/language/ref/SparseArray.html
+//! [CSR]: https://en.wikipedia.org/wiki/Sparse_matrix#Compressed_sparse_row_(CSR,_CRS_or_Yale_format)
+
+use crate::{get::symbol_matches, Error, Link, TokenType};
+
+/// Sparse matrix of [`f64`] values, with all unspecified elements equal to zero.
+///
+/// `SparseMatrix` is returned by [`Link::get_sparse_array()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseMatrix {
+    /// Number of rows in the matrix.
+    pub rows: usize,
+    /// Number of columns in the matrix.
+    pub cols: usize,
+    /// Zero-based `[row, column]` positions of the explicitly stored elements, in
+    /// row-major order.
+    pub indices: Vec<[usize; 2]>,
+    /// Values of the explicitly stored elements. `values[i]` is the element at
+    /// `indices[i]`.
+    pub values: Vec<f64>,
+}
+
+impl Link {
+    /// Put a `rows` by `cols` [`SparseArray`][SparseArray] expression.
+    ///
+    /// `indices` contains the zero-based `[row, column]` position of each explicitly
+    /// specified element, and `values` the corresponding element values. `indices`
+    /// may be given in any order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// // Put the 3x3 matrix {{0, 5., 0}, {0, 0, 0}, {1., 0, 0}}
+    /// link.put_sparse_array(3, 3, &[[0, 1], [2, 0]], &[5.0, 1.0]).unwrap();
+    ///
+    /// let matrix = link.get_sparse_array().unwrap();
+    ///
+    /// assert_eq!(matrix.indices, vec![[0, 1], [2, 0]]);
+    /// assert_eq!(matrix.values, vec![5.0, 1.0]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `indices.len()` is not equal to `values.len()`.
+    ///
+    /// [SparseArray]: https://reference.wolfram.com/language/ref/SparseArray.html
+    pub fn put_sparse_array(
+        &mut self,
+        rows: usize,
+        cols: usize,
+        indices: &[[usize; 2]],
+        values: &[f64],
+    ) -> Result<(), Error> {
+        assert_eq!(
+            indices.len(),
+            values.len(),
+            "sparse array indices length does not equal values length"
+        );
+
+        // Sort the elements into row-major order, as required by the CSR format.
+        let mut order: Vec<usize> = (0..indices.len()).collect();
+        order.sort_by_key(|&index| indices[index]);
+
+        for pair in order.windows(2) {
+            if indices[pair[0]] == indices[pair[1]] {
+                return Err(Error::custom(format!(
+                    "sparse array position {:?} was specified more than once",
+                    indices[pair[0]]
+                )));
+            }
+        }
+
+        let mut row_pointers: Vec<i64> = vec![0; rows + 1];
+        let mut column_indices: Vec<i64> = Vec::with_capacity(indices.len());
+        let mut sorted_values: Vec<f64> = Vec::with_capacity(values.len());
+
+        for index in order {
+            let [row, col] = indices[index];
+
+            if row >= rows || col >= cols {
+                return Err(Error::custom(format!(
+                    "sparse array position {:?} is outside the array dimensions {:?}",
+                    [row, col],
+                    [rows, cols]
+                )));
+            }
+
+            row_pointers[row + 1] += 1;
+            // Column indices are 1-based.
+            column_indices.push(i64_from_usize(col + 1)?);
+            sorted_values.push(values[index]);
+        }
+
+        for row in 0..rows {
+            row_pointers[row + 1] += row_pointers[row];
+        }
+
+        let dimensions = [i64_from_usize(rows)?, i64_from_usize(cols)?];
+
+        self.check_finite(values.iter().copied())?;
+
+        self.put_guarded(|link| {
+            link.put_function("System`SparseArray", 4)?;
+            link.put_symbol("System`Automatic")?;
+            link.put_i64_array(&dimensions, &[2])?;
+            link.put_f64(0.0)?;
+
+            link.put_function("System`List", 3)?;
+            link.put_i64(1)?;
+            link.put_function("System`List", 2)?;
+            link.put_i64_array(&row_pointers, &[row_pointers.len()])?;
+            link.put_i64_array(&column_indices, &[column_indices.len(), 1])?;
+            link.put_f64_array(&sorted_values, &[sorted_values.len()])
+        })
+    }
+
+    /// Get a two-dimensional [`SparseArray`][SparseArray] expression.
+    ///
+    /// The incoming expression must be in the structural
+    /// `SparseArray[Automatic, ...]` form, with a background value of zero.
+    ///
+    /// [SparseArray]: https://reference.wolfram.com/language/ref/SparseArray.html
+    pub fn get_sparse_array(&mut self) -> Result<SparseMatrix, Error> {
+        expect_argc("SparseArray", self.test_head("System`SparseArray")?, 4)?;
+
+        {
+            let automatic = self.get_symbol_ref()?;
+
+            if !symbol_matches(automatic.as_str(), "Automatic") {
+                return Err(Error::custom(format!(
+                    "expected SparseArray[Automatic, ...], got SparseArray[{}, ...]",
+                    automatic.as_str()
+                )));
+            }
+        }
+
+        let (rows, cols) = {
+            let dims = self.get_i64_array()?;
+
+            match *dims.data() {
+                [rows, cols] => (usize_from_i64(rows)?, usize_from_i64(cols)?),
+                _ => {
+                    return Err(Error::custom(format!(
+                        "expected SparseArray of rank 2, got dimensions {:?}",
+                        dims.data()
+                    )))
+                },
+            }
+        };
+
+        let background = match self.get_type()? {
+            TokenType::Integer => self.get_i64()? as f64,
+            _ => self.get_f64()?,
+        };
+
+        if background != 0.0 {
+            return Err(Error::custom(format!(
+                "expected SparseArray background value of 0, got {}",
+                background
+            )));
+        }
+
+        expect_argc("List", self.test_head("System`List")?, 3)?;
+
+        let version = self.get_i64()?;
+        if version != 1 {
+            return Err(Error::custom(format!(
+                "unsupported SparseArray structural version: {}",
+                version
+            )));
+        }
+
+        expect_argc("List", self.test_head("System`List")?, 2)?;
+
+        let row_pointers: Vec<i64> = self.get_i64_array()?.data().to_vec();
+        // Note: This is a n x 1 matrix, but its flattened data is all that's needed.
+        let column_indices: Vec<i64> = self.get_i64_array()?.data().to_vec();
+        let values: Vec<f64> = self.get_f64_array()?.data().to_vec();
+
+        if row_pointers.len() != rows + 1
+            || column_indices.len() != values.len()
+            || row_pointers.last().copied() != Some(i64_from_usize(values.len())?)
+        {
+            return Err(Error::custom(
+                "inconsistent SparseArray row pointers, column indices, and values"
+                    .to_owned(),
+            ));
+        }
+
+        let mut indices = Vec::with_capacity(values.len());
+
+        for row in 0..rows {
+            let start = usize_from_i64(row_pointers[row])?;
+            let end = usize_from_i64(row_pointers[row + 1])?;
+
+            let columns = column_indices.get(start..end).ok_or_else(|| {
+                Error::custom("inconsistent SparseArray row pointers".to_owned())
+            })?;
+
+            for &col in columns {
+                // Column indices are 1-based.
+                match usize_from_i64(col)? {
+                    col @ 1.. if col <= cols => indices.push([row, col - 1]),
+                    _ => {
+                        return Err(Error::custom(format!(
+                            "SparseArray column index {} is out of range",
+                            col
+                        )))
+                    },
+                }
+            }
+        }
+
+        Ok(SparseMatrix {
+            rows,
+            cols,
+            indices,
+            values,
+        })
+    }
+}
+
+fn expect_argc(head: &str, argc: usize, expected: usize) -> Result<(), Error> {
+    if argc != expected {
+        return Err(Error::custom(format!(
+            "expected {} expression with {} arguments, got {}",
+            head, expected, argc
+        )));
+    }
+
+    Ok(())
+}
+
+fn i64_from_usize(value: usize) -> Result<i64, Error> {
+    i64::try_from(value).map_err(|err| {
+        Error::custom(format!(
+            "sparse array size {} overflows i64: {}",
+            value, err
+        ))
+    })
+}
+
+fn usize_from_i64(value: i64) -> Result<usize, Error> {
+    usize::try_from(value).map_err(|_| {
+        Error::custom(format!("invalid SparseArray size or index: {}", value))
+    })
+}
@@ -0,0 +1,133 @@
+//! Summary of the state of a link, for diagnostics.
+
+use std::fmt::{self, Debug, Display};
+
+use crate::{Error, Link, LinkKind};
+
+/// Snapshot of the state of a [`Link`].
+///
+/// Returned by [`Link::status()`]. The [`Display`] implementation formats the status
+/// as a single human-readable line, suitable for logs.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct LinkStatus {
+    /// Name of the link, as returned by [`Link::link_name()`].
+    pub name: String,
+    /// Kind of the link, as returned by [`Link::kind()`].
+    pub kind: LinkKind,
+    /// Whether [`Link::activate()`] has succeeded on this link.
+    ///
+    /// Loopback links do not need to be activated, and are always considered
+    /// activated.
+    pub activated: bool,
+    /// Whether the link is open, as returned by [`Link::is_open()`].
+    pub open: bool,
+    /// The last error to occur on the link, as returned by [`Link::error()`].
+    pub error: Option<Error>,
+    /// Number of bytes put on the link but not yet sent, if it could be determined.
+    pub bytes_to_put: Option<usize>,
+    /// Number of bytes received on the link but not yet read, if it could be
+    /// determined.
+    pub bytes_to_get: Option<usize>,
+}
+
+impl Link {
+    /// Get a snapshot of the state of this link.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::{Link, LinkKind};
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.set_name("fixtures").unwrap();
+    ///
+    /// let status = link.status();
+    ///
+    /// assert_eq!(status.name, "fixtures");
+    /// assert_eq!(status.kind, LinkKind::Loopback);
+    /// assert!(status.activated);
+    /// assert!(status.error.is_none());
+    ///
+    /// println!("{}", status);
+    /// ```
+    pub fn status(&self) -> LinkStatus {
+        LinkStatus {
+            name: self.link_name(),
+            kind: self.kind(),
+            activated: self.is_loopback()
+                || self.with_settings(|settings| settings.activated),
+            open: self.is_open(),
+            error: self.error(),
+            bytes_to_put: self.bytes_to_put().ok(),
+            bytes_to_get: self.bytes_to_get().ok(),
+        }
+    }
+}
+
+impl Debug for Link {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let LinkStatus {
+            name,
+            kind,
+            activated,
+            open,
+            error,
+            bytes_to_put,
+            bytes_to_get,
+        } = self.status();
+
+        f.debug_struct("Link")
+            .field("raw_link", &self.raw_link)
+            .field("name", &name)
+            .field("kind", &kind)
+            .field("activated", &activated)
+            .field("open", &open)
+            .field("error", &error)
+            .field("bytes_to_put", &bytes_to_put)
+            .field("bytes_to_get", &bytes_to_get)
+            .finish()
+    }
+}
+
+impl Display for LinkStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let LinkStatus {
+            name,
+            kind,
+            activated,
+            open,
+            error,
+            bytes_to_put,
+            bytes_to_get,
+        } = self;
+
+        write!(
+            f,
+            "{:?} ({:?}): {}, {}",
+            name,
+            kind,
+            if *activated {
+                "activated"
+            } else {
+                "not activated"
+            },
+            if *open { "open" } else { "closed" }
+        )?;
+
+        if let Some(bytes) = bytes_to_put {
+            write!(f, ", {} bytes to put", bytes)?;
+        }
+
+        if let Some(bytes) = bytes_to_get {
+            write!(f, ", {} bytes to get", bytes)?;
+        }
+
+        if let Some(error) = error {
+            write!(f, ", error: {}", error)?;
+        }
+
+        Ok(())
+    }
+}
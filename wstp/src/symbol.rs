@@ -0,0 +1,297 @@
+//! Context manipulation of symbol names.
+
+use std::borrow::Cow;
+use std::fmt::{self, Display};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{Error, Link};
+
+/// Whether [`Link::set_default_context()`] has ever been called.
+///
+/// This lets [`Link::put_symbol()`] and other functions that resolve relative symbol
+/// names skip looking up the settings of a link when no link has a default context.
+static ANY_DEFAULT_CONTEXT: AtomicBool = AtomicBool::new(false);
+
+/// Borrowed Wolfram Language symbol name, which may be absolute or relative.
+///
+/// An absolute symbol name, like ``Global`x``, includes its context. A relative
+/// symbol name, like `x` or ``` `x ```, is interpreted relative to a default context,
+/// using [`SymbolStr::resolve()`].
+///
+/// # Example
+///
+/// ```
+/// use wstp::SymbolStr;
+///
+/// let symbol = SymbolStr::try_new("Developer`PackedArrayQ").unwrap();
+///
+/// assert_eq!(symbol.context(), Some("Developer`"));
+/// assert_eq!(symbol.name(), "PackedArrayQ");
+/// assert_eq!(symbol.with_context("System`"), "System`PackedArrayQ");
+///
+/// let relative = SymbolStr::try_new("List").unwrap();
+///
+/// assert!(!relative.is_absolute());
+/// assert_eq!(relative.resolve("System`"), "System`List");
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SymbolStr<'s>(&'s str);
+
+impl<'s> SymbolStr<'s> {
+    /// Construct a `SymbolStr` from `string`.
+    ///
+    /// Returns `None` if `string` is not a syntactically valid absolute or relative
+    /// symbol name. The characters of each context and name are not validated beyond
+    /// checking that they are non-empty.
+    pub fn try_new(string: &'s str) -> Option<Self> {
+        let parts = string.strip_prefix('`').unwrap_or(string);
+
+        if parts.split('`').any(str::is_empty) {
+            return None;
+        }
+
+        Some(SymbolStr(string))
+    }
+
+    /// Construct a `SymbolStr` from a `'static` string, panicking if it is not a
+    /// syntactically valid symbol name.
+    ///
+    /// This is a `const fn`, so when it is used to initialize a constant, an invalid
+    /// symbol name is a compile-time error. See the [`symbols`][crate::symbols] module
+    /// for constants for common ``System` `` symbols.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::SymbolStr;
+    ///
+    /// const MY_HEAD: SymbolStr<'static> = SymbolStr::from_static("MyPackage`myHead");
+    ///
+    /// assert_eq!(MY_HEAD.name(), "myHead");
+    /// ```
+    ///
+    /// ```compile_fail
+    /// use wstp::SymbolStr;
+    ///
+    /// // Error: evaluation of constant value failed
+    /// const INVALID: SymbolStr<'static> = SymbolStr::from_static("MyPackage``myHead");
+    /// ```
+    pub const fn from_static(string: &'static str) -> SymbolStr<'static> {
+        let bytes = string.as_bytes();
+
+        // Equivalent to the validation performed by `SymbolStr::try_new()`.
+        let mut index = if !bytes.is_empty() && bytes[0] == b'`' {
+            1
+        } else {
+            0
+        };
+        let mut part_len = 0;
+
+        while index < bytes.len() {
+            if bytes[index] == b'`' {
+                if part_len == 0 {
+                    panic!("invalid symbol name: empty context or name");
+                }
+
+                part_len = 0;
+            } else {
+                part_len += 1;
+            }
+
+            index += 1;
+        }
+
+        if part_len == 0 {
+            panic!("invalid symbol name: empty context or name");
+        }
+
+        SymbolStr(string)
+    }
+
+    /// Get the symbol name as a string.
+    pub fn as_str(&self) -> &'s str {
+        let SymbolStr(string) = *self;
+        string
+    }
+
+    /// Returns `true` if this symbol name includes a context.
+    ///
+    /// Symbol names that start with `` ` `` are relative, because their context is
+    /// relative to the default context.
+    pub fn is_absolute(&self) -> bool {
+        let SymbolStr(string) = *self;
+        string.contains('`') && !string.starts_with('`')
+    }
+
+    /// Get the context of this symbol name, including the trailing `` ` ``.
+    ///
+    /// Returns `None` if this symbol name has no context.
+    pub fn context(&self) -> Option<&'s str> {
+        let SymbolStr(string) = *self;
+
+        string.rfind('`').map(|index| &string[..=index])
+    }
+
+    /// Get the name of this symbol, without its context.
+    pub fn name(&self) -> &'s str {
+        let SymbolStr(string) = *self;
+
+        match string.rfind('`') {
+            Some(index) => &string[index + 1..],
+            None => string,
+        }
+    }
+
+    /// Construct an absolute symbol name from the name of this symbol in `context`.
+    ///
+    /// `context` may be given with or without a trailing `` ` ``.
+    pub fn with_context(&self, context: &str) -> String {
+        join_context(context, self.name())
+    }
+
+    /// Resolve this symbol name against `default_context`.
+    ///
+    /// Absolute symbol names are returned unchanged. A relative symbol name is
+    /// interpreted relative to `default_context`, which may be given with or without a
+    /// trailing `` ` ``.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::SymbolStr;
+    ///
+    /// let resolve = |name| SymbolStr::try_new(name).unwrap().resolve("MyPackage`");
+    ///
+    /// assert_eq!(resolve("System`List"), "System`List");
+    /// assert_eq!(resolve("helper"), "MyPackage`helper");
+    /// assert_eq!(resolve("`Private`helper"), "MyPackage`Private`helper");
+    /// ```
+    pub fn resolve(&self, default_context: &str) -> Cow<'s, str> {
+        let SymbolStr(string) = *self;
+
+        if self.is_absolute() {
+            return Cow::Borrowed(string);
+        }
+
+        let relative = string.strip_prefix('`').unwrap_or(string);
+
+        Cow::Owned(join_context(default_context, relative))
+    }
+}
+
+/// # Default context
+impl Link {
+    /// Set the context used to resolve relative symbol names put on or read from this
+    /// link.
+    ///
+    /// Once a default context is set, relative symbol names passed to
+    /// [`Link::put_symbol()`], and to the methods built on it like
+    /// [`Link::put_function()`], are resolved against it using
+    /// [`SymbolStr::resolve()`]. Symbols read using [`Link::get_symbol_resolved()`]
+    /// are resolved in the same way. Absolute symbol names are always used unchanged.
+    ///
+    /// `context` may be given with or without a trailing `` ` ``.
+    ///
+    /// The default context applies to all [`Link`] values referring to the same
+    /// underlying [`WSLINK`][crate::sys::WSLINK].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.set_default_context("MyApp`");
+    ///
+    /// // Put MyApp`request[System`List[]]
+    /// link.put_function("request", 1).unwrap();
+    /// link.put_function("System`List", 0).unwrap();
+    ///
+    /// assert_eq!(link.test_head("MyApp`request").unwrap(), 1);
+    /// ```
+    pub fn set_default_context(&mut self, context: &str) {
+        let context = context.to_owned();
+
+        ANY_DEFAULT_CONTEXT.store(true, Ordering::Relaxed);
+
+        self.update_settings(|settings| settings.default_context = Some(context))
+    }
+
+    /// Remove the default context of this link, so that relative symbol names are put
+    /// and read unchanged.
+    pub fn clear_default_context(&mut self) {
+        self.update_settings(|settings| settings.default_context = None)
+    }
+
+    /// Get the default context of this link.
+    ///
+    /// See [`Link::set_default_context()`].
+    pub fn default_context(&self) -> Option<String> {
+        if !ANY_DEFAULT_CONTEXT.load(Ordering::Relaxed) {
+            return None;
+        }
+
+        self.with_settings(|settings| settings.default_context.clone())
+    }
+
+    /// Get a symbol, resolving it against the [default context][Link::set_default_context]
+    /// of this link if it is a relative symbol name.
+    ///
+    /// Peers like the Wolfram Language kernel omit the context of symbols in their
+    /// current context, so a symbol read from the link may be relative.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.put_symbol("status").unwrap();
+    ///
+    /// link.set_default_context("MyApp`");
+    ///
+    /// assert_eq!(link.get_symbol_resolved().unwrap(), "MyApp`status");
+    /// ```
+    pub fn get_symbol_resolved(&mut self) -> Result<String, Error> {
+        let symbol = self.get_symbol_ref()?.as_str().to_owned();
+
+        Ok(self.resolve_symbol(&symbol).into_owned())
+    }
+
+    /// Resolve `symbol` against the default context of this link.
+    pub(crate) fn resolve_symbol<'s>(&self, symbol: &'s str) -> Cow<'s, str> {
+        if !ANY_DEFAULT_CONTEXT.load(Ordering::Relaxed) {
+            return Cow::Borrowed(symbol);
+        }
+
+        let symbol_str = match SymbolStr::try_new(symbol) {
+            Some(symbol_str) if !symbol_str.is_absolute() => symbol_str,
+            _ => return Cow::Borrowed(symbol),
+        };
+
+        match self.with_settings(|settings| settings.default_context.clone()) {
+            Some(context) => Cow::Owned(symbol_str.resolve(&context).into_owned()),
+            None => Cow::Borrowed(symbol),
+        }
+    }
+}
+
+impl<'s> AsRef<str> for SymbolStr<'s> {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<'s> Display for SymbolStr<'s> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+fn join_context(context: &str, name: &str) -> String {
+    let context = context.strip_suffix('`').unwrap_or(context);
+
+    format!("{}`{}", context, name)
+}
@@ -0,0 +1,74 @@
+//! Constants for commonly used ``System` `` symbols.
+//!
+//! Each constant is a [`SymbolStr`] whose name is validated at compile time, so using
+//! these constants instead of string literals avoids typo'd symbol names and repeated
+//! validation.
+//!
+//! # Example
+//!
+//! ```
+//! use wstp::{symbols, Link};
+//!
+//! let mut link = Link::new_loopback().unwrap();
+//!
+//! link.put_function(symbols::LIST.as_str(), 2).unwrap();
+//! link.put_symbol(symbols::TRUE.as_str()).unwrap();
+//! link.put_symbol(symbols::FALSE.as_str()).unwrap();
+//! ```
+
+use crate::SymbolStr;
+
+macro_rules! system_symbols {
+    ($($constant:ident = $name:literal),* $(,)?) => {
+        $(
+            #[doc = concat!("The ``System`", $name, "`` symbol.")]
+            pub const $constant: SymbolStr<'static> =
+                SymbolStr::from_static(concat!("System`", $name));
+        )*
+    };
+}
+
+system_symbols! {
+    // Structural
+    LIST = "List",
+    RULE = "Rule",
+    RULE_DELAYED = "RuleDelayed",
+    ASSOCIATION = "Association",
+    SEQUENCE = "Sequence",
+    COMPOUND_EXPRESSION = "CompoundExpression",
+
+    // Constants
+    TRUE = "True",
+    FALSE = "False",
+    NULL = "Null",
+    NONE = "None",
+    AUTOMATIC = "Automatic",
+    FAILED = "$Failed",
+    MISSING = "Missing",
+    FAILURE = "Failure",
+
+    // Numbers
+    COMPLEX = "Complex",
+    RATIONAL = "Rational",
+    DIRECTED_INFINITY = "DirectedInfinity",
+    INFINITY = "Infinity",
+    COMPLEX_INFINITY = "ComplexInfinity",
+    INDETERMINATE = "Indeterminate",
+    OVERFLOW = "Overflow",
+    UNDERFLOW = "Underflow",
+    QUANTITY = "Quantity",
+    INTERVAL = "Interval",
+
+    // Arrays
+    NUMERIC_ARRAY = "NumericArray",
+    BYTE_ARRAY = "ByteArray",
+    SPARSE_ARRAY = "SparseArray",
+
+    // Packets
+    EVALUATE_PACKET = "EvaluatePacket",
+    ENTER_TEXT_PACKET = "EnterTextPacket",
+    RETURN_PACKET = "ReturnPacket",
+    RETURN_TEXT_PACKET = "ReturnTextPacket",
+    MESSAGE_PACKET = "MessagePacket",
+    TEXT_PACKET = "TextPacket",
+}
@@ -0,0 +1,250 @@
+//! Transfer of dates and time-stamped data.
+//!
+//! [`SystemTime`] values are written to a link as UTC
+//! [`DateObject`][DateObject] expressions of the form:
+//!
+//! ```text
+//! DateObject[{year, month, day, hour, minute, second}, "Instant", "Gregorian", 0.]
+//! ```
+//!
+//! [DateObject]: https://reference.wolfram.com/language/ref/DateObject.html
+
+use std::time::{Duration, SystemTime};
+
+use crate::{Error, Link};
+
+/// Type of the time stamps of a [`TimeSeries`][TimeSeries] put using
+/// [`Link::put_time_series()`].
+///
+/// This trait is implemented for:
+///
+/// * [`f64`] — time stamps are put as plain numbers (e.g. seconds since the start of
+///   a measurement).
+/// * [`SystemTime`] — time stamps are put as UTC
+///   [`DateObject`][DateObject] expressions.
+///
+/// This trait is sealed and cannot be implemented outside of `wstp`.
+///
+/// [TimeSeries]: https://reference.wolfram.com/language/ref/TimeSeries.html
+/// [DateObject]: https://reference.wolfram.com/language/ref/DateObject.html
+pub trait TimeSeriesTime: private::Sealed {}
+
+impl Link {
+    /// Put a [`TimeSeries`][TimeSeries] expression whose value at `times[i]` is
+    /// `values[i]`.
+    ///
+    /// The expression is put in the form `TimeSeries[{v1, v2, ...}, {{t1, t2, ...}}]`,
+    /// which evaluates to a [`TemporalData`][TemporalData] object in the Wolfram
+    /// Language.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::{Duration, SystemTime};
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// // Put TimeSeries[{20.5, 21.}, {{0., 60.}}]
+    /// link.put_time_series(&[0.0, 60.0], &[20.5, 21.0]).unwrap();
+    ///
+    /// // Put TimeSeries[{20.5, 21.}, {{DateObject[...], DateObject[...]}}]
+    /// let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+    /// let times = [start, start + Duration::from_secs(60)];
+    /// link.put_time_series(&times, &[20.5, 21.0]).unwrap();
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `times.len()` is not equal to `values.len()`.
+    ///
+    /// [TimeSeries]: https://reference.wolfram.com/language/ref/TimeSeries.html
+    /// [TemporalData]: https://reference.wolfram.com/language/ref/TemporalData.html
+    pub fn put_time_series<T: TimeSeriesTime>(
+        &mut self,
+        times: &[T],
+        values: &[f64],
+    ) -> Result<(), Error> {
+        assert_eq!(
+            times.len(),
+            values.len(),
+            "time series times length does not equal values length"
+        );
+
+        self.put_guarded(|link| {
+            link.put_function("System`TimeSeries", 2)?;
+            link.put_f64_array(values, &[values.len()])?;
+
+            link.put_function("System`List", 1)?;
+            T::put_times(link, times)
+        })
+    }
+
+    /// Put a `List` of [`SystemTime`]s as [`DateObject`][DateObject] expressions in
+    /// the time zone `time_zone`, an offset from UTC in hours.
+    ///
+    /// Putting a separate `DateObject[...]` expression for each time is slow for long
+    /// vectors of times. Instead, this puts a single expression of the form:
+    ///
+    /// ```text
+    /// FromUnixTime[{t1, t2, ...}, TimeZone -> time_zone]
+    /// ```
+    ///
+    /// where the times are put as one packed array of Unix time seconds. This evaluates
+    /// to a `List` of `DateObject`s in the Wolfram Language.
+    ///
+    /// Each time is converted to an [`f64`] number of seconds, so times far from the
+    /// Unix epoch lose sub-microsecond precision.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::{Duration, SystemTime};
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+    ///
+    /// // Put FromUnixTime[{1.6*^9, 1.60000006*^9}, TimeZone -> 0.]
+    /// link.put_datetime_list(&[start, start + Duration::from_secs(60)], 0.0)
+    ///     .unwrap();
+    /// ```
+    ///
+    /// [DateObject]: https://reference.wolfram.com/language/ref/DateObject.html
+    pub fn put_datetime_list(
+        &mut self,
+        times: &[SystemTime],
+        time_zone: f64,
+    ) -> Result<(), Error> {
+        let seconds: Vec<f64> = times.iter().map(|&time| unix_time_f64(time)).collect();
+
+        self.put_guarded(|link| {
+            link.put_function("System`FromUnixTime", 2)?;
+            link.put_f64_array(&seconds, &[seconds.len()])?;
+
+            link.put_function("System`Rule", 2)?;
+            link.put_symbol("System`TimeZone")?;
+            link.put_f64(time_zone)
+        })
+    }
+
+    /// Put a [`SystemTime`] as a UTC [`DateObject`][DateObject] expression.
+    ///
+    /// [DateObject]: https://reference.wolfram.com/language/ref/DateObject.html
+    pub(crate) fn put_date_object(&mut self, time: SystemTime) -> Result<(), Error> {
+        let (seconds, nanos): (i64, u32) =
+            match time.duration_since(SystemTime::UNIX_EPOCH) {
+                Ok(since) => (unix_seconds(since)?, since.subsec_nanos()),
+                Err(err) => {
+                    let before: Duration = err.duration();
+                    let seconds = -unix_seconds(before)?;
+
+                    match before.subsec_nanos() {
+                        0 => (seconds, 0),
+                        nanos => (seconds - 1, 1_000_000_000 - nanos),
+                    }
+                },
+            };
+
+        let days = seconds.div_euclid(86_400);
+        let seconds_of_day = seconds.rem_euclid(86_400);
+
+        let (year, month, day) = civil_from_days(days);
+
+        self.put_guarded(|link| {
+            link.put_function("System`DateObject", 4)?;
+
+            link.put_function("System`List", 6)?;
+            link.put_i64(year)?;
+            link.put_i64(month)?;
+            link.put_i64(day)?;
+            link.put_i64(seconds_of_day / 3600)?;
+            link.put_i64(seconds_of_day % 3600 / 60)?;
+            link.put_f64((seconds_of_day % 60) as f64 + f64::from(nanos) / 1e9)?;
+
+            link.put_str("Instant")?;
+            link.put_str("Gregorian")?;
+            // UTC time zone.
+            link.put_f64(0.0)
+        })
+    }
+}
+
+/// Get the number of seconds from the Unix epoch to `time`.
+fn unix_time_f64(time: SystemTime) -> f64 {
+    match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(since) => since.as_secs_f64(),
+        Err(err) => -err.duration().as_secs_f64(),
+    }
+}
+
+fn unix_seconds(duration: Duration) -> Result<i64, Error> {
+    i64::try_from(duration.as_secs()).map_err(|_| {
+        Error::custom(format!(
+            "time {:?} from the Unix epoch is out of range",
+            duration
+        ))
+    })
+}
+
+/// Convert a number of days since 1970-01-01 into a proleptic Gregorian calendar
+/// `(year, month, day)` date.
+///
+/// See <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524
+        - day_of_era / 146_096)
+        / 365;
+    let day_of_year =
+        day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+//======================================
+// TimeSeriesTime impls
+//======================================
+
+mod private {
+    use crate::{Error, Link};
+
+    pub trait Sealed: Sized {
+        /// Put `times` as a `List`.
+        fn put_times(link: &mut Link, times: &[Self]) -> Result<(), Error>;
+    }
+}
+
+impl TimeSeriesTime for f64 {}
+
+impl private::Sealed for f64 {
+    fn put_times(link: &mut Link, times: &[Self]) -> Result<(), Error> {
+        link.put_f64_array(times, &[times.len()])
+    }
+}
+
+impl TimeSeriesTime for SystemTime {}
+
+impl private::Sealed for SystemTime {
+    fn put_times(link: &mut Link, times: &[Self]) -> Result<(), Error> {
+        link.put_function("System`List", times.len())?;
+
+        for &time in times {
+            link.put_date_object(time)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+//! Bandwidth limiting of the data put on a link.
+
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "expr")]
+use wolfram_expr::Expr;
+
+use crate::{Error, Link};
+
+/// Wrapper around a [`Link`] that limits the rate at which data is put on it.
+///
+/// The rate is limited using a token bucket: each byte put on the link consumes a
+/// token, tokens are added at `bytes_per_sec`, and at most `burst` tokens can be
+/// saved up. When an operation puts more bytes than there are tokens, the current
+/// thread sleeps until the deficit has been paid off.
+///
+/// The number of bytes put by an operation is measured using the change in
+/// [`Link::bytes_to_put()`] caused by the operation, so only operations performed
+/// using [`ThrottledLink::with()`] and the other methods of this type are throttled.
+///
+/// This can be used to simulate slow links in tests, and to avoid saturating a
+/// low-bandwidth connection that is shared with interactive kernel traffic.
+///
+/// # Example
+///
+/// ```
+/// use wstp::{Link, ThrottledLink};
+///
+/// // Limit to 1 MB/s, with bursts of up to 64 KB.
+/// let mut link = ThrottledLink::new(Link::new_loopback().unwrap(), 1_000_000, 65_536);
+///
+/// link.with(|link| link.put_str("hello")).unwrap();
+/// link.flush().unwrap();
+///
+/// assert_eq!(link.link_mut().get_string().unwrap(), "hello");
+/// ```
+#[derive(Debug)]
+pub struct ThrottledLink {
+    link: Link,
+    bytes_per_sec: u64,
+    burst: u64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl ThrottledLink {
+    /// Wrap `link`, limiting the data put on it to `bytes_per_sec`, with bursts of up
+    /// to `burst` bytes.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `bytes_per_sec` is zero.
+    pub fn new(link: Link, bytes_per_sec: u64, burst: u64) -> Self {
+        assert!(bytes_per_sec > 0, "ThrottledLink rate must be non-zero");
+
+        ThrottledLink {
+            link,
+            bytes_per_sec,
+            burst,
+            tokens: burst as f64,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Get the rate limit of this link, in bytes per second.
+    pub fn bytes_per_sec(&self) -> u64 {
+        self.bytes_per_sec
+    }
+
+    /// Get a reference to the wrapped link.
+    pub fn link(&self) -> &Link {
+        &self.link
+    }
+
+    /// Get a mutable reference to the wrapped link.
+    ///
+    /// Operations performed directly on the wrapped link are not throttled.
+    pub fn link_mut(&mut self) -> &mut Link {
+        &mut self.link
+    }
+
+    /// Unwrap the link.
+    pub fn into_inner(self) -> Link {
+        self.link
+    }
+
+    /// Perform `op` on the wrapped link, and then wait until the bytes it put on the link
+    /// are within the rate limit.
+    pub fn with<T, F>(&mut self, op: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Link) -> Result<T, Error>,
+    {
+        let before = self.link.bytes_to_put()?;
+
+        let result = op(&mut self.link)?;
+
+        let after = self.link.bytes_to_put()?;
+
+        // If `after` is smaller, WSTP sent the buffered data during `op`, and at least
+        // `after` bytes were put.
+        let put = if after >= before {
+            after - before
+        } else {
+            after
+        };
+
+        self.acquire(u64::try_from(put).unwrap_or(u64::MAX));
+
+        Ok(result)
+    }
+
+    /// Put `expr`, throttled.
+    #[cfg(feature = "expr")]
+    pub fn put_expr(&mut self, expr: &Expr) -> Result<(), Error> {
+        self.with(|link| link.put_expr(expr))
+    }
+
+    /// Flush the wrapped link.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.link.flush()
+    }
+
+    /// Consume `bytes` tokens, sleeping until they are available.
+    fn acquire(&mut self, bytes: u64) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+
+        self.tokens =
+            (self.tokens + elapsed * self.bytes_per_sec as f64).min(self.burst as f64);
+        self.last_refill = now;
+
+        self.tokens -= bytes as f64;
+
+        if self.tokens < 0.0 {
+            std::thread::sleep(Duration::from_secs_f64(
+                -self.tokens / self.bytes_per_sec as f64,
+            ));
+
+            self.tokens = 0.0;
+            self.last_refill = Instant::now();
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! Streaming copy of expressions between links, with rewriting of sub-expressions.
+
+use wolfram_expr::Expr;
+
+use crate::{sys, Error, Link, Token};
+
+/// Action taken by [`copy_expr_filtered()`] for a sub-expression.
+///
+/// Returned by [`TokenFilter::filter()`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum FilterAction {
+    /// Copy the sub-expression unchanged.
+    ///
+    /// If the sub-expression is a function, the filter is called again for its head
+    /// and each of its elements.
+    Keep,
+    /// Drop the sub-expression.
+    ///
+    /// Because the length of the enclosing function has already been put on the
+    /// destination link, the sub-expression is replaced by `Sequence[]`, which the
+    /// Wolfram Language removes from the enclosing function when it is evaluated. A
+    /// dropped top-level expression is replaced by `Sequence[]` as well.
+    Drop,
+    /// Replace the sub-expression by the given expression.
+    ///
+    /// The original sub-expression is read from the source link and discarded.
+    Replace(Expr),
+    /// Replace a symbol by the symbol with the given name.
+    ///
+    /// This is equivalent to [`FilterAction::Keep`] for tokens that are not symbols.
+    Rename(String),
+    /// Wrap the sub-expression in a function with the given head symbol, i.e. copy
+    /// `expr` as `head[expr]`.
+    ///
+    /// The sub-expression itself is copied as if the filter had returned
+    /// [`FilterAction::Keep`].
+    Wrap(String),
+}
+
+/// Decides how [`copy_expr_filtered()`] copies each sub-expression.
+///
+/// This trait is implemented for closures of type
+/// `FnMut(&Token, usize) -> FilterAction`.
+pub trait TokenFilter {
+    /// Decide how to copy the sub-expression whose first token is `token`.
+    ///
+    /// `depth` is `0` for the expression being copied, `1` for its head and elements,
+    /// and so on.
+    fn filter(&mut self, token: &Token<'_>, depth: usize) -> FilterAction;
+}
+
+impl<F> TokenFilter for F
+where
+    F: FnMut(&Token<'_>, usize) -> FilterAction,
+{
+    fn filter(&mut self, token: &Token<'_>, depth: usize) -> FilterAction {
+        self(token, depth)
+    }
+}
+
+/// Copy the next expression on `src` to `dst`, one token at a time, letting `filter`
+/// drop, rename, replace, or wrap sub-expressions as they are copied.
+///
+/// Unlike reading the expression using [`Link::get_expr()`] and putting a rewritten
+/// [`Expr`], this never holds the full expression in memory, which makes it suitable
+/// for gateways that forward large expressions between links.
+///
+/// Numbers are read using [`Link::get_token()`], so integers and reals that don't fit
+/// in an [`i64`] or [`f64`] cause an error.
+///
+/// # Example
+///
+/// Strip large strings from an expression as it is forwarded:
+///
+/// ```
+/// use wolfram_expr::Expr;
+/// use wstp::{copy_expr_filtered, FilterAction, Link, Token};
+///
+/// let mut src = Link::new_loopback().unwrap();
+/// let mut dst = Link::new_loopback().unwrap();
+///
+/// src.put_expr(&Expr::list(vec![Expr::from(1), Expr::string("x".repeat(1000))]))
+///     .unwrap();
+///
+/// copy_expr_filtered(&mut src, &mut dst, &mut |token: &Token, _depth| match token {
+///     Token::String(s) if s.as_str().len() > 100 => {
+///         FilterAction::Replace(Expr::string("<removed>"))
+///     },
+///     _ => FilterAction::Keep,
+/// })
+/// .unwrap();
+///
+/// assert_eq!(
+///     dst.get_expr().unwrap(),
+///     Expr::list(vec![Expr::from(1), Expr::string("<removed>")])
+/// );
+/// ```
+pub fn copy_expr_filtered<F: TokenFilter>(
+    src: &mut Link,
+    dst: &mut Link,
+    filter: &mut F,
+) -> Result<(), Error> {
+    dst.put_guarded(|dst| copy_filtered(src, dst, filter, 0))
+}
+
+fn copy_filtered<F: TokenFilter>(
+    src: &mut Link,
+    dst: &mut Link,
+    filter: &mut F,
+    depth: usize,
+) -> Result<(), Error> {
+    let (length, action) = {
+        let token = src.get_token()?;
+
+        let length = match token {
+            Token::Function { length } => Some(length),
+            _ => None,
+        };
+
+        let action = filter.filter(&token, depth);
+
+        match action {
+            FilterAction::Keep => put_token(dst, &token)?,
+            FilterAction::Rename(ref name) => match token {
+                Token::Symbol(_) => dst.put_symbol(name)?,
+                _ => put_token(dst, &token)?,
+            },
+            FilterAction::Wrap(ref head) => {
+                dst.put_function(head.as_str(), 1)?;
+                put_token(dst, &token)?;
+            },
+            FilterAction::Drop | FilterAction::Replace(_) => (),
+        }
+
+        (length, action)
+    };
+
+    match action {
+        FilterAction::Drop => {
+            skip_elements(src, length)?;
+            return dst.put_function("System`Sequence", 0);
+        },
+        FilterAction::Replace(expr) => {
+            skip_elements(src, length)?;
+            return dst.put_expr(&expr);
+        },
+        FilterAction::Keep | FilterAction::Rename(_) | FilterAction::Wrap(_) => (),
+    }
+
+    if let Some(length) = length {
+        // Copy the head, then each of the elements.
+        for _ in 0..=length {
+            copy_filtered(src, dst, filter, depth + 1)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn put_token(dst: &mut Link, token: &Token<'_>) -> Result<(), Error> {
+    match token {
+        Token::Integer(value) => dst.put_i64(*value),
+        Token::Real(value) => dst.put_f64(*value),
+        Token::String(value) => dst.put_str(value.as_str()),
+        Token::Symbol(value) => dst.put_symbol(value.as_str()),
+        Token::Function { length } => {
+            dst.put_raw_type(i32::from(sys::WSTKFUNC))?;
+            dst.put_arg_count(*length)
+        },
+    }
+}
+
+/// Skip the head and elements of a function whose header has already been read.
+fn skip_elements(src: &mut Link, length: Option<usize>) -> Result<(), Error> {
+    if let Some(length) = length {
+        for _ in 0..=length {
+            src.skip_expr()?;
+        }
+    }
+
+    Ok(())
+}
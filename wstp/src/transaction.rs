@@ -0,0 +1,129 @@
+//! Putting expressions onto a link all at once, or not at all.
+
+use crate::{Error, Link};
+
+/// Expressions staged to be put onto a link when the transaction is committed.
+///
+/// Putting a large expression directly onto a link leaves the link in a corrupt
+/// state if one of the puts fails part way through, e.g. because of a conversion
+/// error in one element of a long list. A `Transaction` instead stages everything
+/// that is put in a loopback link, and only transfers it to the destination link in
+/// [`Transaction::commit()`].
+///
+/// Dropping a `Transaction` without committing it discards the staged data, leaving
+/// the destination link unchanged.
+///
+/// Like [`ListWriter`][crate::ListWriter], data is staged in its serialized WSTP
+/// form, so no [`Expr`][wolfram_expr::Expr] tree is constructed.
+///
+/// # Example
+///
+/// ```
+/// use wstp::Link;
+///
+/// let mut link = Link::new_loopback().unwrap();
+///
+/// let spans = [(0.0, 1.0), (2.0, 3.0), (5.0, 4.0)];
+///
+/// let mut transaction = link.transaction().unwrap();
+///
+/// let result = transaction.put(|link| {
+///     link.put_function("System`List", spans.len())?;
+///
+///     for (lo, hi) in spans {
+///         // Fails for the invalid span {5., 4.}
+///         link.put_interval(lo, hi)?;
+///     }
+///
+///     Ok(())
+/// });
+///
+/// assert!(result.is_err());
+///
+/// // Discard the partial list.
+/// drop(transaction);
+///
+/// link.put_i64(5).unwrap();
+///
+/// assert_eq!(link.get_i64().unwrap(), 5);
+/// ```
+#[derive(Debug)]
+pub struct Transaction<'link> {
+    dest: &'link mut Link,
+    staging: Link,
+    is_empty: bool,
+    failed: bool,
+}
+
+impl Link {
+    /// Begin a [`Transaction`] that puts data onto this link when it is committed.
+    pub fn transaction(&mut self) -> Result<Transaction<'_>, Error> {
+        Ok(Transaction {
+            dest: self,
+            staging: Link::new_loopback()?,
+            is_empty: true,
+            failed: false,
+        })
+    }
+}
+
+impl<'link> Transaction<'link> {
+    /// Stage the data put by `put` onto the link it is given.
+    ///
+    /// If `put` returns an error, the transaction can no longer be committed, and
+    /// should be dropped.
+    pub fn put<F>(&mut self, put: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut Link) -> Result<(), Error>,
+    {
+        if self.failed {
+            return Err(failed_error());
+        }
+
+        if let Err(err) = put(&mut self.staging) {
+            self.failed = true;
+            return Err(err);
+        }
+
+        self.is_empty = false;
+
+        Ok(())
+    }
+
+    /// Transfer the staged data to the destination link.
+    ///
+    /// An error is returned without changing the destination link if a previous call
+    /// to [`Transaction::put()`] failed.
+    pub fn commit(mut self) -> Result<(), Error> {
+        if self.failed {
+            return Err(failed_error());
+        }
+
+        if self.is_empty {
+            return Ok(());
+        }
+
+        // Note: `transfer_to_end_of_loopback_link()` is not used here, because it
+        //       requires the destination to also be a loopback link.
+        let staging = &mut self.staging;
+
+        self.dest.put_guarded(|dest| {
+            while staging.is_ready() {
+                staging.transfer_expr_to(dest)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Discard the staged data.
+    ///
+    /// This is equivalent to dropping the transaction.
+    pub fn rollback(self) {
+        // Note: The staged data is discarded when `self` is dropped.
+    }
+}
+
+fn failed_error() -> Error {
+    Error::custom("cannot commit a Transaction after a failed put".to_owned())
+}
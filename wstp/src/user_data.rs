@@ -0,0 +1,139 @@
+//! Typed user data values associated with a link.
+
+use std::any::Any;
+
+use crate::{sys, Link};
+
+/// Type-erased user data value stored on a link using [`sys::WSSetUserData`].
+type UserData = Box<dyn Any + Send>;
+
+/// Sentinel object whose address identifies [`typed_user_data_marker`].
+static TYPED_USER_DATA_SENTINEL: u8 = 0;
+
+/// Marker function stored as the `WSUserFunction` of a link whose user data object is
+/// a `UserData` value owned by this crate.
+///
+/// This distinguishes typed user data from raw user data set using
+/// [`Link::set_raw_user_data()`].
+///
+/// The address of this function is compared to identify typed user data, so it must
+/// not be merged with any other function by a linker that folds identical code. Its
+/// body reads [`TYPED_USER_DATA_SENTINEL`], which no other function refers to, so
+/// that its code is unique.
+unsafe extern "C" fn typed_user_data_marker(_: sys::WSLINK) {
+    let _: u8 = std::ptr::read_volatile(&TYPED_USER_DATA_SENTINEL);
+}
+
+/// # User data
+impl Link {
+    /// Associate a user data value with this link, replacing any user data value it
+    /// already has.
+    ///
+    /// The value is stored using the link's WSTP user data object, so it can be
+    /// accessed from any `&Link` referring to the same underlying [`WSLINK`][sys::WSLINK],
+    /// including inside callbacks like the one passed to [`Link::wait_with_callback()`].
+    /// The value is dropped when the link is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// struct Session {
+    ///     requests: u32,
+    /// }
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// link.set_typed_user_data(Session { requests: 0 });
+    ///
+    /// link.typed_user_data_mut::<Session>().unwrap().requests += 1;
+    ///
+    /// assert_eq!(link.typed_user_data::<Session>().unwrap().requests, 1);
+    /// assert!(link.typed_user_data::<String>().is_none());
+    /// ```
+    ///
+    /// *WSTP C API Documentation:* [`WSSetUserData`](https://reference.wolfram.com/language/ref/c/WSSetUserData.html)
+    pub fn set_typed_user_data<T: Any + Send>(&mut self, value: T) {
+        let boxed: Box<UserData> = Box::new(Box::new(value));
+
+        unsafe {
+            self.drop_user_data();
+
+            sys::WSSetUserData(
+                self.raw_link,
+                Box::into_raw(boxed) as *mut std::ffi::c_void,
+                Some(typed_user_data_marker),
+            );
+        }
+    }
+
+    /// Get a reference to the user data value associated with this link, if it has
+    /// type `T`.
+    ///
+    /// See [`Link::set_typed_user_data()`].
+    ///
+    /// *WSTP C API Documentation:* [`WSUserData`](https://reference.wolfram.com/language/ref/c/WSUserData.html)
+    pub fn typed_user_data<T: Any>(&self) -> Option<&T> {
+        let data: &UserData = unsafe { self.typed_user_data_ptr()?.as_ref()? };
+
+        data.downcast_ref::<T>()
+    }
+
+    /// Get a mutable reference to the user data value associated with this link, if
+    /// it has type `T`.
+    ///
+    /// See [`Link::set_typed_user_data()`].
+    pub fn typed_user_data_mut<T: Any>(&mut self) -> Option<&mut T> {
+        let data: &mut UserData = unsafe { self.typed_user_data_ptr()?.as_mut()? };
+
+        data.downcast_mut::<T>()
+    }
+
+    /// Remove the user data value associated with this link and return it, if it has
+    /// type `T`.
+    ///
+    /// If the user data value does not have type `T`, it is left unchanged.
+    pub fn take_typed_user_data<T: Any>(&mut self) -> Option<T> {
+        let _: &T = self.typed_user_data::<T>()?;
+
+        unsafe {
+            let ptr = self.typed_user_data_ptr()?;
+
+            sys::WSSetUserData(self.raw_link, std::ptr::null_mut(), None);
+
+            let data: UserData = *Box::from_raw(ptr);
+
+            // This cannot fail, because the value was checked to have type `T` above.
+            data.downcast::<T>().ok().map(|value| *value)
+        }
+    }
+
+    /// Get a pointer to the typed user data value of this link, if it has one.
+    fn typed_user_data_ptr(&self) -> Option<*mut UserData> {
+        let (data_obj, user_func) = unsafe { self.raw_user_data() };
+
+        // Note: Compare function addresses as integers, because comparing function
+        //       pointers directly is not guaranteed to be meaningful.
+        let is_typed = user_func.map(|func| func as usize)
+            == Some(typed_user_data_marker as unsafe extern "C" fn(sys::WSLINK) as usize);
+
+        if !is_typed || data_obj.is_null() {
+            return None;
+        }
+
+        Some(data_obj as *mut UserData)
+    }
+
+    /// Drop the typed user data value of this link, if it has one.
+    ///
+    /// # Safety
+    ///
+    /// The user data object and function of this link must be replaced before any
+    /// further use of the typed user data.
+    pub(crate) unsafe fn drop_user_data(&mut self) {
+        if let Some(ptr) = self.typed_user_data_ptr() {
+            drop(Box::from_raw(ptr));
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! Version of the WSTP interface implemented by the WSTP library.
+
+use std::fmt::{self, Display};
+use std::os::raw::c_int;
+
+use crate::{sys, Error, Link};
+
+/// Version of the WSTP interface implemented by a WSTP library.
+///
+/// Two WSTP libraries can communicate if they implement compatible interfaces.
+/// Versions are ordered by [`interface`][InterfaceVersion::interface], then
+/// [`revision`][InterfaceVersion::revision], then [`build`][InterfaceVersion::build].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct InterfaceVersion {
+    /// The interface number, incremented when the interface changes incompatibly.
+    pub interface: i32,
+    /// The revision number, incremented when the interface is extended compatibly.
+    pub revision: i32,
+    /// The build number of the library.
+    pub build: i32,
+}
+
+/// Head of the expression exchanged by [`Link::check_compat()`].
+const VERSION_HEAD: &str = "WSTP`InterfaceVersion";
+
+impl InterfaceVersion {
+    /// Get the interface version of the WSTP library this program is linked against.
+    ///
+    /// This is only the version of the local library, not a version negotiated with
+    /// the peer of any link. Use [`Link::check_compat()`] to learn the version used by
+    /// a peer.
+    ///
+    /// *WSTP C API Documentation:* [`WSVersionNumbers()`](https://reference.wolfram.com/language/ref/c/WSVersionNumbers.html)
+    pub fn linked() -> Result<Self, Error> {
+        Ok(InterfaceVersion::from_env(crate::stdenv()?.raw_env))
+    }
+
+    fn from_env(raw_env: sys::WSENV) -> Self {
+        let mut interface: c_int = 0;
+        let mut revision: c_int = 0;
+        let mut build: c_int = 0;
+
+        unsafe {
+            sys::WSVersionNumbers(raw_env, &mut interface, &mut revision, &mut build);
+        }
+
+        InterfaceVersion {
+            interface,
+            revision,
+            build,
+        }
+    }
+
+    /// Returns `true` if a WSTP library implementing this interface version can
+    /// communicate with one implementing `other`.
+    ///
+    /// Versions are compatible if they have the same
+    /// [`interface`][InterfaceVersion::interface] number. Revisions of an interface only
+    /// add to it, so differing [`revision`][InterfaceVersion::revision] and
+    /// [`build`][InterfaceVersion::build] numbers are compatible.
+    pub fn is_compatible_with(&self, other: &InterfaceVersion) -> bool {
+        self.interface == other.interface
+    }
+}
+
+impl Display for InterfaceVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let InterfaceVersion {
+            interface,
+            revision,
+            build,
+        } = self;
+
+        write!(
+            f,
+            "interface {}, revision {}, build {}",
+            interface, revision, build
+        )
+    }
+}
+
+/// # Interface version
+impl Link {
+    /// Get the interface version of the WSTP library used by this link.
+    ///
+    /// The [`interface`][InterfaceVersion::interface] field is the *interface number*
+    /// of the link.
+    ///
+    /// *WSTP C API Documentation:* [`WSVersionNumbers()`](https://reference.wolfram.com/language/ref/c/WSVersionNumbers.html)
+    pub fn interface_version(&self) -> InterfaceVersion {
+        InterfaceVersion::from_env(unsafe { sys::WSLinkEnvironment(self.raw_link) })
+    }
+
+    /// Verify that the peer of this link uses a compatible WSTP interface, returning
+    /// the interface version of the peer.
+    ///
+    /// Both sides of the link must call this method after the link is
+    /// [activated][Link::activate] and before any other data is exchanged. Each side
+    /// sends its [`Link::interface_version()`] to the other, and checks that the
+    /// received version [is compatible][InterfaceVersion::is_compatible_with] with its
+    /// own.
+    ///
+    /// An error explaining the mismatch is returned if the versions are not
+    /// compatible, or if the peer sent something other than its interface version,
+    /// so that an incompatible peer is detected immediately, instead of causing
+    /// confusing errors or corrupt-looking data in later reads.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Protocol;
+    ///
+    /// let (mut a, mut b) = wstp::channel(Protocol::IntraProcess).unwrap();
+    ///
+    /// let thread = std::thread::spawn(move || b.check_compat().map(|_| b));
+    ///
+    /// let peer_version = a.check_compat().unwrap();
+    /// assert!(peer_version.is_compatible_with(&a.interface_version()));
+    ///
+    /// let _b = thread.join().unwrap().unwrap();
+    /// ```
+    pub fn check_compat(&mut self) -> Result<InterfaceVersion, Error> {
+        let local = self.interface_version();
+
+        self.put_guarded(|link| {
+            link.put_function(VERSION_HEAD, 3)?;
+            link.put_i32(local.interface)?;
+            link.put_i32(local.revision)?;
+            link.put_i32(local.build)
+        })?;
+        self.flush()?;
+
+        let peer = self.get_interface_version().map_err(|err| {
+            Error::custom(format!(
+                "check_compat(): peer did not send its WSTP interface version; \
+                 check_compat() must be called on both sides of the link before any \
+                 other data is exchanged: {}",
+                err
+            ))
+        })?;
+
+        if !local.is_compatible_with(&peer) {
+            return Err(Error::custom(format!(
+                "check_compat(): incompatible WSTP interface versions: local WSTP \
+                 library has {}, peer WSTP library has {}",
+                local, peer
+            )));
+        }
+
+        Ok(peer)
+    }
+
+    fn get_interface_version(&mut self) -> Result<InterfaceVersion, Error> {
+        let argc = self.test_head(VERSION_HEAD)?;
+
+        if argc != 3 {
+            return Err(Error::custom(format!(
+                "expected 3 arguments to {}, got {}",
+                VERSION_HEAD, argc
+            )));
+        }
+
+        Ok(InterfaceVersion {
+            interface: self.get_i32()?,
+            revision: self.get_i32()?,
+            build: self.get_i32()?,
+        })
+    }
+}
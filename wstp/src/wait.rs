@@ -4,7 +4,9 @@ use crate::{
 };
 
 use std::collections::HashMap;
+use std::ops::ControlFlow;
 use std::sync::Mutex;
+use std::time::Instant;
 
 use once_cell::sync::Lazy;
 
@@ -68,12 +70,11 @@ impl Link {
     ///     .unwrap();
     /// ```
     ///
-    /// # User data fields
+    /// # User data
     ///
-    /// This function will temporarily replace any user data values (set using
-    /// [Link::set_user_data]) which are associated with the current link. The user
-    /// data values on the `&mut Link` parameter inside the callback are
-    /// an implementation detail of this function and must not be modified.
+    /// The `&mut Link` parameter inside the callback refers to the same underlying
+    /// link, so any user data value set using [`Link::set_typed_user_data()`] can be
+    /// accessed from the callback.
     ///
     /// *WSTP C API Documentation:* [`WSWaitForLinkActivityWithCallback`](https://reference.wolfram.com/language/ref/c/WSWaitForLinkActivityWithCallback.html)
     pub fn wait_with_callback<F>(&mut self, callback: F) -> Result<bool, Error>
@@ -132,6 +133,24 @@ impl Link {
             ))),
         }
     }
+
+    /// Wait until this link has data ready to be read, or until `deadline` has
+    /// passed, whichever happens first.
+    ///
+    /// Returns `false` if `deadline` passed before any data was ready.
+    pub(crate) fn wait_until(&mut self, deadline: Instant) -> Result<bool, Error> {
+        if self.is_ready() {
+            return Ok(true);
+        }
+
+        self.wait_with_callback(move |_: &mut Link| {
+            if Instant::now() < deadline {
+                ControlFlow::Continue(())
+            } else {
+                ControlFlow::Break(())
+            }
+        })
+    }
 }
 
 unsafe extern "C" fn link_wait_callback_trampoline<F>(
@@ -144,7 +163,7 @@ where
     // Catch any panics which result from `expect()` or `user_closure()` to prevent
     // unwinding over C stack frames.
     let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-        // let (raw_user_closure, _) = link.user_data();
+        // let (raw_user_closure, _) = link.raw_user_data();
         let raw_user_closure: *mut std::ffi::c_void = {
             let lock = WAIT_CALLBACKS
                 .lock()
@@ -0,0 +1,198 @@
+//! Notification of incoming data on many links from a single thread.
+
+use std::fmt::{self, Debug};
+use std::ops::ControlFlow;
+use std::time::Duration;
+
+use crate::{
+    stdenv,
+    sys::{self, WSLINK},
+    Error, Link,
+};
+
+type ActivityCallback<'h> = Box<dyn FnMut(&mut Link) -> ControlFlow<()> + 'h>;
+
+/// Identifier of a link registered using [`LinkWatcher::watch()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct WatchId(u64);
+
+/// Calls a callback when data is available to be read from any of a set of links.
+///
+/// A [`LinkWatcher`] lets an event-driven program service many links from a single
+/// thread, instead of dedicating a thread blocked in [`Link::wait()`] to each link.
+///
+/// Links are registered with a callback using [`LinkWatcher::watch()`]. Each call to
+/// [`LinkWatcher::poll()`] waits until at least one of the links is ready to be read
+/// from, and then calls the callback of every ready link. If a callback returns
+/// [`Break`][ControlFlow::Break], its link is no longer watched, and is dropped.
+///
+/// # Example
+///
+/// ```
+/// use std::{ops::ControlFlow, time::Duration};
+/// use wstp::{LinkWatcher, Protocol};
+///
+/// let (mut a, b) = wstp::channel(Protocol::IntraProcess).unwrap();
+///
+/// let mut received = Vec::new();
+///
+/// let mut watcher = LinkWatcher::new();
+///
+/// watcher.watch(b, |link| match link.get_i64() {
+///     Ok(value) => {
+///         received.push(value);
+///         ControlFlow::Continue(())
+///     },
+///     Err(_) => ControlFlow::Break(()),
+/// });
+///
+/// a.put_i64(5).unwrap();
+/// a.flush().unwrap();
+///
+/// assert!(watcher.poll(Duration::from_secs(5)).unwrap());
+///
+/// drop(watcher);
+///
+/// assert_eq!(received, vec![5]);
+/// ```
+///
+/// *WSTP C API Documentation:* [`WSReadyParallel()`](https://reference.wolfram.com/language/ref/c/WSReadyParallel.html)
+pub struct LinkWatcher<'h> {
+    entries: Vec<Entry<'h>>,
+    next_id: u64,
+}
+
+struct Entry<'h> {
+    id: WatchId,
+    link: Link,
+    callback: ActivityCallback<'h>,
+}
+
+impl<'h> LinkWatcher<'h> {
+    /// Construct a new watcher with no links.
+    pub fn new() -> Self {
+        LinkWatcher {
+            entries: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Watch `link`, calling `callback` each time data is available to be read from it.
+    ///
+    /// The callback should read the available data from the link. If it returns
+    /// [`Break`][ControlFlow::Break], `link` stops being watched and is dropped.
+    pub fn watch<F>(&mut self, link: Link, callback: F) -> WatchId
+    where
+        F: FnMut(&mut Link) -> ControlFlow<()> + 'h,
+    {
+        let id = WatchId(self.next_id);
+        self.next_id += 1;
+
+        self.entries.push(Entry {
+            id,
+            link,
+            callback: Box::new(callback),
+        });
+
+        id
+    }
+
+    /// Stop watching the link identified by `id`, and return it.
+    ///
+    /// Returns `None` if the link is not being watched.
+    pub fn unwatch(&mut self, id: WatchId) -> Option<Link> {
+        let index = self.entries.iter().position(|entry| entry.id == id)?;
+
+        Some(self.entries.remove(index).link)
+    }
+
+    /// Get the link identified by `id`, if it is being watched.
+    pub fn link_mut(&mut self, id: WatchId) -> Option<&mut Link> {
+        self.entries
+            .iter_mut()
+            .find(|entry| entry.id == id)
+            .map(|entry| &mut entry.link)
+    }
+
+    /// Returns the number of links being watched.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no links are being watched.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Wait up to `timeout` for data to be available on any of the watched links, and
+    /// call the callback of each link that is ready.
+    ///
+    /// Returns `false` if no link became ready before `timeout` elapsed, or if no links
+    /// are being watched.
+    pub fn poll(&mut self, timeout: Duration) -> Result<bool, Error> {
+        if self.entries.is_empty() {
+            return Ok(false);
+        }
+
+        let mut raw_links: Vec<WSLINK> = self
+            .entries
+            .iter()
+            .map(|entry| entry.link.raw_link)
+            .collect();
+
+        let wait_time = sys::wstimeval {
+            tv_sec: timeout.as_secs() as _,
+            tv_usec: timeout.subsec_micros() as _,
+        };
+
+        // The environment can't be deinitialized while the watched links exist, so it's
+        // not necessary to hold the lock on it while waiting.
+        let raw_env = stdenv()?.raw_env;
+
+        let result = unsafe {
+            sys::WSReadyParallel(
+                raw_env,
+                raw_links.as_mut_ptr(),
+                i32::try_from(raw_links.len()).unwrap(),
+                wait_time,
+            )
+        };
+
+        match result {
+            sys::WSREADYPARALLELTIMEDOUT => return Ok(false),
+            sys::WSREADYPARALLELERROR | sys::WSREADYPARALLELINVALIDARGUMENT => {
+                return Err(Error::custom(format!(
+                    "WSReadyParallel returned error value: {}",
+                    result
+                )))
+            },
+            _ => (),
+        }
+
+        // WSReadyParallel() only returns the index of the first ready link, so check
+        // each link in turn.
+        self.entries.retain_mut(|entry| {
+            if !entry.link.is_ready() {
+                return true;
+            }
+
+            (entry.callback)(&mut entry.link).is_continue()
+        });
+
+        Ok(true)
+    }
+}
+
+impl<'h> Default for LinkWatcher<'h> {
+    fn default() -> Self {
+        LinkWatcher::new()
+    }
+}
+
+impl<'h> Debug for LinkWatcher<'h> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ids: Vec<WatchId> = self.entries.iter().map(|entry| entry.id).collect();
+
+        f.debug_struct("LinkWatcher").field("links", &ids).finish()
+    }
+}
@@ -0,0 +1,572 @@
+//! Transfer of expressions encoded in the [Wolfram Exchange Format][WXF] (WXF).
+//!
+//! [WXF]: https://reference.wolfram.com/language/tutorial/WXFFormatDescription.html
+
+use std::convert::TryFrom;
+
+#[cfg(feature = "flate2")]
+use wolfram_expr::{Expr, ExprKind};
+
+use crate::{sys, Error, Link};
+
+/// Header of an uncompressed WXF byte sequence.
+pub(crate) const HEADER: &[u8] = b"8:";
+
+/// Header of a WXF byte sequence whose body is compressed using zlib.
+pub(crate) const COMPRESSED_HEADER: &[u8] = b"8C:";
+
+/// Maximum nesting depth of the expressions read by [`Link::put_wxf()`].
+///
+/// This prevents deeply nested input from overflowing the stack.
+const MAX_DEPTH: usize = 1024;
+
+mod token {
+    pub const FUNCTION: u8 = b'f';
+    pub const SYMBOL: u8 = b's';
+    pub const STRING: u8 = b'S';
+    pub const BINARY_STRING: u8 = b'B';
+    pub const INTEGER8: u8 = b'C';
+    pub const INTEGER16: u8 = b'j';
+    pub const INTEGER32: u8 = b'i';
+    pub const INTEGER64: u8 = b'L';
+    pub const REAL64: u8 = b'r';
+    pub const BIG_INTEGER: u8 = b'I';
+    pub const BIG_REAL: u8 = b'R';
+    pub const ASSOCIATION: u8 = b'A';
+    pub const RULE: u8 = b'-';
+    pub const RULE_DELAYED: u8 = b':';
+    pub const PACKED_ARRAY: u8 = 0xC1;
+    pub const NUMERIC_ARRAY: u8 = 0xC2;
+}
+
+mod array_type {
+    pub const INTEGER8: u8 = 0x00;
+    pub const INTEGER16: u8 = 0x01;
+    pub const INTEGER32: u8 = 0x02;
+    pub const INTEGER64: u8 = 0x03;
+    pub const UNSIGNED_INTEGER8: u8 = 0x10;
+    pub const UNSIGNED_INTEGER16: u8 = 0x11;
+    pub const UNSIGNED_INTEGER32: u8 = 0x12;
+    pub const UNSIGNED_INTEGER64: u8 = 0x13;
+    pub const REAL32: u8 = 0x22;
+    pub const REAL64: u8 = 0x23;
+    pub const COMPLEX_REAL32: u8 = 0x33;
+    pub const COMPLEX_REAL64: u8 = 0x34;
+}
+
+impl Link {
+    /// Put the expression encoded by the WXF byte sequence `wxf`.
+    ///
+    /// `wxf` must start with the `8:` WXF header, like the bytes of a `ByteArray`
+    /// returned by [`BinarySerialize`][BinarySerialize]. Bodies compressed using zlib,
+    /// which start with the `8C:` header, are supported when the `flate2` feature is
+    /// enabled.
+    ///
+    /// Symbols without a context are put in the ``System` `` context, as specified by
+    /// WXF. Packed arrays are put as WSTP arrays, and numeric arrays as
+    /// `NumericArray[data, "Type"]` expressions.
+    ///
+    /// If `wxf` is not valid, an error is returned, and the part of the expression that
+    /// had been put before the error was found is left on the link, which is
+    /// [poisoned][Link::is_poisoned].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wolfram_expr::Expr;
+    /// use wstp::Link;
+    ///
+    /// let mut link = Link::new_loopback().unwrap();
+    ///
+    /// // BinarySerialize[{1, "a"}]
+    /// link.put_wxf(b"8:f\x02s\x04ListC\x01S\x01a").unwrap();
+    ///
+    /// assert_eq!(
+    ///     link.get_expr().unwrap(),
+    ///     Expr::list(vec![Expr::from(1), Expr::string("a")])
+    /// );
+    /// ```
+    ///
+    /// [BinarySerialize]: https://reference.wolfram.com/language/ref/BinarySerialize.html
+    pub fn put_wxf(&mut self, wxf: &[u8]) -> Result<(), Error> {
+        if let Some(body) = wxf.strip_prefix(HEADER) {
+            return self.put_guarded(|link| put_wxf_body(link, body));
+        }
+
+        if let Some(compressed) = wxf.strip_prefix(COMPRESSED_HEADER) {
+            return self.put_guarded(|link| put_compressed_wxf_body(link, compressed));
+        }
+
+        Err(invalid("missing \"8:\" header"))
+    }
+
+    /// Create a new Loopback type link containing the expression encoded by the WXF
+    /// byte sequence `wxf`.
+    ///
+    /// An error is returned if `wxf` is not valid WXF. See [`Link::put_wxf()`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wstp::Link;
+    ///
+    /// // BinarySerialize[42]
+    /// let mut link = Link::loopback_with_wxf(&[56, 58, 67, 42]).unwrap();
+    ///
+    /// assert_eq!(link.get_i64().unwrap(), 42);
+    /// ```
+    pub fn loopback_with_wxf(wxf: &[u8]) -> Result<Link, Error> {
+        let mut link = Link::new_loopback()?;
+
+        link.put_wxf(wxf)?;
+
+        Ok(link)
+    }
+}
+
+#[cfg(feature = "fuzzing")]
+impl Link {
+    /// Create a loopback link containing the expression encoded by the WXF byte
+    /// sequence `wxf`.
+    ///
+    /// This is intended for use as the entry point of fuzz targets, which can feed
+    /// arbitrary bytes to the getters of this crate, e.g. a
+    /// [`cargo-fuzz`](https://github.com/rust-fuzz/cargo-fuzz) target like:
+    ///
+    /// ```no_run
+    /// # fn fuzz_target(data: &[u8]) {
+    /// if let Ok(mut link) = wstp::Link::from_wxf_for_fuzzing(data) {
+    ///     let _ = link.get_expr();
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// An error is returned if `wxf` is not valid WXF. See [`Link::put_wxf()`].
+    pub fn from_wxf_for_fuzzing(wxf: &[u8]) -> Result<Link, Error> {
+        Link::loopback_with_wxf(wxf)
+    }
+}
+
+fn put_wxf_body(link: &mut Link, body: &[u8]) -> Result<(), Error> {
+    let mut reader = Reader { data: body };
+
+    reader.put_expr(link, 0)?;
+
+    if !reader.data.is_empty() {
+        return Err(invalid(&format!(
+            "{} bytes of trailing data after expression",
+            reader.data.len()
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "flate2")]
+fn put_compressed_wxf_body(link: &mut Link, compressed: &[u8]) -> Result<(), Error> {
+    use std::io::Read;
+
+    let mut body = Vec::new();
+
+    flate2::read::ZlibDecoder::new(compressed)
+        .read_to_end(&mut body)
+        .map_err(|err| invalid(&format!("error decompressing body: {}", err)))?;
+
+    put_wxf_body(link, &body)
+}
+
+#[cfg(not(feature = "flate2"))]
+fn put_compressed_wxf_body(_: &mut Link, _: &[u8]) -> Result<(), Error> {
+    Err(Error::custom(
+        "compressed WXF requires the \"flate2\" feature of wstp".to_owned(),
+    ))
+}
+
+fn invalid(message: &str) -> Error {
+    Error::custom(format!("invalid WXF: {}", message))
+}
+
+//======================================
+// Reading
+//======================================
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<u8, Error> {
+        let (&byte, rest) = self
+            .data
+            .split_first()
+            .ok_or_else(|| invalid("unexpected end of data"))?;
+
+        self.data = rest;
+
+        Ok(byte)
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if len > self.data.len() {
+            return Err(invalid("unexpected end of data"));
+        }
+
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        let bytes = self.bytes(N)?;
+
+        Ok(<[u8; N]>::try_from(bytes).unwrap())
+    }
+
+    /// Read a variable-length unsigned integer.
+    fn varint(&mut self) -> Result<usize, Error> {
+        let mut value: u64 = 0;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+
+            value |= u64::from(byte & 0x7F) << shift;
+
+            if byte & 0x80 == 0 {
+                return usize::try_from(value)
+                    .map_err(|_| invalid("length overflows usize"));
+            }
+        }
+
+        Err(invalid("variable-length integer is too long"))
+    }
+
+    fn str(&mut self) -> Result<&'a str, Error> {
+        let len = self.varint()?;
+        let bytes = self.bytes(len)?;
+
+        std::str::from_utf8(bytes).map_err(|err| invalid(&err.to_string()))
+    }
+
+    fn put_expr(&mut self, link: &mut Link, depth: usize) -> Result<(), Error> {
+        if depth > MAX_DEPTH {
+            return Err(invalid("expression is too deeply nested"));
+        }
+
+        match self.byte()? {
+            token::FUNCTION => {
+                let arg_count = self.varint()?;
+
+                link.put_raw_type(i32::from(sys::WSTKFUNC))?;
+                link.put_arg_count(arg_count)?;
+
+                self.put_expr(link, depth + 1)?;
+
+                for _ in 0..arg_count {
+                    self.put_expr(link, depth + 1)?;
+                }
+
+                Ok(())
+            },
+            token::SYMBOL => {
+                let symbol = self.str()?;
+
+                if symbol.contains('`') {
+                    link.put_symbol(symbol)
+                } else {
+                    link.put_symbol(&format!("System`{}", symbol))
+                }
+            },
+            token::STRING => link.put_str(self.str()?),
+            token::BINARY_STRING => {
+                let len = self.varint()?;
+                link.put_byte_array(self.bytes(len)?)
+            },
+            token::INTEGER8 => link.put_i64(i64::from(i8::from_le_bytes(self.array()?))),
+            token::INTEGER16 => {
+                link.put_i64(i64::from(i16::from_le_bytes(self.array()?)))
+            },
+            token::INTEGER32 => {
+                link.put_i64(i64::from(i32::from_le_bytes(self.array()?)))
+            },
+            token::INTEGER64 => link.put_i64(i64::from_le_bytes(self.array()?)),
+            token::REAL64 => link.put_f64(f64::from_le_bytes(self.array()?)),
+            token::BIG_INTEGER => link.put_integer_digits(self.str()?),
+            token::BIG_REAL => link.put_real_number_str(self.str()?),
+            token::ASSOCIATION => {
+                let len = self.varint()?;
+
+                link.put_function("System`Association", len)?;
+
+                for _ in 0..len {
+                    let rule = match self.byte()? {
+                        token::RULE => "System`Rule",
+                        token::RULE_DELAYED => "System`RuleDelayed",
+                        other => {
+                            return Err(invalid(&format!(
+                                "expected rule in association, got token 0x{:02X}",
+                                other
+                            )))
+                        },
+                    };
+
+                    link.put_function(rule, 2)?;
+                    self.put_expr(link, depth + 1)?;
+                    self.put_expr(link, depth + 1)?;
+                }
+
+                Ok(())
+            },
+            token::PACKED_ARRAY => self.put_packed_array(link),
+            token::NUMERIC_ARRAY => self.put_numeric_array(link),
+            other => Err(invalid(&format!("unknown token 0x{:02X}", other))),
+        }
+    }
+
+    /// Read the type, dimensions, and data of an array.
+    fn array_header(
+        &mut self,
+        elem_size: usize,
+    ) -> Result<(Vec<usize>, &'a [u8]), Error> {
+        let rank = self.varint()?;
+
+        if rank == 0 {
+            return Err(invalid("array has rank 0"));
+        }
+
+        // Each dimension takes at least one byte, so this bounds the allocation below.
+        if rank > self.data.len() {
+            return Err(invalid("unexpected end of data"));
+        }
+
+        let mut dimensions = Vec::with_capacity(rank);
+        let mut len: usize = elem_size;
+
+        for _ in 0..rank {
+            let dim = self.varint()?;
+
+            len = len
+                .checked_mul(dim)
+                .ok_or_else(|| invalid("array size overflows usize"))?;
+
+            dimensions.push(dim);
+        }
+
+        Ok((dimensions, self.bytes(len)?))
+    }
+
+    fn put_packed_array(&mut self, link: &mut Link) -> Result<(), Error> {
+        let type_ = self.byte()?;
+
+        match type_ {
+            array_type::INTEGER8 => {
+                let (dims, data) = self.array_header(1)?;
+                link.put_i16_array(
+                    &elems(data, |b| i16::from(i8::from_le_bytes(b))),
+                    &dims,
+                )
+            },
+            array_type::INTEGER16 => {
+                let (dims, data) = self.array_header(2)?;
+                link.put_i16_array(&elems(data, i16::from_le_bytes), &dims)
+            },
+            array_type::INTEGER32 => {
+                let (dims, data) = self.array_header(4)?;
+                link.put_i32_array(&elems(data, i32::from_le_bytes), &dims)
+            },
+            array_type::INTEGER64 => {
+                let (dims, data) = self.array_header(8)?;
+                link.put_i64_array(&elems(data, i64::from_le_bytes), &dims)
+            },
+            array_type::REAL32 => {
+                let (dims, data) = self.array_header(4)?;
+                link.put_f32_array(&elems(data, f32::from_le_bytes), &dims)
+            },
+            array_type::REAL64 => {
+                let (dims, data) = self.array_header(8)?;
+                link.put_f64_array(&elems(data, f64::from_le_bytes), &dims)
+            },
+            array_type::COMPLEX_REAL32 => {
+                let (dims, data) = self.array_header(8)?;
+                let data = elems(data, complex::<_, 4, 8>(f32::from_le_bytes));
+                link.put_nested_list(&data, &dims, &mut |link, &[re, im]| {
+                    link.put_function("System`Complex", 2)?;
+                    link.put_f32(re)?;
+                    link.put_f32(im)
+                })
+            },
+            array_type::COMPLEX_REAL64 => {
+                let (dims, data) = self.array_header(16)?;
+                let data = elems(data, complex::<_, 8, 16>(f64::from_le_bytes));
+                link.put_nested_list(&data, &dims, &mut |link, &[re, im]| {
+                    link.put_function("System`Complex", 2)?;
+                    link.put_f64(re)?;
+                    link.put_f64(im)
+                })
+            },
+            other => Err(invalid(&format!(
+                "unknown packed array type 0x{:02X}",
+                other
+            ))),
+        }
+    }
+
+    fn put_numeric_array(&mut self, link: &mut Link) -> Result<(), Error> {
+        let type_ = self.byte()?;
+
+        match type_ {
+            array_type::INTEGER8 => {
+                let (dims, data) = self.array_header(1)?;
+                link.put_numeric_array(&elems(data, i8::from_le_bytes), &dims)
+            },
+            array_type::INTEGER16 => {
+                let (dims, data) = self.array_header(2)?;
+                link.put_numeric_array(&elems(data, i16::from_le_bytes), &dims)
+            },
+            array_type::INTEGER32 => {
+                let (dims, data) = self.array_header(4)?;
+                link.put_numeric_array(&elems(data, i32::from_le_bytes), &dims)
+            },
+            array_type::INTEGER64 => {
+                let (dims, data) = self.array_header(8)?;
+                link.put_numeric_array(&elems(data, i64::from_le_bytes), &dims)
+            },
+            array_type::UNSIGNED_INTEGER8 => {
+                let (dims, data) = self.array_header(1)?;
+                link.put_numeric_array(&elems(data, u8::from_le_bytes), &dims)
+            },
+            array_type::UNSIGNED_INTEGER16 => {
+                let (dims, data) = self.array_header(2)?;
+                link.put_numeric_array(&elems(data, u16::from_le_bytes), &dims)
+            },
+            array_type::UNSIGNED_INTEGER32 => {
+                let (dims, data) = self.array_header(4)?;
+                link.put_numeric_array(&elems(data, u32::from_le_bytes), &dims)
+            },
+            array_type::UNSIGNED_INTEGER64 => {
+                let (dims, data) = self.array_header(8)?;
+                link.put_numeric_array(&elems(data, u64::from_le_bytes), &dims)
+            },
+            array_type::REAL32 => {
+                let (dims, data) = self.array_header(4)?;
+                link.put_numeric_array(&elems(data, f32::from_le_bytes), &dims)
+            },
+            array_type::REAL64 => {
+                let (dims, data) = self.array_header(8)?;
+                link.put_numeric_array(&elems(data, f64::from_le_bytes), &dims)
+            },
+            array_type::COMPLEX_REAL32 => {
+                let (dims, data) = self.array_header(8)?;
+                link.put_numeric_array(
+                    &elems(data, complex::<_, 4, 8>(f32::from_le_bytes)),
+                    &dims,
+                )
+            },
+            array_type::COMPLEX_REAL64 => {
+                let (dims, data) = self.array_header(16)?;
+                link.put_numeric_array(
+                    &elems(data, complex::<_, 8, 16>(f64::from_le_bytes)),
+                    &dims,
+                )
+            },
+            other => Err(invalid(&format!(
+                "unknown numeric array type 0x{:02X}",
+                other
+            ))),
+        }
+    }
+}
+
+/// Decode the little-endian elements of the array data `data`.
+fn elems<T, const N: usize>(data: &[u8], from_le_bytes: impl Fn([u8; N]) -> T) -> Vec<T> {
+    data.chunks_exact(N)
+        .map(|chunk| from_le_bytes(<[u8; N]>::try_from(chunk).unwrap()))
+        .collect()
+}
+
+/// Lift `from_le_bytes` into a function decoding a `[re, im]` pair. `M` must be twice
+/// `N`.
+fn complex<T, const N: usize, const M: usize>(
+    from_le_bytes: fn([u8; N]) -> T,
+) -> impl Fn([u8; M]) -> [T; 2] {
+    move |bytes: [u8; M]| {
+        let (re, im) = bytes.split_at(N);
+
+        [
+            from_le_bytes(<[u8; N]>::try_from(re).unwrap()),
+            from_le_bytes(<[u8; N]>::try_from(im).unwrap()),
+        ]
+    }
+}
+
+//======================================
+// Writing
+//======================================
+
+/// Append the WXF encoding of `expr`, without a header, to `out`.
+#[cfg(feature = "flate2")]
+pub(crate) fn write_expr(out: &mut Vec<u8>, expr: &Expr) {
+    match expr.kind() {
+        ExprKind::Normal(normal) => {
+            out.push(token::FUNCTION);
+            write_varint(out, normal.elements().len());
+            write_expr(out, normal.head());
+
+            for elem in normal.elements() {
+                write_expr(out, elem);
+            }
+        },
+        ExprKind::Symbol(symbol) => {
+            let symbol = symbol.as_str();
+
+            let symbol = match symbol.strip_prefix("System`") {
+                Some(name) if !name.contains('`') => name,
+                _ => symbol,
+            };
+
+            out.push(token::SYMBOL);
+            write_str(out, symbol);
+        },
+        ExprKind::String(string) => {
+            out.push(token::STRING);
+            write_str(out, string);
+        },
+        ExprKind::Integer(int) => {
+            let int = *int;
+
+            if let Ok(int) = i8::try_from(int) {
+                out.push(token::INTEGER8);
+                out.extend_from_slice(&int.to_le_bytes());
+            } else if let Ok(int) = i16::try_from(int) {
+                out.push(token::INTEGER16);
+                out.extend_from_slice(&int.to_le_bytes());
+            } else if let Ok(int) = i32::try_from(int) {
+                out.push(token::INTEGER32);
+                out.extend_from_slice(&int.to_le_bytes());
+            } else {
+                out.push(token::INTEGER64);
+                out.extend_from_slice(&int.to_le_bytes());
+            }
+        },
+        ExprKind::Real(real) => {
+            out.push(token::REAL64);
+            out.extend_from_slice(&real.to_le_bytes());
+        },
+    }
+}
+
+#[cfg(feature = "flate2")]
+fn write_str(out: &mut Vec<u8>, string: &str) {
+    write_varint(out, string.len());
+    out.extend_from_slice(string.as_bytes());
+}
+
+#[cfg(feature = "flate2")]
+fn write_varint(out: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7F) | 0x80);
+        value >>= 7;
+    }
+
+    out.push(value as u8);
+}
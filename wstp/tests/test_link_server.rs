@@ -5,7 +5,7 @@ use std::{
 
 use once_cell::sync::Lazy;
 
-use wstp::{sys, Link, LinkServer, Protocol};
+use wstp::{sys, Link, LinkListener, LinkServer, Protocol};
 
 const PORT: u16 = 11235;
 
@@ -189,3 +189,33 @@ fn test_name_taken_error() {
 
     assert_eq!(b.code(), Some(sys::MLENAMETAKEN));
 }
+
+#[test]
+fn test_link_listener_queues_connections() {
+    let _guard = MUTEX.lock().unwrap();
+
+    let listener = LinkListener::new(PORT, 4).unwrap();
+
+    assert_eq!(listener.port(), PORT);
+    assert_eq!(listener.backlog(), 4);
+
+    // Connect twice before any connection is accepted.
+    let mut clients: Vec<Link> = (0..2)
+        .map(|_| Link::connect_to_link_server(("127.0.0.1", PORT)).unwrap())
+        .collect();
+
+    for (index, client) in clients.iter_mut().enumerate() {
+        let mut conn = listener
+            .accept_timeout(Duration::from_secs(10))
+            .expect("connection was not queued");
+
+        conn.put_i64(index as i64).unwrap();
+        conn.flush().unwrap();
+
+        assert_eq!(client.get_i64(), Ok(index as i64));
+    }
+
+    assert_eq!(listener.pending(), 0);
+    assert_eq!(listener.refused(), 0);
+    assert!(listener.try_accept().is_none());
+}
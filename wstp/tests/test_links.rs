@@ -1,23 +1,18 @@
-use std::sync::Mutex;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
 
 use once_cell::sync::Lazy;
 
-use wstp::{sys, Link, Protocol, UrgentMessage};
+use wstp::{
+    sys, ChannelPool, HandshakeStage, Link, LinkKind, LinkRouter, Protocol, UrgentMessage,
+};
 
 /// Guard used to ensure the tests which bind to a port are run sequentially, so that
 /// port is free for each test.
 static MUTEX: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
 
-fn random_link_name() -> String {
-    use rand::{distributions::Alphanumeric, Rng};
-
-    rand::thread_rng()
-        .sample_iter(&Alphanumeric)
-        .take(7)
-        .map(char::from)
-        .collect()
-}
-
 // Helper method to check that data can successfully be sent from `link_a` to `link_b`.
 //
 // This tests reading and writing from both ends of the link.
@@ -76,8 +71,6 @@ fn check_send_data_across_link(mut link_a: Link, mut link_b: Link) {
 
 #[test]
 fn test_intra_process_links() {
-    // let name: String = dbg!(random_link_name());
-
     let listener = Link::listen(Protocol::IntraProcess, "").unwrap();
 
     // FIXME: IntraProcess-mode links ignore the `-linkname` device parameter and instead
@@ -95,11 +88,27 @@ fn test_intra_process_links() {
 ///        listener device first and then ask for it's name.
 #[test]
 fn test_bug_intra_process_device_ignored_linkname() {
-    let name: String = random_link_name();
+    let name = wstp::unique_link_name(Protocol::IntraProcess).unwrap();
     let listener = Link::listen(Protocol::IntraProcess, &name).unwrap();
     assert!(name != listener.link_name())
 }
 
+#[test]
+fn test_listen_any() {
+    for protocol in [
+        Protocol::IntraProcess,
+        Protocol::SharedMemory,
+        Protocol::TCPIP,
+    ] {
+        let (listener, name) = Link::listen_any(protocol.clone()).unwrap();
+        assert_eq!(name, listener.link_name());
+
+        let connector = Link::connect(protocol, &name).unwrap();
+
+        check_send_data_across_link(listener, connector);
+    }
+}
+
 //======================================
 // SharedMemory
 //======================================
@@ -116,6 +125,35 @@ fn test_shared_memory_name_taken_error() {
     assert_eq!(b.unwrap_err().code().unwrap(), sys::MLENAMETAKEN);
 }
 
+#[test]
+fn test_shared_memory_link_name() {
+    let prefix = format!("wstprs-{}-", std::process::id());
+
+    let a = wstp::shared_memory_link_name();
+    let b = wstp::shared_memory_link_name();
+
+    assert!(a.starts_with(&prefix), "{}", a);
+    assert_ne!(a, b);
+
+    let name = wstp::unique_link_name(Protocol::SharedMemory).unwrap();
+    assert!(name.starts_with(&prefix), "{}", name);
+
+    assert!(wstp::unique_link_name(Protocol::TCPIP).is_err());
+
+    let listener = Link::listen_shared_memory().unwrap();
+    let name = listener.link_name();
+    assert!(name.starts_with(&prefix), "{}", name);
+    assert_ne!(Link::listen_shared_memory().unwrap().link_name(), name);
+
+    let connector = Link::connect(Protocol::SharedMemory, &name).unwrap();
+    check_send_data_across_link(listener, connector);
+
+    // Objects for names generated by a process that is still running are kept.
+    let kept = Link::listen_shared_memory().unwrap();
+    let _ = wstp::clean_stale_shared_memory().unwrap();
+    assert!(Link::listen(Protocol::SharedMemory, &kept.link_name()).is_err());
+}
+
 #[test]
 fn test_link_listen_does_not_block_for_connection() {
     // This test is successful by not hanging.
@@ -152,6 +190,48 @@ fn test_single_thread_duplex_links() {
     assert_eq!(b.get_string().unwrap(), "from a to b");
 }
 
+#[test]
+fn test_try_activate() {
+    let mut a = Link::listen(Protocol::IntraProcess, "").unwrap();
+    let mut b = Link::connect(Protocol::IntraProcess, &a.link_name()).unwrap();
+
+    let a = std::thread::spawn(move || a.try_activate().map(|()| a));
+
+    assert_eq!(b.try_activate(), Ok(()));
+
+    let _a = a.join().unwrap().unwrap();
+
+    assert_eq!(HandshakeStage::Connect.to_string(), "connection");
+    assert_eq!(
+        HandshakeStage::ProtocolNegotiation.to_string(),
+        "protocol negotiation"
+    );
+    assert_eq!(HandshakeStage::VersionCheck.to_string(), "version check");
+}
+
+#[test]
+fn test_link_kind() {
+    let loopback = Link::new_loopback().unwrap();
+    assert_eq!(loopback.kind(), LinkKind::Loopback);
+
+    let a = Link::listen(Protocol::SharedMemory, "").unwrap();
+    let b = Link::connect(Protocol::SharedMemory, &a.link_name()).unwrap();
+
+    assert_eq!(a.kind(), LinkKind::Listener(Protocol::SharedMemory));
+    assert_eq!(b.kind(), LinkKind::Connector(Protocol::SharedMemory));
+}
+
+#[test]
+fn test_parent_connect() {
+    let a = Link::listen(Protocol::SharedMemory, "").unwrap();
+    let b = Link::parent_connect(Protocol::SharedMemory, &a.link_name()).unwrap();
+
+    assert_eq!(b.kind(), LinkKind::ParentConnect);
+
+    let args = ["program", "-linkprotocol", "SharedMemory"];
+    assert!(Link::parent_connect_from_args(&args).is_err());
+}
+
 //======================================
 // TCPIP
 //======================================
@@ -355,6 +435,32 @@ fn test_roundtrip_i64_array() {
     assert_eq!(out.dimensions(), &[2, 2]);
 }
 
+#[test]
+fn test_roundtrip_i32_array() {
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_i32_array(&[1, -2, 3, i32::MAX, i32::MIN, 0], &[2, 3])
+        .unwrap();
+
+    let out = link.get_i32_array().unwrap();
+
+    assert_eq!(out.data(), &[1, -2, 3, i32::MAX, i32::MIN, 0]);
+    assert_eq!(out.dimensions(), &[2, 3]);
+}
+
+#[test]
+fn test_roundtrip_i16_array() {
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_i16_array(&[-1, 2, i16::MAX, i16::MIN], &[4])
+        .unwrap();
+
+    let out = link.get_i16_array().unwrap();
+
+    assert_eq!(out.data(), &[-1, 2, i16::MAX, i16::MIN]);
+    assert_eq!(out.dimensions(), &[4]);
+}
+
 #[test]
 fn test_roundtrip_f64_array() {
     let mut link = Link::new_loopback().unwrap();
@@ -368,6 +474,19 @@ fn test_roundtrip_f64_array() {
     assert_eq!(out.dimensions(), &[3]);
 }
 
+#[test]
+fn test_roundtrip_f32_array() {
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_f32_array(&[0.5, -1.25, f32::MAX, f32::MIN_POSITIVE], &[2, 2])
+        .unwrap();
+
+    let out = link.get_f32_array().unwrap();
+
+    assert_eq!(out.data(), &[0.5, -1.25, f32::MAX, f32::MIN_POSITIVE]);
+    assert_eq!(out.dimensions(), &[2, 2]);
+}
+
 // Test that getting an f64 array as an i64 array performs rounding.
 #[test]
 fn test_mismatched_array_type_rounding() {
@@ -416,3 +535,875 @@ fn test_urgent_messages() {
         Some(UrgentMessage::new_with_param(500, 123))
     );
 }
+
+//--------------------------------------
+// Test lifecycle callbacks
+//--------------------------------------
+
+#[test]
+fn test_on_closed_callback() {
+    let (a, mut b) = wstp::channel(Protocol::IntraProcess).unwrap();
+
+    let closed = Arc::new(AtomicBool::new(false));
+
+    b.on_closed({
+        let closed = Arc::clone(&closed);
+        move || closed.store(true, Ordering::SeqCst)
+    });
+
+    assert!(!closed.load(Ordering::SeqCst));
+    assert!(b.is_open());
+
+    drop(a);
+
+    assert!(b.get_i64().unwrap_err().is_peer_closed());
+    assert!(closed.load(Ordering::SeqCst));
+    assert!(!b.is_open());
+
+    // The link is still known to be closed after its error is cleared.
+    b.clear_error();
+    assert!(!b.is_open());
+}
+
+#[test]
+fn test_protocol_error_is_not_peer_closed() {
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_str("not an integer").unwrap();
+
+    assert!(!link.get_i64().unwrap_err().is_peer_closed());
+    assert!(link.is_open());
+}
+
+#[test]
+fn test_ping() {
+    use std::time::Duration;
+
+    let mut loopback = Link::new_loopback().unwrap();
+    assert!(loopback.ping(Duration::from_secs(1)).is_ok());
+
+    let (mut a, mut b) = wstp::channel(Protocol::IntraProcess).unwrap();
+
+    let peer = std::thread::spawn(move || {
+        assert_eq!(b.test_head("System`EvaluatePacket").unwrap(), 1);
+        assert_eq!(b.get_symbol_ref().unwrap().as_str(), "System`Null");
+
+        // Packets other than ReturnPacket are skipped.
+        b.put_function("System`TextPacket", 1).unwrap();
+        b.put_str("busy").unwrap();
+        b.put_function("System`ReturnPacket", 1).unwrap();
+        b.put_symbol("System`Null").unwrap();
+        b.flush().unwrap();
+
+        b
+    });
+
+    assert!(a.ping(Duration::from_secs(10)).is_ok());
+
+    // A peer that doesn't reply causes a timeout.
+    let _b = peer.join().unwrap();
+    assert!(a.ping(Duration::from_millis(50)).is_err());
+}
+
+#[test]
+fn test_issue_message() {
+    use wolfram_expr::{Expr, Symbol};
+
+    let (mut program, mut kernel) = wstp::channel(Protocol::IntraProcess).unwrap();
+
+    let kernel = std::thread::spawn(move || {
+        let message = kernel.get_expr().unwrap();
+
+        kernel.put_function("System`ReturnPacket", 1).unwrap();
+        kernel.put_symbol("System`Null").unwrap();
+        kernel.flush().unwrap();
+
+        message
+    });
+
+    program
+        .issue_message("Global`MyFunc", "badarg", &[Expr::from(-1)])
+        .unwrap();
+
+    assert_eq!(
+        kernel.join().unwrap(),
+        Expr::normal(
+            Symbol::new("System`EvaluatePacket"),
+            vec![Expr::normal(
+                Symbol::new("System`Message"),
+                vec![
+                    Expr::normal(
+                        Symbol::new("System`MessageName"),
+                        vec![
+                            Expr::symbol(Symbol::new("Global`MyFunc")),
+                            Expr::string("badarg"),
+                        ]
+                    ),
+                    Expr::from(-1),
+                ]
+            )]
+        )
+    );
+}
+
+#[test]
+fn test_auto_flush() {
+    use wolfram_expr::Expr;
+
+    let (mut a, mut b) = wstp::channel(Protocol::IntraProcess).unwrap();
+
+    assert!(!a.auto_flush());
+
+    a.set_auto_flush(true);
+    assert!(a.auto_flush());
+
+    // No explicit flush is needed for `b` to receive the expression.
+    a.put_expr(&Expr::from(5)).unwrap();
+    assert_eq!(b.get_i64().unwrap(), 5);
+
+    a.put_function("System`List", 1).unwrap();
+    a.put_i64(6).unwrap();
+    a.end_packet().unwrap();
+    assert_eq!(b.get_expr().unwrap(), Expr::list(vec![Expr::from(6)]));
+
+    a.set_auto_flush(false);
+    a.put_expr_and_flush(&Expr::from(7)).unwrap();
+    assert_eq!(b.get_i64().unwrap(), 7);
+}
+
+#[test]
+fn test_get_set_symbol_value() {
+    use wolfram_expr::{Expr, Symbol};
+
+    let (mut program, mut kernel) = wstp::channel(Protocol::IntraProcess).unwrap();
+
+    let kernel = std::thread::spawn(move || {
+        let mut packets = Vec::new();
+
+        for result in [
+            Expr::string("13.1.0"),
+            Expr::symbol(Symbol::new("System`Null")),
+        ] {
+            packets.push(kernel.get_expr().unwrap());
+
+            // Packets other than ReturnPacket are skipped.
+            kernel.put_function("System`TextPacket", 1).unwrap();
+            kernel.put_str("output").unwrap();
+            kernel.put_function("System`ReturnPacket", 1).unwrap();
+            kernel.put_expr(&result).unwrap();
+            kernel.flush().unwrap();
+        }
+
+        packets
+    });
+
+    assert_eq!(
+        program.get_symbol_value("System`$Version").unwrap(),
+        Expr::string("13.1.0")
+    );
+    program
+        .set_symbol_value("Global`x", &Expr::from(5))
+        .unwrap();
+
+    let packets = kernel.join().unwrap();
+
+    assert_eq!(
+        packets[0],
+        Expr::normal(
+            Symbol::new("System`EvaluatePacket"),
+            vec![Expr::symbol(Symbol::new("System`$Version"))]
+        )
+    );
+    assert_eq!(
+        packets[1],
+        Expr::normal(
+            Symbol::new("System`EvaluatePacket"),
+            vec![Expr::normal(
+                Symbol::new("System`CompoundExpression"),
+                vec![
+                    Expr::normal(
+                        Symbol::new("System`Set"),
+                        vec![
+                            Expr::symbol(Symbol::new("Global`x")),
+                            Expr::normal(
+                                Symbol::new("System`Unevaluated"),
+                                vec![Expr::from(5)]
+                            ),
+                        ]
+                    ),
+                    Expr::symbol(Symbol::new("System`Null")),
+                ]
+            )]
+        )
+    );
+}
+
+#[test]
+fn test_evaluate_with() {
+    use wolfram_expr::{Expr, Symbol};
+
+    let (mut program, mut kernel) = wstp::channel(Protocol::IntraProcess).unwrap();
+
+    let kernel = std::thread::spawn(move || {
+        let packet = kernel.get_expr().unwrap();
+
+        kernel.put_function("System`ReturnPacket", 1).unwrap();
+        kernel.put_i64(3).unwrap();
+        kernel.flush().unwrap();
+
+        packet
+    });
+
+    let result = program
+        .evaluate_with("Plus[#1, #2]", &[Expr::from(1), Expr::string("]; x")])
+        .unwrap();
+    assert_eq!(result, Expr::from(3));
+
+    let unevaluated = |arg| Expr::normal(Symbol::new("System`Unevaluated"), vec![arg]);
+
+    assert_eq!(
+        kernel.join().unwrap(),
+        Expr::normal(
+            Symbol::new("System`EvaluatePacket"),
+            vec![Expr::normal(
+                Expr::normal(
+                    Symbol::new("System`ToExpression"),
+                    vec![
+                        Expr::string("Plus[#1, #2]"),
+                        Expr::symbol(Symbol::new("System`InputForm")),
+                        Expr::symbol(Symbol::new("System`Function")),
+                    ]
+                ),
+                vec![
+                    unevaluated(Expr::from(1)),
+                    unevaluated(Expr::string("]; x"))
+                ]
+            )]
+        )
+    );
+}
+
+#[test]
+fn test_scoped_context() {
+    use wolfram_expr::{Expr, Symbol};
+
+    let (mut program, mut kernel) = wstp::channel(Protocol::IntraProcess).unwrap();
+
+    let null = Expr::symbol(Symbol::new("System`Null"));
+
+    let results = vec![
+        Expr::string("Global`"),
+        Expr::list(vec![Expr::string("System`"), Expr::string("Global`")]),
+        null.clone(),
+        null.clone(),
+        // Evaluated by the closure.
+        Expr::from(5),
+        null.clone(),
+        null.clone(),
+        null.clone(),
+    ];
+
+    let kernel = std::thread::spawn(move || {
+        let mut packets = Vec::new();
+
+        for result in results {
+            packets.push(kernel.get_expr().unwrap());
+
+            kernel.put_function("System`ReturnPacket", 1).unwrap();
+            kernel.put_expr(&result).unwrap();
+            kernel.flush().unwrap();
+        }
+
+        packets
+    });
+
+    let x = program
+        .scoped_context("User1", true, |link| link.get_symbol_value("x"))
+        .unwrap();
+    assert_eq!(x, Expr::from(5));
+
+    let packets = kernel.join().unwrap();
+    assert_eq!(packets.len(), 8);
+
+    let eval = |expr| Expr::normal(Symbol::new("System`EvaluatePacket"), vec![expr]);
+    let set = |name, value| {
+        eval(Expr::normal(
+            Symbol::new("System`CompoundExpression"),
+            vec![
+                Expr::normal(
+                    Symbol::new("System`Set"),
+                    vec![
+                        Expr::symbol(Symbol::new(name)),
+                        Expr::normal(Symbol::new("System`Unevaluated"), vec![value]),
+                    ],
+                ),
+                Expr::symbol(Symbol::new("System`Null")),
+            ],
+        ))
+    };
+
+    assert_eq!(packets[3], set("System`$Context", Expr::string("User1`")));
+    assert_eq!(packets[5], set("System`$Context", Expr::string("Global`")));
+    assert_eq!(
+        packets[7],
+        eval(Expr::normal(
+            Symbol::new("System`Quiet"),
+            vec![Expr::normal(
+                Symbol::new("System`Remove"),
+                vec![Expr::string("User1`*")]
+            )]
+        ))
+    );
+}
+
+#[test]
+fn test_evaluate_streaming() {
+    use wolfram_expr::{Expr, Symbol};
+    use wstp::kernel::EvaluationOutput;
+
+    let (mut program, mut kernel) = wstp::channel(Protocol::IntraProcess).unwrap();
+
+    let kernel = std::thread::spawn(move || {
+        let _ = kernel.get_expr().unwrap();
+
+        for i in 1..=2 {
+            kernel.put_function("Global`partial", 1).unwrap();
+            kernel.put_i64(i).unwrap();
+            kernel.flush().unwrap();
+        }
+
+        kernel.put_function("System`ReturnPacket", 1).unwrap();
+        kernel.put_i64(3).unwrap();
+        kernel.flush().unwrap();
+
+        kernel
+    });
+
+    let expr = Expr::normal(Symbol::new("Global`compute"), vec![]);
+
+    let outputs: Vec<EvaluationOutput> = program
+        .evaluate_streaming(&expr)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    let partial =
+        |i: i64| Expr::normal(Symbol::new("Global`partial"), vec![Expr::from(i)]);
+
+    assert_eq!(
+        outputs,
+        vec![
+            EvaluationOutput::Intermediate(partial(1)),
+            EvaluationOutput::Intermediate(partial(2)),
+            EvaluationOutput::Result(Expr::from(3)),
+        ]
+    );
+
+    let _kernel = kernel.join().unwrap();
+}
+
+#[test]
+fn test_kernel_connection() {
+    use wolfram_expr::Expr;
+    use wstp::kernel::KernelConnection;
+
+    let (main, mut kernel_main) = wstp::channel(Protocol::SharedMemory).unwrap();
+    let (preemptive, mut kernel_preemptive) =
+        wstp::channel(Protocol::IntraProcess).unwrap();
+
+    let mut kernel = KernelConnection::with_preemptive(main, preemptive);
+
+    let thread = std::thread::spawn(move || {
+        let _ = kernel_preemptive.get_expr().unwrap();
+
+        kernel_preemptive
+            .put_function("System`ReturnPacket", 1)
+            .unwrap();
+        kernel_preemptive.put_str("preemptive").unwrap();
+        kernel_preemptive.flush().unwrap();
+
+        kernel_preemptive
+    });
+
+    let preemptive = kernel.preemptive_handle().unwrap();
+
+    assert_eq!(
+        preemptive.evaluate(&Expr::from(1)).unwrap(),
+        Expr::string("preemptive")
+    );
+
+    kernel.abort().unwrap();
+    assert_eq!(kernel_main.get_message(), Some(UrgentMessage::ABORT));
+
+    let _ = thread.join().unwrap();
+
+    // The preemptive link is not returned while a handle to it is alive.
+    let (main, preemptive_link) = kernel.into_links();
+    assert!(preemptive_link.is_none());
+
+    drop(preemptive);
+
+    let kernel = KernelConnection::new(main);
+    assert!(kernel.preemptive_handle().is_none());
+}
+
+/// Test that an evaluation blocked in `KernelConnection::evaluate()` can be interrupted
+/// and preempted from another thread.
+#[test]
+fn test_kernel_connection_interrupt_during_evaluate() {
+    use std::{sync::mpsc, time::Duration};
+    use wolfram_expr::{Expr, Symbol};
+    use wstp::kernel::KernelConnection;
+
+    let (main, mut kernel_main) = wstp::channel(Protocol::IntraProcess).unwrap();
+    let (preemptive, mut kernel_preemptive) =
+        wstp::channel(Protocol::IntraProcess).unwrap();
+
+    let mut kernel = KernelConnection::with_preemptive(main, preemptive);
+
+    let interrupt = kernel.interrupt_handle();
+    let preemptive = kernel.preemptive_handle().unwrap();
+
+    let (started_tx, started_rx) = mpsc::channel();
+
+    // Fake kernel, which only finishes the evaluation once it has been interrupted.
+    let fake_main = std::thread::spawn(move || {
+        let packet = kernel_main.get_expr().unwrap();
+        started_tx.send(packet).unwrap();
+
+        while kernel_main.get_message() != Some(UrgentMessage::INTERRUPT) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        kernel_main.put_function("System`ReturnPacket", 1).unwrap();
+        kernel_main.put_str("interrupted").unwrap();
+        kernel_main.flush().unwrap();
+
+        kernel_main
+    });
+
+    let fake_preemptive = std::thread::spawn(move || {
+        let _ = kernel_preemptive.get_expr().unwrap();
+
+        kernel_preemptive
+            .put_function("System`ReturnPacket", 1)
+            .unwrap();
+        kernel_preemptive.put_str("preemptive").unwrap();
+        kernel_preemptive.flush().unwrap();
+
+        kernel_preemptive
+    });
+
+    let evaluator = std::thread::spawn(move || {
+        let result = kernel.evaluate(&Expr::from(1));
+        (kernel, result)
+    });
+
+    // Wait until the evaluation is in progress.
+    assert_eq!(
+        started_rx.recv().unwrap(),
+        Expr::normal(Symbol::new("System`EvaluatePacket"), vec![Expr::from(1)])
+    );
+
+    assert_eq!(
+        preemptive.evaluate(&Expr::from(2)).unwrap(),
+        Expr::string("preemptive")
+    );
+
+    interrupt.interrupt();
+
+    let (_kernel, result) = evaluator.join().unwrap();
+    assert_eq!(result.unwrap(), Expr::string("interrupted"));
+
+    let _ = fake_main.join().unwrap();
+    let _ = fake_preemptive.join().unwrap();
+}
+
+#[test]
+fn test_kernel_connection_connect_preemptive() {
+    use wolfram_expr::{Expr, ExprKind};
+    use wstp::kernel::KernelConnection;
+
+    let (main, mut kernel_main) = wstp::channel(Protocol::IntraProcess).unwrap();
+
+    let mut kernel = KernelConnection::new(main);
+
+    // Fake kernel, which connects to the link named in the `LinkConnect[name, ...]`
+    // passed to `AddSharingLink`.
+    let fake_kernel = std::thread::spawn(move || {
+        let packet = kernel_main.get_expr().unwrap();
+
+        let name = match packet.normal_part(0).and_then(|add| add.normal_part(0)) {
+            Some(connect) => match connect.normal_part(0).map(Expr::kind) {
+                Some(ExprKind::String(name)) => name.clone(),
+                _ => panic!("unexpected LinkConnect expression: {connect}"),
+            },
+            None => panic!("unexpected packet: {packet}"),
+        };
+
+        let mut preemptive = Link::connect(Protocol::IntraProcess, &name).unwrap();
+        preemptive.activate().unwrap();
+
+        kernel_main.put_function("System`ReturnPacket", 1).unwrap();
+        kernel_main.put_symbol("System`Null").unwrap();
+        kernel_main.flush().unwrap();
+
+        let _ = preemptive.get_expr().unwrap();
+
+        preemptive.put_function("System`ReturnPacket", 1).unwrap();
+        preemptive.put_str("preemptive").unwrap();
+        preemptive.flush().unwrap();
+
+        (kernel_main, preemptive)
+    });
+
+    kernel.connect_preemptive(Protocol::IntraProcess).unwrap();
+
+    let preemptive = kernel.preemptive_handle().unwrap();
+
+    assert_eq!(
+        preemptive.evaluate(&Expr::from(1)).unwrap(),
+        Expr::string("preemptive")
+    );
+
+    let _ = fake_kernel.join().unwrap();
+}
+
+#[test]
+fn test_link_watcher() {
+    use std::{ops::ControlFlow, time::Duration};
+    use wstp::LinkWatcher;
+
+    let (mut a1, b1) = wstp::channel(Protocol::IntraProcess).unwrap();
+    let (mut a2, b2) = wstp::channel(Protocol::IntraProcess).unwrap();
+
+    let mut received = Vec::new();
+
+    let mut watcher = LinkWatcher::new();
+
+    let id1 = watcher.watch(b1, |link| {
+        received.push(("b1", link.get_i64().unwrap()));
+        ControlFlow::Continue(())
+    });
+    let _id2 = watcher.watch(b2, |link| {
+        let _ = link.get_i64().unwrap();
+        ControlFlow::Break(())
+    });
+
+    assert!(!watcher.poll(Duration::from_millis(50)).unwrap());
+
+    a1.put_i64(1).unwrap();
+    a1.flush().unwrap();
+    a2.put_i64(2).unwrap();
+    a2.flush().unwrap();
+
+    // Wait until both links have been serviced.
+    while watcher.len() == 2 {
+        assert!(watcher.poll(Duration::from_secs(5)).unwrap());
+    }
+
+    assert!(watcher.unwatch(id1).is_some());
+    assert!(watcher.is_empty());
+
+    drop(watcher);
+
+    assert_eq!(received, vec![("b1", 1)]);
+}
+
+#[test]
+fn test_queued_link() {
+    use wolfram_expr::Expr;
+    use wstp::{OverflowPolicy, QueuedLink};
+
+    let (a, mut b) = wstp::channel(Protocol::IntraProcess).unwrap();
+
+    let queue = std::sync::Arc::new(QueuedLink::new(a, 4, OverflowPolicy::Block));
+
+    let producers: Vec<_> = (0..2)
+        .map(|_| {
+            let queue = std::sync::Arc::clone(&queue);
+            std::thread::spawn(move || {
+                for i in 0..10 {
+                    queue.send(Expr::from(i)).unwrap();
+                }
+            })
+        })
+        .collect();
+
+    let mut total = 0;
+    for _ in 0..20 {
+        total += b.get_i64().unwrap();
+    }
+    assert_eq!(total, 2 * 45);
+
+    for producer in producers {
+        producer.join().unwrap();
+    }
+
+    let queue = std::sync::Arc::try_unwrap(queue).unwrap();
+    assert_eq!(queue.dropped(), 0);
+    let _a = queue.close().unwrap();
+}
+
+#[test]
+fn test_request_client() {
+    use wolfram_expr::Expr;
+    use wstp::RequestClient;
+
+    let (client, mut server) = wstp::channel(Protocol::IntraProcess).unwrap();
+
+    let server = std::thread::spawn(move || {
+        let mut requests = Vec::new();
+        for _ in 0..3 {
+            requests.push(server.get_request().unwrap());
+        }
+
+        // Respond in reverse order, doubling each payload.
+        for (id, payload) in requests.into_iter().rev() {
+            let value = match payload.kind() {
+                wolfram_expr::ExprKind::Integer(value) => *value,
+                _ => panic!("unexpected payload: {payload}"),
+            };
+            server.put_response(id, &Expr::from(value * 2)).unwrap();
+        }
+        server.flush().unwrap();
+
+        server
+    });
+
+    let client = std::sync::Arc::new(RequestClient::new(client));
+
+    let threads: Vec<_> = (1..=3)
+        .map(|i: i64| {
+            let client = std::sync::Arc::clone(&client);
+            std::thread::spawn(move || client.request(Expr::from(i)).unwrap().wait())
+        })
+        .collect();
+
+    let mut results: Vec<Expr> = threads
+        .into_iter()
+        .map(|thread| thread.join().unwrap().unwrap())
+        .collect();
+    results.sort_by_key(|expr| expr.to_string());
+
+    assert_eq!(results, vec![Expr::from(2), Expr::from(4), Expr::from(6)]);
+
+    let _server = server.join().unwrap();
+
+    let client = std::sync::Arc::try_unwrap(client).unwrap();
+    assert_eq!(client.pending(), 0);
+    let _link = client.close().unwrap();
+}
+
+#[test]
+fn test_channel_pool() {
+    let pool = ChannelPool::new(2);
+
+    pool.fill().unwrap();
+    assert_eq!(pool.len(), 2);
+    assert_eq!(pool.capacity(), 2);
+
+    // Take more pairs than the pool holds; the extra pair is created on demand.
+    for value in 0..3 {
+        let (mut a, mut b) = pool.take().unwrap();
+
+        assert_eq!(a.kind(), LinkKind::Listener(Protocol::IntraProcess));
+
+        a.put_i64(value).unwrap();
+        a.flush().unwrap();
+        assert_eq!(b.get_i64().unwrap(), value);
+
+        b.put_i64(-value).unwrap();
+        b.flush().unwrap();
+        assert_eq!(a.get_i64().unwrap(), -value);
+    }
+
+    // The background thread refills the pool.
+    pool.fill().unwrap();
+    assert_eq!(pool.len(), 2);
+    assert!(!pool.is_empty());
+}
+
+#[test]
+fn test_link_router() {
+    use std::time::Duration;
+    use wolfram_expr::{Expr, Symbol};
+
+    let event =
+        |head: &str, value: i64| Expr::normal(Symbol::new(head), vec![Expr::from(value)]);
+
+    let (mut kernel, link) = wstp::channel(Protocol::IntraProcess).unwrap();
+
+    let router = LinkRouter::new(link);
+
+    let events_a = router.subscribe("Global`EventA");
+    let events_a2 = router.subscribe("Global`EventA");
+    let events_b = router.subscribe("Global`EventB");
+
+    assert_eq!(events_b.head(), "Global`EventB");
+
+    kernel.put_expr(&event("Global`EventA", 1)).unwrap();
+    kernel.put_expr(&event("Global`Unknown", 2)).unwrap();
+    kernel.put_expr(&event("Global`EventB", 3)).unwrap();
+    kernel.put_expr(&event("Global`EventA", 4)).unwrap();
+    kernel.flush().unwrap();
+
+    let timeout = Duration::from_secs(10);
+
+    assert_eq!(
+        events_a.recv_timeout(timeout),
+        Some(event("Global`EventA", 1))
+    );
+    assert_eq!(
+        events_a.recv_timeout(timeout),
+        Some(event("Global`EventA", 4))
+    );
+    assert_eq!(
+        events_a2.recv_timeout(timeout),
+        Some(event("Global`EventA", 1))
+    );
+    assert_eq!(
+        events_b.recv_timeout(timeout),
+        Some(event("Global`EventB", 3))
+    );
+
+    assert_eq!(router.discarded(), 1);
+    assert_eq!(events_b.try_recv(), None);
+
+    // Expressions for dropped subscriptions are discarded.
+    drop(events_b);
+    kernel.put_expr(&event("Global`EventB", 5)).unwrap();
+    kernel.put_expr(&event("Global`EventA", 6)).unwrap();
+    kernel.flush().unwrap();
+
+    assert_eq!(
+        events_a.recv_timeout(timeout),
+        Some(event("Global`EventA", 6))
+    );
+    assert_eq!(router.discarded(), 2);
+
+    let _link = router.close().unwrap();
+
+    // Closing the router ends the remaining subscriptions.
+    assert_eq!(events_a.count(), 0);
+    assert_eq!(
+        events_a2.collect::<Vec<_>>(),
+        vec![event("Global`EventA", 4), event("Global`EventA", 6)]
+    );
+}
+
+#[test]
+fn test_call() {
+    use std::time::Duration;
+    use wolfram_expr::Expr;
+
+    let (mut client, mut server) = wstp::channel(Protocol::IntraProcess).unwrap();
+
+    let server = std::thread::spawn(move || {
+        let text = server.get_string().unwrap();
+        server.put_str(&text.to_uppercase()).unwrap();
+        server.flush().unwrap();
+
+        // Receive the second call, but never respond to it.
+        let _ = server.get_string().unwrap();
+
+        server
+    });
+
+    assert_eq!(
+        client
+            .call(&Expr::string("hello"), Duration::from_secs(10))
+            .unwrap(),
+        Expr::string("HELLO")
+    );
+
+    let err = client
+        .call(&Expr::string("ignored"), Duration::from_millis(100))
+        .unwrap_err();
+    assert!(err.to_string().contains("no response"), "{}", err);
+
+    let _server = server.join().unwrap();
+}
+
+#[test]
+fn test_interface_version_linked() {
+    let version = wstp::InterfaceVersion::linked().unwrap();
+
+    assert!(version.interface > 0, "{}", version);
+    assert!(version.to_string().starts_with("interface "));
+}
+
+#[test]
+fn test_check_compat() {
+    // Both sides compatible.
+    {
+        let (mut a, mut b) = wstp::channel(Protocol::IntraProcess).unwrap();
+
+        let thread = std::thread::spawn(move || b.check_compat().map(|_| b));
+
+        assert_eq!(a.check_compat(), Ok(a.interface_version()));
+
+        let _b = thread.join().unwrap().unwrap();
+    }
+
+    // Peer uses a different interface number.
+    {
+        let (mut a, mut b) = wstp::channel(Protocol::IntraProcess).unwrap();
+
+        let version = b.interface_version();
+        b.put_function("WSTP`InterfaceVersion", 3).unwrap();
+        b.put_i32(version.interface + 1).unwrap();
+        b.put_i32(version.revision).unwrap();
+        b.put_i32(version.build).unwrap();
+        b.flush().unwrap();
+
+        let err = a.check_compat().unwrap_err();
+        assert!(err.to_string().contains("incompatible"), "{}", err);
+    }
+
+    // Peer doesn't perform the check.
+    {
+        let (mut a, mut b) = wstp::channel(Protocol::IntraProcess).unwrap();
+
+        b.put_str("hello").unwrap();
+        b.flush().unwrap();
+
+        let err = a.check_compat().unwrap_err();
+        assert!(err.to_string().contains("did not send"), "{}", err);
+    }
+}
+
+//--------------------------------------
+// Test staged writes
+//--------------------------------------
+
+#[test]
+fn test_transaction_commit_to_intra_process_link() {
+    let (mut a, mut b) = wstp::channel(Protocol::IntraProcess).unwrap();
+
+    let mut transaction = a.transaction().unwrap();
+    transaction.put(|link| link.put_i64(5)).unwrap();
+    transaction.put(|link| link.put_str("hello")).unwrap();
+    transaction.commit().unwrap();
+
+    a.flush().unwrap();
+
+    assert_eq!(b.get_i64().unwrap(), 5);
+    assert_eq!(b.get_string().unwrap(), "hello");
+}
+
+#[test]
+fn test_list_writer_finish_to_intra_process_link() {
+    let (mut a, mut b) = wstp::channel(Protocol::IntraProcess).unwrap();
+
+    let mut list = wstp::ListWriter::new().unwrap();
+    list.push(|link| link.put_i64(1)).unwrap();
+    list.push(|link| link.put_i64(2)).unwrap();
+    list.finish(&mut a).unwrap();
+
+    a.flush().unwrap();
+
+    assert_eq!(b.test_head("System`List").unwrap(), 2);
+    assert_eq!(b.get_i64().unwrap(), 1);
+    assert_eq!(b.get_i64().unwrap(), 2);
+}
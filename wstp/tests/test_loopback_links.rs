@@ -1,5 +1,13 @@
+use std::cell::RefCell;
+
 use wolfram_expr::{Expr, Symbol};
-use wstp::{sys, Link, LinkStr, Protocol, Token, TokenType};
+use wstp::{
+    copy_expr_filtered, symbols, sys, BoolEncoding, Dispatcher, ErrorRecovery, ExprPath,
+    ExprSchema, ExprView, FaultConfig, FilterAction, FlakyLink, InfinityDirection, Link,
+    LinkLimits, LinkReader, LinkStr, NanPolicy, NumberDigits, NumericArrayKind,
+    OptionsParser, Progress, Protocol, RealPrecision, RealValue, SymbolStr,
+    ThrottledLink, Token, TokenType, UnknownOptionPolicy,
+};
 
 fn check_loopback_roundtrip(expr: Expr) {
     let mut link = Link::new_loopback().expect("failed to create Loopback link");
@@ -44,6 +52,21 @@ fn test_loopback_get_put_atoms() {
     }
 }
 
+#[test]
+fn test_loopback_put_small_integers() {
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_i32(-100_000).unwrap();
+    link.put_i16(-1_000).unwrap();
+    link.put_i8(-5).unwrap();
+    link.put_u8(255).unwrap();
+
+    assert_eq!(link.get_i32(), Ok(-100_000));
+    assert_eq!(link.get_i16(), Ok(-1_000));
+    assert_eq!(link.get_i16(), Ok(-5));
+    assert_eq!(link.get_u8(), Ok(255));
+}
+
 #[test]
 fn test_is_loopback() {
     let link = Link::new_loopback().unwrap();
@@ -243,3 +266,2295 @@ fn test_loopback_get_token_type_is_idempotent() {
 
     assert!(matches!(link.get_token().unwrap(), Token::Integer(5)));
 }
+
+#[test]
+fn test_loopback_byte_array_roundtrip() {
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_byte_array(&[0, 1, 254, 255]).unwrap();
+
+    let array = link.get_byte_array().unwrap();
+
+    assert_eq!(array.data(), &[0, 1, 254, 255]);
+    assert_eq!(array.dimensions(), &[4]);
+}
+
+#[test]
+fn test_loopback_byte_array_base64() {
+    let mut link = Link::new_loopback().unwrap();
+
+    for (encoded, expected) in [
+        ("AAH+/w==", &[0u8, 1, 254, 255][..]),
+        ("YWI=", b"ab"),
+        ("YWI", b"ab"),
+        ("YQ==", b"a"),
+        ("", b""),
+    ] {
+        link.put_function("System`ByteArray", 1).unwrap();
+        link.put_str(encoded).unwrap();
+
+        let array = link.get_byte_array().unwrap();
+
+        assert_eq!(array.data(), expected, "{}", encoded);
+        assert_eq!(array.dimensions(), &[expected.len()]);
+    }
+
+    // Non-canonical encodings, with non-zero unused bits or incomplete padding, are
+    // rejected.
+    for encoded in [
+        "not base64!",
+        "YWJ",
+        "YWJ=",
+        "YR==",
+        "YWI==",
+        "AAH+/w=",
+        "YWJj=",
+    ] {
+        link.put_function("System`ByteArray", 1).unwrap();
+        link.put_str(encoded).unwrap();
+
+        assert!(link.get_byte_array().is_err(), "{}", encoded);
+    }
+}
+
+#[test]
+fn test_loopback_byte_array_wrong_head() {
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_u8_array(&[1, 2, 3], &[3]).unwrap();
+
+    assert_eq!(
+        link.get_byte_array().unwrap_err().code(),
+        Some(sys::WSEGSEQ)
+    );
+}
+
+#[cfg(feature = "bytes")]
+#[test]
+fn test_loopback_bytes_roundtrip() {
+    let mut link = Link::new_loopback().unwrap();
+
+    let payload = bytes::Bytes::from_static(b"binary payload");
+
+    link.put_bytes(&payload).unwrap();
+
+    assert_eq!(link.get_bytes().unwrap(), payload);
+}
+
+#[cfg(feature = "rust_decimal")]
+#[test]
+fn test_loopback_decimal_roundtrip() {
+    use rust_decimal::Decimal;
+    use wstp::DecimalPolicy;
+
+    let mut link = Link::new_loopback().unwrap();
+
+    for value in [
+        Decimal::new(1250, 2),
+        Decimal::new(-3, 0),
+        Decimal::new(1, 28),
+        Decimal::MAX,
+    ] {
+        link.put_decimal(&value, DecimalPolicy::ExactRational)
+            .unwrap();
+        assert_eq!(link.get_decimal().unwrap(), value);
+
+        link.put_decimal(&value, DecimalPolicy::PrecisionReal)
+            .unwrap();
+        assert_eq!(link.get_decimal().unwrap(), value);
+    }
+}
+
+#[cfg(feature = "rust_decimal")]
+#[test]
+fn test_loopback_decimal_exact_rational_form() {
+    use rust_decimal::Decimal;
+    use wstp::DecimalPolicy;
+
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_decimal(&Decimal::new(1250, 2), DecimalPolicy::ExactRational)
+        .unwrap();
+
+    assert_eq!(link.test_head("System`Rational"), Ok(2));
+    assert_eq!(link.get_i64(), Ok(25));
+    assert_eq!(link.get_i64(), Ok(2));
+}
+
+#[cfg(feature = "bigdecimal")]
+#[test]
+fn test_loopback_big_decimal_roundtrip() {
+    use bigdecimal::BigDecimal;
+    use std::str::FromStr;
+    use wstp::DecimalPolicy;
+
+    let mut link = Link::new_loopback().unwrap();
+
+    for value in ["0.1", "-12.50", "123456789012345678901234567890.000001"] {
+        let value = BigDecimal::from_str(value).unwrap();
+
+        link.put_big_decimal(&value, DecimalPolicy::ExactRational)
+            .unwrap();
+        assert_eq!(link.get_big_decimal().unwrap(), value);
+
+        link.put_big_decimal(&value, DecimalPolicy::PrecisionReal)
+            .unwrap();
+        assert_eq!(link.get_big_decimal().unwrap(), value);
+    }
+}
+
+#[cfg(feature = "uom")]
+#[test]
+fn test_loopback_uom_quantity() {
+    use uom::si::{
+        f64::{Length, ThermodynamicTemperature},
+        length::{foot, meter},
+        thermodynamic_temperature::{degree_celsius, kelvin},
+    };
+
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_quantity(&Length::new::<meter>(3.0)).unwrap();
+    assert_eq!(link.get_quantity::<Length>().unwrap().get::<meter>(), 3.0);
+
+    // Quantities using a non-canonical unit are converted.
+    link.put_function("System`Quantity", 2).unwrap();
+    link.put_i64(10).unwrap();
+    link.put_str("Feet").unwrap();
+    let length: Length = link.get_quantity().unwrap();
+    assert!((length.get::<foot>() - 10.0).abs() < 1e-9);
+
+    link.put_function("System`Quantity", 2).unwrap();
+    link.put_f64(0.0).unwrap();
+    link.put_str("DegreesCelsius").unwrap();
+    let temp: ThermodynamicTemperature = link.get_quantity().unwrap();
+    assert!((temp.get::<kelvin>() - 273.15).abs() < 1e-9);
+    assert!(temp.get::<degree_celsius>().abs() < 1e-9);
+
+    // Units of the wrong dimension are rejected.
+    link.put_function("System`Quantity", 2).unwrap();
+    link.put_f64(1.0).unwrap();
+    link.put_str("Seconds").unwrap();
+    assert!(link.get_quantity::<Length>().is_err());
+}
+
+#[cfg(feature = "uom")]
+#[test]
+fn test_loopback_uom_quantity_poisons_link() {
+    use uom::si::{f64::Length, length::meter};
+
+    let mut link = Link::new_loopback().unwrap();
+    link.set_nan_policy(NanPolicy::Error);
+
+    // The NaN magnitude is refused after the Quantity head has been put.
+    let err = link
+        .put_quantity(&Length::new::<meter>(f64::NAN))
+        .unwrap_err();
+    assert!(!err.is_poisoned());
+    assert!(link.is_poisoned());
+
+    assert!(link.put_i64(1).unwrap_err().is_poisoned());
+    assert!(link.put_str("text").unwrap_err().is_poisoned());
+}
+
+#[test]
+fn test_loopback_sparse_array_roundtrip() {
+    let mut link = Link::new_loopback().unwrap();
+
+    // Positions are given out of row-major order.
+    link.put_sparse_array(3, 4, &[[2, 3], [0, 1], [2, 0]], &[3.0, 1.0, 2.0])
+        .unwrap();
+
+    let matrix = link.get_sparse_array().unwrap();
+
+    assert_eq!(
+        matrix,
+        wstp::SparseMatrix {
+            rows: 3,
+            cols: 4,
+            indices: vec![[0, 1], [2, 0], [2, 3]],
+            values: vec![1.0, 2.0, 3.0],
+        }
+    );
+}
+
+#[test]
+fn test_loopback_sparse_array_contextless_automatic() {
+    let mut link = Link::new_loopback().unwrap();
+
+    // Put SparseArray[Automatic, {2, 2}, 0., {1, {{0, 1, 1}, {{2}}}, {5.}}], with the
+    // `Automatic` symbol written without its context.
+    link.put_function("System`SparseArray", 4).unwrap();
+    link.put_symbol("Automatic").unwrap();
+    link.put_i64_array(&[2, 2], &[2]).unwrap();
+    link.put_f64(0.0).unwrap();
+    link.put_function("System`List", 3).unwrap();
+    link.put_i64(1).unwrap();
+    link.put_function("System`List", 2).unwrap();
+    link.put_i64_array(&[0, 1, 1], &[3]).unwrap();
+    link.put_i64_array(&[2], &[1, 1]).unwrap();
+    link.put_f64_array(&[5.0], &[1]).unwrap();
+
+    assert_eq!(
+        link.get_sparse_array().unwrap(),
+        wstp::SparseMatrix {
+            rows: 2,
+            cols: 2,
+            indices: vec![[0, 1]],
+            values: vec![5.0],
+        }
+    );
+}
+
+#[test]
+fn test_loopback_sparse_array_invalid_positions() {
+    let mut link = Link::new_loopback().unwrap();
+
+    assert!(link.put_sparse_array(2, 2, &[[2, 0]], &[1.0]).is_err());
+    assert!(link
+        .put_sparse_array(2, 2, &[[1, 1], [1, 1]], &[1.0, 2.0])
+        .is_err());
+}
+
+#[test]
+fn test_loopback_quantity_array_roundtrip() {
+    let mut link = Link::new_loopback().unwrap();
+
+    let expected = wstp::QuantityVector {
+        magnitudes: vec![1.0, 2.5, -3.0],
+        unit: "Seconds".to_owned(),
+    };
+
+    link.put_quantity_array(&[1.0, 2.5, -3.0], "Seconds")
+        .unwrap();
+    assert_eq!(link.get_quantity_array(), Ok(expected.clone()));
+
+    link.put_quantity_list(&[1.0, 2.5, -3.0], "Seconds")
+        .unwrap();
+    assert_eq!(link.get_quantity_array(), Ok(expected.clone()));
+
+    // Put the structured form of an evaluated QuantityArray.
+    link.put_function("System`StructuredArray", 3).unwrap();
+    link.put_symbol("System`QuantityArray").unwrap();
+    link.put_i64_array(&[3], &[1]).unwrap();
+    link.put_function("StructuredArray`StructuredData", 4)
+        .unwrap();
+    link.put_symbol("System`QuantityArray").unwrap();
+    link.put_f64_array(&[1.0, 2.5, -3.0], &[3]).unwrap();
+    link.put_str("Seconds").unwrap();
+    link.put_function("System`List", 0).unwrap();
+    assert_eq!(link.get_quantity_array(), Ok(expected));
+}
+
+#[test]
+fn test_loopback_quantity_list_mixed_units() {
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_function("System`List", 2).unwrap();
+    link.put_function("System`Quantity", 2).unwrap();
+    link.put_f64(1.0).unwrap();
+    link.put_str("Meters").unwrap();
+    link.put_function("System`Quantity", 2).unwrap();
+    link.put_i64(2).unwrap();
+    link.put_str("Feet").unwrap();
+
+    let err = link.get_quantity_array().unwrap_err();
+    assert!(err.to_string().contains("\"Feet\""), "{}", err);
+}
+
+#[test]
+fn test_loopback_numeric_array_roundtrip() {
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_numeric_array::<i8>(&[-128, 0, 127], &[3]).unwrap();
+    let array = link.get_numeric_array::<i8>().unwrap();
+    assert_eq!(array.data, vec![-128, 0, 127]);
+    assert_eq!(array.dimensions, vec![3]);
+
+    link.put_numeric_array::<u32>(&[0, 1, u32::MAX, 3], &[2, 2])
+        .unwrap();
+    let array = link.get_numeric_array::<u32>().unwrap();
+    assert_eq!(array.data, vec![0, 1, u32::MAX, 3]);
+    assert_eq!(array.dimensions, vec![2, 2]);
+
+    link.put_numeric_array::<u64>(&[1, u64::MAX], &[2, 1])
+        .unwrap();
+    let array = link.get_numeric_array::<u64>().unwrap();
+    assert_eq!(array.data, vec![1, u64::MAX]);
+    assert_eq!(array.dimensions, vec![2, 1]);
+
+    link.put_numeric_array::<[f64; 2]>(&[[1.0, -1.0], [0.5, 2.0]], &[1, 2])
+        .unwrap();
+    let array = link.get_numeric_array::<[f64; 2]>().unwrap();
+    assert_eq!(array.data, vec![[1.0, -1.0], [0.5, 2.0]]);
+    assert_eq!(array.dimensions, vec![1, 2]);
+}
+
+#[test]
+fn test_loopback_numeric_array_wrong_kind() {
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_numeric_array::<i16>(&[1, 2, 3], &[3]).unwrap();
+
+    assert!(link.get_numeric_array::<i32>().is_err());
+
+    assert_eq!(
+        NumericArrayKind::from_name(NumericArrayKind::ComplexReal64.as_str()),
+        Some(NumericArrayKind::ComplexReal64)
+    );
+}
+
+#[test]
+fn test_loopback_str_and_symbol_lists() {
+    let mut link = Link::new_loopback().unwrap();
+
+    let owned: Vec<String> = vec!["a".to_owned(), "".to_owned(), "c d".to_owned()];
+
+    link.put_str_list(&owned).unwrap();
+    assert_eq!(link.get_string_list().unwrap(), owned);
+
+    link.put_str_list::<&str>(&[]).unwrap();
+    assert_eq!(link.get_string_list().unwrap(), Vec::<String>::new());
+
+    link.put_symbol_list(&["Global`x", "System`Pi"]).unwrap();
+    assert_eq!(
+        link.get_symbol_list().unwrap(),
+        vec!["Global`x", "System`Pi"]
+    );
+
+    // A list containing a symbol is not a list of strings.
+    link.put_symbol_list(&["Global`x"]).unwrap();
+    assert!(link.get_string_list().is_err());
+}
+
+#[test]
+fn test_loopback_bool_list_encodings() {
+    let mut link = Link::new_loopback().unwrap();
+
+    let mask = [true, false, false, true];
+
+    link.put_bool_list(&mask, BoolEncoding::List).unwrap();
+    assert_eq!(link.get_bool_list().unwrap(), mask);
+
+    link.put_bool_list(&mask, BoolEncoding::NumericArray)
+        .unwrap();
+    assert_eq!(link.get_bool_list().unwrap(), mask);
+
+    // Non-boolean byte values are rejected.
+    link.put_numeric_array::<u8>(&[0, 2], &[2]).unwrap();
+    assert!(link.get_bool_list().is_err());
+}
+
+#[test]
+fn test_loopback_time_series() {
+    use std::time::{Duration, SystemTime};
+
+    fn get_date_object(link: &mut Link) -> (Vec<i64>, f64) {
+        assert_eq!(link.test_head("System`DateObject").unwrap(), 4);
+        assert_eq!(link.test_head("System`List").unwrap(), 6);
+        let date: Vec<i64> = (0..5).map(|_| link.get_i64().unwrap()).collect();
+        let seconds = link.get_f64().unwrap();
+        assert_eq!(link.get_string().unwrap(), "Instant");
+        assert_eq!(link.get_string().unwrap(), "Gregorian");
+        assert_eq!(link.get_f64().unwrap(), 0.0);
+        (date, seconds)
+    }
+
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_time_series(&[0.0, 1.5], &[10.0, 20.0]).unwrap();
+
+    assert_eq!(link.test_head("System`TimeSeries").unwrap(), 2);
+    assert_eq!(link.get_f64_array().unwrap().data(), &[10.0, 20.0]);
+    assert_eq!(link.test_head("System`List").unwrap(), 1);
+    assert_eq!(link.get_f64_array().unwrap().data(), &[0.0, 1.5]);
+
+    let times = [
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000),
+        SystemTime::UNIX_EPOCH - Duration::from_millis(500),
+    ];
+
+    link.put_time_series(&times, &[1.0, 2.0]).unwrap();
+
+    assert_eq!(link.test_head("System`TimeSeries").unwrap(), 2);
+    assert_eq!(link.get_f64_array().unwrap().data(), &[1.0, 2.0]);
+    assert_eq!(link.test_head("System`List").unwrap(), 1);
+    assert_eq!(link.test_head("System`List").unwrap(), 2);
+    assert_eq!(
+        get_date_object(&mut link),
+        (vec![2020, 9, 13, 12, 26], 40.0)
+    );
+    assert_eq!(
+        get_date_object(&mut link),
+        (vec![1969, 12, 31, 23, 59], 59.5)
+    );
+}
+
+#[test]
+fn test_loopback_list_writer() {
+    let mut list = wstp::ListWriter::new().unwrap();
+
+    list.push(|link| link.put_str("first")).unwrap();
+    list.push(|link| {
+        link.put_function("System`List", 1)?;
+        link.put_i64(2)
+    })
+    .unwrap();
+
+    assert_eq!(list.len(), 2);
+
+    let mut link = Link::new_loopback().unwrap();
+
+    list.finish(&mut link).unwrap();
+
+    assert_eq!(link.test_head("System`List").unwrap(), 2);
+    assert_eq!(link.get_string().unwrap(), "first");
+    assert_eq!(link.test_head("System`List").unwrap(), 1);
+    assert_eq!(link.get_i64().unwrap(), 2);
+
+    // An empty ListWriter puts {}.
+    wstp::ListWriter::new().unwrap().finish(&mut link).unwrap();
+    assert_eq!(link.test_head("System`List").unwrap(), 0);
+}
+
+#[test]
+fn test_loopback_dataset_writer() {
+    let mut dataset = wstp::DatasetWriter::new(&["id", "label"]).unwrap();
+
+    for id in 0..3 {
+        dataset
+            .write_row(|row| {
+                row.put_i64(id)?;
+                row.put_str(&format!("item {id}"))
+            })
+            .unwrap();
+    }
+
+    assert_eq!(dataset.row_count(), 3);
+
+    let mut link = Link::new_loopback().unwrap();
+
+    dataset.finish(&mut link).unwrap();
+
+    assert_eq!(link.test_head("System`Dataset").unwrap(), 1);
+    assert_eq!(link.test_head("System`List").unwrap(), 3);
+
+    for id in 0..3 {
+        assert_eq!(link.test_head("System`Association").unwrap(), 2);
+        assert_eq!(link.test_head("System`Rule").unwrap(), 2);
+        assert_eq!(link.get_string().unwrap(), "id");
+        assert_eq!(link.get_i64().unwrap(), id);
+        assert_eq!(link.test_head("System`Rule").unwrap(), 2);
+        assert_eq!(link.get_string().unwrap(), "label");
+        assert_eq!(link.get_string().unwrap(), format!("item {id}"));
+    }
+}
+
+#[test]
+fn test_loopback_dataset_writer_wrong_value_count() {
+    let mut dataset = wstp::DatasetWriter::new(&["a", "b"]).unwrap();
+
+    assert!(dataset.write_row(|row| row.put_i64(1)).is_err());
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn test_loopback_put_csv_reader() {
+    use wstp::{CsvOptions, CsvOutput};
+
+    let csv = "name,score\nAlice,9.5\nBob,7\n";
+
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_csv_reader(csv.as_bytes(), &CsvOptions::default())
+        .unwrap();
+
+    assert_eq!(link.test_head("System`List").unwrap(), 3);
+    assert_eq!(link.get_string_list().unwrap(), vec!["name", "score"]);
+    assert_eq!(link.test_head("System`List").unwrap(), 2);
+    assert_eq!(link.get_string().unwrap(), "Alice");
+    assert_eq!(link.get_f64().unwrap(), 9.5);
+    assert_eq!(link.test_head("System`List").unwrap(), 2);
+    assert_eq!(link.get_string().unwrap(), "Bob");
+    assert_eq!(link.get_i64().unwrap(), 7);
+
+    let options = CsvOptions {
+        has_headers: false,
+        infer_types: false,
+        output: CsvOutput::Dataset,
+        ..CsvOptions::default()
+    };
+
+    link.put_csv_reader(
+        "1;2\n".as_bytes(),
+        &CsvOptions {
+            delimiter: b';',
+            ..options
+        },
+    )
+    .unwrap();
+
+    assert_eq!(link.test_head("System`Dataset").unwrap(), 1);
+    assert_eq!(link.test_head("System`List").unwrap(), 1);
+    assert_eq!(link.test_head("System`Association").unwrap(), 2);
+    assert_eq!(link.test_head("System`Rule").unwrap(), 2);
+    assert_eq!(link.get_string().unwrap(), "1");
+    assert_eq!(link.get_string().unwrap(), "1");
+    assert_eq!(link.test_head("System`Rule").unwrap(), 2);
+    assert_eq!(link.get_string().unwrap(), "2");
+    assert_eq!(link.get_string().unwrap(), "2");
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_loopback_json_roundtrip() {
+    use serde_json::json;
+
+    let mut link = Link::new_loopback().unwrap();
+
+    let value = json!({
+        "name": "sensor",
+        "ok": true,
+        "readings": [1, 2.5, -3],
+        "max": u64::MAX,
+        "meta": { "unit": null },
+    });
+
+    link.put_json(&value).unwrap();
+    assert_eq!(link.get_json().unwrap(), value);
+
+    // Symbols other than Null, True, and False have no JSON representation.
+    link.put_symbol("Global`x").unwrap();
+    assert!(link.get_json().is_err());
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_loopback_put_json_lines() {
+    use serde_json::json;
+
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_json_lines("{\"a\": 1}\n\n[true]\n".as_bytes())
+        .unwrap();
+
+    assert_eq!(link.get_json().unwrap(), json!([{ "a": 1 }, [true]]));
+
+    assert!(link.put_json_lines("{\"a\": 1}\n{".as_bytes()).is_err());
+}
+
+#[cfg(feature = "rmpv")]
+#[test]
+fn test_loopback_msgpack_roundtrip() {
+    use rmpv::Value;
+
+    let mut link = Link::new_loopback().unwrap();
+
+    let value = Value::Array(vec![
+        Value::Nil,
+        Value::Boolean(false),
+        Value::from(u64::MAX),
+        Value::from(-5),
+        Value::F64(0.25),
+        Value::from("text"),
+        Value::Binary(vec![1, 2, 3]),
+        Value::Map(vec![(Value::from(1), Value::from("one"))]),
+    ]);
+
+    link.put_msgpack(&value).unwrap();
+    assert_eq!(link.get_msgpack().unwrap(), value);
+
+    let mut payload = Vec::new();
+    rmpv::encode::write_value(&mut payload, &value).unwrap();
+
+    link.put_msgpack_bytes(&payload).unwrap();
+    assert_eq!(link.get_msgpack_bytes().unwrap(), payload);
+
+    assert!(link.put_msgpack(&Value::Ext(1, vec![0])).is_err());
+}
+
+#[cfg(feature = "prost-reflect")]
+#[test]
+fn test_loopback_dynamic_message_roundtrip() {
+    use prost_reflect::{
+        prost_types::{
+            field_descriptor_proto::{Label, Type},
+            DescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+        },
+        DescriptorPool, DynamicMessage, Value,
+    };
+
+    fn field(name: &str, number: i32, type_: Type, label: Label) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_owned()),
+            number: Some(number),
+            r#type: Some(type_ as i32),
+            label: Some(label as i32),
+            ..Default::default()
+        }
+    }
+
+    let file = FileDescriptorProto {
+        name: Some("reading.proto".to_owned()),
+        package: Some("test".to_owned()),
+        syntax: Some("proto3".to_owned()),
+        message_type: vec![DescriptorProto {
+            name: Some("Reading".to_owned()),
+            field: vec![
+                field("sensor", 1, Type::String, Label::Optional),
+                field("value", 2, Type::Double, Label::Optional),
+                field("samples", 3, Type::Uint64, Label::Repeated),
+            ],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    let mut pool = DescriptorPool::new();
+    pool.add_file_descriptor_proto(file).unwrap();
+    let descriptor = pool.get_message_by_name("test.Reading").unwrap();
+
+    let mut message = DynamicMessage::new(descriptor.clone());
+    message.set_field_by_name("sensor", Value::String("t1".to_owned()));
+    message.set_field_by_name("value", Value::F64(3.5));
+    message.set_field_by_name(
+        "samples",
+        Value::List(vec![Value::U64(1), Value::U64(u64::MAX)]),
+    );
+
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_dynamic_message(&message).unwrap();
+    assert_eq!(
+        link.get_dynamic_message(descriptor.clone()).unwrap(),
+        message
+    );
+
+    // Unknown fields are rejected.
+    link.put_function("System`Association", 1).unwrap();
+    link.put_function("System`Rule", 2).unwrap();
+    link.put_str("unknown").unwrap();
+    link.put_i64(1).unwrap();
+    assert!(link.get_dynamic_message(descriptor).is_err());
+}
+
+#[test]
+fn test_peek_and_skip_expr() {
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_function("Global`f", 2).unwrap();
+    link.put_integer_digits("123456789012345678901234567890")
+        .unwrap();
+    link.put_str("x").unwrap();
+    link.put_symbol("Global`done").unwrap();
+
+    // Peeking doesn't consume any data, even if the closure fails.
+    let argc = link.peek(|link| link.get_arg_count()).unwrap();
+    assert_eq!(argc, 2);
+    assert!(link.peek(|link| link.get_i64()).is_err());
+    assert_eq!(link.get_type().unwrap(), TokenType::Function);
+
+    link.skip_expr().unwrap();
+    assert_eq!(link.get_symbol_ref().unwrap().as_str(), "Global`done");
+}
+
+#[test]
+fn test_dispatcher() {
+    let mut link = Link::new_loopback().unwrap();
+
+    // f[1], f[1, 2], f["a", "b"], g[], 5
+    link.put_function("Global`f", 1).unwrap();
+    link.put_i64(1).unwrap();
+    link.put_function("Global`f", 2).unwrap();
+    link.put_i64(1).unwrap();
+    link.put_i64(2).unwrap();
+    link.put_function("Global`f", 2).unwrap();
+    link.put_str("a").unwrap();
+    link.put_str("b").unwrap();
+    link.put_function("Global`g", 0).unwrap();
+    link.put_i64(5).unwrap();
+
+    let calls: RefCell<Vec<String>> = RefCell::new(Vec::new());
+
+    let mut dispatcher = Dispatcher::new();
+    dispatcher
+        .on_args(
+            "Global`f",
+            &[TokenType::Integer, TokenType::Integer],
+            |link| {
+                calls.borrow_mut().push("f[int, int]".to_owned());
+                link.skip_expr()
+            },
+        )
+        .on_arity("Global`f", 1, |link| {
+            calls.borrow_mut().push("f[_]".to_owned());
+            link.skip_expr()
+        })
+        .on("Global`f", |link| {
+            calls.borrow_mut().push("f[___]".to_owned());
+            link.skip_expr()
+        })
+        .on_default(|link| {
+            calls
+                .borrow_mut()
+                .push(format!("default {:?}", link.get_type()?));
+            link.skip_expr()
+        });
+
+    dispatcher.run(&mut link).unwrap();
+    drop(dispatcher);
+
+    assert_eq!(
+        calls.into_inner(),
+        [
+            "f[_]",
+            "f[int, int]",
+            "f[___]",
+            "default Function",
+            "default Integer"
+        ]
+    );
+
+    // Without a default handler, unmatched expressions are discarded.
+    let mut dispatcher = Dispatcher::new();
+    link.put_function("Global`h", 0).unwrap();
+    link.put_i64(1).unwrap();
+    assert!(dispatcher.dispatch(&mut link).is_err());
+    assert_eq!(link.get_i64().unwrap(), 1);
+}
+
+#[test]
+fn test_get_validated() {
+    let schema = ExprSchema::function("Global`Foo")
+        .arg(ExprSchema::integer())
+        .arg(
+            ExprSchema::function("Global`Bar")
+                .arg(ExprSchema::string())
+                .rest(ExprSchema::real()),
+        );
+
+    let mut link = Link::new_loopback().unwrap();
+
+    // Foo[1, Bar["a", 1.5, 2.5]]
+    link.put_function("Global`Foo", 2).unwrap();
+    link.put_i64(1).unwrap();
+    link.put_function("Global`Bar", 3).unwrap();
+    link.put_str("a").unwrap();
+    link.put_f64(1.5).unwrap();
+    link.put_f64(2.5).unwrap();
+
+    let expr = link.get_validated(&schema).unwrap();
+    assert_eq!(
+        expr.to_string(),
+        "Global`Foo[1, Global`Bar[\"a\", 1.5, 2.5]]"
+    );
+
+    // Foo[1, Bar["a", 1.5, "b"]]
+    link.put_function("Global`Foo", 2).unwrap();
+    link.put_i64(1).unwrap();
+    link.put_function("Global`Bar", 3).unwrap();
+    link.put_str("a").unwrap();
+    link.put_f64(1.5).unwrap();
+    link.put_str("b").unwrap();
+
+    let err = link.get_validated(&schema).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "WSTP error: argument 2 of Global`Foo: argument 3 of Global`Bar: expected Real, \
+         got String"
+    );
+
+    // The invalid expression was not consumed.
+    assert_eq!(link.test_head("Global`Foo").unwrap(), 2);
+    link.skip_expr().unwrap();
+    link.skip_expr().unwrap();
+
+    // Foo[1]
+    link.put_function("Global`Foo", 1).unwrap();
+    link.put_i64(1).unwrap();
+
+    let err = link.validate(&schema).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "WSTP error: Global`Foo: expected 2 arguments, got 1"
+    );
+}
+
+#[test]
+fn test_get_at_path() {
+    fn put_response(link: &mut Link) {
+        // Response[200, <|"Name" -> "wstp", "Items" -> {1, 2, 3}|>]
+        link.put_function("Global`Response", 2).unwrap();
+        link.put_i64(200).unwrap();
+        link.put_function("System`Association", 2).unwrap();
+        link.put_function("System`Rule", 2).unwrap();
+        link.put_str("Name").unwrap();
+        link.put_str("wstp").unwrap();
+        link.put_function("System`Rule", 2).unwrap();
+        link.put_str("Items").unwrap();
+        link.put_i64_array(&[1, 2, 3], &[3]).unwrap();
+    }
+
+    let mut link = Link::new_loopback().unwrap();
+
+    let path: ExprPath = "args[2] / key \"Items\" / args[3]".parse().unwrap();
+    assert_eq!(path, ExprPath::new().arg(2).key("Items").arg(3));
+    assert_eq!(path.to_string(), "args[2]/key \"Items\"/args[3]");
+
+    put_response(&mut link);
+    assert_eq!(
+        link.peek_at_path(&ExprPath::new().head())
+            .unwrap()
+            .to_string(),
+        "Global`Response"
+    );
+    assert_eq!(link.get_at_path(&path).unwrap(), Expr::from(3i64));
+    assert!(!link.is_ready());
+
+    // Paths that don't exist in the expression are errors, but the full expression is
+    // still consumed.
+    put_response(&mut link);
+    link.put_symbol("Global`next").unwrap();
+    let err = link
+        .get_at_path(&ExprPath::new().arg(2).key("Size"))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "WSTP error: step 2 (key \"Size\") of expression path \"args[2]/key \"Size\"\": \
+         key \"Size\" does not exist"
+    );
+    assert_eq!(link.get_symbol_ref().unwrap().as_str(), "Global`next");
+
+    assert!("args[0]".parse::<ExprPath>().is_err());
+    assert!("key \"unterminated".parse::<ExprPath>().is_err());
+    assert!("head/".parse::<ExprPath>().is_err());
+}
+
+#[test]
+fn test_hash_next_expr() {
+    use std::collections::hash_map::DefaultHasher;
+
+    let mut link = Link::new_loopback().unwrap();
+
+    let hash = |link: &mut Link| link.hash_next_expr::<DefaultHasher>().unwrap();
+
+    // The same expression written as a packed array and as individual tokens.
+    link.put_i64_array(&[1, 2, 3], &[3]).unwrap();
+    let packed = hash(&mut link);
+
+    link.put_expr(&Expr::list(vec![
+        Expr::from(1i64),
+        Expr::from(2i64),
+        Expr::from(3i64),
+    ]))
+    .unwrap();
+    assert_eq!(hash(&mut link), packed);
+
+    link.put_function("System`List", 3).unwrap();
+    link.put_i64(1).unwrap();
+    link.put_i64(2).unwrap();
+    link.put_f64(3.0).unwrap();
+    assert_ne!(hash(&mut link), packed);
+
+    // Strings and symbols with the same name hash differently.
+    link.put_str("Global`x").unwrap();
+    let string = hash(&mut link);
+    link.put_symbol("Global`x").unwrap();
+    assert_ne!(hash(&mut link), string);
+}
+
+#[test]
+fn test_compare_exprs() {
+    let mut a = Link::new_loopback().unwrap();
+    let mut b = Link::new_loopback().unwrap();
+
+    let expr = Expr::normal(
+        Symbol::new("Global`f"),
+        vec![Expr::string("x"), Expr::list(vec![Expr::from(1i64)])],
+    );
+
+    a.put_expr(&expr).unwrap();
+    b.put_expr(&expr).unwrap();
+    assert!(wstp::exprs_equal(&mut a, &mut b).unwrap());
+
+    // Differing heads, followed by a marker expression on each link.
+    a.put_expr(&expr).unwrap();
+    a.put_i64(1).unwrap();
+    b.put_expr(&Expr::normal(
+        Symbol::new("Global`g"),
+        vec![Expr::from(5i64)],
+    ))
+    .unwrap();
+    b.put_i64(2).unwrap();
+
+    let difference = wstp::compare_exprs(&mut a, &mut b).unwrap().unwrap();
+    assert_eq!(difference.path, ExprPath::new());
+    assert_eq!(difference.left, "expression with 2 arguments");
+    assert_eq!(difference.right, "expression with 1 arguments");
+
+    // The remainder of both expressions was skipped.
+    assert_eq!(a.get_i64().unwrap(), 1);
+    assert_eq!(b.get_i64().unwrap(), 2);
+
+    // Differing heads with the same arity.
+    a.put_function("Global`f", 1).unwrap();
+    a.put_i64(1).unwrap();
+    b.put_function("Global`g", 1).unwrap();
+    b.put_i64(1).unwrap();
+
+    let difference = wstp::compare_exprs(&mut a, &mut b).unwrap().unwrap();
+    assert_eq!(difference.path, ExprPath::new().head());
+    assert!(!a.is_ready());
+    assert!(!b.is_ready());
+}
+
+#[test]
+fn test_user_data() {
+    use std::sync::Arc;
+
+    let dropped = Arc::new(());
+
+    let mut link = Link::new_loopback().unwrap();
+
+    assert!(link.typed_user_data::<u32>().is_none());
+
+    link.set_typed_user_data(5u32);
+    assert_eq!(link.typed_user_data::<u32>(), Some(&5));
+    assert!(link.typed_user_data::<i64>().is_none());
+
+    *link.typed_user_data_mut::<u32>().unwrap() += 1;
+
+    // Taking a value of the wrong type leaves the user data unchanged.
+    assert_eq!(link.take_typed_user_data::<i64>(), None);
+    assert_eq!(link.take_typed_user_data::<u32>(), Some(6));
+    assert!(link.typed_user_data::<u32>().is_none());
+
+    // Replacing or dropping the link drops the previous user data value.
+    link.set_typed_user_data(Arc::clone(&dropped));
+    assert_eq!(Arc::strong_count(&dropped), 2);
+    link.set_typed_user_data(String::from("replaced"));
+    assert_eq!(Arc::strong_count(&dropped), 1);
+
+    link.set_typed_user_data(Arc::clone(&dropped));
+    drop(link);
+    assert_eq!(Arc::strong_count(&dropped), 1);
+}
+
+#[test]
+fn test_put_function_with_expr_head() {
+    let mut link = Link::new_loopback().unwrap();
+
+    let head = Expr::normal(Symbol::new("Global`foo"), vec![Expr::string("a")]);
+
+    link.put_function_with_expr_head(&head, 2).unwrap();
+    link.put_str("b").unwrap();
+    link.put_i64(1).unwrap();
+
+    assert_eq!(
+        link.get_expr().unwrap(),
+        Expr::normal(head, vec![Expr::string("b"), Expr::from(1i64)])
+    );
+}
+
+#[test]
+fn test_put_expr_macro() {
+    let mut link = Link::new_loopback().unwrap();
+
+    let name = String::from("rust");
+    let inner = Expr::normal(Symbol::new("Global`g"), vec![]);
+
+    wstp::put_expr!(
+        &mut link,
+        Global::f[-1, 2.5, "s", true, Global::x, Global::h[], {name}, {inner}, {u64::MAX},]
+    )
+    .unwrap();
+
+    assert_eq!(
+        link.get_expr().unwrap().to_string(),
+        "Global`f[-1, 2.5, \"s\", System`True, Global`x, Global`h[], \"rust\", \
+         Global`g[], 18446744073709551615]"
+    );
+
+    // Atoms can be put at the top level.
+    wstp::put_expr!(&mut link, 5).unwrap();
+    assert_eq!(link.get_i64().unwrap(), 5);
+}
+
+#[test]
+fn test_get_expr_view() {
+    let mut link = Link::new_loopback().unwrap();
+
+    // Put Global`f[{{1., 2.}, {3., 4.}}, {1, "two"}, -5]
+    link.put_function("Global`f", 3).unwrap();
+    link.put_f64_array(&[1.0, 2.0, 3.0, 4.0], &[2, 2]).unwrap();
+    link.put_function("System`List", 2).unwrap();
+    link.put_i64(1).unwrap();
+    link.put_str("two").unwrap();
+    link.put_i64(-5).unwrap();
+
+    let view = link.get_expr_view().unwrap();
+
+    let (head, args) = match &view {
+        ExprView::Normal { head, args } => (head, args),
+        other => panic!("unexpected view: {:?}", other),
+    };
+
+    assert!(matches!(&**head, ExprView::Symbol(s) if s.as_str() == "Global`f"));
+
+    match &args[..] {
+        [ExprView::Real64Array(array), ExprView::Normal { args: list, .. }, ExprView::Integer(-5)] =>
+        {
+            assert_eq!(array.dimensions(), &[2, 2]);
+            assert_eq!(array.data(), &[1.0, 2.0, 3.0, 4.0]);
+
+            // A mixed list is not read as an array.
+            assert!(matches!(
+                &list[..],
+                [ExprView::Integer(1), ExprView::String(s)] if s.as_str() == "two"
+            ));
+        },
+        other => panic!("unexpected arguments: {:?}", other),
+    }
+
+    assert_eq!(
+        view.to_owned().to_string(),
+        "Global`f[{{1., 2.}, {3., 4.}}, {1, \"two\"}, -5]"
+    );
+
+    drop(view);
+
+    // Symbols without a context are rejected.
+    link.put_symbol("x").unwrap();
+    assert!(link.get_expr_view().is_err());
+}
+
+#[test]
+fn test_link_reader_peek_token() {
+    let mut link = Link::new_loopback().unwrap();
+
+    // Put {"a", Global`b, 3}
+    link.put_function("System`List", 3).unwrap();
+    link.put_str("a").unwrap();
+    link.put_symbol("Global`b").unwrap();
+    link.put_i64(3).unwrap();
+
+    let mut reader = LinkReader::new(&mut link);
+
+    assert_eq!(
+        reader.peek_expr().unwrap().to_string(),
+        "System`List[\"a\", Global`b, 3]"
+    );
+    assert!(matches!(
+        reader.peek_token().unwrap(),
+        Token::Function { length: 3 }
+    ));
+    assert!(matches!(
+        reader.next_token().unwrap(),
+        Token::Function { length: 3 }
+    ));
+
+    reader.skip_expr().unwrap();
+
+    // A peeked string remains valid after the link is reset.
+    let peeked = match reader.peek_token().unwrap() {
+        Token::String(string) => string.as_str().to_owned(),
+        _ => panic!("expected a string"),
+    };
+    assert_eq!(peeked, "a");
+    assert_eq!(reader.link().get_string().unwrap(), "a");
+
+    assert_eq!(reader.peek_type().unwrap(), TokenType::Symbol);
+    assert!(
+        matches!(reader.peek_token().unwrap(), Token::Symbol(s) if s.as_str() == "Global`b")
+    );
+    reader.skip_expr().unwrap();
+
+    assert!(matches!(reader.peek_token().unwrap(), Token::Integer(3)));
+    assert_eq!(reader.into_inner().get_i64().unwrap(), 3);
+}
+
+#[test]
+fn test_get_str_cow() {
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_str("αβγ").unwrap();
+
+    let owned: String = link.get_str_cow(|string| string.into_owned()).unwrap();
+    assert_eq!(owned, "αβγ");
+}
+
+#[test]
+fn test_symbol_str() {
+    let symbol = SymbolStr::try_new("A`B`c").unwrap();
+
+    assert!(symbol.is_absolute());
+    assert_eq!(symbol.context(), Some("A`B`"));
+    assert_eq!(symbol.name(), "c");
+    assert_eq!(symbol.with_context("D"), "D`c");
+    assert_eq!(symbol.resolve("Global`"), "A`B`c");
+
+    let relative = SymbolStr::try_new("`c").unwrap();
+
+    assert!(!relative.is_absolute());
+    assert_eq!(relative.context(), Some("`"));
+    assert_eq!(relative.resolve("Global`"), "Global`c");
+
+    for invalid in ["", "`", "A`", "A``c"] {
+        assert_eq!(SymbolStr::try_new(invalid), None);
+    }
+
+    // Resolved names can be put on a link directly.
+    let mut link = Link::new_loopback().unwrap();
+    link.put_symbol(&SymbolStr::try_new("x").unwrap().resolve("Global`"))
+        .unwrap();
+    assert_eq!(link.get_symbol_ref().unwrap().as_str(), "Global`x");
+}
+
+#[test]
+fn test_try_get() {
+    let mut link = Link::new_loopback().unwrap();
+
+    // Put Failure["Tag", <||>], then 1
+    link.put_function("System`Failure", 2).unwrap();
+    link.put_str("Tag").unwrap();
+    link.put_function("System`Association", 0).unwrap();
+    link.put_i64(1).unwrap();
+
+    // A failed attempt leaves the expression unconsumed.
+    assert!(link.try_get(|link| link.test_head("System`List")).is_err());
+
+    let tag = link
+        .try_get(|link| {
+            let _ = link.test_head("System`Failure")?;
+            let tag = link.get_string()?;
+            link.skip_expr()?;
+            Ok(tag)
+        })
+        .unwrap();
+    assert_eq!(tag, "Tag");
+
+    assert_eq!(link.get_i64().unwrap(), 1);
+}
+
+#[test]
+fn test_get_real_value() {
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_i64(2).unwrap();
+    link.put_symbol("System`Infinity").unwrap();
+    link.put_function("System`DirectedInfinity", 0).unwrap();
+    link.put_function("System`Missing", 1).unwrap();
+    link.put_str("NotAvailable").unwrap();
+    link.put_function("Global`f", 1).unwrap();
+    link.put_i64(1).unwrap();
+    link.put_i64(3).unwrap();
+
+    assert_eq!(link.get_real_value().unwrap(), RealValue::Finite(2.0));
+    assert_eq!(
+        link.get_real_value().unwrap(),
+        RealValue::Infinity(InfinityDirection::Positive)
+    );
+    assert_eq!(
+        link.get_real_value().unwrap(),
+        RealValue::Infinity(InfinityDirection::Complex)
+    );
+    assert_eq!(link.get_real_value().unwrap(), RealValue::Missing);
+
+    // Unsupported expressions are consumed.
+    assert!(link.get_real_value().is_err());
+    assert_eq!(link.get_real_value().unwrap().to_f64(), 3.0);
+}
+
+#[test]
+fn test_nan_policy() {
+    let mut link = Link::new_loopback().unwrap();
+
+    assert_eq!(link.nan_policy(), NanPolicy::Forward);
+
+    link.set_nan_policy(NanPolicy::Error);
+    assert_eq!(link.nan_policy(), NanPolicy::Error);
+    assert!(link.put_f64(f64::NAN).is_err());
+    assert!(link.put_f32(f32::INFINITY).is_err());
+
+    // Finite values are unaffected by the policy.
+    link.put_f64(1.5).unwrap();
+    assert_eq!(link.get_f64().unwrap(), 1.5);
+
+    link.set_nan_policy(NanPolicy::AsIndeterminate);
+    link.put_f32(f32::NAN).unwrap();
+    assert_eq!(
+        link.get_symbol_ref().unwrap().as_str(),
+        "System`Indeterminate"
+    );
+
+    link.set_nan_policy(NanPolicy::AsDirectedInfinity);
+    link.put_f64(f64::INFINITY).unwrap();
+    assert_eq!(
+        link.get_expr().unwrap().to_string(),
+        "System`DirectedInfinity[1]"
+    );
+
+    // The policy is not shared with other links.
+    let other = Link::new_loopback().unwrap();
+    assert_eq!(other.nan_policy(), NanPolicy::Forward);
+}
+
+#[test]
+fn test_nan_policy_arrays() {
+    let list = |elements: Vec<Expr>| Expr::normal(Symbol::new("System`List"), elements);
+    let indeterminate = || Expr::symbol(Symbol::new("System`Indeterminate"));
+
+    let mut link = Link::new_loopback().unwrap();
+
+    // Rejected arrays are checked before anything is put, so the link is not poisoned.
+    link.set_nan_policy(NanPolicy::Error);
+    assert!(link.put_f64_array(&[1.0, f64::NAN], &[2]).is_err());
+    assert!(link.put_f32_array(&[f32::NEG_INFINITY], &[1]).is_err());
+    assert!(link
+        .put_numeric_array::<f64>(&[f64::INFINITY], &[1])
+        .is_err());
+    assert!(link
+        .put_numeric_array::<[f32; 2]>(&[[0.0, f32::NAN]], &[1])
+        .is_err());
+    assert!(link.put_sparse_array(2, 2, &[[0, 0]], &[f64::NAN]).is_err());
+    assert!(!link.is_poisoned());
+
+    link.put_f64_array(&[1.0, 2.0], &[2]).unwrap();
+    assert_eq!(link.get_f64_array().unwrap().data(), &[1.0, 2.0]);
+
+    // Arrays containing replaced values are put element by element.
+    link.set_nan_policy(NanPolicy::AsIndeterminate);
+    link.put_f64_array(&[1.5, f64::NAN, 2.5, 3.5], &[2, 2])
+        .unwrap();
+    assert_eq!(
+        link.get_expr().unwrap(),
+        list(vec![
+            list(vec![Expr::real(1.5), indeterminate()]),
+            list(vec![Expr::real(2.5), Expr::real(3.5)]),
+        ])
+    );
+
+    // Infinite values are not replaced by AsIndeterminate, so the array is packed.
+    link.put_f64_array(&[f64::INFINITY], &[1]).unwrap();
+    assert_eq!(link.get_f64_array().unwrap().data(), &[f64::INFINITY]);
+
+    link.set_nan_policy(NanPolicy::AsDirectedInfinity);
+    link.put_f32_array(&[0.5, f32::NEG_INFINITY], &[2]).unwrap();
+    assert_eq!(
+        link.get_expr().unwrap(),
+        list(vec![
+            Expr::real(0.5),
+            Expr::normal(Symbol::new("System`DirectedInfinity"), vec![Expr::from(-1)]),
+        ])
+    );
+}
+
+#[test]
+fn test_coercing_numeric_getters() {
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_i64(-3).unwrap();
+    link.put_f64(-3.0).unwrap();
+    link.put_i64(7).unwrap();
+    link.put_f64(1e300).unwrap();
+    link.put_f64(f64::NAN).unwrap();
+
+    assert_eq!(link.get_f64_coercing().unwrap(), -3.0);
+    assert_eq!(link.get_i64_coercing().unwrap(), -3);
+    assert_eq!(link.get_i64_coercing().unwrap(), 7);
+
+    // Out of range and non-integral values are rejected.
+    assert!(link.get_i64_coercing().is_err());
+    assert!(link.get_i64_coercing().is_err());
+}
+
+#[test]
+fn test_link_limits() {
+    let mut link = Link::new_loopback().unwrap();
+
+    assert_eq!(link.limits(), LinkLimits::default());
+
+    let limits = LinkLimits {
+        max_string_bytes: Some(3),
+        max_array_elems: Some(4),
+        max_arg_count: Some(2),
+    };
+    link.set_limits(limits);
+    assert_eq!(link.limits(), limits);
+
+    link.put_i64_array(&[1, 2, 3, 4], &[2, 2]).unwrap();
+    link.put_i64_array(&[1, 2, 3, 4, 5], &[5]).unwrap();
+    assert_eq!(link.get_i64_array().unwrap().length(), 4);
+    assert!(link.get_i64_array().is_err());
+
+    // Data that exceeds a limit is rejected before it is read, and left on the link.
+    let mut link = Link::new_loopback().unwrap();
+    link.set_limits(limits);
+
+    link.put_str("abcd").unwrap();
+    assert!(link.get_string().is_err());
+    link.set_limits(LinkLimits::default());
+    assert_eq!(link.get_string().unwrap(), "abcd");
+
+    let mut link = Link::new_loopback().unwrap();
+    link.set_limits(limits);
+
+    link.put_symbol("Global`x").unwrap();
+    assert!(link.get_symbol_ref().is_err());
+
+    let mut link = Link::new_loopback().unwrap();
+    link.set_limits(limits);
+
+    // Put Global`f[1, 2, 3]
+    link.put_function("Global`f", 3).unwrap();
+    for i in 1..=3 {
+        link.put_i64(i).unwrap();
+    }
+    assert!(link.get_expr().is_err());
+}
+
+#[test]
+fn test_set_name() {
+    let mut a = Link::new_loopback().unwrap();
+    let b = Link::new_loopback().unwrap();
+
+    a.set_name("first").unwrap();
+    assert_eq!(a.link_name(), "first");
+    assert_ne!(b.link_name(), "first");
+
+    a.set_name("second").unwrap();
+    assert_eq!(a.link_name(), "second");
+
+    assert!(a.set_name("nul\0byte").is_err());
+}
+
+#[test]
+fn test_put_wxf() {
+    let mut link = Link::new_loopback().unwrap();
+
+    // <|"a" -> 1.5, "b" :> Global`x|>
+    link.put_wxf(b"8:A\x02-S\x01ar\x00\x00\x00\x00\x00\x00\xf8?:S\x01bs\x08Global`x")
+        .unwrap();
+
+    assert_eq!(
+        link.get_expr().unwrap(),
+        Expr::normal(
+            Symbol::new("System`Association"),
+            vec![
+                Expr::rule(Expr::string("a"), Expr::real(1.5)),
+                Expr::rule_delayed(
+                    Expr::string("b"),
+                    Expr::symbol(Symbol::new("Global`x"))
+                ),
+            ]
+        )
+    );
+
+    // Packed array {{1, 2}, {3, 4}} of Integer8 elements.
+    link.put_wxf(b"8:\xc1\x00\x02\x02\x02\x01\x02\x03\x04")
+        .unwrap();
+
+    {
+        let array = link.get_i64_array().unwrap();
+        assert_eq!(array.data(), &[1, 2, 3, 4]);
+        assert_eq!(array.dimensions(), &[2, 2]);
+    }
+
+    // Big integer 2^100.
+    link.put_wxf(b"8:I\x1f1267650600228229401496703205376")
+        .unwrap();
+    assert_eq!(
+        link.get_number_string().unwrap(),
+        "1267650600228229401496703205376"
+    );
+
+    // Invalid input is rejected.
+    assert!(link.put_wxf(b"").is_err());
+    assert!(link.put_wxf(b"8:").is_err());
+    assert!(link.put_wxf(b"8:S\xff").is_err());
+    assert!(link.put_wxf(b"8:\xc1\x03\x01\xff\xff\xff\xff\x0f").is_err());
+}
+
+#[test]
+fn test_flaky_link() {
+    let expr = Expr::normal(Symbol::new("Global`f"), vec![Expr::from(1), Expr::from(2)]);
+
+    // No faults are injected by default.
+    let mut link = FlakyLink::new(Link::new_loopback().unwrap(), FaultConfig::default());
+    link.put_expr(&expr).unwrap();
+    assert_eq!(link.get_expr().unwrap(), expr);
+
+    // Injected errors don't touch the link.
+    let mut link = FlakyLink::new(
+        Link::new_loopback().unwrap(),
+        FaultConfig {
+            error_probability: 1.0,
+            ..FaultConfig::default()
+        },
+    );
+    assert!(link.put_expr(&expr).is_err());
+    assert!(!link.is_disconnected());
+    assert!(link.link().is_open());
+
+    // The same seed injects the same faults.
+    let faults = |seed| {
+        let mut link = FlakyLink::new(
+            Link::new_loopback().unwrap(),
+            FaultConfig {
+                error_probability: 0.5,
+                seed,
+                ..FaultConfig::default()
+            },
+        );
+
+        (0..32)
+            .map(|_| link.put_expr(&Expr::from(0)).is_err())
+            .collect::<Vec<bool>>()
+    };
+    assert_eq!(faults(42), faults(42));
+    assert!(faults(42).contains(&true));
+    assert!(faults(42).contains(&false));
+
+    // A disconnect happens mid-expression, and is permanent.
+    let mut link = FlakyLink::new(
+        Link::new_loopback().unwrap(),
+        FaultConfig {
+            disconnect_probability: 1.0,
+            ..FaultConfig::default()
+        },
+    );
+    let err = link.put_expr(&expr).unwrap_err();
+    assert!(err.is_peer_closed());
+    assert!(link.is_disconnected());
+    assert!(!link.link().is_open());
+    assert!(link.flush().unwrap_err().is_peer_closed());
+
+    let mut link = link.into_inner();
+    assert_eq!(link.test_head("Global`f").unwrap(), 2);
+    assert_eq!(link.get_i64().unwrap(), 1);
+}
+
+#[test]
+fn test_throttled_link() {
+    let data = "x".repeat(2000);
+
+    let mut link =
+        ThrottledLink::new(Link::new_loopback().unwrap(), 1_000_000_000, 1 << 20);
+
+    // Data within the burst allowance is not delayed.
+    let start = std::time::Instant::now();
+    link.with(|link| link.put_str(&data)).unwrap();
+    link.put_expr(&Expr::from(5)).unwrap();
+    assert!(start.elapsed() < std::time::Duration::from_secs(1));
+
+    let mut link = link.into_inner();
+    assert_eq!(link.get_string().unwrap(), data);
+    assert_eq!(link.get_i64().unwrap(), 5);
+    assert!(link.bytes_to_put().is_ok());
+}
+
+#[cfg(feature = "fuzzing")]
+#[test]
+fn test_from_wxf_for_fuzzing() {
+    let mut link = Link::from_wxf_for_fuzzing(b"8:f\x01s\x04ListS\x01a").unwrap();
+    assert_eq!(
+        link.get_expr().unwrap(),
+        Expr::list(vec![Expr::string("a")])
+    );
+
+    // Truncated and deeply nested input is rejected without panicking.
+    assert!(Link::from_wxf_for_fuzzing(b"8:f\x01s\x04List").is_err());
+    assert!(Link::from_wxf_for_fuzzing(&[b'f', 1].repeat(10_000)).is_err());
+
+    let mut nested = b"8:".to_vec();
+    nested.extend([b'f', 0].repeat(10_000));
+    assert!(Link::from_wxf_for_fuzzing(&nested).is_err());
+}
+
+#[test]
+fn test_get_list_into() {
+    let mut link = Link::new_loopback().unwrap();
+
+    let mut reals = [0.0; 4];
+    let mut ints = [0; 4];
+
+    // Reused across reads without reallocating.
+    for round in 0..3 {
+        let round = round as f64;
+        link.put_f64_array(&[round, round + 1.0], &[2]).unwrap();
+        link.put_i64_array(&[1, 2, 3], &[3]).unwrap();
+
+        assert_eq!(link.get_f64_into(&mut reals).unwrap(), 2);
+        assert_eq!(&reals[..2], &[round, round + 1.0]);
+
+        assert_eq!(link.get_i64_into(&mut ints).unwrap(), 3);
+        assert_eq!(&ints[..3], &[1, 2, 3]);
+    }
+
+    // Elements put individually are read the same way.
+    link.put_function("System`List", 2).unwrap();
+    link.put_f32(1.5).unwrap();
+    link.put_f32(2.5).unwrap();
+
+    let mut floats = [0.0f32; 2];
+    assert_eq!(link.get_f32_into(&mut floats).unwrap(), 2);
+    assert_eq!(floats, [1.5, 2.5]);
+
+    // Lists that don't fit are rejected.
+    link.put_i32_array(&[1, 2, 3], &[3]).unwrap();
+    assert!(link.get_i32_into(&mut [0; 2]).is_err());
+}
+
+#[test]
+fn test_get_array_fixed() {
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_i64_array(&[1, 2, 3], &[3]).unwrap();
+    assert_eq!(link.get_array_fixed::<i64, 3>().unwrap(), [1, 2, 3]);
+
+    let data: Vec<f64> = (0..16).map(f64::from).collect();
+    link.put_f64_array(&data, &[4, 4]).unwrap();
+    let matrix: [[f64; 4]; 4] = link.get_matrix_fixed().unwrap();
+    assert_eq!(matrix[1], [4.0, 5.0, 6.0, 7.0]);
+    assert_eq!(matrix[3][3], 15.0);
+
+    // The dimensions are validated.
+    link.put_f64_array(&[1.0, 2.0], &[2]).unwrap();
+    assert!(link.get_array_fixed::<f64, 3>().is_err());
+
+    let mut link = Link::new_loopback().unwrap();
+    link.put_f64_array(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], &[2, 3])
+        .unwrap();
+    assert!(link.get_matrix_fixed::<f64, 3, 2>().is_err());
+}
+
+#[test]
+fn test_put_array_fixed() {
+    let mut link = Link::new_loopback().unwrap();
+
+    let transform: [[f64; 4]; 4] = [
+        [1.0, 0.0, 0.0, 5.0],
+        [0.0, 1.0, 0.0, 6.0],
+        [0.0, 0.0, 1.0, 7.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ];
+
+    link.put_array_fixed(&transform).unwrap();
+    assert_eq!(link.get_matrix_fixed::<f64, 4, 4>().unwrap(), transform);
+
+    link.put_slice_as_vector(&[1i32, 2, 3]).unwrap();
+    {
+        let array = link.get_i32_array().unwrap();
+        assert_eq!(array.dimensions(), &[3]);
+        assert_eq!(array.data(), &[1, 2, 3]);
+    }
+
+    link.put_array_fixed(&[[1u8, 2], [3, 4], [5, 6]]).unwrap();
+    {
+        let array = link.get_u8_array().unwrap();
+        assert_eq!(array.dimensions(), &[3, 2]);
+        assert_eq!(array.data(), &[1, 2, 3, 4, 5, 6]);
+    }
+}
+
+#[test]
+fn test_put_matrix_from_rows() {
+    let mut link = Link::new_loopback().unwrap();
+
+    let a = [1.0, 2.0, 3.0];
+    let b = [4.0, 5.0, 6.0];
+
+    link.put_matrix_from_rows([&a[..], &b[..]], 3).unwrap();
+    assert_eq!(
+        link.get_matrix_fixed::<f64, 2, 3>().unwrap(),
+        [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]
+    );
+
+    // An empty matrix.
+    link.put_matrix_from_rows(std::iter::empty(), 3).unwrap();
+    assert_eq!(link.test_head("System`List").unwrap(), 0);
+
+    // Ragged rows are rejected.
+    let mut link = Link::new_loopback().unwrap();
+    assert!(link.put_matrix_from_rows([&a[..], &b[..2]], 3).is_err());
+    assert!(link.is_poisoned());
+}
+
+#[test]
+fn test_jagged() {
+    let mut link = Link::new_loopback().unwrap();
+
+    let rows = vec![vec![1, 2, 3], vec![], vec![-4, i64::MAX]];
+
+    link.put_jagged(&rows).unwrap();
+    assert_eq!(link.get_jagged().unwrap(), rows);
+
+    link.put_jagged(&[]).unwrap();
+    assert_eq!(link.get_jagged().unwrap(), Vec::<Vec<i64>>::new());
+
+    // A rectangular array can also be read as jagged rows.
+    link.put_i64_array(&[1, 2, 3, 4], &[2, 2]).unwrap();
+    assert_eq!(link.get_jagged().unwrap(), vec![vec![1, 2], vec![3, 4]]);
+}
+
+#[test]
+fn test_interval() {
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_interval(-1.0, f64::INFINITY).unwrap();
+    assert_eq!(
+        link.get_expr().unwrap(),
+        Expr::normal(
+            Symbol::new("System`Interval"),
+            vec![Expr::list(vec![
+                Expr::real(-1.0),
+                Expr::normal(Symbol::new("System`DirectedInfinity"), vec![Expr::from(1)]),
+            ])]
+        )
+    );
+
+    // Integer endpoints are accepted.
+    link.put_expr(&Expr::normal(
+        Symbol::new("System`Interval"),
+        vec![Expr::list(vec![Expr::from(1), Expr::from(2)])],
+    ))
+    .unwrap();
+    assert_eq!(link.get_interval().unwrap(), (1.0, 2.0));
+
+    link.put_intervals(&[(0.0, 1.0), (2.0, 3.0)]).unwrap();
+    assert!(link.get_interval().is_err());
+
+    assert!(link.put_interval(2.0, 1.0).is_err());
+    assert!(link.put_interval(f64::NAN, 1.0).is_err());
+}
+
+#[test]
+fn test_put_datetime_list() {
+    use std::time::{Duration, SystemTime};
+
+    let mut link = Link::new_loopback().unwrap();
+
+    let times = [
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000),
+        SystemTime::UNIX_EPOCH - Duration::from_millis(500),
+    ];
+
+    link.put_datetime_list(&times, -5.0).unwrap();
+
+    assert_eq!(link.test_head("System`FromUnixTime").unwrap(), 2);
+    assert_eq!(
+        link.get_f64_array().unwrap().data(),
+        &[1_600_000_000.0, -0.5]
+    );
+    assert_eq!(
+        link.get_expr().unwrap(),
+        Expr::rule(Symbol::new("System`TimeZone"), Expr::real(-5.0))
+    );
+}
+
+#[test]
+fn test_copy_expr_filtered() {
+    let mut src = Link::new_loopback().unwrap();
+    let mut dst = Link::new_loopback().unwrap();
+
+    // Global`f[Global`x, "secret", {1, 2}, 3.5]
+    let expr = Expr::normal(
+        Symbol::new("Global`f"),
+        vec![
+            Expr::symbol(Symbol::new("Global`x")),
+            Expr::string("secret"),
+            Expr::list(vec![Expr::from(1), Expr::from(2)]),
+            Expr::real(3.5),
+        ],
+    );
+
+    src.put_expr(&expr).unwrap();
+
+    let mut depths = Vec::new();
+
+    copy_expr_filtered(&mut src, &mut dst, &mut |token: &Token, depth| {
+        depths.push(depth);
+
+        match token {
+            Token::Symbol(s) if s.as_str() == "Global`x" => {
+                FilterAction::Rename("Private`x".to_owned())
+            },
+            Token::String(_) => FilterAction::Drop,
+            Token::Function { length: 2 } => FilterAction::Wrap("Global`g".to_owned()),
+            Token::Real(_) => FilterAction::Replace(Expr::from(0)),
+            _ => FilterAction::Keep,
+        }
+    })
+    .unwrap();
+
+    assert_eq!(
+        dst.get_expr().unwrap(),
+        Expr::normal(
+            Symbol::new("Global`f"),
+            vec![
+                Expr::symbol(Symbol::new("Private`x")),
+                Expr::normal(Symbol::new("System`Sequence"), vec![]),
+                Expr::normal(
+                    Symbol::new("Global`g"),
+                    vec![Expr::list(vec![Expr::from(1), Expr::from(2)])]
+                ),
+                Expr::from(0),
+            ]
+        )
+    );
+
+    assert_eq!(depths, vec![0, 1, 1, 1, 1, 2, 2, 2, 1]);
+
+    // Dropping a function skips all of its elements on the source link.
+    src.put_expr(&expr).unwrap();
+    src.put_i64(7).unwrap();
+
+    copy_expr_filtered(&mut src, &mut dst, &mut |_: &Token, _| FilterAction::Drop)
+        .unwrap();
+
+    assert_eq!(dst.get_expr().unwrap().to_string(), "System`Sequence[]");
+    assert_eq!(src.get_i64().unwrap(), 7);
+}
+
+#[test]
+fn test_link_status() {
+    let mut link = Link::new_loopback().unwrap();
+
+    link.set_name("status-test").unwrap();
+    link.put_i64(1).unwrap();
+
+    let status = link.status();
+
+    assert_eq!(status.name, "status-test");
+    assert!(status.activated);
+    assert!(status.open);
+    assert!(status.error.is_none());
+
+    assert!(status
+        .to_string()
+        .starts_with("\"status-test\" (Loopback): activated, open"));
+    assert!(format!("{:?}", link).contains("name: \"status-test\""));
+
+    // Reading the wrong type of token sets an error on the link.
+    assert!(link.get_string().is_err());
+    assert!(link.status().error.is_some());
+}
+
+#[test]
+fn test_loopback_with() {
+    let exprs = vec![
+        Expr::from(1),
+        Expr::list(vec![Expr::string("a"), Expr::real(2.5)]),
+    ];
+
+    let mut link = Link::loopback_with(&exprs).unwrap();
+
+    assert_eq!(link.get_expr().unwrap(), exprs[0]);
+    assert_eq!(link.get_expr().unwrap(), exprs[1]);
+
+    // BinarySerialize[{1, "a"}]
+    let wxf = [56, 58, 102, 2, 115, 4, 76, 105, 115, 116, 67, 1, 83, 1, 97];
+
+    let mut link = Link::loopback_with_wxf(&wxf).unwrap();
+    assert_eq!(
+        link.get_expr().unwrap(),
+        Expr::list(vec![Expr::from(1), Expr::string("a")])
+    );
+
+    assert!(Link::loopback_with_wxf(b"not wxf").is_err());
+}
+
+#[test]
+fn test_expect_getters() {
+    let mut link = Link::new_loopback().unwrap();
+
+    // System`List[Global`x, 5]
+    link.put_function("System`List", 2).unwrap();
+    link.put_symbol("Global`x").unwrap();
+    link.put_i64(5).unwrap();
+
+    assert_eq!(link.expect_function("List").unwrap(), 2);
+    assert!(link.expect_symbol("x").is_err());
+    assert_eq!(link.get_i64().unwrap(), 5);
+
+    link.put_function("System`List", 1).unwrap();
+    link.put_symbol("Global`x").unwrap();
+
+    link.expect_head_arity("System`List", 1).unwrap();
+    link.expect_symbol("Global`x").unwrap();
+
+    // Mismatched token types are described in the error.
+    link.put_i64(5).unwrap();
+    assert_eq!(
+        link.expect_function("List").unwrap_err().to_string(),
+        "WSTP error: expected function with head List, got Integer token"
+    );
+    assert_eq!(link.get_i64().unwrap(), 5);
+
+    link.put_function("System`Plus", 2).unwrap();
+    assert_eq!(
+        link.expect_head_arity("Plus", 3).unwrap_err().to_string(),
+        "WSTP error: expected function Plus to have 3 elements, got 2"
+    );
+}
+
+#[test]
+fn test_transaction() {
+    let mut link = Link::new_loopback().unwrap();
+
+    let mut transaction = link.transaction().unwrap();
+    transaction.put(|link| link.put_i64(1)).unwrap();
+    transaction.put(|link| link.put_str("two")).unwrap();
+    transaction.commit().unwrap();
+
+    assert_eq!(link.get_i64().unwrap(), 1);
+    assert_eq!(link.get_string().unwrap(), "two");
+
+    // A failed put prevents the transaction from being committed.
+    let mut transaction = link.transaction().unwrap();
+    transaction
+        .put(|link| link.put_function("System`List", 2))
+        .unwrap();
+    assert!(transaction
+        .put(|link| {
+            link.put_i64(1)?;
+            link.put_interval(1.0, 0.0)
+        })
+        .is_err());
+    assert!(transaction.commit().is_err());
+
+    // Empty and rolled back transactions leave the link unchanged.
+    link.transaction().unwrap().commit().unwrap();
+
+    let mut transaction = link.transaction().unwrap();
+    transaction.put(|link| link.put_i64(3)).unwrap();
+    transaction.rollback();
+
+    link.put_i64(4).unwrap();
+    assert_eq!(link.get_i64().unwrap(), 4);
+}
+
+#[test]
+fn test_get_rule() {
+    let mut link = Link::new_loopback().unwrap();
+
+    let key = Expr::symbol(Symbol::new("Global`x"));
+    let value = Expr::list(vec![Expr::from(1), Expr::from(2)]);
+
+    link.put_expr(&Expr::rule(key.clone(), value.clone()))
+        .unwrap();
+    assert_eq!(link.get_rule().unwrap(), (key.clone(), value.clone()));
+
+    link.put_expr(&Expr::rule_delayed(key.clone(), value.clone()))
+        .unwrap();
+    assert_eq!(link.get_rule_delayed().unwrap(), (key.clone(), value));
+
+    link.put_expr(&Expr::rule("n", Expr::from(3))).unwrap();
+    assert_eq!(
+        link.get_rule_with(Link::get_string, Link::get_i64).unwrap(),
+        ("n".to_owned(), 3)
+    );
+
+    // The head is verified.
+    link.put_expr(&Expr::rule("n", Expr::from(3))).unwrap();
+    assert!(link.get_rule_delayed().is_err());
+}
+
+#[test]
+fn test_options_parser() {
+    let parser = OptionsParser::new()
+        .option("Method", Expr::string("Automatic"))
+        .option("Tolerance", Expr::real(1e-6))
+        .option("Verbose", Expr::symbol(Symbol::new("System`False")));
+
+    let options = vec![
+        Expr::rule(Symbol::new("System`Method"), Expr::string("Newton")),
+        Expr::list(vec![Expr::rule_delayed("Tolerance", Expr::from(1))]),
+        Expr::rule(Symbol::new("Global`Method"), Expr::string("Ignored")),
+    ];
+
+    let mut link = Link::loopback_with(&options).unwrap();
+
+    let parsed = parser.parse(&mut link, 3).unwrap();
+
+    assert_eq!(parsed.get_str("Method").unwrap(), "Newton");
+    assert_eq!(parsed.get_f64("Tolerance").unwrap(), 1.0);
+    assert!(!parsed.get_bool("Verbose").unwrap());
+    assert!(parsed.is_explicit("Method"));
+    assert!(!parsed.is_explicit("Verbose"));
+    assert!(parsed.get_i64("Method").is_err());
+
+    // Unknown options.
+    let unknown = Expr::rule("Color", Expr::string("Red"));
+
+    let mut link = Link::loopback_with([&unknown]).unwrap();
+    assert!(parser.parse(&mut link, 1).is_err());
+
+    let mut link = Link::loopback_with([&unknown]).unwrap();
+    let parsed = parser
+        .clone()
+        .unknown_options(UnknownOptionPolicy::Collect)
+        .parse(&mut link, 1)
+        .unwrap();
+    assert_eq!(
+        parsed.unknown(),
+        &[("Color".to_owned(), Expr::string("Red"))]
+    );
+
+    // Positional arguments are not options.
+    let mut link = Link::loopback_with(&[Expr::from(5)]).unwrap();
+    assert!(parser.parse(&mut link, 1).is_err());
+}
+
+#[test]
+fn test_get_expr_input_form() {
+    fn input_form(expr: Expr) -> String {
+        Link::loopback_with([&expr])
+            .unwrap()
+            .get_expr_input_form()
+            .unwrap()
+    }
+
+    assert_eq!(input_form(Expr::from(-5)), "-5");
+    assert_eq!(input_form(Expr::real(2.0)), "2.");
+    assert_eq!(input_form(Expr::real(-0.25)), "-0.25");
+    assert_eq!(input_form(Expr::real(1.5e-7)), "1.5*^-7");
+    assert_eq!(input_form(Expr::real(1e300)), "1.*^300");
+    assert_eq!(
+        input_form(Expr::string("say \"hi\"\n")),
+        r#""say \"hi\"\n""#
+    );
+    assert_eq!(input_form(Expr::symbol(Symbol::new("Global`x"))), "x");
+    assert_eq!(
+        input_form(Expr::symbol(Symbol::new("MyPackage`x"))),
+        "MyPackage`x"
+    );
+    assert_eq!(input_form(Expr::list(vec![])), "{}");
+    assert_eq!(
+        input_form(Expr::rule(
+            Expr::rule("a", Expr::from(1)),
+            Expr::rule_delayed("b", Expr::from(2))
+        )),
+        r#"("a" -> 1) -> "b" :> 2"#
+    );
+    assert_eq!(
+        input_form(Expr::normal(
+            Symbol::new("System`Association"),
+            vec![Expr::rule("a", Expr::from(1))]
+        )),
+        r#"<|"a" -> 1|>"#
+    );
+}
+
+#[test]
+fn test_put_expr_ref() {
+    let expr = Expr::normal(
+        Symbol::new("Global`f"),
+        vec![
+            Expr::string("text"),
+            Expr::list(vec![Expr::real(1.5), Expr::real(2.5)]),
+            Expr::list(vec![Expr::from(1), Expr::string("mixed")]),
+        ],
+    );
+
+    let mut source = Link::loopback_with([&expr]).unwrap();
+    let mut dest = Link::new_loopback().unwrap();
+
+    {
+        let view = source.get_expr_view().unwrap();
+        dest.put_expr_ref(&view).unwrap();
+    }
+
+    dest.put_expr_ref(&expr).unwrap();
+    dest.put_expr_ref(&&expr).unwrap();
+
+    assert_eq!(dest.get_expr().unwrap(), expr);
+    assert_eq!(dest.get_expr().unwrap(), expr);
+    assert_eq!(dest.get_expr().unwrap(), expr);
+}
+
+#[test]
+fn test_default_context() {
+    let mut link = Link::new_loopback().unwrap();
+
+    assert_eq!(link.default_context(), None);
+
+    link.set_default_context("MyApp");
+    assert_eq!(link.default_context().as_deref(), Some("MyApp"));
+
+    link.put_symbol("x").unwrap();
+    link.put_symbol("`Private`y").unwrap();
+    link.put_symbol("Global`z").unwrap();
+
+    assert_eq!(link.get_symbol_ref().unwrap().as_str(), "MyApp`x");
+    assert_eq!(link.get_symbol_ref().unwrap().as_str(), "MyApp`Private`y");
+    assert_eq!(link.get_symbol_resolved().unwrap(), "Global`z");
+
+    // Expressions always contain absolute symbols, and are unaffected.
+    let expr = Expr::symbol(Symbol::new("Global`w"));
+    link.put_expr(&expr).unwrap();
+    assert_eq!(link.get_expr().unwrap(), expr);
+
+    link.clear_default_context();
+    link.put_symbol("x").unwrap();
+    assert_eq!(link.get_symbol_resolved().unwrap(), "x");
+}
+
+#[test]
+fn test_progress() {
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_progress(0.0, "Starting").unwrap();
+    link.put_progress(1.0, "Done").unwrap();
+    assert!(link.put_progress(1.5, "Too far").is_err());
+
+    let first = link.get_expr().unwrap();
+    assert_eq!(
+        Progress::from_expr(&first),
+        Some(Progress::new(0.0, "Starting"))
+    );
+    assert_eq!(Progress::new(0.0, "Starting").to_expr(), first);
+
+    assert_eq!(link.get_progress().unwrap(), Progress::new(1.0, "Done"));
+
+    assert_eq!(Progress::from_expr(&Expr::from(1)), None);
+}
+
+#[test]
+fn test_error_recovery() {
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_str("text").unwrap();
+
+    let err = link.get_i64().unwrap_err();
+    assert!(err.is_recoverable());
+    assert_eq!(err.recovery(), ErrorRecovery::ClearAndRetry);
+
+    link.clear_error();
+    assert_eq!(link.get_string().unwrap(), "text");
+
+    // Errors without a WSTP error code don't affect the link.
+    let err = link.put_interval(2.0, 1.0).unwrap_err();
+    assert_eq!(err.code(), None);
+    assert!(err.is_recoverable());
+}
+
+#[test]
+fn test_get_number_digits() {
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_real_number_str("-0.00125``10.5").unwrap();
+    link.put_real_number_str("1.5`20.*^10").unwrap();
+    link.put_i64(-120).unwrap();
+    link.put_str("not a number").unwrap();
+
+    let digits = link.get_number_digits().unwrap();
+    assert_eq!(
+        digits,
+        NumberDigits {
+            negative: true,
+            digits: "125".to_owned(),
+            exponent: -5,
+            precision: RealPrecision::Accuracy(10.5),
+        }
+    );
+    assert_eq!(digits.to_f64(), -0.00125);
+
+    assert_eq!(
+        link.get_number_digits().unwrap(),
+        NumberDigits {
+            negative: false,
+            digits: "15".to_owned(),
+            exponent: 9,
+            precision: RealPrecision::Precision(20.0),
+        }
+    );
+
+    assert_eq!(
+        link.get_number_digits().unwrap(),
+        NumberDigits {
+            negative: true,
+            digits: "120".to_owned(),
+            exponent: 0,
+            precision: RealPrecision::Exact,
+        }
+    );
+
+    assert!(link.get_number_digits().is_err());
+}
+
+#[test]
+fn test_real_value_specials() {
+    let mut link = Link::new_loopback().unwrap();
+
+    let values = [
+        RealValue::Finite(-2.5),
+        RealValue::Infinity(InfinityDirection::Positive),
+        RealValue::Infinity(InfinityDirection::Negative),
+        RealValue::Infinity(InfinityDirection::Complex),
+        RealValue::Indeterminate,
+        RealValue::Missing,
+        RealValue::Overflow,
+        RealValue::Underflow,
+    ];
+
+    for value in values {
+        link.put_real_value(value).unwrap();
+    }
+
+    for value in values {
+        let expr = link.get_expr().unwrap();
+
+        assert_eq!(expr, value.to_expr());
+        assert_eq!(RealValue::from_expr(&expr), Some(value));
+    }
+
+    assert_eq!(RealValue::Overflow.to_f64(), f64::INFINITY);
+    assert_eq!(RealValue::Underflow.to_f64(), 0.0);
+}
+
+#[test]
+fn test_symbols_constants() {
+    assert_eq!(symbols::LIST.as_str(), "System`List");
+    assert_eq!(symbols::FAILED.as_str(), "System`$Failed");
+    assert_eq!(symbols::RULE_DELAYED.context(), Some("System`"));
+    assert_eq!(symbols::RULE_DELAYED.name(), "RuleDelayed");
+
+    assert_eq!(
+        SymbolStr::try_new(symbols::ASSOCIATION.as_str()),
+        Some(symbols::ASSOCIATION)
+    );
+
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_function(symbols::LIST.as_str(), 1).unwrap();
+    link.put_symbol(symbols::TRUE.as_str()).unwrap();
+
+    assert_eq!(
+        link.get_expr().unwrap(),
+        Expr::normal(
+            Symbol::new("System`List"),
+            vec![Expr::symbol(Symbol::new("System`True"))]
+        )
+    );
+}
+
+#[test]
+fn test_get_all_exprs() {
+    let exprs = vec![
+        Expr::from(1),
+        Expr::normal(
+            Symbol::new("System`List"),
+            vec![Expr::from(2), Expr::from(3)],
+        ),
+        Expr::string("four"),
+    ];
+
+    let mut link = Link::loopback_with(&exprs).unwrap();
+
+    assert_eq!(link.get_all_exprs().unwrap(), exprs);
+    assert!(!link.is_ready());
+    assert_eq!(link.get_all_exprs().unwrap(), Vec::<Expr>::new());
+
+    // The link can be reused as a queue after being drained.
+    link.put_expr(&Expr::from(5)).unwrap();
+    assert_eq!(link.get_all_exprs().unwrap(), vec![Expr::from(5)]);
+}
+
+#[test]
+fn test_loopback_emptiness_and_count() {
+    let mut link = Link::new_loopback().unwrap();
+
+    assert!(link.is_loopback_empty());
+    assert_eq!(link.loopback_expr_count().unwrap(), 0);
+
+    link.put_i64(1).unwrap();
+    link.put_function("System`List", 1).unwrap();
+    link.put_str("two").unwrap();
+    link.put_symbol("System`Three").unwrap();
+
+    assert!(!link.is_loopback_empty());
+    assert_eq!(link.loopback_expr_count().unwrap(), 3);
+
+    // Counting does not change the contents of the link.
+    assert_eq!(link.get_i64().unwrap(), 1);
+    assert_eq!(link.loopback_expr_count().unwrap(), 2);
+    assert_eq!(
+        link.get_expr().unwrap(),
+        Expr::normal(Symbol::new("System`List"), vec![Expr::string("two")])
+    );
+    assert_eq!(link.get_symbol_ref().unwrap().as_str(), "System`Three");
+
+    assert!(link.is_loopback_empty());
+}
+
+#[test]
+fn test_clone_expr() {
+    let expr = Expr::normal(
+        Symbol::new("Global`f"),
+        vec![
+            Expr::from(1),
+            Expr::normal(Symbol::new("System`List"), vec![Expr::string("a")]),
+        ],
+    );
+
+    let mut link = Link::loopback_with(&[expr.clone(), Expr::from(2)]).unwrap();
+
+    assert_eq!(link.clone_expr().unwrap(), expr);
+    assert_eq!(link.clone_expr().unwrap(), expr);
+
+    assert_eq!(link.get_expr().unwrap(), expr);
+    assert_eq!(link.clone_expr().unwrap(), Expr::from(2));
+    assert_eq!(link.get_i64().unwrap(), 2);
+}
+
+#[test]
+fn test_poisoned_link() {
+    let mut link = Link::new_loopback().unwrap();
+    link.set_nan_policy(NanPolicy::Error);
+
+    // Successful put sequences don't poison the link.
+    link.put_guarded(|link| link.put_i64(1)).unwrap();
+    assert!(!link.is_poisoned());
+
+    // A put sequence that fails partway through an expression poisons the link.
+    let err = link
+        .put_guarded(|link| {
+            link.put_function("System`List", 2)?;
+            link.put_f64(1.0)?;
+            link.put_f64(f64::NAN)
+        })
+        .unwrap_err();
+    assert!(!err.is_poisoned());
+    assert!(link.is_poisoned());
+
+    let err = link.flush().unwrap_err();
+    assert!(err.is_poisoned());
+    assert_eq!(err.recovery(), ErrorRecovery::Reconnect);
+    assert!(link.end_packet().unwrap_err().is_poisoned());
+    assert!(link.put_expr(&Expr::from(2)).unwrap_err().is_poisoned());
+    assert!(link.put_guarded(|_| Ok(())).unwrap_err().is_poisoned());
+
+    link.repair();
+    assert!(!link.is_poisoned());
+    link.flush().unwrap();
+
+    // A panic partway through a put sequence poisons the link.
+    let mut link = Link::new_loopback().unwrap();
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let _ = link.put_guarded(|link| -> Result<(), wstp::Error> {
+            link.put_function("System`List", 2)?;
+            panic!("abandoned put sequence")
+        });
+    }));
+    assert!(result.is_err());
+    assert!(link.is_poisoned());
+    assert!(link.flush().unwrap_err().is_poisoned());
+
+    // Poisoning one link does not affect other links.
+    let mut other = Link::new_loopback().unwrap();
+    assert!(!other.is_poisoned());
+    other.put_expr(&Expr::from(3)).unwrap();
+    assert_eq!(other.get_i64().unwrap(), 3);
+}
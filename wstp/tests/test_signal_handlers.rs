@@ -0,0 +1,19 @@
+//! These tests are in a separate test binary from the other tests, so that the WSTP
+//! library is not initialized before the test is run.
+
+use wstp::Link;
+
+#[test]
+fn test_disable_signal_handlers() {
+    wstp::disable_signal_handlers().unwrap();
+
+    // Calling this more than once before initialization is allowed.
+    wstp::disable_signal_handlers().unwrap();
+
+    let mut link = Link::new_loopback().unwrap();
+
+    link.put_i64(1).unwrap();
+    assert_eq!(link.get_i64().unwrap(), 1);
+
+    assert!(wstp::disable_signal_handlers().is_err());
+}
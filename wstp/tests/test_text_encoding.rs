@@ -0,0 +1,20 @@
+//! These tests are in a separate test binary from the other tests, so that the WSTP
+//! library is not initialized before the test is run.
+
+use wstp::{Link, TextEncoding};
+
+#[test]
+fn test_set_text_encoding() {
+    assert_eq!(wstp::text_encoding(), None);
+
+    wstp::set_text_encoding(TextEncoding::Utf8).unwrap();
+    assert_eq!(wstp::text_encoding(), Some(TextEncoding::Utf8));
+
+    let mut link = Link::new_loopback().unwrap();
+
+    // A code point outside of the Basic Multilingual Plane.
+    link.put_str("emoji: \u{1F600}").unwrap();
+    assert_eq!(link.get_string().unwrap(), "emoji: \u{1F600}");
+
+    assert!(wstp::set_text_encoding(TextEncoding::Ucs2).is_err());
+}